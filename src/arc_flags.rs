@@ -0,0 +1,351 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{NodeId, Weight, WEIGHT_MAX};
+use crate::fast_graph::FastGraph;
+use crate::landmarks::Adjacency;
+
+/// Divides the nodes into regions and stores for every edge of the prepared graph (including the
+/// shortcuts) the regions it leads to on a shortest path. A `PathCalculator` can use these flags
+/// to skip the edges that cannot lead to the region of the target (or come from the region of the
+/// source), see `PathCalculator::set_arc_flags`.
+///
+/// Calculating the flags takes two Dijkstra searches on the entire graph for every node at the
+/// border of a region, so the regions should be chosen such that only few nodes lie at their
+/// borders.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ArcFlags {
+    num_nodes: usize,
+    graph_fingerprint: u64,
+    regions: Vec<usize>,
+    num_regions: usize,
+    /// the flags of the forward edge `e` are `flags_fwd[e * words..(e + 1) * words]`, where
+    /// `words` is the number of 64bit words needed for one bit per region
+    flags_fwd: Vec<u64>,
+    flags_bwd: Vec<u64>,
+}
+
+impl ArcFlags {
+    /// Calculates the flags for the given regions, where `regions[node]` is the region of each
+    /// node, e.g. obtained using `partition` or `calc_grid_cells`. Region ids should be small,
+    /// because every edge stores one bit for all regions up to the largest region id.
+    pub fn new(graph: &FastGraph, regions: &[usize]) -> Self {
+        assert_eq!(
+            graph.get_num_nodes(),
+            regions.len(),
+            "there has to be one region per node"
+        );
+        let num_regions = regions.iter().max().map_or(0, |r| r + 1);
+        let words = num_words(num_regions);
+        let mut arc_flags = ArcFlags {
+            num_nodes: graph.get_num_nodes(),
+            graph_fingerprint: graph.get_fingerprint(),
+            regions: regions.to_vec(),
+            num_regions,
+            flags_fwd: vec![0; graph.get_num_out_edges() * words],
+            flags_bwd: vec![0; graph.get_num_in_edges() * words],
+        };
+        let adjacency = Adjacency::new(graph);
+        for node in 0..graph.get_num_nodes() {
+            // the edges starting in a region lead to this region. a forward edge goes from the
+            // node to its adjacent node, a backward edge from the adjacent node to the node.
+            for edge_id in graph.begin_out_edges(node)..graph.end_out_edges(node) {
+                arc_flags.set_flag_fwd(edge_id, regions[node]);
+            }
+            for edge_id in graph.begin_in_edges(node)..graph.end_in_edges(node) {
+                arc_flags.set_flag_bwd(edge_id, regions[graph.edges_bwd[edge_id].adj_node]);
+            }
+        }
+        // every shortest path from outside a region to a node in this region goes through a
+        // border node, and the same is true for the paths leaving a region
+        for border_node in
+            (0..graph.get_num_nodes()).filter(|n| is_border_node(&adjacency, regions, *n))
+        {
+            let region = regions[border_node];
+            let weights_to = adjacency.calc_weights(border_node, false);
+            let weights_from = adjacency.calc_weights(border_node, true);
+            for node in 0..graph.get_num_nodes() {
+                for edge_id in graph.begin_out_edges(node)..graph.end_out_edges(node) {
+                    let edge = &graph.edges_fwd[edge_id];
+                    if leads_to(
+                        border_node,
+                        node,
+                        edge.adj_node,
+                        edge.weight,
+                        &weights_to,
+                        &weights_from,
+                    ) {
+                        arc_flags.set_flag_fwd(edge_id, region);
+                    }
+                }
+                for edge_id in graph.begin_in_edges(node)..graph.end_in_edges(node) {
+                    let edge = &graph.edges_bwd[edge_id];
+                    // the edge goes from the adjacent node to the node, i.e. the other way round
+                    if leads_to(
+                        border_node,
+                        node,
+                        edge.adj_node,
+                        edge.weight,
+                        &weights_from,
+                        &weights_to,
+                    ) {
+                        arc_flags.set_flag_bwd(edge_id, region);
+                    }
+                }
+            }
+        }
+        arc_flags
+    }
+
+    /// Divides the nodes of the given graph into `num_regions` regions of nodes that are close to
+    /// each other in terms of the number of edges between them. The regions can be used to
+    /// calculate the flags using `new`.
+    pub fn partition(graph: &FastGraph, num_regions: usize) -> Vec<usize> {
+        assert!(num_regions > 0, "there has to be at least one region");
+        let num_nodes = graph.get_num_nodes();
+        let adjacency = Adjacency::new(graph);
+        let mut regions = vec![usize::MAX; num_nodes];
+        if num_nodes == 0 {
+            return regions;
+        }
+        // we pick the region centers one after the other, always using the node with the
+        // largest number of hops to all the previous ones
+        let mut centers = vec![];
+        let mut hops = vec![usize::MAX; num_nodes];
+        let mut next = 0;
+        for _ in 0..num_regions.min(num_nodes) {
+            centers.push(next);
+            let new_hops = calc_hops(&adjacency, &[next]);
+            for (h, new_h) in hops.iter_mut().zip(new_hops) {
+                *h = (*h).min(new_h);
+            }
+            next = (0..num_nodes).max_by_key(|n| hops[*n]).unwrap();
+            if hops[next] == 0 {
+                break;
+            }
+        }
+        // then every node belongs to the region of its closest center
+        let mut queue: VecDeque<NodeId> = VecDeque::new();
+        for (region, center) in centers.iter().enumerate() {
+            regions[*center] = region;
+            queue.push_back(*center);
+        }
+        while let Some(node) = queue.pop_front() {
+            for adj in neighbors(&adjacency, node) {
+                if regions[adj] == usize::MAX {
+                    regions[adj] = regions[node];
+                    queue.push_back(adj);
+                }
+            }
+        }
+        // nodes that are not connected to any center do not matter
+        for region in regions.iter_mut().filter(|r| **r == usize::MAX) {
+            *region = 0;
+        }
+        regions
+    }
+
+    pub fn get_regions(&self) -> &[usize] {
+        &self.regions
+    }
+
+    pub fn get_num_regions(&self) -> usize {
+        self.num_regions
+    }
+
+    /// Returns true if these flags were calculated for the given graph.
+    pub fn is_compatible(&self, graph: &FastGraph) -> bool {
+        self.num_nodes == graph.get_num_nodes() && self.graph_fingerprint == graph.get_fingerprint()
+    }
+
+    /// Returns true if the forward edge `edge_id` lies on a shortest path to a node in `region`.
+    pub fn is_flagged_fwd(&self, edge_id: usize, region: usize) -> bool {
+        let words = num_words(self.num_regions);
+        self.flags_fwd[edge_id * words + region / 64] & (1 << (region % 64)) != 0
+    }
+
+    /// Returns true if the backward edge `edge_id` lies on a shortest path from a node in
+    /// `region`.
+    pub fn is_flagged_bwd(&self, edge_id: usize, region: usize) -> bool {
+        let words = num_words(self.num_regions);
+        self.flags_bwd[edge_id * words + region / 64] & (1 << (region % 64)) != 0
+    }
+
+    /// Returns the share of all flags that are set, i.e. one for flags that do not prune anything.
+    pub fn get_flagged_ratio(&self) -> f64 {
+        let num_edges = self.flags_fwd.len() + self.flags_bwd.len();
+        if num_edges == 0 {
+            return 1.0;
+        }
+        let num_flags: u32 = self
+            .flags_fwd
+            .iter()
+            .chain(self.flags_bwd.iter())
+            .map(|w| w.count_ones())
+            .sum();
+        num_flags as f64 / (num_edges / num_words(self.num_regions) * self.num_regions) as f64
+    }
+
+    fn set_flag_fwd(&mut self, edge_id: usize, region: usize) {
+        let words = num_words(self.num_regions);
+        self.flags_fwd[edge_id * words + region / 64] |= 1 << (region % 64);
+    }
+
+    fn set_flag_bwd(&mut self, edge_id: usize, region: usize) {
+        let words = num_words(self.num_regions);
+        self.flags_bwd[edge_id * words + region / 64] |= 1 << (region % 64);
+    }
+}
+
+fn num_words(num_regions: usize) -> usize {
+    num_regions.div_ceil(64)
+}
+
+/// Checks if the edge `from -> to` lies on a shortest path to the border node, either before the
+/// border node or, if it is a shortcut, going through it. `weights_to` are the weights from all
+/// nodes to the border node and `weights_from` the weights from the border node to all nodes.
+fn leads_to(
+    border_node: NodeId,
+    from: NodeId,
+    to: NodeId,
+    weight: Weight,
+    weights_to: &[Weight],
+    weights_from: &[Weight],
+) -> bool {
+    let before = weights_to[from] != WEIGHT_MAX
+        && weights_to[to] != WEIGHT_MAX
+        && weight.saturating_add(weights_to[to]) == weights_to[from];
+    let through = border_node != from
+        && border_node != to
+        && weights_to[from] != WEIGHT_MAX
+        && weights_from[to] != WEIGHT_MAX
+        && weights_to[from].saturating_add(weights_from[to]) == weight;
+    before || through
+}
+
+fn is_border_node(adjacency: &Adjacency, regions: &[usize], node: NodeId) -> bool {
+    neighbors(adjacency, node).any(|adj| regions[adj] != regions[node])
+}
+
+fn neighbors(adjacency: &Adjacency, node: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+    adjacency.out_edges[node]
+        .iter()
+        .chain(adjacency.in_edges[node].iter())
+        .map(|(adj, _)| *adj)
+}
+
+/// Returns the minimum number of edges (in either direction) between each node and the given
+/// nodes
+fn calc_hops(adjacency: &Adjacency, sources: &[NodeId]) -> Vec<usize> {
+    let mut hops = vec![usize::MAX; adjacency.out_edges.len()];
+    let mut queue = VecDeque::new();
+    for source in sources {
+        hops[*source] = 0;
+        queue.push_back(*source);
+    }
+    while let Some(node) = queue.pop_front() {
+        for adj in neighbors(adjacency, node) {
+            if hops[adj] == usize::MAX {
+                hops[adj] = hops[node] + 1;
+                queue.push_back(adj);
+            }
+        }
+    }
+    hops
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::input_graph::InputGraph;
+
+    use super::*;
+
+    #[test]
+    fn flags() {
+        // 0 -> 1 -> 2 -> 3
+        // |              ^
+        // 4 -> 5 ------> 6
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 1);
+        g.add_edge(2, 3, 1);
+        g.add_edge(0, 4, 1);
+        g.add_edge(4, 5, 1);
+        g.add_edge(5, 6, 1);
+        g.add_edge(6, 3, 5);
+        g.freeze();
+        let fast_graph = crate::prepare(&g);
+        let arc_flags = ArcFlags::new(&fast_graph, &[0, 0, 1, 1, 2, 2, 1]);
+        assert_eq!(3, arc_flags.get_num_regions());
+        assert!(arc_flags.is_compatible(&fast_graph));
+        assert!(arc_flags.get_flagged_ratio() < 1.0);
+        // the edge 5 -> 6 starts in region 2, so it is always flagged for region 2
+        let (fwd, id) = find_edge(&fast_graph, 5, 6);
+        if fwd {
+            // it leads to 6 and 3 in region 1, but not to region 0
+            assert!(arc_flags.is_flagged_fwd(id, 1));
+            assert!(arc_flags.is_flagged_fwd(id, 2));
+            assert!(!arc_flags.is_flagged_fwd(id, 0));
+        } else {
+            // it can be reached from 0 in region 0, but not from region 1
+            assert!(arc_flags.is_flagged_bwd(id, 0));
+            assert!(arc_flags.is_flagged_bwd(id, 2));
+            assert!(!arc_flags.is_flagged_bwd(id, 1));
+        }
+    }
+
+    #[test]
+    fn partition() {
+        // 0 - 1 - 2 - 3 - 4 - 5
+        let mut g = InputGraph::new();
+        for i in 0..5 {
+            g.add_edge_bidir(i, i + 1, 1);
+        }
+        g.freeze();
+        let fast_graph = crate::prepare(&g);
+        assert_eq!(vec![0; 6], ArcFlags::partition(&fast_graph, 1));
+        let regions = ArcFlags::partition(&fast_graph, 2);
+        assert_eq!(vec![0, 0, 0, 1, 1, 1], regions);
+        let regions = ArcFlags::partition(&fast_graph, 10);
+        assert_eq!(
+            6,
+            regions
+                .iter()
+                .collect::<std::collections::HashSet<_>>()
+                .len()
+        );
+    }
+
+    /// Returns the direction and id of the edge of the prepared graph between the given nodes
+    fn find_edge(graph: &FastGraph, from: NodeId, to: NodeId) -> (bool, usize) {
+        if let Some(id) = (graph.begin_out_edges(from)..graph.end_out_edges(from))
+            .find(|id| graph.edges_fwd[*id].adj_node == to)
+        {
+            return (true, id);
+        }
+        let id = (graph.begin_in_edges(to)..graph.end_in_edges(to))
+            .find(|id| graph.edges_bwd[*id].adj_node == from)
+            .unwrap();
+        (false, id)
+    }
+}
@@ -0,0 +1,123 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use std::collections::HashSet;
+
+use crate::constants::NodeId;
+use crate::fast_graph::FastGraph;
+use crate::original_edge_search;
+use crate::shortest_path::ShortestPath;
+
+/// A set of nodes and edges that are temporarily closed, e.g. because of road works or
+/// incidents, see `calc_path`.
+#[derive(Debug, Clone, Default)]
+pub struct BlockedElements {
+    nodes: HashSet<NodeId>,
+    edges: HashSet<(NodeId, NodeId)>,
+}
+
+impl BlockedElements {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn block_node(&mut self, node: NodeId) {
+        self.nodes.insert(node);
+    }
+
+    /// Blocks the edge from `from` to `to`, but not the one in the opposite direction.
+    pub fn block_edge(&mut self, from: NodeId, to: NodeId) {
+        self.edges.insert((from, to));
+    }
+
+    pub fn is_node_blocked(&self, node: NodeId) -> bool {
+        self.nodes.contains(&node)
+    }
+
+    pub fn is_edge_blocked(&self, from: NodeId, to: NodeId) -> bool {
+        self.edges.contains(&(from, to))
+    }
+}
+
+/// Calculates the shortest path from `start` to `end` that does not use any of the `blocked`
+/// nodes and edges.
+///
+/// Simply refusing the shortcuts that contain blocked elements is not enough, because the
+/// preparation did not create shortcuts for paths that were not shortest paths before, and these
+/// might be needed to get around the blocked elements. So this uses the original edges only, see
+/// `original_edge_search::calc_path`.
+pub fn calc_path(
+    graph: &FastGraph,
+    start: NodeId,
+    end: NodeId,
+    blocked: &BlockedElements,
+) -> Option<ShortestPath> {
+    if blocked.is_node_blocked(start) || blocked.is_node_blocked(end) {
+        return None;
+    }
+    original_edge_search::calc_path(graph, start, end, |edge| {
+        if blocked.is_node_blocked(edge.to) || blocked.is_edge_blocked(edge.from, edge.to) {
+            None
+        } else {
+            Some(edge.weight)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::input_graph::InputGraph;
+
+    use super::*;
+
+    #[test]
+    fn avoid_blocked_elements() {
+        // 0 -> 1 -> 2 -> 3
+        //  \-> 4 -> 5 ->/
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 1);
+        g.add_edge(2, 3, 1);
+        g.add_edge(0, 4, 2);
+        g.add_edge(4, 5, 2);
+        g.add_edge(5, 3, 2);
+        g.freeze();
+        let fast_graph = crate::prepare(&g);
+        let mut blocked = BlockedElements::new();
+        let path = calc_path(&fast_graph, 0, 3, &blocked).unwrap();
+        assert_eq!(&vec![0, 1, 2, 3], path.get_nodes());
+
+        blocked.block_node(2);
+        let path = calc_path(&fast_graph, 0, 3, &blocked).unwrap();
+        assert_eq!(&vec![0, 4, 5, 3], path.get_nodes());
+        assert_eq!(6, path.get_weight());
+        assert!(calc_path(&fast_graph, 2, 3, &blocked).is_none());
+
+        let mut blocked = BlockedElements::new();
+        blocked.block_edge(1, 2);
+        let path = calc_path(&fast_graph, 0, 3, &blocked).unwrap();
+        assert_eq!(&vec![0, 4, 5, 3], path.get_nodes());
+        blocked.block_edge(5, 3);
+        assert!(calc_path(&fast_graph, 0, 3, &blocked).is_none());
+        // only the given direction is blocked
+        blocked.block_edge(3, 2);
+        assert!(calc_path(&fast_graph, 1, 3, &blocked).is_none());
+        assert!(calc_path(&fast_graph, 2, 3, &blocked).is_some());
+    }
+}
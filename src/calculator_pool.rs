@@ -0,0 +1,190 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+
+use crate::fast_graph::FastGraph;
+use crate::path_calculator::PathCalculator;
+
+/// A pool of `PathCalculator`s that can be shared between threads, e.g. by the request handlers
+/// of a web server. Creating a `PathCalculator` is expensive for large graphs, so rather than
+/// creating a new one for every request the calculators are checked out from the pool and
+/// returned to it automatically once the returned guard is dropped.
+pub struct CalculatorPool {
+    num_nodes: usize,
+    calculators: Mutex<Vec<PathCalculator>>,
+}
+
+impl CalculatorPool {
+    /// Creates a pool for the given graph that initially contains `size` calculators.
+    pub fn new(fast_graph: &FastGraph, size: usize) -> Self {
+        let num_nodes = fast_graph.get_num_nodes();
+        CalculatorPool {
+            num_nodes,
+            calculators: Mutex::new((0..size).map(|_| PathCalculator::new(num_nodes)).collect()),
+        }
+    }
+
+    /// Takes a calculator from the pool. If all calculators are in use a new one is created,
+    /// which is added to the pool when it is returned, so this never blocks for long.
+    pub fn checkout(&self) -> PooledCalculator<'_> {
+        let calculator = self
+            .lock()
+            .pop()
+            .unwrap_or_else(|| PathCalculator::new(self.num_nodes));
+        PooledCalculator {
+            pool: self,
+            calculator: Some(calculator),
+        }
+    }
+
+    /// Returns the number of calculators that are currently available in the pool.
+    pub fn get_num_available(&self) -> usize {
+        self.lock().len()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<PathCalculator>> {
+        // the pool only ever contains complete calculators, so we can keep using it even when
+        // another thread panicked while holding the lock
+        self.calculators
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A `PathCalculator` checked out from a `CalculatorPool`. It is returned to the pool on drop,
+/// after its settings (e.g. `PathCalculator::set_max_settled_nodes` or
+/// `PathCalculator::set_source`) were reset, so they do not leak into the next checkout.
+pub struct PooledCalculator<'a> {
+    pool: &'a CalculatorPool,
+    calculator: Option<PathCalculator>,
+}
+
+impl Deref for PooledCalculator<'_> {
+    type Target = PathCalculator;
+
+    fn deref(&self) -> &PathCalculator {
+        self.calculator.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledCalculator<'_> {
+    fn deref_mut(&mut self) -> &mut PathCalculator {
+        self.calculator.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledCalculator<'_> {
+    fn drop(&mut self) {
+        if let Some(mut calculator) = self.calculator.take() {
+            calculator.reset_settings();
+            self.pool.lock().push(calculator);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+
+    use crate::arc_flags::ArcFlags;
+    use crate::input_graph::InputGraph;
+    use crate::landmarks::Landmarks;
+    use crate::path_calculator::DirectionBias;
+
+    use super::*;
+
+    #[test]
+    fn checkout_and_return() {
+        // 0 -> 1 -> 2
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 3);
+        g.add_edge(1, 2, 4);
+        g.freeze();
+        let fast_graph = crate::prepare(&g);
+        let pool = CalculatorPool::new(&fast_graph, 2);
+        assert_eq!(2, pool.get_num_available());
+        {
+            let mut calc1 = pool.checkout();
+            let calc2 = pool.checkout();
+            let mut calc3 = pool.checkout();
+            assert_eq!(0, pool.get_num_available());
+            assert_eq!(7, calc1.calc_path(&fast_graph, 0, 2).unwrap().get_weight());
+            assert_eq!(4, calc3.calc_path(&fast_graph, 1, 2).unwrap().get_weight());
+            drop(calc2);
+            assert_eq!(1, pool.get_num_available());
+        }
+        assert_eq!(3, pool.get_num_available());
+    }
+
+    #[test]
+    fn reset_settings_on_return() {
+        // 0 -> 1 -> 2
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 3);
+        g.add_edge(1, 2, 4);
+        g.freeze();
+        let fast_graph = crate::prepare(&g);
+        let pool = CalculatorPool::new(&fast_graph, 1);
+        {
+            let mut calc = pool.checkout();
+            calc.set_max_settled_nodes(Some(1));
+            calc.set_direction_bias(DirectionBias::Factor(2.0));
+            calc.set_record_search_space(true);
+            calc.set_landmarks(Some(Arc::new(Landmarks::new(&fast_graph, 1))));
+            calc.set_arc_flags(Some(Arc::new(ArcFlags::new(&fast_graph, &[0, 0, 1]))));
+            calc.set_source(&fast_graph, 0);
+        }
+        let mut calc = pool.checkout();
+        assert_eq!(None, calc.get_max_settled_nodes());
+        assert_eq!(DirectionBias::Balanced, calc.get_direction_bias());
+        assert!(calc.get_landmarks().is_none());
+        assert!(calc.get_arc_flags().is_none());
+        assert_eq!(None, calc.get_source());
+        assert_eq!(7, calc.calc_path(&fast_graph, 0, 2).unwrap().get_weight());
+        assert!(calc.get_search_space().is_empty());
+    }
+
+    #[test]
+    fn share_between_threads() {
+        // 0 -> 1 -> 2
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 3);
+        g.add_edge(1, 2, 4);
+        g.freeze();
+        let fast_graph = Arc::new(crate::prepare(&g));
+        let pool = Arc::new(CalculatorPool::new(&fast_graph, 2));
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let fast_graph = fast_graph.clone();
+                let pool = pool.clone();
+                thread::spawn(move || {
+                    let mut calc = pool.checkout();
+                    calc.calc_path(&fast_graph, 0, 2).unwrap().get_weight()
+                })
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(7, handle.join().unwrap());
+        }
+        assert!(pool.get_num_available() >= 2);
+    }
+}
@@ -0,0 +1,320 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+//! Importing contraction hierarchies that were built by other tools like GraphHopper or OSRM.
+//!
+//! These tools store their hierarchies in their own binary formats, which are not read directly.
+//! Instead, the node levels and the edges (including shortcuts) of the hierarchy need to be
+//! exported first, either as a list of `ChEdge`s or in the text format read by `from_ch_file`.
+//! Edges that can be traversed in both directions (as they are usually stored by GraphHopper and
+//! OSRM) need to be given once per direction.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use crate::constants::{EdgeId, NodeId, Weight, INVALID_EDGE, INVALID_NODE};
+use crate::fast_graph::{FastGraph, FastGraphEdge};
+
+/// A directed edge of a contraction hierarchy. For shortcuts `center_node` is the node that is
+/// bypassed by the shortcut, i.e. the shortcut replaces the two edges `from->center_node` and
+/// `center_node->to`. For original edges it is `INVALID_NODE`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChEdge {
+    pub from: NodeId,
+    pub to: NodeId,
+    pub weight: Weight,
+    pub center_node: NodeId,
+}
+
+impl ChEdge {
+    pub fn new(from: NodeId, to: NodeId, weight: Weight) -> Self {
+        ChEdge {
+            from,
+            to,
+            weight,
+            center_node: INVALID_NODE,
+        }
+    }
+
+    pub fn shortcut(from: NodeId, to: NodeId, weight: Weight, center_node: NodeId) -> Self {
+        ChEdge {
+            from,
+            to,
+            weight,
+            center_node,
+        }
+    }
+}
+
+/// Creates a `FastGraph` from the ranks of all nodes (the node with id `i` was contracted as the
+/// `ranks[i]`-th node, GraphHopper and OSRM call this the level of a node) and all edges of the
+/// hierarchy. Returns an error if the edges do not form a valid contraction hierarchy.
+pub fn from_ch_edges(ranks: Vec<usize>, edges: &[ChEdge]) -> Result<FastGraph, String> {
+    let num_nodes = ranks.len();
+    let mut sorted_ranks = ranks.clone();
+    sorted_ranks.sort_unstable();
+    if sorted_ranks.iter().enumerate().any(|(i, rank)| i != *rank) {
+        return Err("ranks must be a permutation of 0..num_nodes".to_string());
+    }
+    let mut nodes_fwd: Vec<Vec<&ChEdge>> = vec![Vec::new(); num_nodes];
+    let mut nodes_bwd: Vec<Vec<&ChEdge>> = vec![Vec::new(); num_nodes];
+    for e in edges {
+        if e.from >= num_nodes || e.to >= num_nodes {
+            return Err(format!("edge {}->{} has invalid node id", e.from, e.to));
+        }
+        if e.center_node != INVALID_NODE && e.center_node >= num_nodes {
+            return Err(format!(
+                "shortcut {}->{} has invalid center node",
+                e.from, e.to
+            ));
+        }
+        if e.from == e.to {
+            return Err(format!("loop edge at node {}", e.from));
+        }
+        // all edges are stored at the node with the lower rank
+        if ranks[e.from] < ranks[e.to] {
+            nodes_fwd[ranks[e.from]].push(e);
+        } else {
+            nodes_bwd[ranks[e.to]].push(e);
+        }
+    }
+
+    let mut edges_fwd = Vec::new();
+    let mut first_edge_ids_fwd = vec![0; num_nodes + 1];
+    let mut edge_ids_fwd = HashMap::new();
+    for (rank, node_edges) in nodes_fwd.iter().enumerate() {
+        for e in node_edges {
+            if edge_ids_fwd
+                .insert((e.from, e.to), edges_fwd.len())
+                .is_some()
+            {
+                return Err(format!("duplicate edge {}->{}", e.from, e.to));
+            }
+            edges_fwd.push(FastGraphEdge::new(
+                e.from,
+                e.to,
+                e.weight,
+                INVALID_EDGE,
+                INVALID_EDGE,
+            ));
+        }
+        first_edge_ids_fwd[rank + 1] = edges_fwd.len();
+    }
+
+    let mut edges_bwd = Vec::new();
+    let mut first_edge_ids_bwd = vec![0; num_nodes + 1];
+    let mut edge_ids_bwd = HashMap::new();
+    for (rank, node_edges) in nodes_bwd.iter().enumerate() {
+        for e in node_edges {
+            if edge_ids_bwd
+                .insert((e.from, e.to), edges_bwd.len())
+                .is_some()
+            {
+                return Err(format!("duplicate edge {}->{}", e.from, e.to));
+            }
+            edges_bwd.push(FastGraphEdge::new(
+                e.to,
+                e.from,
+                e.weight,
+                INVALID_EDGE,
+                INVALID_EDGE,
+            ));
+        }
+        first_edge_ids_bwd[rank + 1] = edges_bwd.len();
+    }
+
+    // the edges replaced by a shortcut are both stored at the center node, which has the lowest
+    // rank of the three nodes involved
+    let find_replaced_edges = |e: &ChEdge| -> Result<(EdgeId, EdgeId), String> {
+        let c = e.center_node;
+        match (edge_ids_bwd.get(&(e.from, c)), edge_ids_fwd.get(&(c, e.to))) {
+            (Some(in_edge), Some(out_edge)) => Ok((*in_edge, *out_edge)),
+            _ => Err(format!(
+                "could not find the edges replaced by shortcut {}->{} via {}",
+                e.from, e.to, c
+            )),
+        }
+    };
+    for node_edges in &nodes_fwd {
+        for e in node_edges.iter().filter(|e| e.center_node != INVALID_NODE) {
+            let (in_edge, out_edge) = find_replaced_edges(e)?;
+            let edge = &mut edges_fwd[edge_ids_fwd[&(e.from, e.to)]];
+            edge.replaced_in_edge = in_edge;
+            edge.replaced_out_edge = out_edge;
+        }
+    }
+    for node_edges in &nodes_bwd {
+        for e in node_edges.iter().filter(|e| e.center_node != INVALID_NODE) {
+            let (in_edge, out_edge) = find_replaced_edges(e)?;
+            let edge = &mut edges_bwd[edge_ids_bwd[&(e.from, e.to)]];
+            edge.replaced_in_edge = in_edge;
+            edge.replaced_out_edge = out_edge;
+        }
+    }
+
+    FastGraph::from_parts(
+        ranks,
+        edges_fwd,
+        first_edge_ids_fwd,
+        edges_bwd,
+        first_edge_ids_bwd,
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Reads a contraction hierarchy from a text file, using the following format:
+/// r <node> <rank>
+/// e <from> <to> <weight>
+/// s <from> <to> <weight> <center_node>
+/// There has to be one `r` line for every node, and `e` and `s` lines are used for original edges
+/// and shortcuts respectively. Empty lines and lines starting with `#` are ignored.
+pub fn from_ch_file(filename: &str) -> Result<FastGraph, String> {
+    let file = File::open(filename).map_err(|e| e.to_string())?;
+    let reader = BufReader::new(file);
+    let mut node_ranks = Vec::new();
+    let mut edges = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| e.to_string())?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid_line = || format!("invalid line {}: '{}'", index + 1, line);
+        let mut entries = line.split_whitespace();
+        let kind = entries.next().ok_or_else(invalid_line)?;
+        let numbers = entries
+            .map(|s| s.parse::<usize>())
+            .collect::<Result<Vec<usize>, _>>()
+            .map_err(|_| invalid_line())?;
+        match (kind, numbers.as_slice()) {
+            ("r", [node, rank]) => node_ranks.push((*node, *rank)),
+            ("e", [from, to, weight]) => edges.push(ChEdge::new(*from, *to, *weight)),
+            ("s", [from, to, weight, center_node]) => {
+                edges.push(ChEdge::shortcut(*from, *to, *weight, *center_node))
+            }
+            _ => return Err(invalid_line()),
+        }
+    }
+    let mut ranks = vec![INVALID_NODE; node_ranks.len()];
+    for (node, rank) in node_ranks {
+        if node >= ranks.len() || ranks[node] != INVALID_NODE {
+            return Err(format!("invalid or duplicate rank entry for node {}", node));
+        }
+        ranks[node] = rank;
+    }
+    from_ch_edges(ranks, &edges)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::remove_file;
+    use std::io::Write;
+
+    use crate::input_graph::InputGraph;
+    use crate::shortest_path::ShortestPath;
+
+    use super::*;
+
+    #[test]
+    fn import_prepared_graph() {
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 3);
+        g.add_edge(1, 2, 4);
+        g.add_edge(2, 3, 1);
+        g.add_edge(3, 4, 2);
+        g.add_edge(4, 0, 6);
+        g.add_edge(1, 4, 9);
+        g.freeze();
+        let fast_graph = crate::prepare_with_order(&g, &[2, 0, 4, 1, 3]).unwrap();
+        let imported = from_ch_edges(fast_graph.ranks.clone(), &export(&fast_graph)).unwrap();
+        assert_eq!(fast_graph.edges_fwd.len(), imported.edges_fwd.len());
+        assert_eq!(fast_graph.edges_bwd.len(), imported.edges_bwd.len());
+        for source in 0..5 {
+            for target in 0..5 {
+                assert_eq!(
+                    crate::calc_path(&fast_graph, source, target),
+                    crate::calc_path(&imported, source, target)
+                );
+            }
+        }
+        assert_eq!(
+            Some(ShortestPath::new(1, 0, 13, vec![1, 2, 3, 4, 0])),
+            crate::calc_path(&imported, 1, 0)
+        );
+    }
+
+    #[test]
+    fn import_from_file() {
+        // 0 -> 1 -> 2, node 1 was contracted first
+        let filename = "ch_import_test.txt";
+        let mut f = File::create(filename).unwrap();
+        writeln!(f, "# a small hierarchy").unwrap();
+        writeln!(f, "r 0 1\nr 1 0\nr 2 2").unwrap();
+        writeln!(f, "e 0 1 3\ne 1 2 4\ns 0 2 7 1").unwrap();
+        let graph = from_ch_file(filename);
+        remove_file(filename).unwrap();
+        let graph = graph.unwrap();
+        assert_eq!(
+            Some(ShortestPath::new(0, 2, 7, vec![0, 1, 2])),
+            crate::calc_path(&graph, 0, 2)
+        );
+    }
+
+    #[test]
+    fn invalid_hierarchies() {
+        // the replaced edges are missing
+        let result = from_ch_edges(vec![1, 0, 2], &[ChEdge::shortcut(0, 2, 7, 1)]);
+        assert!(result.is_err());
+        // ranks are not a permutation
+        let result = from_ch_edges(vec![0, 0], &[ChEdge::new(0, 1, 3)]);
+        assert!(result.is_err());
+        // duplicate edge
+        let result = from_ch_edges(vec![0, 1], &[ChEdge::new(0, 1, 3), ChEdge::new(0, 1, 4)]);
+        assert!(result.is_err());
+    }
+
+    fn export(graph: &FastGraph) -> Vec<ChEdge> {
+        let center_node = |e: &FastGraphEdge| {
+            if e.is_shortcut() {
+                graph.edges_bwd[e.replaced_in_edge].base_node
+            } else {
+                INVALID_NODE
+            }
+        };
+        let mut edges = Vec::new();
+        for e in &graph.edges_fwd {
+            edges.push(ChEdge::shortcut(
+                e.base_node,
+                e.adj_node,
+                e.weight,
+                center_node(e),
+            ));
+        }
+        for e in &graph.edges_bwd {
+            edges.push(ChEdge::shortcut(
+                e.adj_node,
+                e.base_node,
+                e.weight,
+                center_node(e),
+            ));
+        }
+        edges
+    }
+}
@@ -0,0 +1,404 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use std::collections::HashMap;
+
+use crate::constants::{NodeId, Weight};
+use crate::fast_graph::FastGraph;
+use crate::input_graph::InputGraph;
+use crate::path_calculator::PathCalculator;
+use crate::shortest_path::ShortestPath;
+
+/// Collapses chains of nodes with degree two (like the many nodes along a curvy road that only
+/// describe its geometry) into single edges before the graph preparation. The collapsed nodes
+/// keep their ids but have no edges in the compressed graph, so they are cheap to contract.
+/// Paths between any nodes, including the collapsed ones, can still be calculated exactly using
+/// `calc_path`.
+///
+/// A node is collapsed if it has exactly two neighbors `u` and `w` and its edges are either
+/// `u->node->w` or `u<->node<->w`.
+pub struct ChainCompression {
+    compressed_graph: InputGraph,
+    edges: Vec<ChainEdge>,
+    /// for each pair of nodes the edge with the lowest weight between them
+    best_edges: HashMap<(NodeId, NodeId), usize>,
+    /// for each collapsed node the edges it lies on and its position within these edges
+    positions: Vec<Vec<(usize, usize)>>,
+    num_collapsed: usize,
+}
+
+/// An edge of the compressed graph, along with the collapsed nodes it goes through
+struct ChainEdge {
+    from: NodeId,
+    to: NodeId,
+    weight: Weight,
+    nodes: Vec<NodeId>,
+    /// the weight from `from` to each of the `nodes`
+    offsets: Vec<Weight>,
+}
+
+impl ChainCompression {
+    pub fn new(input_graph: &InputGraph) -> Self {
+        let num_nodes = input_graph.get_num_nodes();
+        let mut edges: Vec<ChainEdge> = input_graph
+            .get_edges()
+            .iter()
+            .map(|e| ChainEdge {
+                from: e.from,
+                to: e.to,
+                weight: e.weight,
+                nodes: vec![],
+                offsets: vec![],
+            })
+            .collect();
+        let mut alive = vec![true; edges.len()];
+        let mut out_edges = vec![vec![]; num_nodes];
+        let mut in_edges = vec![vec![]; num_nodes];
+        for (i, e) in edges.iter().enumerate() {
+            out_edges[e.from].push(i);
+            in_edges[e.to].push(i);
+        }
+
+        let mut collapsed = vec![false; num_nodes];
+        // nodes that are the start and end of a loop edge must not be collapsed, because the loop
+        // can only be entered and left via them
+        let mut has_loop = vec![false; num_nodes];
+        let mut num_collapsed = 0;
+        let mut candidates: Vec<NodeId> = (0..num_nodes).rev().collect();
+        while let Some(node) = candidates.pop() {
+            if collapsed[node] {
+                continue;
+            }
+            out_edges[node].retain(|e| alive[*e]);
+            in_edges[node].retain(|e| alive[*e]);
+            if has_loop[node] || !is_chain_node(&edges, &out_edges[node], &in_edges[node]) {
+                continue;
+            }
+            let node_in_edges = std::mem::take(&mut in_edges[node]);
+            let node_out_edges = std::mem::take(&mut out_edges[node]);
+            for in_edge in &node_in_edges {
+                for out_edge in &node_out_edges {
+                    let (e1, e2) = (&edges[*in_edge], &edges[*out_edge]);
+                    // a loop that goes back the way it came is never part of a shortest path, but
+                    // if the way back differs the collapsed nodes on it might only be reachable
+                    // via the loop
+                    let is_loop = e1.from == e2.to;
+                    if is_loop && e1.nodes.iter().rev().eq(e2.nodes.iter()) {
+                        continue;
+                    }
+                    let mut nodes = e1.nodes.clone();
+                    nodes.push(node);
+                    nodes.extend_from_slice(&e2.nodes);
+                    let mut offsets = e1.offsets.clone();
+                    offsets.push(e1.weight);
+                    offsets.extend(e2.offsets.iter().map(|o| e1.weight + o));
+                    let edge = ChainEdge {
+                        from: e1.from,
+                        to: e2.to,
+                        weight: e1.weight + e2.weight,
+                        nodes,
+                        offsets,
+                    };
+                    if is_loop {
+                        // loops are not part of the compressed graph, they are only used to find
+                        // the paths from and to the nodes they go through
+                        has_loop[edge.from] = true;
+                    } else {
+                        out_edges[edge.from].push(edges.len());
+                        in_edges[edge.to].push(edges.len());
+                    }
+                    edges.push(edge);
+                    alive.push(true);
+                }
+            }
+            for e in node_in_edges.iter().chain(node_out_edges.iter()) {
+                alive[*e] = false;
+                // the neighbors might have become chain nodes now
+                candidates.push(edges[*e].from);
+                candidates.push(edges[*e].to);
+            }
+            collapsed[node] = true;
+            num_collapsed += 1;
+        }
+
+        let edges: Vec<ChainEdge> = edges
+            .into_iter()
+            .zip(alive)
+            .filter(|(_, alive)| *alive)
+            .map(|(e, _)| e)
+            .collect();
+        let mut compressed_graph = InputGraph::new();
+        let mut best_edges = HashMap::new();
+        let mut positions = vec![vec![]; num_nodes];
+        for (i, e) in edges.iter().enumerate() {
+            for (position, node) in e.nodes.iter().enumerate() {
+                positions[*node].push((i, position));
+            }
+            if e.from == e.to {
+                continue;
+            }
+            compressed_graph.add_edge(e.from, e.to, e.weight);
+            let best = best_edges.entry((e.from, e.to)).or_insert(i);
+            if e.weight < edges[*best].weight {
+                *best = i;
+            }
+        }
+        compressed_graph.set_min_num_nodes(num_nodes);
+        compressed_graph.freeze();
+        info!(
+            "collapsed {} of {} nodes, {} edges were replaced by {} edges",
+            num_collapsed,
+            num_nodes,
+            input_graph.get_num_edges(),
+            compressed_graph.get_num_edges()
+        );
+        ChainCompression {
+            compressed_graph,
+            edges,
+            best_edges,
+            positions,
+            num_collapsed,
+        }
+    }
+
+    /// Returns the graph that should be prepared instead of the original input graph. It has the
+    /// same node ids as the original graph.
+    pub fn get_compressed_graph(&self) -> &InputGraph {
+        &self.compressed_graph
+    }
+
+    pub fn get_num_collapsed_nodes(&self) -> usize {
+        self.num_collapsed
+    }
+
+    pub fn is_collapsed(&self, node: NodeId) -> bool {
+        !self.positions[node].is_empty()
+    }
+
+    /// Calculates the shortest path from `source` to `target` in the original graph. The given
+    /// `fast_graph` must have been prepared from the compressed graph. The returned path contains
+    /// all nodes of the original graph, including the collapsed ones.
+    pub fn calc_path(
+        &self,
+        path_calculator: &mut PathCalculator,
+        fast_graph: &FastGraph,
+        source: NodeId,
+        target: NodeId,
+    ) -> Option<ShortestPath> {
+        if source == target {
+            return Some(ShortestPath::singular(source));
+        }
+        // collapsed nodes can only be left/reached via the ends of the edges they lie on
+        let sources: Vec<(NodeId, Weight)> = if self.is_collapsed(source) {
+            self.positions[source]
+                .iter()
+                .map(|(e, p)| {
+                    let edge = &self.edges[*e];
+                    (edge.to, edge.weight - edge.offsets[*p])
+                })
+                .collect()
+        } else {
+            vec![(source, 0)]
+        };
+        let targets: Vec<(NodeId, Weight)> = if self.is_collapsed(target) {
+            self.positions[target]
+                .iter()
+                .map(|(e, p)| (self.edges[*e].from, self.edges[*e].offsets[*p]))
+                .collect()
+        } else {
+            vec![(target, 0)]
+        };
+        let mut best = path_calculator
+            .calc_path_multiple_sources_and_targets(fast_graph, sources, targets)
+            .map(|path| {
+                let mut nodes = vec![];
+                if self.is_collapsed(source) {
+                    let (e, p) = self.find_position(source, |e, p| {
+                        (e.to == path.get_source()).then_some(e.weight - e.offsets[p])
+                    });
+                    nodes.push(source);
+                    nodes.extend_from_slice(&self.edges[e].nodes[p + 1..]);
+                }
+                nodes.extend(self.expand(path.get_nodes()));
+                if self.is_collapsed(target) {
+                    let (e, p) = self.find_position(target, |e, p| {
+                        (e.from == path.get_target()).then_some(e.offsets[p])
+                    });
+                    nodes.extend_from_slice(&self.edges[e].nodes[..p]);
+                    nodes.push(target);
+                }
+                ShortestPath::new(source, target, path.get_weight(), nodes)
+            });
+        // the target might also lie on the same chain behind the source
+        for (e, p_source) in &self.positions[source] {
+            for (e_target, p_target) in &self.positions[target] {
+                let edge = &self.edges[*e];
+                if e == e_target && p_source < p_target {
+                    let weight = edge.offsets[*p_target] - edge.offsets[*p_source];
+                    if best.as_ref().map_or(true, |b| weight < b.get_weight()) {
+                        let nodes = edge.nodes[*p_source..=*p_target].to_vec();
+                        best = Some(ShortestPath::new(source, target, weight, nodes));
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    /// Finds the edge and position of the given collapsed node for which `weight` returns the
+    /// smallest weight. `weight` returns `None` for the edges the path does not use.
+    fn find_position<F>(&self, node: NodeId, weight: F) -> (usize, usize)
+    where
+        F: Fn(&ChainEdge, usize) -> Option<Weight>,
+    {
+        self.positions[node]
+            .iter()
+            .filter_map(|(e, p)| weight(&self.edges[*e], *p).map(|w| ((*e, *p), w)))
+            .min_by_key(|(_, w)| *w)
+            .map(|(position, _)| position)
+            .expect("the path must start or end at an edge of the collapsed node")
+    }
+
+    fn expand(&self, nodes: &[NodeId]) -> Vec<NodeId> {
+        let mut result = Vec::with_capacity(nodes.len());
+        for pair in nodes.windows(2) {
+            result.push(pair[0]);
+            let e = self.best_edges[&(pair[0], pair[1])];
+            result.extend_from_slice(&self.edges[e].nodes);
+        }
+        result.extend(nodes.last());
+        result
+    }
+}
+
+/// Checks if a node with the given edges is part of a chain, i.e. its edges are `u->node->w`
+/// or `u<->node<->w` for two different nodes `u` and `w`.
+fn is_chain_node(edges: &[ChainEdge], out_edges: &[usize], in_edges: &[usize]) -> bool {
+    let mut out_adj: Vec<NodeId> = out_edges.iter().map(|e| edges[*e].to).collect();
+    let mut in_adj: Vec<NodeId> = in_edges.iter().map(|e| edges[*e].from).collect();
+    out_adj.sort_unstable();
+    in_adj.sort_unstable();
+    match (in_adj.as_slice(), out_adj.as_slice()) {
+        ([u], [w]) => u != w,
+        ([u1, w1], [u2, w2]) => u1 != w1 && u1 == u2 && w1 == w2,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collapse_chains() {
+        //     3 -> 4 -> 5
+        //    /           \
+        // 0 <-> 1 <-> 2 <-> 6 -> 7
+        //  \------------------/
+        let mut g = InputGraph::new();
+        g.add_edge_bidir(0, 1, 1);
+        g.add_edge_bidir(1, 2, 2);
+        g.add_edge_bidir(2, 6, 3);
+        g.add_edge(0, 3, 4);
+        g.add_edge(3, 4, 5);
+        g.add_edge(4, 5, 6);
+        g.add_edge(5, 6, 7);
+        g.add_edge(6, 7, 8);
+        g.add_edge(0, 7, 30);
+        g.freeze();
+        let compression = ChainCompression::new(&g);
+        // 6 -> 7 remains and 0/6 have degree three. the two edges 0 -> 6 are merged into one
+        assert_eq!(5, compression.get_num_collapsed_nodes());
+        assert!(compression.is_collapsed(1));
+        assert!(compression.is_collapsed(4));
+        assert!(!compression.is_collapsed(6));
+        assert!(!compression.is_collapsed(7));
+        assert_eq!(4, compression.get_compressed_graph().get_num_edges());
+        assert_eq!(8, compression.get_compressed_graph().get_num_nodes());
+
+        let fast_graph = crate::prepare(compression.get_compressed_graph());
+        let mut calc = crate::create_calculator(&fast_graph);
+        let mut path = |s, t| compression.calc_path(&mut calc, &fast_graph, s, t);
+        assert_path(path(0, 7), 14, vec![0, 1, 2, 6, 7]);
+        assert_path(path(1, 7), 13, vec![1, 2, 6, 7]);
+        assert_path(path(1, 2), 2, vec![1, 2]);
+        assert_path(path(2, 1), 2, vec![2, 1]);
+        assert_path(path(2, 0), 3, vec![2, 1, 0]);
+        assert_path(path(4, 2), 16, vec![4, 5, 6, 2]);
+        assert_path(path(3, 5), 11, vec![3, 4, 5]);
+        assert_path(path(5, 3), 17, vec![5, 6, 2, 1, 0, 3]);
+        assert_path(path(7, 7), 0, vec![7]);
+        assert!(path(7, 0).is_none());
+        assert!(path(7, 4).is_none());
+    }
+
+    #[test]
+    fn ring() {
+        // 0 -> 1 -> 2 -> 0
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 2);
+        g.add_edge(2, 0, 3);
+        g.freeze();
+        let compression = ChainCompression::new(&g);
+        assert_eq!(1, compression.get_num_collapsed_nodes());
+        let fast_graph = crate::prepare(compression.get_compressed_graph());
+        let mut calc = crate::create_calculator(&fast_graph);
+        let mut path = |s, t| compression.calc_path(&mut calc, &fast_graph, s, t);
+        assert_path(path(0, 2), 3, vec![0, 1, 2]);
+        assert_path(path(1, 0), 5, vec![1, 2, 0]);
+        assert_path(path(2, 1), 4, vec![2, 0, 1]);
+        assert_path(path(1, 1), 0, vec![1]);
+    }
+
+    #[test]
+    fn loop_through_chain() {
+        // 4 -> 3 -> 1 -> 0 -> 5
+        //      ^    |    |
+        //      2 <--/    |
+        //      ^---------/
+        let mut g = InputGraph::new();
+        g.add_edge(4, 3, 1);
+        g.add_edge(3, 1, 14);
+        g.add_edge(1, 2, 29);
+        g.add_edge(2, 3, 48);
+        g.add_edge(1, 0, 76);
+        g.add_edge(0, 1, 45);
+        g.add_edge(0, 5, 1);
+        g.freeze();
+        let compression = ChainCompression::new(&g);
+        assert!(compression.is_collapsed(1));
+        assert!(compression.is_collapsed(2));
+        let fast_graph = crate::prepare(compression.get_compressed_graph());
+        let mut calc = crate::create_calculator(&fast_graph);
+        let mut path = |s, t| compression.calc_path(&mut calc, &fast_graph, s, t);
+        // 2 can only be reached from 3 by turning around at 1
+        assert_path(path(3, 2), 43, vec![3, 1, 2]);
+        assert_path(path(4, 2), 44, vec![4, 3, 1, 2]);
+        assert_path(path(2, 1), 62, vec![2, 3, 1]);
+        assert_path(path(2, 0), 138, vec![2, 3, 1, 0]);
+        assert_path(path(0, 3), 122, vec![0, 1, 2, 3]);
+    }
+
+    fn assert_path(path: Option<ShortestPath>, weight: Weight, nodes: Vec<NodeId>) {
+        let path = path.unwrap();
+        assert_eq!(weight, path.get_weight());
+        assert_eq!(&nodes, path.get_nodes());
+    }
+}
@@ -0,0 +1,349 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+//! Preparing graphs that are too large for a `PreparationGraph` one cell at a time, see
+//! `prepare_chunked`.
+
+use std::cmp::{max, Reverse};
+use std::collections::BTreeSet;
+
+use priority_queue::PriorityQueue;
+
+use crate::constants::{NodeId, Weight, INVALID_NODE};
+use crate::fast_graph::FastGraph;
+use crate::fast_graph_builder::{FastGraphBuilder, Params};
+use crate::input_graph::Edge;
+use crate::node_contractor;
+use crate::preparation_graph::PreparationGraph;
+use crate::preparation_info::PreparationParams;
+use crate::witness_search::WitnessSearch;
+
+/// See `prepare_chunked`.
+pub fn build_chunked<F>(
+    num_nodes: usize,
+    cells: &[usize],
+    mut load_cell_edges: F,
+    params: &Params,
+) -> Result<FastGraph, String>
+where
+    F: FnMut(usize) -> Result<Vec<Edge>, String>,
+{
+    if num_nodes != cells.len() {
+        return Err(String::from("There must be one cell for every node"));
+    }
+    let num_cells = cells.iter().max().map_or(0, |cell| cell + 1);
+    // the nodes sorted by cell, such that nodes[first_nodes[c]..first_nodes[c + 1]] are the nodes
+    // of cell c
+    let mut first_nodes = vec![0; num_cells + 1];
+    for cell in cells {
+        first_nodes[cell + 1] += 1;
+    }
+    for cell in 0..num_cells {
+        first_nodes[cell + 1] += first_nodes[cell];
+    }
+    let mut nodes = vec![INVALID_NODE; num_nodes];
+    let mut next = first_nodes.clone();
+    for (node, cell) in cells.iter().enumerate() {
+        nodes[next[*cell]] = node;
+        next[*cell] += 1;
+    }
+
+    let mut builder = FastGraphBuilder::with_num_nodes(num_nodes);
+    let mut rank = 0;
+    let mut local_ids = vec![INVALID_NODE; num_nodes];
+    let mut is_boundary = vec![false; num_nodes];
+    let mut cut_edges = vec![];
+    // the edges between the boundary nodes of each cell that remain after contracting its inner
+    // nodes, as (from, to, weight, center node, protected), see Params::approximation_epsilon
+    let mut boundary_edges = vec![];
+    for cell in 0..num_cells {
+        let cell_nodes = &nodes[first_nodes[cell]..first_nodes[cell + 1]];
+        for (local_id, node) in cell_nodes.iter().enumerate() {
+            local_ids[*node] = local_id;
+        }
+        let mut graph = PreparationGraph::new(cell_nodes.len());
+        for edge in load_cell_edges(cell)? {
+            if edge.from >= num_nodes || edge.to >= num_nodes {
+                return Err(format!(
+                    "Invalid edge {} -> {} for cell {}, there are only {} nodes",
+                    edge.from, edge.to, cell, num_nodes
+                ));
+            }
+            let from_cell = cells[edge.from];
+            let to_cell = cells[edge.to];
+            if from_cell == cell && to_cell == cell {
+                if edge.from != edge.to {
+                    graph.add_or_reduce_edge(
+                        local_ids[edge.from],
+                        local_ids[edge.to],
+                        edge.weight,
+                        INVALID_NODE,
+                    );
+                }
+            } else if from_cell == cell {
+                is_boundary[edge.from] = true;
+                // the same edge is also loaded for the other cell, but we only keep it once
+                cut_edges.push(edge);
+            } else if to_cell == cell {
+                is_boundary[edge.to] = true;
+            } else {
+                return Err(format!(
+                    "The edge {} -> {} was loaded for cell {}, but it does not start or end there",
+                    edge.from, edge.to, cell
+                ));
+            }
+        }
+        let contract: Vec<bool> = cell_nodes.iter().map(|node| !is_boundary[*node]).collect();
+        contract_nodes(
+            &mut builder,
+            &mut graph,
+            &contract,
+            cell_nodes,
+            params,
+            &mut rank,
+        );
+        for (local_id, node) in cell_nodes.iter().enumerate() {
+            for edge in graph.get_out_edges(local_id) {
+                boundary_edges.push((
+                    *node,
+                    cell_nodes[edge.adj_node],
+                    edge.weight,
+                    if edge.center_node == INVALID_NODE {
+                        INVALID_NODE
+                    } else {
+                        cell_nodes[edge.center_node]
+                    },
+                    graph.is_protected(local_id, edge.adj_node),
+                ));
+            }
+        }
+        debug!(
+            "contracted the inner nodes of cell {} / {}, rank: {}, boundary edges: {}",
+            cell + 1,
+            num_cells,
+            rank,
+            boundary_edges.len()
+        );
+    }
+
+    // the overlay graph consists of the boundary nodes. the ids of the inner nodes that are the
+    // center nodes of the boundary edges are added after them, so the shortcuts can be translated
+    // back to their original center nodes
+    let mut ids: Vec<NodeId> = (0..num_nodes).filter(|node| is_boundary[*node]).collect();
+    let num_boundary_nodes = ids.len();
+    for (local_id, node) in ids.iter().enumerate() {
+        local_ids[*node] = local_id;
+    }
+    let mut graph = PreparationGraph::new(num_boundary_nodes);
+    for edge in cut_edges {
+        graph.add_or_reduce_edge(
+            local_ids[edge.from],
+            local_ids[edge.to],
+            edge.weight,
+            INVALID_NODE,
+        );
+    }
+    for (from, to, weight, center_node, protected) in boundary_edges {
+        let center_node = if center_node == INVALID_NODE {
+            INVALID_NODE
+        } else {
+            ids.push(center_node);
+            ids.len() - 1
+        };
+        graph.add_or_reduce_edge(local_ids[from], local_ids[to], weight, center_node);
+        if protected {
+            graph.protect_arc(local_ids[from], local_ids[to]);
+        }
+    }
+    contract_nodes(
+        &mut builder,
+        &mut graph,
+        &vec![true; num_boundary_nodes],
+        &ids,
+        params,
+        &mut rank,
+    );
+    builder.finish_contraction();
+    Ok(builder.finish(None, PreparationParams::Heuristic(params.clone())))
+}
+
+/// Contracts the nodes of the given graph that are marked in `contract`, in the order of their
+/// priorities, and adds their edges to the fast graph, see `FastGraphBuilder::add_edges_with_ids`.
+fn contract_nodes(
+    builder: &mut FastGraphBuilder,
+    graph: &mut PreparationGraph,
+    contract: &[bool],
+    ids: &[NodeId],
+    params: &Params,
+    rank: &mut usize,
+) {
+    let num_nodes = graph.get_num_nodes();
+    let mut witness_search = WitnessSearch::with_max_hops(num_nodes, params.max_witness_hops);
+    let mut levels = vec![0; num_nodes];
+    let mut queue = PriorityQueue::new();
+    for node in (0..num_nodes).filter(|node| contract[*node]) {
+        let priority = node_contractor::calc_relevance(
+            graph,
+            params,
+            &mut witness_search,
+            node,
+            0,
+            params.max_settled_nodes_initial_relevance,
+        ) as Weight;
+        queue.push(node, Reverse(priority));
+    }
+    while let Some((node, _)) = queue.pop() {
+        builder.add_edges_with_ids(graph, node, *rank, ids);
+        let neighbors: BTreeSet<NodeId> = graph.out_edges[node]
+            .iter()
+            .chain(graph.in_edges[node].iter())
+            .map(|edge| edge.adj_node)
+            .collect();
+        node_contractor::contract_node_with_params(graph, &mut witness_search, node, params);
+        for neighbor in neighbors {
+            levels[neighbor] = max(levels[neighbor], levels[node] + 1);
+            if !contract[neighbor] {
+                continue;
+            }
+            let priority = node_contractor::calc_relevance(
+                graph,
+                params,
+                &mut witness_search,
+                neighbor,
+                levels[neighbor],
+                params.max_settled_nodes_neighbor_relevance,
+            ) as Weight;
+            queue.change_priority(&neighbor, Reverse(priority));
+        }
+        *rank += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::floyd_warshall::FloydWarshall;
+    use crate::input_graph::InputGraph;
+    use crate::{calc_path, calc_weight, WEIGHT_MAX};
+
+    use super::*;
+
+    #[test]
+    fn prepare_cell_by_cell() {
+        // 0 -> 1 -> 2 | 3 -> 4
+        // |         | | |    |
+        // 5 <- 6 <- 7 | 8 <- 9
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 1);
+        g.add_edge_bidir(2, 3, 5);
+        g.add_edge(3, 4, 1);
+        g.add_edge(0, 5, 2);
+        g.add_edge(7, 2, 1);
+        g.add_edge(7, 6, 1);
+        g.add_edge(6, 5, 1);
+        g.add_edge(3, 8, 1);
+        g.add_edge(4, 9, 1);
+        g.add_edge(9, 8, 1);
+        g.add_edge(8, 7, 7);
+        g.freeze();
+        let cells = [0, 0, 0, 1, 1, 0, 0, 0, 1, 1];
+        let mut loaded = vec![];
+        let fast_graph = build_chunked(
+            10,
+            &cells,
+            |cell| {
+                loaded.push(cell);
+                Ok(g.get_edges()
+                    .iter()
+                    .filter(|e| cells[e.from] == cell || cells[e.to] == cell)
+                    .cloned()
+                    .collect())
+            },
+            &Params::default(),
+        )
+        .unwrap();
+        assert_eq!(vec![0, 1], loaded);
+        fast_graph.validate().unwrap();
+        // the boundary nodes are contracted last
+        let ranks = fast_graph.get_ranks();
+        for node in [2, 3, 7, 8] {
+            assert!(ranks[node] >= 6);
+        }
+        let path = calc_path(&fast_graph, 4, 5).unwrap();
+        assert_eq!(&vec![4, 9, 8, 7, 6, 5], path.get_nodes());
+        assert_eq!(Some(9), calc_weight(&fast_graph, 3, 6));
+        assert_eq!(None, calc_weight(&fast_graph, 5, 0));
+    }
+
+    #[test]
+    fn prepare_random_graph_cell_by_cell() {
+        use rand::Rng;
+        let mut rng = crate::tests::create_rng();
+        for _ in 0..20 {
+            let g = InputGraph::random(|| rng.gen(), 50, 2.0);
+            let num_nodes = g.get_num_nodes();
+            let num_cells = rng.gen_range(1, 6);
+            let cells: Vec<usize> = (0..num_nodes)
+                .map(|_| rng.gen_range(0, num_cells))
+                .collect();
+            let fast_graph = build_chunked(
+                num_nodes,
+                &cells,
+                |cell| {
+                    Ok(g.get_edges()
+                        .iter()
+                        .filter(|e| cells[e.from] == cell || cells[e.to] == cell)
+                        .cloned()
+                        .collect())
+                },
+                &Params::default(),
+            )
+            .unwrap();
+            fast_graph.validate().unwrap();
+            let mut fw = FloydWarshall::new(num_nodes);
+            fw.prepare(&g);
+            for source in 0..num_nodes {
+                for target in 0..num_nodes {
+                    assert_eq!(
+                        fw.calc_weight(source, target),
+                        calc_weight(&fast_graph, source, target).unwrap_or(WEIGHT_MAX),
+                        "{:?}",
+                        g
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reject_invalid_edges() {
+        let load = |cell: usize| Ok(vec![Edge::new(cell, 2, 1)]);
+        assert!(build_chunked(3, &[0, 1], load, &Params::default()).is_err());
+        // the edge 1 -> 2 is loaded for cell 1, but it belongs to cells 0 and 2
+        let error = build_chunked(3, &[0, 0, 2], load, &Params::default()).unwrap_err();
+        assert!(error.contains("does not start or end there"), "{}", error);
+        let load = |_| Ok(vec![Edge::new(0, 5, 1)]);
+        assert!(build_chunked(3, &[0, 0, 0], load, &Params::default()).is_err());
+        let load = |_| Err(String::from("could not read cell"));
+        assert_eq!(
+            Err(String::from("could not read cell")),
+            build_chunked(3, &[0, 0, 0], load, &Params::default()).map(|_| ())
+        );
+    }
+}
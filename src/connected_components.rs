@@ -0,0 +1,219 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+use std::cmp::Reverse;
+
+use crate::constants::{NodeId, INVALID_NODE};
+use crate::input_graph::InputGraph;
+
+/// Finds the strongly connected components of the given graph using Tarjan's algorithm, see
+/// `InputGraph::strongly_connected_components`. The recursion is replaced by an explicit stack,
+/// so this also works for large graphs.
+pub(crate) fn find_strongly_connected_components(input_graph: &InputGraph) -> Vec<Vec<NodeId>> {
+    let num_nodes = input_graph.get_num_nodes();
+    let edges = input_graph.get_edges();
+    // the edges are sorted by their source node, so the out edges of node n are
+    // edges[first_edges[n]..first_edges[n + 1]]
+    let mut first_edges = vec![0; num_nodes + 1];
+    for edge in edges {
+        first_edges[edge.from + 1] += 1;
+    }
+    for node in 0..num_nodes {
+        first_edges[node + 1] += first_edges[node];
+    }
+
+    let mut tarjan = Tarjan {
+        indices: vec![INVALID_NODE; num_nodes],
+        low_links: vec![0; num_nodes],
+        on_stack: vec![false; num_nodes],
+        stack: vec![],
+        call_stack: vec![],
+        next_index: 0,
+    };
+    let mut components = vec![];
+    for root in 0..num_nodes {
+        if tarjan.indices[root] != INVALID_NODE {
+            continue;
+        }
+        tarjan.visit(root, first_edges[root]);
+        while let Some((node, edge)) = tarjan.call_stack.last_mut() {
+            let node = *node;
+            if *edge < first_edges[node + 1] {
+                let to = edges[*edge].to;
+                *edge += 1;
+                if tarjan.indices[to] == INVALID_NODE {
+                    tarjan.visit(to, first_edges[to]);
+                } else if tarjan.on_stack[to] {
+                    tarjan.low_links[node] = tarjan.low_links[node].min(tarjan.indices[to]);
+                }
+                continue;
+            }
+            tarjan.call_stack.pop();
+            if let Some((parent, _)) = tarjan.call_stack.last() {
+                let parent = *parent;
+                tarjan.low_links[parent] = tarjan.low_links[parent].min(tarjan.low_links[node]);
+            }
+            if tarjan.low_links[node] == tarjan.indices[node] {
+                components.push(tarjan.pop_component(node));
+            }
+        }
+    }
+    components.sort_by_key(|component| (Reverse(component.len()), component[0]));
+    components
+}
+
+struct Tarjan {
+    indices: Vec<usize>,
+    low_links: Vec<usize>,
+    on_stack: Vec<bool>,
+    stack: Vec<NodeId>,
+    /// the nodes whose out edges are being explored, along with the position of the next edge
+    call_stack: Vec<(NodeId, usize)>,
+    next_index: usize,
+}
+
+impl Tarjan {
+    fn visit(&mut self, node: NodeId, first_edge: usize) {
+        self.indices[node] = self.next_index;
+        self.low_links[node] = self.next_index;
+        self.next_index += 1;
+        self.stack.push(node);
+        self.on_stack[node] = true;
+        self.call_stack.push((node, first_edge));
+    }
+
+    /// Removes the nodes up to and including `root` from the stack and returns them sorted
+    fn pop_component(&mut self, root: NodeId) -> Vec<NodeId> {
+        let mut component = vec![];
+        loop {
+            let node = self.stack.pop().unwrap();
+            self.on_stack[node] = false;
+            component.push(node);
+            if node == root {
+                break;
+            }
+        }
+        component.sort_unstable();
+        component
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn disconnected() {
+        let g = fixtures::disconnected();
+        assert_eq!(
+            vec![vec![0, 1, 2], vec![3], vec![4], vec![5]],
+            find_strongly_connected_components(&g)
+        );
+    }
+
+    #[test]
+    fn isolated_nodes() {
+        let g = fixtures::isolated_nodes();
+        let components = find_strongly_connected_components(&g);
+        assert_eq!(vec![2, 5, 7], components[0]);
+        assert_eq!(8, components.len());
+        assert!(components[1..].iter().all(|c| c.len() == 1));
+    }
+
+    #[test]
+    fn nested_cycles() {
+        // 0 -> 1 -> 2 -> 0 -> 3 -> 4 -> 5 -> 3, 4 -> 6 -> 7 -> 6, 2 -> 4
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 1);
+        g.add_edge(2, 0, 1);
+        g.add_edge(0, 3, 1);
+        g.add_edge(3, 4, 1);
+        g.add_edge(4, 5, 1);
+        g.add_edge(5, 3, 1);
+        g.add_edge(4, 6, 1);
+        g.add_edge(6, 7, 1);
+        g.add_edge(7, 6, 1);
+        g.add_edge(2, 4, 1);
+        g.freeze();
+        assert_eq!(
+            vec![vec![0, 1, 2], vec![3, 4, 5], vec![6, 7]],
+            find_strongly_connected_components(&g)
+        );
+    }
+
+    #[test]
+    fn random_graphs() {
+        use rand::rngs::StdRng;
+        use rand::Rng;
+        use rand::SeedableRng;
+
+        let mut rng: StdRng = SeedableRng::from_seed([7; 32]);
+        for _ in 0..20 {
+            let g = InputGraph::random(|| rng.gen(), 50, 1.2);
+            let components = find_strongly_connected_components(&g);
+            let mut component_ids = vec![INVALID_NODE; g.get_num_nodes()];
+            for (id, component) in components.iter().enumerate() {
+                for node in component {
+                    assert_eq!(INVALID_NODE, component_ids[*node]);
+                    component_ids[*node] = id;
+                }
+            }
+            let reachable: Vec<Vec<bool>> = (0..g.get_num_nodes())
+                .map(|node| find_reachable(&g, node))
+                .collect();
+            for a in 0..g.get_num_nodes() {
+                for b in 0..g.get_num_nodes() {
+                    assert_eq!(
+                        reachable[a][b] && reachable[b][a],
+                        component_ids[a] == component_ids[b]
+                    );
+                }
+            }
+        }
+    }
+
+    fn find_reachable(g: &InputGraph, source: NodeId) -> Vec<bool> {
+        let mut reachable = vec![false; g.get_num_nodes()];
+        reachable[source] = true;
+        let mut stack = vec![source];
+        while let Some(node) = stack.pop() {
+            for edge in g.get_edges().iter().filter(|e| e.from == node) {
+                if !reachable[edge.to] {
+                    reachable[edge.to] = true;
+                    stack.push(edge.to);
+                }
+            }
+        }
+        reachable
+    }
+
+    #[test]
+    fn long_chain() {
+        // this would overflow the stack if the search was recursive
+        let mut g = InputGraph::new();
+        for i in 0..1_000_000 {
+            g.add_edge_bidir(i, i + 1, 1);
+        }
+        g.freeze();
+        let components = find_strongly_connected_components(&g);
+        assert_eq!(1, components.len());
+        assert_eq!(1_000_001, components[0].len());
+    }
+}
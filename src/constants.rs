@@ -20,8 +20,12 @@
 pub type NodeId = usize;
 pub type EdgeId = usize;
 pub type Weight = usize;
+/// A key that identifies an edge outside of this crate, e.g. the primary key of a database row,
+/// see `InputGraph::add_edge_with_key`
+pub type EdgeKey = u64;
 
-pub const INVALID_NODE: NodeId = std::usize::MAX;
-pub const INVALID_EDGE: EdgeId = std::usize::MAX;
-pub const WEIGHT_MAX: Weight = std::usize::MAX;
+pub const INVALID_NODE: NodeId = usize::MAX;
+pub const INVALID_EDGE: EdgeId = usize::MAX;
+pub const INVALID_EDGE_KEY: EdgeKey = u64::MAX;
+pub const WEIGHT_MAX: Weight = usize::MAX;
 pub const WEIGHT_ZERO: Weight = 0;
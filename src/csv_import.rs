@@ -0,0 +1,351 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use crate::constants::{EdgeKey, NodeId, Weight, INVALID_EDGE_KEY};
+use crate::error::ParseError;
+use crate::input_graph::InputGraph;
+
+/// A column of a CSV file, given either by its name in the header line or by its zero-based
+/// position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CsvColumn {
+    Name(String),
+    Index(usize),
+}
+
+impl From<&str> for CsvColumn {
+    fn from(name: &str) -> Self {
+        CsvColumn::Name(name.to_string())
+    }
+}
+
+impl From<usize> for CsvColumn {
+    fn from(index: usize) -> Self {
+        CsvColumn::Index(index)
+    }
+}
+
+/// Describes the layout of a CSV file for `InputGraph::from_csv`. By default the fields are
+/// separated by commas, the first line is a header, and the edges are read from the columns named
+/// `from`, `to` and `weight`. Fields can be quoted using double quotes.
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    delimiter: char,
+    has_header: bool,
+    from: CsvColumn,
+    to: CsvColumn,
+    weight: CsvColumn,
+    key: Option<CsvColumn>,
+    skip_invalid_edges: bool,
+}
+
+impl CsvOptions {
+    pub fn new() -> Self {
+        CsvOptions {
+            delimiter: ',',
+            has_header: true,
+            from: CsvColumn::from("from"),
+            to: CsvColumn::from("to"),
+            weight: CsvColumn::from("weight"),
+            key: None,
+            skip_invalid_edges: false,
+        }
+    }
+
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Sets whether the first line is a header. Without a header the columns must be given by
+    /// their index.
+    pub fn has_header(mut self, has_header: bool) -> Self {
+        self.has_header = has_header;
+        self
+    }
+
+    pub fn from_column<C: Into<CsvColumn>>(mut self, column: C) -> Self {
+        self.from = column.into();
+        self
+    }
+
+    pub fn to_column<C: Into<CsvColumn>>(mut self, column: C) -> Self {
+        self.to = column.into();
+        self
+    }
+
+    pub fn weight_column<C: Into<CsvColumn>>(mut self, column: C) -> Self {
+        self.weight = column.into();
+        self
+    }
+
+    /// Sets the column that contains the id of each edge, which is stored as its key, see
+    /// `InputGraph::add_edge_with_key`.
+    pub fn key_column<C: Into<CsvColumn>>(mut self, column: C) -> Self {
+        self.key = Some(column.into());
+        self
+    }
+
+    /// By default reading the file fails for loops and edges with zero weight (see
+    /// `InputGraph::try_add_edge`). If this is set they are skipped with a warning instead.
+    pub fn skip_invalid_edges(mut self, skip_invalid_edges: bool) -> Self {
+        self.skip_invalid_edges = skip_invalid_edges;
+        self
+    }
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub(crate) fn read_csv_file(
+    filename: &str,
+    options: &CsvOptions,
+) -> Result<InputGraph, ParseError> {
+    let file = File::open(filename)?;
+    read_csv(BufReader::new(file), options)
+}
+
+pub(crate) fn read_csv<R: BufRead>(
+    reader: R,
+    options: &CsvOptions,
+) -> Result<InputGraph, ParseError> {
+    let mut g = InputGraph::new();
+    let mut columns: Option<[Option<usize>; 4]> = None;
+    if !options.has_header {
+        columns =
+            Some(find_columns(options, &[]).map_err(|reason| ParseError::InvalidFile { reason })?);
+    }
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let error = |reason: String| ParseError::InvalidLine {
+            line_number: index + 1,
+            line: line.clone(),
+            reason,
+        };
+        let fields = split_line(&line, options.delimiter).map_err(error)?;
+        let columns = match columns {
+            Some(columns) => columns,
+            None => {
+                columns = Some(
+                    find_columns(options, &fields)
+                        .map_err(|reason| ParseError::InvalidFile { reason })?,
+                );
+                continue;
+            }
+        };
+        let field = |column: usize| {
+            fields
+                .get(column)
+                .ok_or_else(|| error(format!("missing column {}", column)))
+        };
+        let parse = |column: usize, name: &str| {
+            field(column)?
+                .parse::<u64>()
+                .map_err(|_| error(format!("invalid {}: '{}'", name, fields[column])))
+        };
+        let from = parse(columns[0].unwrap(), "from node")? as NodeId;
+        let to = parse(columns[1].unwrap(), "to node")? as NodeId;
+        let weight = parse(columns[2].unwrap(), "weight")? as Weight;
+        let key = match columns[3] {
+            Some(column) => parse(column, "key")? as EdgeKey,
+            None => INVALID_EDGE_KEY,
+        };
+        if options.skip_invalid_edges {
+            g.add_edge_with_key(from, to, weight, key);
+        } else {
+            g.try_add_edge_with_key(from, to, weight, key)
+                .map_err(|e| error(e.to_string()))?;
+        }
+    }
+    g.freeze();
+    Ok(g)
+}
+
+/// Returns the positions of the from, to, weight and key columns.
+fn find_columns(options: &CsvOptions, header: &[String]) -> Result<[Option<usize>; 4], String> {
+    let find = |column: &CsvColumn| match column {
+        CsvColumn::Index(index) => Ok(*index),
+        CsvColumn::Name(name) if !options.has_header => Err(format!(
+            "column '{}' can only be found by its name if there is a header",
+            name
+        )),
+        CsvColumn::Name(name) => header.iter().position(|h| h == name).ok_or_else(|| {
+            format!(
+                "column '{}' not found in header: {}",
+                name,
+                header.join(&options.delimiter.to_string())
+            )
+        }),
+    };
+    Ok([
+        Some(find(&options.from)?),
+        Some(find(&options.to)?),
+        Some(find(&options.weight)?),
+        options.key.as_ref().map(find).transpose()?,
+    ])
+}
+
+/// Splits the given line at the delimiter, except within double quotes. Quotes inside a quoted
+/// field are written as two double quotes. Whitespace around the fields is removed.
+fn split_line(line: &str, delimiter: char) -> Result<Vec<String>, String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => quoted = false,
+                _ => field.push(c),
+            }
+        } else if c == '"' && field.trim().is_empty() {
+            field.clear();
+            quoted = true;
+        } else if c == delimiter {
+            fields.push(field.trim().to_string());
+            field.clear();
+        } else {
+            field.push(c);
+        }
+    }
+    if quoted {
+        return Err(String::from("unterminated quote"));
+    }
+    fields.push(field.trim().to_string());
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::remove_file;
+    use std::io::Write;
+
+    use super::*;
+
+    fn read(csv: &str, options: &CsvOptions) -> Result<InputGraph, String> {
+        read_csv(csv.as_bytes(), options).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn default_options() {
+        let g = read(
+            "from,to,weight\n0,1,5\n\n 1 , 2 , 3 \n\"2\",\"0\",\"4\"\n",
+            &CsvOptions::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            "g.add_edge(0, 1, 5);\ng.add_edge(1, 2, 3);\ng.add_edge(2, 0, 4);\n",
+            g.unit_test_output_string()
+        );
+    }
+
+    #[test]
+    fn named_columns_and_key() {
+        let options = CsvOptions::new()
+            .delimiter(';')
+            .from_column("source")
+            .to_column("target")
+            .weight_column("cost")
+            .key_column("id");
+        let g = read(
+            "id;name;cost;target;source\n17;\"Main St; north\";5;1;0\n18;\"\"\"Side\"\" St\";3;2;1\n",
+            &options,
+        )
+        .unwrap();
+        assert_eq!(
+            "g.add_edge(0, 1, 5);\ng.add_edge(1, 2, 3);\n",
+            g.unit_test_output_string()
+        );
+        assert_eq!(Some(17), g.get_edge_key(0));
+        assert_eq!(Some(18), g.get_edge_key(1));
+    }
+
+    #[test]
+    fn column_indices_without_header() {
+        let options = CsvOptions::new()
+            .has_header(false)
+            .delimiter('\t')
+            .from_column(2)
+            .to_column(0)
+            .weight_column(1);
+        let g = read("1\t7\t0\n0\t2\t1\n", &options).unwrap();
+        assert_eq!(
+            "g.add_edge(0, 1, 7);\ng.add_edge(1, 0, 2);\n",
+            g.unit_test_output_string()
+        );
+        // names cannot be used without a header
+        let options = CsvOptions::new().has_header(false);
+        assert!(read("0,1,5\n", &options).unwrap_err().contains("header"));
+    }
+
+    #[test]
+    fn errors() {
+        let options = CsvOptions::new();
+        assert_eq!(
+            "column 'weight' not found in header: from,to,cost",
+            read("from,to,cost\n0,1,5\n", &options).unwrap_err()
+        );
+        assert_eq!(
+            "line 3: invalid weight: 'x' | 1,2,x",
+            read("from,to,weight\n0,1,5\n1,2,x\n", &options).unwrap_err()
+        );
+        assert_eq!(
+            "line 2: missing column 2 | 0,1",
+            read("from,to,weight\n0,1\n", &options).unwrap_err()
+        );
+        assert_eq!(
+            "line 2: unterminated quote | 0,\"1,5",
+            read("from,to,weight\n0,\"1,5\n", &options).unwrap_err()
+        );
+        assert!(read("from,to,weight\n0,1,-5\n", &options).is_err());
+        assert_eq!(
+            "line 3: loop edges are not allowed, but got one at node 2 | 2,2,1",
+            read("from,to,weight\n0,1,5\n2,2,1\n", &options).unwrap_err()
+        );
+        let options = CsvOptions::new().skip_invalid_edges(true);
+        let g = read("from,to,weight\n0,1,5\n2,2,1\n1,0,0\n", &options).unwrap();
+        assert_eq!(1, g.get_num_edges());
+    }
+
+    #[test]
+    fn read_file() {
+        let filename = "csv_import_test.csv";
+        let mut f = File::create(filename).unwrap();
+        writeln!(f, "from,to,weight\n0,1,5\n1,2,3").unwrap();
+        let g = InputGraph::from_csv(filename, &CsvOptions::default());
+        remove_file(filename).unwrap();
+        assert_eq!(2, g.unwrap().get_num_edges());
+        assert!(matches!(
+            InputGraph::from_csv("does_not_exist.csv", &CsvOptions::default()),
+            Err(ParseError::Io(_))
+        ));
+    }
+}
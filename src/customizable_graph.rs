@@ -0,0 +1,321 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{EdgeId, NodeId, INVALID_EDGE, INVALID_NODE, WEIGHT_MAX};
+use crate::error::CustomizationError;
+use crate::fast_graph::{FastGraph, FastGraphEdge};
+use crate::input_graph::InputGraph;
+use crate::order_validation::{self, OrderError};
+use crate::preparation_info::{PreparationInfo, PreparationParams};
+
+/// The shortcuts of a contraction hierarchy for a fixed node ordering, independent of the edge
+/// weights (customizable contraction hierarchy). Contracting a node connects all of its neighbors
+/// with higher rank, no matter whether there is a shorter path between them, so the shortcuts
+/// only have to be calculated once. After the weights changed, `customize` calculates the weights
+/// of all shortcuts without any witness searches, which is much faster than `prepare_with_order`.
+///
+/// The resulting graphs usually have more shortcuts than those of `prepare_with_order`. How many
+/// depends a lot on the node ordering, e.g. one obtained using `FastGraph::get_node_ordering`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CustomizableGraph {
+    ranks: Vec<usize>,
+    order: Vec<NodeId>,
+    /// the arcs of the node with rank `r` lead to the nodes with the ranks
+    /// `upper_ranks[first_arc[r]..first_arc[r + 1]]`, sorted by rank. every arc stands for an edge
+    /// in both directions.
+    first_arc: Vec<usize>,
+    upper_ranks: Vec<usize>,
+}
+
+impl CustomizableGraph {
+    /// Calculates the shortcuts for the given graph and node ordering, which has to be a
+    /// permutation of the node ids, otherwise an `OrderError` is returned. Only the edges of the
+    /// graph are used, not their weights.
+    pub fn new(input_graph: &InputGraph, order: &[NodeId]) -> Result<Self, OrderError> {
+        let num_nodes = input_graph.get_num_nodes();
+        order_validation::validate_order(order, num_nodes)?;
+        let mut ranks = vec![INVALID_NODE; num_nodes];
+        for (rank, node) in order.iter().enumerate() {
+            ranks[*node] = rank;
+        }
+        let mut neighbors: Vec<Vec<usize>> = vec![vec![]; num_nodes];
+        for edge in input_graph.get_edges() {
+            let (a, b) = (ranks[edge.from], ranks[edge.to]);
+            neighbors[a.min(b)].push(a.max(b));
+        }
+        let mut first_arc = vec![0; num_nodes + 1];
+        let mut upper_ranks = vec![];
+        for rank in 0..num_nodes {
+            let mut upper = std::mem::take(&mut neighbors[rank]);
+            upper.sort_unstable();
+            upper.dedup();
+            // contracting the node connects all its upper neighbors. it is enough to connect the
+            // lowest of them to the others, because it is the next one to be contracted, which
+            // connects the others as well.
+            if let Some((lowest, others)) = upper.split_first() {
+                neighbors[*lowest].extend_from_slice(others);
+            }
+            upper_ranks.extend_from_slice(&upper);
+            first_arc[rank + 1] = upper_ranks.len();
+        }
+        Ok(CustomizableGraph {
+            ranks,
+            order: order.to_vec(),
+            first_arc,
+            upper_ranks,
+        })
+    }
+
+    /// Calculates the weights of all shortcuts for the weights of the given graph and returns
+    /// the resulting contraction hierarchy. The graph may only contain edges of the graph this
+    /// was created for, but their weights can be different, otherwise a `CustomizationError` is
+    /// returned. Shortcuts that do not stand for any path are left out.
+    pub fn customize(&self, input_graph: &InputGraph) -> Result<FastGraph, CustomizationError> {
+        let num_nodes = self.ranks.len();
+        if input_graph.get_num_nodes() > num_nodes {
+            return Err(CustomizationError::TooManyNodes {
+                num_nodes: input_graph.get_num_nodes(),
+                max_nodes: num_nodes,
+            });
+        }
+        let num_arcs = self.upper_ranks.len();
+        // the weights from the lower to the upper node of every arc and the other way round
+        let mut up = vec![WEIGHT_MAX; num_arcs];
+        let mut down = vec![WEIGHT_MAX; num_arcs];
+        // the (in, out) arcs at the center node of shortcuts
+        let mut up_center = vec![None; num_arcs];
+        let mut down_center = vec![None; num_arcs];
+        // the ids of the input edges of the arcs that are not shortcuts
+        let mut up_input = vec![INVALID_EDGE; num_arcs];
+        let mut down_input = vec![INVALID_EDGE; num_arcs];
+        for (id, edge) in input_graph.get_edges().iter().enumerate() {
+            let (a, b) = (self.ranks[edge.from], self.ranks[edge.to]);
+            let arc = self
+                .find_arc(a.min(b), a.max(b))
+                .ok_or(CustomizationError::UnknownEdge {
+                    from: edge.from,
+                    to: edge.to,
+                })?;
+            let (weights, input) = if a < b {
+                (&mut up, &mut up_input)
+            } else {
+                (&mut down, &mut down_input)
+            };
+            if edge.weight < weights[arc] {
+                weights[arc] = edge.weight;
+                input[arc] = id;
+            }
+        }
+        // the lower triangles of an arc are finished once we get to its lower node
+        for rank in 0..num_nodes {
+            let arcs = self.first_arc[rank]..self.first_arc[rank + 1];
+            for i in arcs.clone() {
+                for j in i + 1..arcs.end {
+                    let arc = self
+                        .find_arc(self.upper_ranks[i], self.upper_ranks[j])
+                        .expect("the upper neighbors of every node are connected");
+                    let weight = down[i].saturating_add(up[j]);
+                    if weight < up[arc] {
+                        up[arc] = weight;
+                        up_center[arc] = Some((i, j));
+                    }
+                    let weight = down[j].saturating_add(up[i]);
+                    if weight < down[arc] {
+                        down[arc] = weight;
+                        down_center[arc] = Some((j, i));
+                    }
+                }
+            }
+        }
+        let fwd_ids = assign_edge_ids(&up);
+        let bwd_ids = assign_edge_ids(&down);
+        let build_edges =
+            |weights: &[usize], centers: &[Option<(usize, usize)>], input: &[EdgeId]| {
+                let mut edges = vec![];
+                let mut first_edge_ids = vec![0];
+                for rank in 0..num_nodes {
+                    for arc in self.first_arc[rank]..self.first_arc[rank + 1] {
+                        if weights[arc] == WEIGHT_MAX {
+                            continue;
+                        }
+                        let edge = match centers[arc] {
+                            Some((i, o)) => FastGraphEdge::new(
+                                self.order[rank],
+                                self.order[self.upper_ranks[arc]],
+                                weights[arc],
+                                bwd_ids[i],
+                                fwd_ids[o],
+                            ),
+                            None => FastGraphEdge::new(
+                                self.order[rank],
+                                self.order[self.upper_ranks[arc]],
+                                weights[arc],
+                                INVALID_EDGE,
+                                INVALID_EDGE,
+                            )
+                            .with_input_edge_id(input[arc]),
+                        };
+                        edges.push(edge);
+                    }
+                    first_edge_ids.push(edges.len());
+                }
+                (edges, first_edge_ids)
+            };
+        let (edges_fwd, first_edge_ids_fwd) = build_edges(&up, &up_center, &up_input);
+        let (edges_bwd, first_edge_ids_bwd) = build_edges(&down, &down_center, &down_input);
+        let mut fast_graph = FastGraph::from_parts(
+            self.ranks.clone(),
+            edges_fwd,
+            first_edge_ids_fwd,
+            edges_bwd,
+            first_edge_ids_bwd,
+        )
+        // the edges are built from the arcs, so they are always consistent with the ranks
+        .unwrap_or_else(|e| panic!("the customized graph is invalid: {}", e));
+        let mut preparation_info = PreparationInfo::new(PreparationParams::Customized);
+        preparation_info.set_weight_unit(input_graph.get_weight_unit());
+        fast_graph.preparation_info = preparation_info;
+        Ok(fast_graph)
+    }
+
+    pub fn get_num_nodes(&self) -> usize {
+        self.ranks.len()
+    }
+
+    /// Returns the number of edges between the nodes, including the shortcuts. Every arc can be
+    /// used in both directions.
+    pub fn get_num_arcs(&self) -> usize {
+        self.upper_ranks.len()
+    }
+
+    fn find_arc(&self, lower_rank: usize, upper_rank: usize) -> Option<usize> {
+        let begin = self.first_arc[lower_rank];
+        let end = self.first_arc[lower_rank + 1];
+        self.upper_ranks[begin..end]
+            .binary_search(&upper_rank)
+            .ok()
+            .map(|i| begin + i)
+    }
+}
+
+/// Numbers the arcs with finite weights consecutively.
+fn assign_edge_ids(weights: &[usize]) -> Vec<EdgeId> {
+    let mut next = 0;
+    weights
+        .iter()
+        .map(|weight| {
+            if *weight == WEIGHT_MAX {
+                INVALID_EDGE
+            } else {
+                next += 1;
+                next - 1
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::calc_path;
+
+    use super::*;
+
+    #[test]
+    fn customize() {
+        // 0 -> 1 -> 2
+        // |         |
+        // 3 ------- 4
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 1);
+        g.add_edge(0, 3, 1);
+        g.add_edge_bidir(3, 4, 1);
+        g.add_edge(4, 2, 1);
+        g.freeze();
+        let customizable_graph = CustomizableGraph::new(&g, &[1, 3, 0, 4, 2]).unwrap();
+        // contracting 1 connects 0 and 2, contracting 3 connects 0 and 4
+        assert_eq!(7, customizable_graph.get_num_arcs());
+        let fast_graph = customizable_graph.customize(&g).unwrap();
+        fast_graph.validate().unwrap();
+        assert_eq!(
+            &PreparationParams::Customized,
+            fast_graph.get_preparation_info().get_params()
+        );
+        let path = calc_path(&fast_graph, 0, 2).unwrap();
+        assert_eq!(&vec![0, 1, 2], path.get_nodes());
+        assert_eq!(
+            Some(2),
+            calc_path(&fast_graph, 3, 2).map(|p| p.get_weight())
+        );
+        assert!(calc_path(&fast_graph, 2, 0).is_none());
+
+        // the same edges with other weights
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 5);
+        g.add_edge(1, 2, 5);
+        g.add_edge(0, 3, 1);
+        g.add_edge_bidir(3, 4, 1);
+        g.add_edge(4, 2, 1);
+        g.freeze();
+        let fast_graph = customizable_graph.customize(&g).unwrap();
+        fast_graph.validate().unwrap();
+        let path = calc_path(&fast_graph, 0, 2).unwrap();
+        assert_eq!(&vec![0, 3, 4, 2], path.get_nodes());
+        assert_eq!(3, path.get_weight());
+
+        // fewer edges are fine, but not new ones
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 5);
+        g.freeze();
+        assert!(customizable_graph.customize(&g).is_ok());
+        let mut g = InputGraph::new();
+        g.add_edge(1, 4, 5);
+        g.freeze();
+        assert_eq!(
+            Some(CustomizationError::UnknownEdge { from: 1, to: 4 }),
+            customizable_graph.customize(&g).err()
+        );
+    }
+
+    #[test]
+    fn invalid_order() {
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 1);
+        g.freeze();
+        assert_eq!(
+            Some(OrderError::WrongLength {
+                order_len: 2,
+                num_nodes: 3
+            }),
+            CustomizableGraph::new(&g, &[0, 1]).err()
+        );
+        assert!(matches!(
+            CustomizableGraph::new(&g, &[0, 1, 1]),
+            Err(OrderError::DuplicateNode { node: 1, .. })
+        ));
+        assert!(matches!(
+            CustomizableGraph::new(&g, &[0, 1, 3]),
+            Err(OrderError::InvalidNode { node: 3, .. })
+        ));
+        assert!(CustomizableGraph::new(&g, &[2, 0, 1]).is_ok());
+    }
+}
@@ -34,7 +34,7 @@ pub struct Dijkstra {
 }
 
 /// Dijkstra's algorithm using pre-allocated memory for the shortest path tree. Currently only used
-/// to test the correctness of the path_calculator implementation. Providing a flexible Dijkstra
+/// to test and verify the correctness of the path_calculator implementation. Providing a flexible Dijkstra
 /// implementation that works for arbitrary weight functions and that runs on the fast_graph
 /// datastructure might be useful, but this was not the intention here.
 impl Dijkstra {
@@ -48,7 +48,6 @@ impl Dijkstra {
         }
     }
 
-    #[allow(dead_code)]
     pub fn calc_path(
         &mut self,
         graph: &PreparationGraph,
@@ -240,7 +239,7 @@ mod tests {
         source: NodeId,
         target: NodeId,
     ) {
-        assert_eq!(dijkstra.calc_path(&graph, source, target), None);
+        assert_eq!(dijkstra.calc_path(graph, source, target), None);
     }
 
     fn assert_path(
@@ -251,7 +250,7 @@ mod tests {
         weight: Weight,
         nodes: Vec<NodeId>,
     ) {
-        let dijkstra_path = dijkstra.calc_path(&graph, source, target);
+        let dijkstra_path = dijkstra.calc_path(graph, source, target);
         assert_eq!(
             dijkstra_path,
             Some(ShortestPath::new(source, target, weight, nodes.clone()))
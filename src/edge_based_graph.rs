@@ -0,0 +1,210 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use std::collections::HashMap;
+
+use crate::constants::{NodeId, Weight, WEIGHT_MAX};
+use crate::fast_graph::FastGraph;
+use crate::input_graph::{Edge, InputGraph};
+use crate::path_calculator::PathCalculator;
+use crate::shortest_path::ShortestPath;
+
+/// The costs of turning from one edge to another, identified by the three nodes `from -> via -> to`
+/// of the turn. Turns without an explicit cost are free.
+#[derive(Debug, Clone, Default)]
+pub struct TurnCosts {
+    costs: HashMap<(NodeId, NodeId, NodeId), Weight>,
+}
+
+impl TurnCosts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the cost of the turn `from -> via -> to`. A cost of `WEIGHT_MAX` forbids the turn.
+    pub fn set_turn_cost(&mut self, from: NodeId, via: NodeId, to: NodeId, cost: Weight) {
+        self.costs.insert((from, via, to), cost);
+    }
+
+    /// Forbids the turn `from -> via -> to`, e.g. for a 'no left turn' sign.
+    pub fn restrict_turn(&mut self, from: NodeId, via: NodeId, to: NodeId) {
+        self.set_turn_cost(from, via, to, WEIGHT_MAX);
+    }
+
+    pub fn get_turn_cost(&self, from: NodeId, via: NodeId, to: NodeId) -> Weight {
+        self.costs.get(&(from, via, to)).copied().unwrap_or(0)
+    }
+}
+
+/// Expands a graph such that the shortest paths respect turn costs and turn restrictions. Every
+/// edge of the original graph becomes a node of the expanded graph, and every allowed turn becomes
+/// an edge whose weight is the turn cost plus the weight of the edge we turn into. The expanded
+/// graph is prepared like any other graph, and `calc_path` translates the queries and paths
+/// between the original and the expanded graph.
+///
+/// The expanded graph has as many nodes as the original graph has edges, and one edge per allowed
+/// turn, so its preparation takes considerably longer.
+pub struct EdgeBasedGraph {
+    expanded_graph: InputGraph,
+    edges: Vec<Edge>,
+    out_edges: Vec<Vec<NodeId>>,
+    in_edges: Vec<Vec<NodeId>>,
+}
+
+impl EdgeBasedGraph {
+    pub fn new(input_graph: &InputGraph, turn_costs: &TurnCosts) -> Self {
+        let num_nodes = input_graph.get_num_nodes();
+        let edges = input_graph.get_edges().clone();
+        let mut out_edges = vec![vec![]; num_nodes];
+        let mut in_edges = vec![vec![]; num_nodes];
+        for (i, e) in edges.iter().enumerate() {
+            out_edges[e.from].push(i);
+            in_edges[e.to].push(i);
+        }
+        let mut expanded_graph = InputGraph::new();
+        for (i, e) in edges.iter().enumerate() {
+            for j in &out_edges[e.to] {
+                let next = &edges[*j];
+                let cost = turn_costs.get_turn_cost(e.from, e.to, next.to);
+                if cost == WEIGHT_MAX {
+                    continue;
+                }
+                expanded_graph.add_edge(i, *j, cost.saturating_add(next.weight));
+            }
+        }
+        expanded_graph.set_min_num_nodes(edges.len());
+        expanded_graph.freeze();
+        info!(
+            "expanded {} nodes and {} edges into {} nodes and {} edges",
+            num_nodes,
+            edges.len(),
+            expanded_graph.get_num_nodes(),
+            expanded_graph.get_num_edges()
+        );
+        EdgeBasedGraph {
+            expanded_graph,
+            edges,
+            out_edges,
+            in_edges,
+        }
+    }
+
+    /// Returns the graph that should be prepared instead of the original input graph. Its node ids
+    /// are the indices of the edges of the original graph, i.e. `InputGraph::get_edges()`.
+    pub fn get_expanded_graph(&self) -> &InputGraph {
+        &self.expanded_graph
+    }
+
+    /// Calculates the shortest path from `source` to `target` in the original graph, respecting
+    /// the turn costs. The given `fast_graph` must have been prepared from the expanded graph. The
+    /// weight of the returned path includes the turn costs and the path contains the nodes of the
+    /// original graph. Note that such a path can visit the same node more than once, e.g. to get
+    /// around a forbidden turn.
+    pub fn calc_path(
+        &self,
+        path_calculator: &mut PathCalculator,
+        fast_graph: &FastGraph,
+        source: NodeId,
+        target: NodeId,
+    ) -> Option<ShortestPath> {
+        if source == target {
+            return Some(ShortestPath::singular(source));
+        }
+        let sources: Vec<(NodeId, Weight)> = self.out_edges[source]
+            .iter()
+            .map(|e| (*e, self.edges[*e].weight))
+            .collect();
+        let targets: Vec<(NodeId, Weight)> =
+            self.in_edges[target].iter().map(|e| (*e, 0)).collect();
+        if sources.is_empty() || targets.is_empty() {
+            return None;
+        }
+        path_calculator
+            .calc_path_multiple_sources_and_targets(fast_graph, sources, targets)
+            .map(|path| {
+                let mut nodes = Vec::with_capacity(path.get_nodes().len() + 1);
+                nodes.push(source);
+                nodes.extend(path.get_nodes().iter().map(|e| self.edges[*e].to));
+                ShortestPath::new(source, target, path.get_weight(), nodes)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restrict_turns() {
+        //      3
+        //      |
+        // 0 -- 1 -- 2
+        //      |
+        //      4
+        let mut g = InputGraph::new();
+        g.add_edge_bidir(0, 1, 1);
+        g.add_edge_bidir(1, 2, 1);
+        g.add_edge_bidir(1, 3, 1);
+        g.add_edge_bidir(1, 4, 1);
+        g.freeze();
+        let mut turn_costs = TurnCosts::new();
+        turn_costs.restrict_turn(0, 1, 3);
+        turn_costs.set_turn_cost(0, 1, 2, 2);
+        // turning around at the ends of the streets
+        turn_costs.set_turn_cost(1, 4, 1, 3);
+        turn_costs.set_turn_cost(1, 2, 1, 10);
+        let edge_based_graph = EdgeBasedGraph::new(&g, &turn_costs);
+        let fast_graph = crate::prepare(edge_based_graph.get_expanded_graph());
+        let mut calc = crate::create_calculator(&fast_graph);
+        let mut calc_path = |source, target| {
+            edge_based_graph
+                .calc_path(&mut calc, &fast_graph, source, target)
+                .map(|p| (p.get_nodes().clone(), p.get_weight()))
+        };
+        // we are not allowed to turn left, so we turn around at 4, which is cheaper than at 2
+        assert_eq!(Some((vec![0, 1, 4, 1, 3], 7)), calc_path(0, 3));
+        // going straight costs extra
+        assert_eq!(Some((vec![0, 1, 2], 4)), calc_path(0, 2));
+        assert_eq!(Some((vec![3, 1, 0], 2)), calc_path(3, 0));
+        assert_eq!(Some((vec![1], 0)), calc_path(1, 1));
+    }
+
+    #[test]
+    fn dead_end() {
+        // 0 -> 1 -> 2, but turning at 1 is forbidden
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 1);
+        g.freeze();
+        let mut turn_costs = TurnCosts::new();
+        turn_costs.restrict_turn(0, 1, 2);
+        let edge_based_graph = EdgeBasedGraph::new(&g, &turn_costs);
+        let fast_graph = crate::prepare(edge_based_graph.get_expanded_graph());
+        let mut calc = crate::create_calculator(&fast_graph);
+        assert!(edge_based_graph
+            .calc_path(&mut calc, &fast_graph, 0, 2)
+            .is_none());
+        let path = edge_based_graph.calc_path(&mut calc, &fast_graph, 0, 1);
+        assert_eq!(Some(1), path.map(|p| p.get_weight()));
+        // nodes without any edges
+        assert!(edge_based_graph
+            .calc_path(&mut calc, &fast_graph, 2, 0)
+            .is_none());
+    }
+}
@@ -0,0 +1,160 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{EdgeKey, NodeId, Weight};
+use crate::input_graph::InputGraph;
+use crate::shortest_path::ShortestPath;
+
+/// Stores a value of any type for every edge of an `InputGraph`, e.g. the street name and the
+/// turn instruction of every road segment, and returns the values along a calculated path. The
+/// values are not part of the graph itself: every value gets its own edge key (see
+/// `InputGraph::add_edge_with_key`), so the graph should not contain edges with other keys. If
+/// there are parallel edges, only the value of the one that is kept when the graph is frozen is
+/// found.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EdgePayloads<T> {
+    payloads: Vec<T>,
+}
+
+impl<T: Clone> EdgePayloads<T> {
+    pub fn new() -> Self {
+        EdgePayloads {
+            payloads: Vec::new(),
+        }
+    }
+
+    /// Adds an edge to the graph and stores the given payload for it, see `InputGraph::add_edge`.
+    /// The payload is not stored if the edge is skipped, e.g. because it is a loop.
+    pub fn add_edge(
+        &mut self,
+        graph: &mut InputGraph,
+        from: NodeId,
+        to: NodeId,
+        weight: Weight,
+        payload: T,
+    ) -> usize {
+        let num_added = graph.add_edge_with_key(from, to, weight, self.next_key());
+        self.push(num_added, payload)
+    }
+
+    /// Like `add_edge`, but adds the edge in both directions. Both directions share the payload.
+    pub fn add_edge_bidir(
+        &mut self,
+        graph: &mut InputGraph,
+        from: NodeId,
+        to: NodeId,
+        weight: Weight,
+        payload: T,
+    ) -> usize {
+        let num_added = graph.add_edge_bidir_with_key(from, to, weight, self.next_key());
+        self.push(num_added, payload)
+    }
+
+    /// Returns the payload of the edge with the given id (see `InputGraph::get_edge_id`), or
+    /// `None` if the edge was not added using this struct.
+    pub fn get(&self, input_graph: &InputGraph, edge_id: usize) -> Option<&T> {
+        let key = input_graph.get_edge_key(edge_id)?;
+        self.payloads.get(key as usize)
+    }
+
+    /// Returns the payloads of the edges of the given path, or `None` if one of its edges does not
+    /// exist in the given graph or was not added using this struct, see
+    /// `ShortestPath::as_edge_keys`. This works for paths calculated on the prepared graph as well,
+    /// because they consist of the original edges of the input graph.
+    pub fn get_path_payloads(
+        &self,
+        path: &ShortestPath,
+        input_graph: &InputGraph,
+    ) -> Option<Vec<T>> {
+        path.as_edge_keys(input_graph)?
+            .into_iter()
+            .map(|key| self.payloads.get(key as usize).cloned())
+            .collect()
+    }
+
+    /// Returns the number of stored payloads.
+    pub fn len(&self) -> usize {
+        self.payloads.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.payloads.is_empty()
+    }
+
+    fn next_key(&self) -> EdgeKey {
+        self.payloads.len() as EdgeKey
+    }
+
+    fn push(&mut self, num_added: usize, payload: T) -> usize {
+        if num_added > 0 {
+            self.payloads.push(payload);
+        }
+        num_added
+    }
+}
+
+impl<T: Clone> Default for EdgePayloads<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fast_graph_builder::FastGraphBuilder;
+    use crate::path_calculator::PathCalculator;
+
+    use super::*;
+
+    #[test]
+    fn payloads_along_path() {
+        // 0 -> 1 -> 2 -> 3
+        //  \-------------/
+        let mut payloads = EdgePayloads::new();
+        let mut g = InputGraph::new();
+        payloads.add_edge(&mut g, 0, 1, 3, "first");
+        payloads.add_edge_bidir(&mut g, 1, 2, 2, "second");
+        payloads.add_edge(&mut g, 2, 3, 4, "third");
+        payloads.add_edge(&mut g, 0, 3, 20, "direct");
+        // the loop is skipped and so is its payload
+        payloads.add_edge(&mut g, 3, 3, 1, "loop");
+        g.add_edge(3, 0, 5);
+        g.freeze();
+        assert_eq!(4, payloads.len());
+
+        let fast_graph = FastGraphBuilder::build(&g);
+        let mut calc = PathCalculator::new(fast_graph.get_num_nodes());
+        let path = calc.calc_path(&fast_graph, 0, 3).unwrap();
+        assert_eq!(
+            Some(vec!["first", "second", "third"]),
+            payloads.get_path_payloads(&path, &g)
+        );
+        let path = calc.calc_path(&fast_graph, 2, 1).unwrap();
+        assert_eq!(Some(vec!["second"]), payloads.get_path_payloads(&path, &g));
+        // there is no payload for the edge that was added directly
+        let path = calc.calc_path(&fast_graph, 3, 1).unwrap();
+        assert_eq!(None, payloads.get_path_payloads(&path, &g));
+
+        let edge_id = g.get_edge_id(0, 3).unwrap();
+        assert_eq!(Some(&"direct"), payloads.get(&g, edge_id));
+        let edge_id = g.get_edge_id(3, 0).unwrap();
+        assert_eq!(None, payloads.get(&g, edge_id));
+    }
+}
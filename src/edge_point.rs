@@ -0,0 +1,197 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use crate::constants::{NodeId, Weight};
+use crate::fast_graph::FastGraph;
+use crate::path_calculator::PathCalculator;
+use crate::shortest_path::ShortestPath;
+
+/// A location somewhere along an edge of the original graph, e.g. the position of an address
+/// snapped to the closest road. Paths between such points are calculated by temporarily treating
+/// them as additional, virtual nodes that split the edge, without changing the prepared graph.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EdgePoint {
+    from: NodeId,
+    to: NodeId,
+    weight: Weight,
+    bidir: bool,
+    /// the weight from `from` to the point
+    offset: Weight,
+}
+
+impl EdgePoint {
+    /// Creates a point on the edge `from -> to` with the given weight. `fraction` is the relative
+    /// position of the point on the edge, i.e. 0 is at `from` and 1 is at `to`.
+    pub fn new(from: NodeId, to: NodeId, weight: Weight, fraction: f64) -> Self {
+        EdgePoint::create(from, to, weight, fraction, false)
+    }
+
+    /// Like `new`, but for a point on the edges `from -> to` and `to -> from`, which both have the
+    /// given weight, like the edges added by `InputGraph::add_edge_bidir`.
+    pub fn new_bidir(from: NodeId, to: NodeId, weight: Weight, fraction: f64) -> Self {
+        EdgePoint::create(from, to, weight, fraction, true)
+    }
+
+    fn create(from: NodeId, to: NodeId, weight: Weight, fraction: f64, bidir: bool) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&fraction),
+            "the fraction must be between 0 and 1, but was: {}",
+            fraction
+        );
+        EdgePoint {
+            from,
+            to,
+            weight,
+            bidir,
+            offset: ((fraction * weight as f64).round() as Weight).min(weight),
+        }
+    }
+
+    /// The nodes that can be reached from this point and the weights to get there
+    fn get_exits(&self) -> Vec<(NodeId, Weight)> {
+        let mut exits = vec![(self.to, self.weight - self.offset)];
+        if self.bidir {
+            exits.push((self.from, self.offset));
+        }
+        exits
+    }
+
+    /// The nodes from which this point can be reached and the weights to get here
+    fn get_entries(&self) -> Vec<(NodeId, Weight)> {
+        let mut entries = vec![(self.from, self.offset)];
+        if self.bidir {
+            entries.push((self.to, self.weight - self.offset));
+        }
+        entries
+    }
+
+    /// Returns the weight from this point to `other` if both lie on the same edge and `other` can
+    /// be reached without leaving it
+    fn calc_weight_on_same_edge(&self, other: &EdgePoint) -> Option<Weight> {
+        let other_offset = if (other.from, other.to) == (self.from, self.to) {
+            other.offset
+        } else if (other.from, other.to) == (self.to, self.from)
+            && other.bidir
+            && self.bidir
+            && other.weight == self.weight
+        {
+            other.weight - other.offset
+        } else {
+            return None;
+        };
+        if self.offset <= other_offset {
+            Some(other_offset - self.offset)
+        } else if self.bidir {
+            Some(self.offset - other_offset)
+        } else {
+            None
+        }
+    }
+}
+
+/// Calculates the shortest path between two points on the edges of the graph. In the returned path
+/// the source point is represented by the virtual node id `num_nodes` and the target point by the
+/// virtual node id `num_nodes + 1`, so the path starts with the former and ends with the latter.
+pub fn calc_path(
+    path_calculator: &mut PathCalculator,
+    graph: &FastGraph,
+    source: &EdgePoint,
+    target: &EdgePoint,
+) -> Option<ShortestPath> {
+    let virtual_source = graph.get_num_nodes();
+    let virtual_target = virtual_source + 1;
+    let mut best = path_calculator
+        .calc_path_multiple_sources_and_targets(graph, source.get_exits(), target.get_entries())
+        .map(|path| {
+            let mut nodes = Vec::with_capacity(path.get_nodes().len() + 2);
+            nodes.push(virtual_source);
+            nodes.extend_from_slice(path.get_nodes());
+            nodes.push(virtual_target);
+            ShortestPath::new(virtual_source, virtual_target, path.get_weight(), nodes)
+        });
+    if let Some(weight) = source.calc_weight_on_same_edge(target) {
+        if best.as_ref().map_or(true, |b| weight <= b.get_weight()) {
+            let nodes = vec![virtual_source, virtual_target];
+            best = Some(ShortestPath::new(
+                virtual_source,
+                virtual_target,
+                weight,
+                nodes,
+            ));
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::input_graph::InputGraph;
+
+    use super::*;
+
+    #[test]
+    fn paths_between_edge_points() {
+        // 0 -> 1 -> 2 -> 3
+        //      |         |
+        //      4 <------/
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 10);
+        g.add_edge(1, 2, 10);
+        g.add_edge(2, 3, 10);
+        g.add_edge(3, 4, 10);
+        g.add_edge_bidir(1, 4, 20);
+        g.freeze();
+        let fast_graph = crate::prepare(&g);
+        let mut calc = crate::create_calculator(&fast_graph);
+        let mut calc_path = |source: EdgePoint, target: EdgePoint| {
+            calc_path(&mut calc, &fast_graph, &source, &target)
+                .map(|p| (p.get_nodes().clone(), p.get_weight()))
+        };
+        // same edge
+        assert_eq!(
+            Some((vec![5, 6], 5)),
+            calc_path(EdgePoint::new(0, 1, 10, 0.2), EdgePoint::new(0, 1, 10, 0.7))
+        );
+        // same edge, but we have to go around
+        assert_eq!(
+            Some((vec![5, 2, 3, 4, 1, 6], 45)),
+            calc_path(EdgePoint::new(1, 2, 10, 0.7), EdgePoint::new(1, 2, 10, 0.2))
+        );
+        // on a bidirectional edge we can simply turn around, also if the target was given in the
+        // other direction
+        assert_eq!(
+            Some((vec![5, 6], 5)),
+            calc_path(
+                EdgePoint::new_bidir(1, 4, 20, 0.75),
+                EdgePoint::new_bidir(4, 1, 20, 0.5)
+            )
+        );
+        assert_eq!(
+            Some((vec![5, 1, 6], 17)),
+            calc_path(
+                EdgePoint::new_bidir(4, 1, 20, 0.6),
+                EdgePoint::new(1, 2, 10, 0.9)
+            )
+        );
+        assert_eq!(
+            None,
+            calc_path(EdgePoint::new(1, 2, 10, 0.5), EdgePoint::new(0, 1, 10, 0.5))
+        );
+    }
+}
@@ -0,0 +1,387 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use std::fmt;
+
+use crate::constants::{NodeId, Weight};
+
+/// The error returned by the `try_` variants of the queries, e.g. `PathCalculator::try_calc_path`,
+/// for invalid input. The other variants panic in these cases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The given node id does not belong to the graph
+    InvalidNode { node: NodeId, num_nodes: usize },
+    /// The calculator was created for a graph with a different number of nodes, see
+    /// `PathCalculator::for_graph`
+    NodeCountMismatch {
+        graph_nodes: usize,
+        calculator_nodes: usize,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidNode { node, num_nodes } => write!(
+                f,
+                "invalid node {}, the graph only has {} nodes",
+                node, num_nodes
+            ),
+            Error::NodeCountMismatch {
+                graph_nodes,
+                calculator_nodes,
+            } => write!(
+                f,
+                "the graph has {} nodes, but the calculator was created for {} nodes",
+                graph_nodes, calculator_nodes
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// The error returned by `InputGraph::try_add_edge` for edges that cannot be added. `add_edge`
+/// skips such edges with a warning instead, unless the graph is strict, see
+/// `InputGraph::set_strict`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputError {
+    /// The graph is frozen, so no edges can be added until `InputGraph::thaw` is called
+    Frozen,
+    /// The edge starts and ends at the same node
+    Loop { node: NodeId },
+    /// The edge has zero weight, see `InputGraph::set_allow_zero_weights`
+    ZeroWeight { from: NodeId, to: NodeId },
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputError::Frozen => write!(
+                f,
+                "the graph is frozen already, for further changes first use thaw()"
+            ),
+            InputError::Loop { node } => {
+                write!(
+                    f,
+                    "loop edges are not allowed, but got one at node {}",
+                    node
+                )
+            }
+            InputError::ZeroWeight { from, to } => write!(
+                f,
+                "zero weight edges are not allowed unless set_allow_zero_weights is used, but \
+                 edge {} -> {} has zero weight",
+                from, to
+            ),
+        }
+    }
+}
+
+impl std::error::Error for InputError {}
+
+/// The error returned by `InputGraph::try_from_file`, `InputGraph::try_from_dimacs_file` and
+/// `InputGraph::from_csv`.
+#[derive(Debug)]
+pub enum ParseError {
+    /// The file could not be read
+    Io(std::io::Error),
+    /// A line of the file is invalid. The line number starts at 1.
+    InvalidLine {
+        line_number: usize,
+        line: String,
+        reason: String,
+    },
+    /// The file is invalid as a whole, e.g. because it contains fewer edges than announced or a
+    /// CSV column is not found in the header
+    InvalidFile { reason: String },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Io(e) => write!(f, "could not read the file: {}", e),
+            ParseError::InvalidLine {
+                line_number,
+                line,
+                reason,
+            } => write!(f, "line {}: {} | {}", line_number, reason, line),
+            ParseError::InvalidFile { reason } => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ParseError {
+    fn from(e: std::io::Error) -> Self {
+        ParseError::Io(e)
+    }
+}
+
+/// The error returned by the preparations that can be aborted, e.g. `try_prepare_with_params`,
+/// `prepare_with_hook` or `prepare_with_cells`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PreparationError {
+    /// A node got more than `Params::max_degree` edges, so the preparation would take very long.
+    /// Besides the node and its degree this describes the state of the preparation when it was
+    /// aborted and the parameters that are most likely to blame.
+    MaxDegreeExceeded {
+        num_contracted: usize,
+        num_nodes: usize,
+        node: NodeId,
+        out_degree: usize,
+        in_degree: usize,
+        max_degree: usize,
+        num_remaining_edges: usize,
+        num_fast_graph_edges: usize,
+        max_settled_nodes_contraction: usize,
+        hierarchy_depth_factor: f32,
+    },
+    /// The hook passed to `prepare_with_hook` returned false
+    AbortedByHook {
+        num_contracted: usize,
+        num_nodes: usize,
+    },
+    /// The number of cells passed to `prepare_with_cells` differs from the number of nodes
+    CellCountMismatch { num_cells: usize, num_nodes: usize },
+}
+
+impl fmt::Display for PreparationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PreparationError::MaxDegreeExceeded {
+                num_contracted,
+                num_nodes,
+                node,
+                out_degree,
+                in_degree,
+                max_degree,
+                num_remaining_edges,
+                num_fast_graph_edges,
+                max_settled_nodes_contraction,
+                hierarchy_depth_factor,
+            } => {
+                let num_remaining = num_nodes - num_contracted;
+                write!(
+                    f,
+                    "Aborted the preparation after contracting {} of {} nodes, because node {} \
+                     has {} edges ({} out, {} in), which is more than max_degree: {}. The \
+                     remaining {} nodes have an average out-degree of {:.1} and there are {} fast \
+                     graph edges so far. This means too many shortcuts are being added, so the \
+                     preparation would take very long. Possible causes are a dense graph with \
+                     many high degree nodes or a very small value of \
+                     max_settled_nodes_contraction (currently {}), consider increasing it or \
+                     lowering hierarchy_depth_factor (currently {}).",
+                    num_contracted,
+                    num_nodes,
+                    node,
+                    out_degree + in_degree,
+                    out_degree,
+                    in_degree,
+                    max_degree,
+                    num_remaining,
+                    *num_remaining_edges as f64 / num_remaining.max(1) as f64,
+                    num_fast_graph_edges,
+                    max_settled_nodes_contraction,
+                    hierarchy_depth_factor,
+                )
+            }
+            PreparationError::AbortedByHook {
+                num_contracted,
+                num_nodes,
+            } => write!(
+                f,
+                "The preparation was aborted by the hook after contracting {} of {} nodes",
+                num_contracted, num_nodes
+            ),
+            PreparationError::CellCountMismatch {
+                num_cells,
+                num_nodes,
+            } => write!(
+                f,
+                "There must be one cell for every node of the input graph, but there are {} cells \
+                 for {} nodes",
+                num_cells, num_nodes
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PreparationError {}
+
+/// The error returned by `FastGraph::update_weights` and `FastGraph::add_edges`, which leave the
+/// graph unchanged in this case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateError {
+    /// The graph has uncontracted core nodes, see `Params::max_shortcuts`
+    CoreNodes { num_core_nodes: usize },
+    /// The edge does not start or end at a node of the graph
+    InvalidNode {
+        from: NodeId,
+        to: NodeId,
+        num_nodes: usize,
+    },
+    /// The edge is a loop or its weight is zero or `WEIGHT_MAX`
+    InvalidEdge {
+        from: NodeId,
+        to: NodeId,
+        weight: Weight,
+    },
+    /// The weight of the edge increases, which requires a new preparation
+    WeightIncrease {
+        from: NodeId,
+        to: NodeId,
+        current: Weight,
+        weight: Weight,
+    },
+}
+
+impl fmt::Display for UpdateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UpdateError::CoreNodes { num_core_nodes } => write!(
+                f,
+                "graphs with core nodes cannot be updated, but this one has {} core nodes",
+                num_core_nodes
+            ),
+            UpdateError::InvalidNode {
+                from,
+                to,
+                num_nodes,
+            } => write!(
+                f,
+                "invalid edge {} -> {}, the graph only has {} nodes",
+                from, to, num_nodes
+            ),
+            UpdateError::InvalidEdge { from, to, weight } => write!(
+                f,
+                "invalid edge {} -> {} with weight {}, loops, zero weights and WEIGHT_MAX are not \
+                 allowed",
+                from, to, weight
+            ),
+            UpdateError::WeightIncrease {
+                from,
+                to,
+                current,
+                weight,
+            } => write!(
+                f,
+                "the weight of edge {} -> {} increases from {} to {}, which requires a new \
+                 preparation",
+                from, to, current, weight
+            ),
+        }
+    }
+}
+
+impl std::error::Error for UpdateError {}
+
+/// The error returned by `OverlayGraph::new` and `OverlayGraph::customize`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlayError {
+    /// The cells of a level do not contain one cell per node
+    CellCountMismatch {
+        level: usize,
+        num_cells: usize,
+        num_nodes: usize,
+    },
+    /// The cells of `level` are not nested in those of the next level, e.g. at the given node
+    CellsNotNested { level: usize, node: NodeId },
+    /// The weights passed to `OverlayGraph::customize` do not contain one weight per edge
+    WeightCountMismatch {
+        num_weights: usize,
+        num_edges: usize,
+    },
+}
+
+impl fmt::Display for OverlayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OverlayError::CellCountMismatch {
+                level,
+                num_cells,
+                num_nodes,
+            } => write!(
+                f,
+                "there has to be one cell per node on level {}, but there were {} cells for {} \
+                 nodes",
+                level, num_cells, num_nodes
+            ),
+            OverlayError::CellsNotNested { level, node } => write!(
+                f,
+                "the cells of level {} are not nested in those of level {}, see node {}",
+                level,
+                level + 1,
+                node
+            ),
+            OverlayError::WeightCountMismatch {
+                num_weights,
+                num_edges,
+            } => write!(
+                f,
+                "there has to be one weight per edge, but there were {} weights for {} edges",
+                num_weights, num_edges
+            ),
+        }
+    }
+}
+
+impl std::error::Error for OverlayError {}
+
+/// The error returned by `CustomizableGraph::customize` for graphs that differ from the one the
+/// customizable graph was created for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CustomizationError {
+    /// The graph has more nodes than the customizable graph
+    TooManyNodes { num_nodes: usize, max_nodes: usize },
+    /// The graph has an edge that does not belong to the graph the customizable graph was created
+    /// for
+    UnknownEdge { from: NodeId, to: NodeId },
+}
+
+impl fmt::Display for CustomizationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CustomizationError::TooManyNodes {
+                num_nodes,
+                max_nodes,
+            } => write!(
+                f,
+                "The input graph has {} nodes, but the customizable graph only has {} nodes",
+                num_nodes, max_nodes
+            ),
+            CustomizationError::UnknownEdge { from, to } => write!(
+                f,
+                "The edge from {} to {} does not belong to the graph the customizable graph was \
+                 created for",
+                from, to
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CustomizationError {}
@@ -17,11 +17,21 @@
  * under the License.
  */
 
+use std::sync::OnceLock;
+
 use serde::Deserialize;
 use serde::Serialize;
 
 use crate::constants::Weight;
 use crate::constants::{EdgeId, NodeId, INVALID_EDGE};
+use crate::error::UpdateError;
+use crate::fast_graph_update;
+use crate::fast_graph_validation;
+use crate::fast_graph_validation::ValidationError;
+use crate::input_graph::Edge;
+use crate::preparation_info::{PreparationInfo, PreparationParams};
+use crate::shortcut_pruning;
+use crate::weight_unit::WeightUnit;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct FastGraph {
@@ -32,6 +42,15 @@ pub struct FastGraph {
 
     pub(crate) edges_bwd: Vec<FastGraphEdge>,
     pub(crate) first_edge_ids_bwd: Vec<EdgeId>,
+
+    pub(crate) preparation_info: PreparationInfo,
+    /// the number of (highest ranked) nodes that were not contracted, see `get_num_core_nodes`
+    pub(crate) num_core_nodes: usize,
+
+    #[serde(skip)]
+    fingerprint: OnceLock<u64>,
+    #[serde(skip)]
+    original_edge_index: OnceLock<OriginalEdgeIndex>,
 }
 
 impl FastGraph {
@@ -43,9 +62,318 @@ impl FastGraph {
             first_edge_ids_fwd: vec![0; num_nodes + 1],
             edges_bwd: vec![],
             first_edge_ids_bwd: vec![0; num_nodes + 1],
+            preparation_info: PreparationInfo::new(PreparationParams::Unknown),
+            num_core_nodes: 0,
+            fingerprint: OnceLock::new(),
+            original_edge_index: OnceLock::new(),
         }
     }
 
+    /// Creates a `FastGraph` from its raw parts, for example to use a contraction hierarchy that
+    /// was built by another tool. The node with id `i` has rank `ranks[i]`. The edges of all nodes
+    /// are stored one after the other, sorted by rank, such that the out-edges of the node with
+    /// rank `r` are `edges_fwd[first_edge_ids_fwd[r]..first_edge_ids_fwd[r + 1]]`, and the same for
+    /// the in-edges. All edges must point to nodes with higher rank. Shortcuts must reference the
+    /// in-edge and the out-edge of the node they bypass, and for original edges both references
+    /// must be `INVALID_EDGE`.
+    ///
+    /// Returns an error if any of these conditions is violated.
+    pub fn from_parts(
+        ranks: Vec<usize>,
+        edges_fwd: Vec<FastGraphEdge>,
+        first_edge_ids_fwd: Vec<EdgeId>,
+        edges_bwd: Vec<FastGraphEdge>,
+        first_edge_ids_bwd: Vec<EdgeId>,
+    ) -> Result<Self, ValidationError> {
+        let mut graph = FastGraph::new(ranks.len());
+        graph.ranks = ranks;
+        graph.edges_fwd = edges_fwd;
+        graph.first_edge_ids_fwd = first_edge_ids_fwd;
+        graph.edges_bwd = edges_bwd;
+        graph.first_edge_ids_bwd = first_edge_ids_bwd;
+        fast_graph_validation::validate(&graph)?;
+        Ok(graph)
+    }
+
+    /// Returns information about how this graph was prepared, like the parameters that were used.
+    pub fn get_preparation_info(&self) -> &PreparationInfo {
+        &self.preparation_info
+    }
+
+    /// Returns the number of nodes that were left uncontracted because the preparation ran out of
+    /// its shortcut budget, see `Params::max_shortcuts` and `Params::max_shortcuts_per_node`.
+    /// These are the nodes with the highest ranks and queries within this core are answered by a
+    /// plain bidirectional Dijkstra, so they get slower the larger the core is.
+    pub fn get_num_core_nodes(&self) -> usize {
+        self.num_core_nodes
+    }
+
+    /// Returns the number of bytes this graph occupies in memory, including the index that is
+    /// built on demand by the methods that look up original edges, if it was built already.
+    pub fn memory_usage_bytes(&self) -> usize {
+        let usize_bytes = std::mem::size_of::<usize>();
+        let edge_bytes = std::mem::size_of::<FastGraphEdge>();
+        let index_bytes = self.original_edge_index.get().map_or(0, |index| {
+            (index.first_in_ids.capacity()
+                + index.in_edges.capacity()
+                + index.first_out_ids.capacity()
+                + index.out_edges.capacity())
+                * usize_bytes
+        });
+        std::mem::size_of::<FastGraph>()
+            + (self.ranks.capacity()
+                + self.first_edge_ids_fwd.capacity()
+                + self.first_edge_ids_bwd.capacity())
+                * usize_bytes
+            + (self.edges_fwd.capacity() + self.edges_bwd.capacity()) * edge_bytes
+            + index_bytes
+    }
+
+    /// Stores the time the graph was created (in seconds since the unix epoch) in the
+    /// `PreparationInfo`. This is not done automatically, because preparing the same graph twice
+    /// is supposed to yield identical results.
+    pub fn set_created_at(&mut self, seconds_since_epoch: u64) {
+        self.preparation_info.set_created_at(seconds_since_epoch);
+    }
+
+    /// Returns the unit of the weights, see `WeightUnit`. This is taken from the input graph, but
+    /// can also be set using `set_weight_unit()`.
+    pub fn get_weight_unit(&self) -> Option<WeightUnit> {
+        self.preparation_info.get_weight_unit()
+    }
+
+    pub fn set_weight_unit(&mut self, weight_unit: WeightUnit) {
+        self.preparation_info.set_weight_unit(Some(weight_unit));
+    }
+
+    /// Returns a hash of the nodes and edges of this graph, which can be used to detect whether
+    /// cached query results belong to another graph, for example after loading an updated graph.
+    /// Graphs with the same fingerprint answer all queries the same way, while the
+    /// `PreparationInfo` is not included. The fingerprint is calculated when this method is
+    /// called for the first time, which takes time proportional to the size of the graph. It
+    /// might change between versions of this crate.
+    pub fn get_fingerprint(&self) -> u64 {
+        *self.fingerprint.get_or_init(|| self.calc_fingerprint())
+    }
+
+    fn calc_fingerprint(&self) -> u64 {
+        // FNV-1a, which unlike the hashers of the standard library is guaranteed to be stable
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut add = |value: usize| {
+            for byte in (value as u64).to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        };
+        add(self.num_nodes);
+        add(self.num_core_nodes);
+        self.ranks.iter().for_each(|rank| add(*rank));
+        for (edges, first_edge_ids) in [
+            (&self.edges_fwd, &self.first_edge_ids_fwd),
+            (&self.edges_bwd, &self.first_edge_ids_bwd),
+        ] {
+            add(edges.len());
+            first_edge_ids.iter().for_each(|id| add(*id));
+            for edge in edges {
+                add(edge.base_node);
+                add(edge.adj_node);
+                add(edge.weight);
+                add(edge.replaced_in_edge);
+                add(edge.replaced_out_edge);
+                add(edge.input_edge_id);
+            }
+        }
+        hash
+    }
+
+    /// Checks that the graph is a valid contraction hierarchy, see `from_parts`.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        fast_graph_validation::validate(self)
+    }
+
+    /// Decreases the weights of the given `(from, to, weight)` edges, e.g. to take into account
+    /// that a road got faster, without preparing the graph again. Shortcuts containing the
+    /// changed edges are updated, and new shortcuts are added where the old ones are no longer
+    /// enough, so all queries still return shortest paths. Edges that cannot be found, because
+    /// the preparation dropped them in favor of a shorter path or because they never existed,
+    /// are added, see also `add_edges`.
+    ///
+    /// Increasing a weight can turn any path that was used to avoid a shortcut into a longer one,
+    /// so this requires a new preparation, see `prepare_with_order` and `CustomizableGraph`.
+    /// Returns an `UpdateError` and leaves the graph unchanged if any of the weights increases, or
+    /// if the graph has core nodes.
+    pub fn update_weights(
+        &mut self,
+        changes: &[(NodeId, NodeId, Weight)],
+    ) -> Result<(), UpdateError> {
+        let updated_edges = fast_graph_update::update_weights(self, changes)?;
+        self.set_updated_edges(updated_edges);
+        Ok(())
+    }
+
+    /// Adds the given `(from, to, weight)` edges between existing nodes, e.g. a road that was
+    /// opened recently, without preparing the graph again. Like for `update_weights`, shortcuts are
+    /// added where necessary, so all queries still return shortest paths. If there already is an
+    /// edge between the same two nodes the shorter one is kept. Returns an `UpdateError` and leaves
+    /// the graph unchanged if any of the edges is invalid, or if the graph has core nodes.
+    pub fn add_edges(&mut self, edges: &[(NodeId, NodeId, Weight)]) -> Result<(), UpdateError> {
+        let updated_edges = fast_graph_update::add_edges(self, edges)?;
+        self.set_updated_edges(updated_edges);
+        Ok(())
+    }
+
+    /// Removes the shortcuts that are never part of a shortest path, because there is a shorter
+    /// path between the same two nodes, e.g. via a node that was contracted later. This makes the
+    /// graph smaller and the queries somewhat faster, but takes about as long as running one query
+    /// per shortcut. Returns the number of removed shortcuts.
+    pub fn prune_shortcuts(&mut self) -> usize {
+        let (updated_edges, num_removed) = shortcut_pruning::prune_shortcuts(self);
+        if num_removed > 0 {
+            self.set_updated_edges(updated_edges);
+        }
+        num_removed
+    }
+
+    fn set_updated_edges(&mut self, updated_edges: fast_graph_update::UpdatedEdges) {
+        let (edges_fwd, first_edge_ids_fwd, edges_bwd, first_edge_ids_bwd) = updated_edges;
+        self.edges_fwd = edges_fwd;
+        self.first_edge_ids_fwd = first_edge_ids_fwd;
+        self.edges_bwd = edges_bwd;
+        self.first_edge_ids_bwd = first_edge_ids_bwd;
+        self.fingerprint = OnceLock::new();
+        self.original_edge_index = OnceLock::new();
+    }
+
+    /// Returns the original edges that the forward edge `edge_id` consists of, in the order they
+    /// are traversed. For edges that are not shortcuts this is just the edge itself.
+    pub fn expand_edge_fwd(&self, edge_id: EdgeId) -> Vec<Edge> {
+        let mut edges = vec![];
+        self.expand_fwd(edge_id, &mut |edge, _| edges.push(edge));
+        edges
+    }
+
+    /// Returns the original edges that the backward edge `edge_id` consists of, in the order they
+    /// are traversed. Note that backward edges point from their `adj_node` to their `base_node`.
+    pub fn expand_edge_bwd(&self, edge_id: EdgeId) -> Vec<Edge> {
+        let mut edges = vec![];
+        self.expand_bwd(edge_id, &mut |edge, _| edges.push(edge));
+        edges
+    }
+
+    /// Like `expand_edge_fwd`, but returns the ids of the input edges the original edges were
+    /// created from, see `FastGraphEdge::input_edge_id`.
+    pub fn expand_edge_fwd_to_input_ids(&self, edge_id: EdgeId) -> Vec<EdgeId> {
+        let mut ids = vec![];
+        self.expand_fwd(edge_id, &mut |_, id| ids.push(id));
+        ids
+    }
+
+    /// Like `expand_edge_bwd`, but returns the ids of the input edges the original edges were
+    /// created from, see `FastGraphEdge::input_edge_id`.
+    pub fn expand_edge_bwd_to_input_ids(&self, edge_id: EdgeId) -> Vec<EdgeId> {
+        let mut ids = vec![];
+        self.expand_bwd(edge_id, &mut |_, id| ids.push(id));
+        ids
+    }
+
+    fn expand_fwd(&self, edge_id: EdgeId, visit: &mut impl FnMut(Edge, EdgeId)) {
+        let edge = &self.edges_fwd[edge_id];
+        if edge.is_shortcut() {
+            self.expand_bwd(edge.replaced_in_edge, visit);
+            self.expand_fwd(edge.replaced_out_edge, visit);
+        } else {
+            visit(
+                Edge::new(edge.base_node, edge.adj_node, edge.weight),
+                edge.input_edge_id,
+            );
+        }
+    }
+
+    fn expand_bwd(&self, edge_id: EdgeId, visit: &mut impl FnMut(Edge, EdgeId)) {
+        let edge = &self.edges_bwd[edge_id];
+        if edge.is_shortcut() {
+            self.expand_bwd(edge.replaced_in_edge, visit);
+            self.expand_fwd(edge.replaced_out_edge, visit);
+        } else {
+            visit(
+                Edge::new(edge.adj_node, edge.base_node, edge.weight),
+                edge.input_edge_id,
+            );
+        }
+    }
+
+    /// Returns the id of the input edge from `from` to `to`, see `FastGraphEdge::input_edge_id`.
+    /// This can be used for two consecutive nodes of a path calculated on this graph, see
+    /// `ShortestPath::as_input_edge_ids`. Returns `None` if there is no such original edge, or
+    /// if it was not created from an input edge.
+    pub fn get_input_edge_id(&self, from: NodeId, to: NodeId) -> Option<EdgeId> {
+        let fwd = (self.begin_out_edges(from)..self.end_out_edges(from))
+            .map(|edge_id| &self.edges_fwd[edge_id])
+            .find(|edge| edge.adj_node == to && !edge.is_shortcut());
+        let bwd = || {
+            (self.begin_in_edges(to)..self.end_in_edges(to))
+                .map(|edge_id| &self.edges_bwd[edge_id])
+                .find(|edge| edge.adj_node == from && !edge.is_shortcut())
+        };
+        fwd.or_else(bwd)
+            .map(|edge| edge.input_edge_id)
+            .filter(|id| *id != INVALID_EDGE)
+    }
+
+    /// Returns the edges of the input graph that start at `node`, i.e. the original edges rather
+    /// than shortcuts. Input edges for which the preparation found a shorter path between the
+    /// same two nodes might be missing. The first call builds an index of the original edges,
+    /// which takes time proportional to the size of the graph.
+    pub fn get_original_out_edges(&self, node: NodeId) -> Vec<Edge> {
+        let index = self.get_original_edge_index();
+        let upward = (self.begin_out_edges(node)..self.end_out_edges(node))
+            .map(|edge_id| &self.edges_fwd[edge_id])
+            .filter(|edge| !edge.is_shortcut())
+            .map(|edge| Edge::new(node, edge.adj_node, edge.weight));
+        let downward = index.out_edges[index.first_out_ids[node]..index.first_out_ids[node + 1]]
+            .iter()
+            .map(|edge_id| &self.edges_bwd[*edge_id])
+            .map(|edge| Edge::new(node, edge.base_node, edge.weight));
+        upward.chain(downward).collect()
+    }
+
+    /// Returns the edges of the input graph that end at `node`, see `get_original_out_edges`.
+    pub fn get_original_in_edges(&self, node: NodeId) -> Vec<Edge> {
+        let index = self.get_original_edge_index();
+        let upward = (self.begin_in_edges(node)..self.end_in_edges(node))
+            .map(|edge_id| &self.edges_bwd[edge_id])
+            .filter(|edge| !edge.is_shortcut())
+            .map(|edge| Edge::new(edge.adj_node, node, edge.weight));
+        let downward = index.in_edges[index.first_in_ids[node]..index.first_in_ids[node + 1]]
+            .iter()
+            .map(|edge_id| &self.edges_fwd[*edge_id])
+            .map(|edge| Edge::new(edge.base_node, node, edge.weight));
+        upward.chain(downward).collect()
+    }
+
+    /// Returns the nodes that are connected to `node` by an edge of the input graph in either
+    /// direction, ordered by node id, see `get_original_out_edges`.
+    pub fn get_original_neighbors(&self, node: NodeId) -> Vec<NodeId> {
+        let mut neighbors: Vec<NodeId> = self
+            .get_original_out_edges(node)
+            .iter()
+            .map(|edge| edge.to)
+            .chain(
+                self.get_original_in_edges(node)
+                    .iter()
+                    .map(|edge| edge.from),
+            )
+            .collect();
+        neighbors.sort_unstable();
+        neighbors.dedup();
+        neighbors
+    }
+
+    fn get_original_edge_index(&self) -> &OriginalEdgeIndex {
+        self.original_edge_index
+            .get_or_init(|| OriginalEdgeIndex::new(self))
+    }
+
     pub fn get_node_ordering(&self) -> Vec<NodeId> {
         let mut ordering = vec![0; self.ranks.len()];
         for i in 0..self.ranks.len() {
@@ -81,6 +409,78 @@ impl FastGraph {
     pub fn end_out_edges(&self, node: NodeId) -> usize {
         self.first_edge_ids_fwd[self.ranks[node] + 1]
     }
+
+    pub fn get_ranks(&self) -> &[usize] {
+        &self.ranks
+    }
+
+    pub fn get_edges_fwd(&self) -> &[FastGraphEdge] {
+        &self.edges_fwd
+    }
+
+    pub fn get_first_edge_ids_fwd(&self) -> &[EdgeId] {
+        &self.first_edge_ids_fwd
+    }
+
+    pub fn get_edges_bwd(&self) -> &[FastGraphEdge] {
+        &self.edges_bwd
+    }
+
+    pub fn get_first_edge_ids_bwd(&self) -> &[EdgeId] {
+        &self.first_edge_ids_bwd
+    }
+}
+
+/// The original edges that are only stored at their lower ranked node, indexed by their higher
+/// ranked node. The original edges between core nodes are stored at both nodes, so they are not
+/// included here.
+#[derive(Debug, Clone)]
+struct OriginalEdgeIndex {
+    /// the forward edges pointing to each node, which are in-edges of the node
+    first_in_ids: Vec<usize>,
+    in_edges: Vec<EdgeId>,
+    /// the backward edges pointing to each node, which are out-edges of the node
+    first_out_ids: Vec<usize>,
+    out_edges: Vec<EdgeId>,
+}
+
+impl OriginalEdgeIndex {
+    fn new(graph: &FastGraph) -> Self {
+        let core_rank = graph.num_nodes.saturating_sub(graph.num_core_nodes);
+        let is_indexed =
+            |edge: &FastGraphEdge| !edge.is_shortcut() && graph.ranks[edge.base_node] < core_rank;
+        let (first_in_ids, in_edges) =
+            Self::group_by_adj_node(graph.num_nodes, &graph.edges_fwd, is_indexed);
+        let (first_out_ids, out_edges) =
+            Self::group_by_adj_node(graph.num_nodes, &graph.edges_bwd, is_indexed);
+        OriginalEdgeIndex {
+            first_in_ids,
+            in_edges,
+            first_out_ids,
+            out_edges,
+        }
+    }
+
+    fn group_by_adj_node(
+        num_nodes: usize,
+        edges: &[FastGraphEdge],
+        is_indexed: impl Fn(&FastGraphEdge) -> bool,
+    ) -> (Vec<usize>, Vec<EdgeId>) {
+        let mut first_ids = vec![0; num_nodes + 1];
+        for edge in edges.iter().filter(|e| is_indexed(e)) {
+            first_ids[edge.adj_node + 1] += 1;
+        }
+        for i in 0..num_nodes {
+            first_ids[i + 1] += first_ids[i];
+        }
+        let mut next_ids = first_ids.clone();
+        let mut edge_ids = vec![0; first_ids[num_nodes]];
+        for (edge_id, edge) in edges.iter().enumerate().filter(|(_, e)| is_indexed(e)) {
+            edge_ids[next_ids[edge.adj_node]] = edge_id;
+            next_ids[edge.adj_node] += 1;
+        }
+        (first_ids, edge_ids)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
@@ -94,6 +494,18 @@ pub struct FastGraphEdge {
     pub weight: Weight,
     pub replaced_in_edge: EdgeId,
     pub replaced_out_edge: EdgeId,
+    /// For original edges this is the index of the edge of the input graph it was created from,
+    /// see `InputGraph::get_edge_id`, such that paths can be mapped back to the input edges.
+    /// This is `INVALID_EDGE` for shortcuts and for edges that were not created from an
+    /// `InputGraph`, like the ones of `prepare_chunked` or the ones added by
+    /// `FastGraph::add_edges`, as well as when reading data with self-describing formats that was
+    /// serialized before this field existed.
+    #[serde(default = "invalid_edge")]
+    pub input_edge_id: EdgeId,
+}
+
+fn invalid_edge() -> EdgeId {
+    INVALID_EDGE
 }
 
 impl FastGraphEdge {
@@ -110,9 +522,16 @@ impl FastGraphEdge {
             weight,
             replaced_in_edge: replaced_edge1,
             replaced_out_edge: replaced_edge2,
+            input_edge_id: INVALID_EDGE,
         }
     }
 
+    /// Returns this edge with the given `input_edge_id`
+    pub fn with_input_edge_id(mut self, input_edge_id: EdgeId) -> Self {
+        self.input_edge_id = input_edge_id;
+        self
+    }
+
     pub fn is_shortcut(&self) -> bool {
         assert!(
             (self.replaced_in_edge == INVALID_EDGE && self.replaced_out_edge == INVALID_EDGE)
@@ -122,3 +541,164 @@ impl FastGraphEdge {
         self.replaced_in_edge != INVALID_EDGE
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{calc_path, prepare, InputGraph};
+    use serde::de::value::{Error, MapDeserializer};
+
+    #[test]
+    fn deserialize_edge_without_input_edge_id() {
+        let fields = vec![
+            ("base_node", 1),
+            ("adj_node", 2),
+            ("weight", 3),
+            ("replaced_in_edge", INVALID_EDGE),
+            ("replaced_out_edge", INVALID_EDGE),
+        ];
+        let edge = FastGraphEdge::deserialize(MapDeserializer::<_, Error>::new(fields.into_iter()))
+            .unwrap();
+        assert_eq!(2, edge.adj_node);
+        assert_eq!(INVALID_EDGE, edge.input_edge_id);
+    }
+
+    #[test]
+    fn expand_edges() {
+        // 0 -> 1 -> 2 -> 3, contracting 1 and 2 first yields the shortcut 0 -> 3
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 2);
+        g.add_edge(2, 3, 3);
+        g.freeze();
+        let fast_graph = crate::prepare_with_order(&g, &[1, 2, 0, 3]).unwrap();
+        let shortcut = (0..fast_graph.get_num_out_edges())
+            .find(|e| fast_graph.edges_fwd[*e].base_node == 0)
+            .unwrap();
+        assert!(fast_graph.edges_fwd[shortcut].is_shortcut());
+        assert_eq!(INVALID_EDGE, fast_graph.edges_fwd[shortcut].input_edge_id);
+        assert_eq!(
+            vec![0, 1, 2],
+            fast_graph.expand_edge_fwd_to_input_ids(shortcut)
+        );
+        assert_eq!(Some(1), fast_graph.get_input_edge_id(1, 2));
+        assert_eq!(None, fast_graph.get_input_edge_id(0, 3));
+        let expanded = fast_graph.expand_edge_fwd(shortcut);
+        assert_eq!(
+            vec![(0, 1, 1), (1, 2, 2), (2, 3, 3)],
+            expanded
+                .iter()
+                .map(|e| (e.from, e.to, e.weight))
+                .collect::<Vec<_>>()
+        );
+        // all edges expand to a connected sequence of original edges with the same weight
+        for edge_id in 0..fast_graph.get_num_in_edges() {
+            let edge = &fast_graph.edges_bwd[edge_id];
+            let expanded = fast_graph.expand_edge_bwd(edge_id);
+            assert_eq!(edge.adj_node, expanded[0].from);
+            assert_eq!(edge.base_node, expanded[expanded.len() - 1].to);
+            assert_eq!(edge.weight, expanded.iter().map(|e| e.weight).sum());
+        }
+    }
+
+    #[test]
+    fn from_parts() {
+        // 0 -> 1 -> 2 -> 3
+        // |         |
+        // 4 -> 5 -> 6
+        let mut g = InputGraph::new();
+        g.add_edge_bidir(0, 1, 1);
+        g.add_edge_bidir(1, 2, 1);
+        g.add_edge_bidir(2, 3, 1);
+        g.add_edge_bidir(0, 4, 2);
+        g.add_edge_bidir(4, 5, 2);
+        g.add_edge_bidir(5, 6, 2);
+        g.add_edge_bidir(6, 2, 2);
+        g.freeze();
+        let fast_graph = prepare(&g);
+        assert_eq!(Ok(()), fast_graph.validate());
+        let from_parts = |g: &FastGraph| {
+            FastGraph::from_parts(
+                g.ranks.clone(),
+                g.edges_fwd.clone(),
+                g.first_edge_ids_fwd.clone(),
+                g.edges_bwd.clone(),
+                g.first_edge_ids_bwd.clone(),
+            )
+        };
+        let copy = from_parts(&fast_graph).unwrap();
+        assert_eq!(calc_path(&fast_graph, 3, 5), calc_path(&copy, 3, 5));
+        let from_parts = |g: &FastGraph| from_parts(g).map(|_| ());
+
+        let mut invalid = fast_graph.clone();
+        invalid.ranks.swap(0, 1);
+        invalid.ranks[2] = invalid.ranks[3];
+        assert_eq!(Err(ValidationError::InvalidRanks), from_parts(&invalid));
+
+        let mut invalid = fast_graph.clone();
+        invalid.first_edge_ids_bwd.pop();
+        assert_eq!(
+            Err(ValidationError::InvalidFirstEdgeIds { forward: false }),
+            from_parts(&invalid)
+        );
+
+        let mut invalid = fast_graph.clone();
+        invalid.edges_fwd[0].adj_node = 100;
+        assert_eq!(
+            Err(ValidationError::InvalidEdge {
+                forward: true,
+                edge_id: 0
+            }),
+            from_parts(&invalid)
+        );
+
+        let shortcut_id = fast_graph
+            .edges_fwd
+            .iter()
+            .position(|e| e.is_shortcut())
+            .expect("there should be a shortcut");
+        let mut invalid = fast_graph.clone();
+        invalid.edges_fwd[shortcut_id].weight += 1;
+        assert_eq!(
+            Err(ValidationError::InvalidShortcut {
+                forward: true,
+                edge_id: shortcut_id
+            }),
+            from_parts(&invalid)
+        );
+        let mut invalid = fast_graph.clone();
+        invalid.edges_fwd[shortcut_id].replaced_out_edge = INVALID_EDGE;
+        assert!(from_parts(&invalid).is_err());
+    }
+
+    #[test]
+    fn fingerprint() {
+        // 0 -> 1 -> 2
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 2);
+        g.freeze();
+        let fast_graph = prepare(&g);
+        assert_eq!(fast_graph.get_fingerprint(), prepare(&g).get_fingerprint());
+        let path = calc_path(&fast_graph, 0, 2).unwrap();
+        assert_eq!(
+            Some(fast_graph.get_fingerprint()),
+            path.get_graph_fingerprint()
+        );
+
+        let mut changed = InputGraph::new();
+        changed.add_edge(0, 1, 1);
+        changed.add_edge(1, 2, 3);
+        changed.freeze();
+        let changed_graph = prepare(&changed);
+        assert_ne!(
+            fast_graph.get_fingerprint(),
+            changed_graph.get_fingerprint()
+        );
+        let changed_path = calc_path(&changed_graph, 0, 2).unwrap();
+        assert_ne!(
+            path.get_graph_fingerprint(),
+            changed_path.get_graph_fingerprint()
+        );
+    }
+}
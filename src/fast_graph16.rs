@@ -0,0 +1,246 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use std::convert::TryFrom;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::fast_graph::FastGraphEdge;
+use crate::fast_graph_validation::ValidationError;
+use crate::preparation_info::PreparationInfo;
+use crate::FastGraph;
+
+/// Even more compact representation of a `FastGraph` than `FastGraph32`, which uses u32 integers
+/// for node and edge ids and u16 integers for the weights. This is meant for storing small graphs,
+/// e.g. a city, on devices with very little memory. Note that the weights of the shortcuts must
+/// fit into 16 bits as well, not just the weights of the input edges, so the weights of all
+/// shortest paths should stay below `u16::MAX`, see `InputGraph::get_max_path_weight`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FastGraph16 {
+    num_nodes: u32,
+    pub ranks: Vec<u32>,
+    pub edges_fwd: Vec<FastGraphEdge16>,
+    pub first_edge_ids_fwd: Vec<u32>,
+
+    pub edges_bwd: Vec<FastGraphEdge16>,
+    pub first_edge_ids_bwd: Vec<u32>,
+
+    pub preparation_info: PreparationInfo,
+    pub num_core_nodes: u32,
+}
+
+impl FastGraph16 {
+    /// Creates a 16bit graph from a given `FastGraph`, or returns an error if any of the ids
+    /// exceeds the 32bit limit or any of the weights exceeds the 16bit limit. `usize::MAX` is
+    /// converted to `u32::MAX` or `u16::MAX` respectively, so these values cannot be used otherwise.
+    pub fn try_new(fast_graph: &FastGraph) -> Result<Self, String> {
+        Ok(FastGraph16 {
+            num_nodes: narrow(fast_graph.get_num_nodes())?,
+            ranks: narrow_vec(&fast_graph.ranks)?,
+            edges_fwd: narrow_edges(&fast_graph.edges_fwd)?,
+            first_edge_ids_fwd: narrow_vec(&fast_graph.first_edge_ids_fwd)?,
+            edges_bwd: narrow_edges(&fast_graph.edges_bwd)?,
+            first_edge_ids_bwd: narrow_vec(&fast_graph.first_edge_ids_bwd)?,
+            preparation_info: fast_graph.preparation_info.clone(),
+            num_core_nodes: narrow(fast_graph.num_core_nodes)?,
+        })
+    }
+
+    /// Like `convert_to_usize`, but checks that the result is a valid `FastGraph`, see
+    /// `FastGraph::validate`. Use this for data that might be corrupted, e.g. because it was read
+    /// from a file, because otherwise invalid data leads to panics or wrong results when the graph
+    /// is queried.
+    pub fn try_convert_to_usize(self) -> Result<FastGraph, ValidationError> {
+        let graph = self.convert_to_usize();
+        graph.validate()?;
+        Ok(graph)
+    }
+
+    /// Converts a 16bit graph to an actual `FastGraph` using `usize`. Any integers that equal
+    /// `u32::MAX` or `u16::MAX` are mapped to `usize::MAX`. The data is not checked, see
+    /// `try_convert_to_usize`.
+    pub fn convert_to_usize(self) -> FastGraph {
+        let mut g = FastGraph::new(self.num_nodes as usize);
+        g.ranks = widen_vec(&self.ranks);
+        g.edges_fwd = widen_edges(&self.edges_fwd);
+        g.first_edge_ids_fwd = widen_vec(&self.first_edge_ids_fwd);
+        g.edges_bwd = widen_edges(&self.edges_bwd);
+        g.first_edge_ids_bwd = widen_vec(&self.first_edge_ids_bwd);
+        g.preparation_info = self.preparation_info;
+        g.num_core_nodes = self.num_core_nodes as usize;
+        g
+    }
+}
+
+/// 16bit equivalent to `FastGraphEdge`, see `FastGraph16` docs.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct FastGraphEdge16 {
+    pub base_node: u32,
+    pub adj_node: u32,
+    pub weight: u16,
+    pub replaced_in_edge: u32,
+    pub replaced_out_edge: u32,
+    pub input_edge_id: u32,
+}
+
+/// Unsigned integers smaller than `usize` whose maximum value represents `usize::MAX`
+trait Narrow: TryFrom<usize> + Into<u64> + Copy {
+    const MAX: Self;
+}
+
+impl Narrow for u16 {
+    const MAX: Self = u16::MAX;
+}
+
+impl Narrow for u32 {
+    const MAX: Self = u32::MAX;
+}
+
+fn narrow<T: Narrow>(int: usize) -> Result<T, String> {
+    if int == usize::MAX {
+        return Ok(T::MAX);
+    }
+    match T::try_from(int) {
+        Ok(x) if x.into() != T::MAX.into() => Ok(x),
+        _ => Err(format!(
+            "Could not convert {} to a {}-bit integer",
+            int,
+            8 * std::mem::size_of::<T>()
+        )),
+    }
+}
+
+fn narrow_vec(vec: &[usize]) -> Result<Vec<u32>, String> {
+    vec.iter().map(|i| narrow(*i)).collect()
+}
+
+fn narrow_edges(vec: &[FastGraphEdge]) -> Result<Vec<FastGraphEdge16>, String> {
+    vec.iter()
+        .map(|edge| {
+            Ok(FastGraphEdge16 {
+                base_node: narrow(edge.base_node)?,
+                adj_node: narrow(edge.adj_node)?,
+                weight: narrow(edge.weight)?,
+                replaced_in_edge: narrow(edge.replaced_in_edge)?,
+                replaced_out_edge: narrow(edge.replaced_out_edge)?,
+                input_edge_id: narrow(edge.input_edge_id)?,
+            })
+        })
+        .collect()
+}
+
+fn widen<T: Narrow>(int: T) -> usize {
+    if int.into() == T::MAX.into() {
+        usize::MAX
+    } else {
+        int.into() as usize
+    }
+}
+
+fn widen_vec(vec: &[u32]) -> Vec<usize> {
+    vec.iter().map(|i| widen(*i)).collect()
+}
+
+fn widen_edges(vec: &[FastGraphEdge16]) -> Vec<FastGraphEdge> {
+    vec.iter()
+        .map(|edge| FastGraphEdge {
+            base_node: widen(edge.base_node),
+            adj_node: widen(edge.adj_node),
+            weight: widen(edge.weight),
+            replaced_in_edge: widen(edge.replaced_in_edge),
+            replaced_out_edge: widen(edge.replaced_out_edge),
+            input_edge_id: widen(edge.input_edge_id),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::INVALID_EDGE;
+    use crate::input_graph::InputGraph;
+
+    use super::*;
+
+    #[test]
+    fn convert_and_back() {
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 3);
+        g.add_edge(1, 2, 40_000);
+        g.add_edge(2, 0, 5);
+        g.freeze();
+        let fast_graph = crate::prepare(&g);
+        let g16 = FastGraph16::try_new(&fast_graph).unwrap();
+        assert_eq!(3, g16.num_nodes);
+        let g_from16 = g16.convert_to_usize();
+        assert_eq!(fast_graph.get_fingerprint(), g_from16.get_fingerprint());
+        assert_eq!(Some(40_003), crate::calc_weight(&g_from16, 0, 2),);
+    }
+
+    #[test]
+    fn try_convert_to_usize() {
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 3);
+        g.add_edge(1, 2, 4);
+        g.add_edge(2, 0, 5);
+        g.freeze();
+        let fast_graph = crate::prepare(&g);
+        assert!(FastGraph16::try_new(&fast_graph)
+            .unwrap()
+            .try_convert_to_usize()
+            .is_ok());
+
+        let mut g16 = FastGraph16::try_new(&fast_graph).unwrap();
+        g16.ranks[0] = g16.ranks[1];
+        assert_eq!(
+            Some(ValidationError::InvalidRanks),
+            g16.try_convert_to_usize().err()
+        );
+
+        let mut g16 = FastGraph16::try_new(&fast_graph).unwrap();
+        g16.edges_bwd[0].adj_node = 17;
+        assert_eq!(
+            Some(ValidationError::InvalidEdge {
+                forward: false,
+                edge_id: 0
+            }),
+            g16.try_convert_to_usize().err()
+        );
+    }
+
+    #[test]
+    fn create_fails_with_too_large_numbers() {
+        let mut g = FastGraph::new(2);
+        g.edges_fwd
+            .push(FastGraphEdge::new(0, 1, 70_000, INVALID_EDGE, INVALID_EDGE));
+        assert_eq!(
+            Err("Could not convert 70000 to a 16-bit integer".to_string()),
+            FastGraph16::try_new(&g).map(|_| ())
+        );
+        // u16::MAX is reserved for usize::MAX
+        g.edges_fwd[0].weight = u16::MAX as usize;
+        assert!(FastGraph16::try_new(&g).is_err());
+        g.edges_fwd[0].weight = usize::MAX;
+        let g16 = FastGraph16::try_new(&g).unwrap();
+        assert_eq!(u16::MAX, g16.edges_fwd[0].weight);
+        assert_eq!(usize::MAX, g16.convert_to_usize().edges_fwd[0].weight);
+        g.ranks = vec![5_000_000_000, 0];
+        assert!(FastGraph16::try_new(&g).is_err());
+    }
+}
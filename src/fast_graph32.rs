@@ -23,6 +23,8 @@ use serde::Deserialize;
 use serde::Serialize;
 
 use crate::fast_graph::FastGraphEdge;
+use crate::fast_graph_validation::ValidationError;
+use crate::preparation_info::PreparationInfo;
 use crate::FastGraph;
 
 /// Special graph data-structure that is identical to `FastGraph` except that it uses u32 integers
@@ -37,12 +39,15 @@ pub struct FastGraph32 {
 
     pub edges_bwd: Vec<FastGraphEdge32>,
     pub first_edge_ids_bwd: Vec<u32>,
+
+    pub preparation_info: PreparationInfo,
+    pub num_core_nodes: u32,
 }
 
 impl FastGraph32 {
     /// Creates a 32bit Graph from a given `FastGraph`. All (potentially 64bit) `usize` integers are
     /// simply converted to u32 and if a value exceeds the 32bit limit an error is thrown. The only
-    /// exception is `std::u32::MAX`, which is converted to `std::usize::MAX`.
+    /// exception is `u32::MAX`, which is converted to `usize::MAX`.
     pub fn new(fast_graph: &FastGraph) -> Self {
         FastGraph32 {
             num_nodes: usize_to_u32(fast_graph.get_num_nodes()),
@@ -51,11 +56,24 @@ impl FastGraph32 {
             first_edge_ids_fwd: usize_to_u32_vec(&fast_graph.first_edge_ids_fwd),
             edges_bwd: usize_to_u32_edges(&fast_graph.edges_bwd),
             first_edge_ids_bwd: usize_to_u32_vec(&fast_graph.first_edge_ids_bwd),
+            preparation_info: fast_graph.preparation_info.clone(),
+            num_core_nodes: usize_to_u32(fast_graph.num_core_nodes),
         }
     }
 
+    /// Like `convert_to_usize`, but checks that the result is a valid `FastGraph`, see
+    /// `FastGraph::validate`. Use this for data that might be corrupted, e.g. because it was read
+    /// from a file, because otherwise invalid data leads to panics or wrong results when the graph
+    /// is queried.
+    pub fn try_convert_to_usize(self) -> Result<FastGraph, ValidationError> {
+        let graph = self.convert_to_usize();
+        graph.validate()?;
+        Ok(graph)
+    }
+
     /// Converts a 32bit Graph to an actual `FastGraph` using `usize` such that it can be used with
-    /// FastPaths crate. Any integers that equal `std::u32::MAX` are mapped to `std::usize::MAX`.
+    /// FastPaths crate. Any integers that equal `u32::MAX` are mapped to `usize::MAX`. The data is
+    /// not checked, see `try_convert_to_usize`.
     pub fn convert_to_usize(self) -> FastGraph {
         let mut g = FastGraph::new(self.num_nodes as usize);
         g.ranks = u32_to_usize_vec(&self.ranks);
@@ -63,6 +81,8 @@ impl FastGraph32 {
         g.first_edge_ids_fwd = u32_to_usize_vec(&self.first_edge_ids_fwd);
         g.edges_bwd = u32_to_usize_edges(&self.edges_bwd);
         g.first_edge_ids_bwd = u32_to_usize_vec(&self.first_edge_ids_bwd);
+        g.preparation_info = self.preparation_info;
+        g.num_core_nodes = self.num_core_nodes as usize;
         g
     }
 }
@@ -75,11 +95,18 @@ pub struct FastGraphEdge32 {
     pub weight: u32,
     pub replaced_in_edge: u32,
     pub replaced_out_edge: u32,
+    /// see `FastGraphEdge::input_edge_id`
+    #[serde(default = "invalid_edge_32")]
+    pub input_edge_id: u32,
+}
+
+fn invalid_edge_32() -> u32 {
+    u32::MAX
 }
 
 fn usize_to_u32(int: usize) -> u32 {
-    if int == std::usize::MAX {
-        usize_to_u32(std::u32::MAX as usize)
+    if int == usize::MAX {
+        usize_to_u32(u32::MAX as usize)
     } else if let Ok(x) = u32::try_from(int) {
         x
     } else {
@@ -92,7 +119,7 @@ fn usize_to_u32_vec(vec: &[usize]) -> Vec<u32> {
 }
 
 fn usize_to_u32_edges(vec: &[FastGraphEdge]) -> Vec<FastGraphEdge32> {
-    vec.iter().map(|e| usize_to_u32_edge(e)).collect()
+    vec.iter().map(usize_to_u32_edge).collect()
 }
 
 fn usize_to_u32_edge(edge: &FastGraphEdge) -> FastGraphEdge32 {
@@ -102,12 +129,13 @@ fn usize_to_u32_edge(edge: &FastGraphEdge) -> FastGraphEdge32 {
         weight: usize_to_u32(edge.weight),
         replaced_in_edge: usize_to_u32(edge.replaced_in_edge),
         replaced_out_edge: usize_to_u32(edge.replaced_out_edge),
+        input_edge_id: usize_to_u32(edge.input_edge_id),
     }
 }
 
 fn u32_to_usize(int: u32) -> usize {
-    if int == std::u32::MAX {
-        std::usize::MAX
+    if int == u32::MAX {
+        usize::MAX
     } else {
         int as usize
     }
@@ -118,7 +146,7 @@ fn u32_to_usize_vec(vec: &[u32]) -> Vec<usize> {
 }
 
 fn u32_to_usize_edges(vec: &[FastGraphEdge32]) -> Vec<FastGraphEdge> {
-    vec.iter().map(|e| u32_to_usize_edge(e)).collect()
+    vec.iter().map(u32_to_usize_edge).collect()
 }
 
 fn u32_to_usize_edge(edge: &FastGraphEdge32) -> FastGraphEdge {
@@ -128,6 +156,7 @@ fn u32_to_usize_edge(edge: &FastGraphEdge32) -> FastGraphEdge {
         weight: u32_to_usize(edge.weight),
         replaced_in_edge: u32_to_usize(edge.replaced_in_edge),
         replaced_out_edge: u32_to_usize(edge.replaced_out_edge),
+        input_edge_id: u32_to_usize(edge.input_edge_id),
     }
 }
 
@@ -135,26 +164,21 @@ fn u32_to_usize_edge(edge: &FastGraphEdge32) -> FastGraphEdge {
 mod tests {
     use crate::fast_graph::FastGraph;
     use crate::fast_graph::FastGraphEdge;
+    use crate::input_graph::InputGraph;
 
     use super::*;
 
     #[test]
     fn create() {
         let num_nodes = 5;
-        let ranks = vec![286, 45, 480_001, std::usize::MAX, 4468];
+        let ranks = vec![286, 45, 480_001, usize::MAX, 4468];
         let edges_fwd = vec![
-            FastGraphEdge::new(std::usize::MAX, 598, 48, std::usize::MAX, std::usize::MAX),
-            FastGraphEdge::new(
-                std::usize::MAX,
-                std::usize::MAX,
-                std::usize::MAX,
-                4,
-                std::usize::MAX,
-            ),
+            FastGraphEdge::new(usize::MAX, 598, 48, usize::MAX, usize::MAX),
+            FastGraphEdge::new(usize::MAX, usize::MAX, usize::MAX, 4, usize::MAX),
         ];
-        let edges_bwd = vec![FastGraphEdge::new(0, 1, 3, 4, std::usize::MAX)];
-        let first_edge_ids_fwd = vec![1, std::usize::MAX, std::usize::MAX];
-        let first_edge_ids_bwd = vec![1, std::usize::MAX, 5, std::usize::MAX, 9, 10];
+        let edges_bwd = vec![FastGraphEdge::new(0, 1, 3, 4, usize::MAX)];
+        let first_edge_ids_fwd = vec![1, usize::MAX, usize::MAX];
+        let first_edge_ids_bwd = vec![1, usize::MAX, 5, usize::MAX, 9, 10];
 
         let mut g = FastGraph::new(num_nodes);
         g.ranks = ranks;
@@ -169,47 +193,93 @@ mod tests {
         assert_eq!(g32.ranks.len(), 5);
         assert_eq!(g32.ranks[0], 286);
         assert_eq!(g32.ranks[2], 480_001);
-        assert_eq!(g32.ranks[3], std::u32::MAX);
+        assert_eq!(g32.ranks[3], u32::MAX);
 
         assert_eq!(g32.edges_fwd.len(), 2);
-        assert_eq!(g32.edges_fwd[0].base_node, std::u32::MAX);
+        assert_eq!(g32.edges_fwd[0].base_node, u32::MAX);
         assert_eq!(g32.edges_fwd[0].adj_node, 598);
         assert_eq!(g32.edges_fwd[0].weight, 48);
-        assert_eq!(g32.edges_fwd[0].replaced_in_edge, std::u32::MAX);
-        assert_eq!(g32.edges_fwd[0].replaced_out_edge, std::u32::MAX);
+        assert_eq!(g32.edges_fwd[0].replaced_in_edge, u32::MAX);
+        assert_eq!(g32.edges_fwd[0].replaced_out_edge, u32::MAX);
 
-        assert_eq!(g32.edges_fwd[1].base_node, std::u32::MAX);
-        assert_eq!(g32.edges_fwd[1].adj_node, std::u32::MAX);
-        assert_eq!(g32.edges_fwd[1].weight, std::u32::MAX);
+        assert_eq!(g32.edges_fwd[1].base_node, u32::MAX);
+        assert_eq!(g32.edges_fwd[1].adj_node, u32::MAX);
+        assert_eq!(g32.edges_fwd[1].weight, u32::MAX);
         assert_eq!(g32.edges_fwd[1].replaced_in_edge, 4);
-        assert_eq!(g32.edges_fwd[1].replaced_out_edge, std::u32::MAX);
+        assert_eq!(g32.edges_fwd[1].replaced_out_edge, u32::MAX);
 
         assert_eq!(g32.edges_bwd.len(), 1);
         assert_eq!(g32.edges_bwd[0].weight, 3);
-        assert_eq!(g32.edges_bwd[0].replaced_out_edge, std::u32::MAX);
+        assert_eq!(g32.edges_bwd[0].replaced_out_edge, u32::MAX);
 
         assert_eq!(g32.first_edge_ids_fwd.len(), 3);
-        assert_eq!(g32.first_edge_ids_fwd[1], std::u32::MAX);
+        assert_eq!(g32.first_edge_ids_fwd[1], u32::MAX);
         assert_eq!(g32.first_edge_ids_bwd.len(), 6);
-        assert_eq!(g32.first_edge_ids_bwd[3], std::u32::MAX);
+        assert_eq!(g32.first_edge_ids_bwd[3], u32::MAX);
         assert_eq!(g32.first_edge_ids_bwd[4], 9);
 
         // briefly check back-conversion
         let g_from32 = g32.convert_to_usize();
         assert_eq!(g_from32.get_num_nodes(), 5);
-        assert_eq!(
-            g_from32.ranks,
-            vec![286, 45, 480_001, std::usize::MAX, 4468]
-        );
-        assert_eq!(g_from32.first_edge_ids_fwd[2], std::usize::MAX);
+        assert_eq!(g_from32.ranks, vec![286, 45, 480_001, usize::MAX, 4468]);
+        assert_eq!(g_from32.first_edge_ids_fwd[2], usize::MAX);
         assert_eq!(g_from32.first_edge_ids_bwd[0], 1);
-        assert_eq!(g_from32.first_edge_ids_bwd[1], std::usize::MAX);
-        assert_eq!(g_from32.edges_fwd[0].base_node, std::usize::MAX);
+        assert_eq!(g_from32.first_edge_ids_bwd[1], usize::MAX);
+        assert_eq!(g_from32.edges_fwd[0].base_node, usize::MAX);
         assert_eq!(g_from32.edges_fwd[0].adj_node, 598);
         assert_eq!(g_from32.edges_fwd[0].weight, 48);
         assert_eq!(g_from32.edges_bwd[0].replaced_in_edge, 4);
     }
 
+    #[test]
+    fn try_convert_to_usize() {
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 3);
+        g.add_edge(1, 2, 4);
+        g.add_edge(2, 0, 5);
+        g.freeze();
+        let fast_graph = crate::prepare(&g);
+        assert!(FastGraph32::new(&fast_graph).try_convert_to_usize().is_ok());
+
+        let mut g32 = FastGraph32::new(&fast_graph);
+        g32.ranks[0] = g32.ranks[1];
+        assert_eq!(
+            Some(ValidationError::InvalidRanks),
+            g32.try_convert_to_usize().err()
+        );
+
+        let mut g32 = FastGraph32::new(&fast_graph);
+        g32.first_edge_ids_fwd.swap(1, 2);
+        g32.first_edge_ids_fwd[1] += 1;
+        assert_eq!(
+            Some(ValidationError::InvalidFirstEdgeIds { forward: true }),
+            g32.try_convert_to_usize().err()
+        );
+
+        let mut g32 = FastGraph32::new(&fast_graph);
+        g32.edges_bwd[0].adj_node = 17;
+        assert_eq!(
+            Some(ValidationError::InvalidEdge {
+                forward: false,
+                edge_id: 0
+            }),
+            g32.try_convert_to_usize().err()
+        );
+
+        let mut g32 = FastGraph32::new(&fast_graph);
+        let shortcut = g32
+            .edges_fwd
+            .iter_mut()
+            .chain(g32.edges_bwd.iter_mut())
+            .find(|e| e.replaced_in_edge != u32::MAX)
+            .unwrap();
+        shortcut.weight += 1;
+        assert!(matches!(
+            g32.try_convert_to_usize(),
+            Err(ValidationError::InvalidShortcut { .. })
+        ));
+    }
+
     #[test]
     #[should_panic]
     fn create_fails_with_too_large_numbers() {
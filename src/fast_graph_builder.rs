@@ -21,17 +21,27 @@ use std::cmp::{max, Reverse};
 use std::collections::BTreeSet;
 
 use priority_queue::PriorityQueue;
+use serde::Deserialize;
+use serde::Serialize;
 
 use crate::constants::Weight;
-use crate::constants::{EdgeId, NodeId, INVALID_EDGE, INVALID_NODE};
+use crate::constants::{EdgeId, NodeId, INVALID_EDGE, INVALID_NODE, WEIGHT_MAX};
+use crate::error::PreparationError;
 use crate::fast_graph::FastGraphEdge;
+use crate::preparation_info::{PreparationInfo, PreparationParams};
 
 use super::fast_graph::FastGraph;
+use super::grid_graph::GridGraph;
 use super::input_graph::InputGraph;
 use super::preparation_graph::PreparationGraph;
 use crate::node_contractor;
+use crate::order_validation::{self, OrderError};
+use crate::weight_unit::WeightUnit;
 use crate::witness_search::WitnessSearch;
 
+/// The group of the nodes at the cell boundaries, which are contracted after all other nodes
+const BOUNDARY_GROUP: usize = usize::MAX;
+
 pub struct FastGraphBuilder {
     fast_graph: FastGraph,
     num_nodes: usize,
@@ -39,9 +49,18 @@ pub struct FastGraphBuilder {
 
 impl FastGraphBuilder {
     fn new(input_graph: &InputGraph) -> Self {
+        if input_graph.get_max_path_weight() == WEIGHT_MAX {
+            warn!(
+                "The edge weights of the input graph are so large that path weights might overflow"
+            );
+        }
+        FastGraphBuilder::with_num_nodes(input_graph.get_num_nodes())
+    }
+
+    pub(crate) fn with_num_nodes(num_nodes: usize) -> Self {
         FastGraphBuilder {
-            fast_graph: FastGraph::new(input_graph.get_num_nodes()),
-            num_nodes: input_graph.get_num_nodes(),
+            fast_graph: FastGraph::new(num_nodes),
+            num_nodes,
         }
     }
 
@@ -49,16 +68,153 @@ impl FastGraphBuilder {
         FastGraphBuilder::build_with_params(input_graph, &Params::default())
     }
 
+    /// Panics if the preparation is aborted, see `Params::max_degree`.
     pub fn build_with_params(input_graph: &InputGraph, params: &Params) -> FastGraph {
+        FastGraphBuilder::try_build_with_params(input_graph, params)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `build_with_params`, but returns an error if the preparation is aborted, see
+    /// `Params::max_degree`.
+    pub fn try_build_with_params(
+        input_graph: &InputGraph,
+        params: &Params,
+    ) -> Result<FastGraph, PreparationError> {
+        FastGraphBuilder::try_build_with_hook(input_graph, params, |_| true)
+    }
+
+    /// Like `try_build_with_params`, but calls `hook` after each node that was contracted, e.g.
+    /// to log the progress of the preparation or to watch the number of shortcuts. If `hook`
+    /// returns false the preparation is aborted and an error is returned. The nodes that are left
+    /// uncontracted because of `Params::max_shortcuts` or `Params::max_shortcuts_per_node` are not
+    /// reported.
+    pub fn try_build_with_hook<F>(
+        input_graph: &InputGraph,
+        params: &Params,
+        mut hook: F,
+    ) -> Result<FastGraph, PreparationError>
+    where
+        F: FnMut(&ContractedNode) -> bool,
+    {
+        let mut contraction = Contraction::new(
+            PreparationGraph::from_input_graph(input_graph),
+            params.clone(),
+            vec![0; input_graph.get_num_nodes()],
+        );
+        FastGraphBuilder::build_with_contraction(input_graph, &mut contraction, &mut hook)
+    }
+
+    /// Like `try_build_with_params`, but keeps the memory used by the preparation in `buffers`
+    /// and reuses it for the following calls, see `PreparationBuffers`.
+    pub fn try_build_with_buffers(
+        input_graph: &InputGraph,
+        params: &Params,
+        buffers: &mut PreparationBuffers,
+    ) -> Result<FastGraph, PreparationError> {
+        let reuse = buffers.contraction.is_some();
+        let contraction = buffers.contraction.get_or_insert_with(|| {
+            Contraction::new(
+                PreparationGraph::from_input_graph(input_graph),
+                params.clone(),
+                vec![0; input_graph.get_num_nodes()],
+            )
+        });
+        if reuse {
+            contraction.reset(input_graph, params);
+        }
+        FastGraphBuilder::build_with_contraction(input_graph, contraction, &mut |_| true)
+    }
+
+    fn build_with_contraction(
+        input_graph: &InputGraph,
+        contraction: &mut Contraction,
+        hook: &mut dyn FnMut(&ContractedNode) -> bool,
+    ) -> Result<FastGraph, PreparationError> {
+        let mut builder = FastGraphBuilder::new(input_graph);
+        while !builder.run_contraction_step(contraction, usize::MAX, hook)? {}
+        Ok(builder.finish(
+            input_graph.get_weight_unit(),
+            PreparationParams::Heuristic(contraction.params.clone()),
+        ))
+    }
+
+    /// Like `build_with_params`, but for the implicit grid graph described by `grid`, see
+    /// `GridGraph`. The edges of the grid are added to the preparation directly, without
+    /// creating an `InputGraph` first.
+    pub fn build_grid(grid: &GridGraph, params: &Params) -> FastGraph {
+        let mut preparation_graph = PreparationGraph::new(grid.get_num_nodes());
+        grid.for_each_edge(|from, to, weight| preparation_graph.add_edge(from, to, weight));
+        let mut builder = FastGraphBuilder::with_num_nodes(grid.get_num_nodes());
+        let groups = vec![0; grid.get_num_nodes()];
+        builder
+            .run_contraction(preparation_graph, params, groups)
+            .unwrap_or_else(|e| panic!("{}", e));
+        builder.finish(None, PreparationParams::Heuristic(params.clone()))
+    }
+
+    /// Like `try_build_with_params`, but contracts the graph cell by cell, where `cells[node]` is
+    /// the cell of each node, e.g. obtained from a graph partition or by putting the coordinates of
+    /// the nodes into a grid. The nodes whose neighbors all belong to the same cell are contracted
+    /// first, one cell after the other, and the nodes at the cell boundaries are contracted last.
+    /// This way the nodes of a cell get consecutive ranks, which improves the memory locality of
+    /// the preparation and the queries. The number of shortcuts is usually about the same.
+    pub fn try_build_with_cells(
+        input_graph: &InputGraph,
+        cells: &[usize],
+        params: &Params,
+    ) -> Result<FastGraph, PreparationError> {
+        if input_graph.get_num_nodes() != cells.len() {
+            return Err(PreparationError::CellCountMismatch {
+                num_cells: cells.len(),
+                num_nodes: input_graph.get_num_nodes(),
+            });
+        }
+        let mut groups = cells.to_vec();
+        for edge in input_graph.get_edges() {
+            if cells[edge.from] != cells[edge.to] {
+                groups[edge.from] = BOUNDARY_GROUP;
+                groups[edge.to] = BOUNDARY_GROUP;
+            }
+        }
+        let mut builder = FastGraphBuilder::new(input_graph);
+        builder.run_contraction(
+            PreparationGraph::from_input_graph(input_graph),
+            params,
+            groups,
+        )?;
+        Ok(builder.finish(
+            input_graph.get_weight_unit(),
+            PreparationParams::Heuristic(params.clone()),
+        ))
+    }
+
+    /// Like `build_with_params`, but contracts independent sets of nodes in parallel, see
+    /// `run_parallel_contraction`. Panics if the preparation is aborted, see `Params::max_degree`.
+    #[cfg(feature = "rayon")]
+    pub fn build_parallel(input_graph: &InputGraph, params: &Params) -> FastGraph {
+        FastGraphBuilder::try_build_parallel(input_graph, params)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `build_parallel`, but returns an error if the preparation is aborted, see
+    /// `Params::max_degree`.
+    #[cfg(feature = "rayon")]
+    pub fn try_build_parallel(
+        input_graph: &InputGraph,
+        params: &Params,
+    ) -> Result<FastGraph, PreparationError> {
         let mut builder = FastGraphBuilder::new(input_graph);
-        builder.run_contraction(input_graph, params);
-        builder.fast_graph
+        builder.run_parallel_contraction(input_graph, params)?;
+        Ok(builder.finish(
+            input_graph.get_weight_unit(),
+            PreparationParams::Heuristic(params.clone()),
+        ))
     }
 
     pub fn build_with_order(
         input_graph: &InputGraph,
         order: &[NodeId],
-    ) -> Result<FastGraph, String> {
+    ) -> Result<FastGraph, OrderError> {
         FastGraphBuilder::build_with_order_with_params(
             input_graph,
             order,
@@ -70,125 +226,668 @@ impl FastGraphBuilder {
         input_graph: &InputGraph,
         order: &[NodeId],
         params: &ParamsWithOrder,
-    ) -> Result<FastGraph, String> {
-        if input_graph.get_num_nodes() != order.len() {
-            return Err(String::from(
-                "The given order must have as many nodes as the input graph",
-            ));
-        }
+    ) -> Result<FastGraph, OrderError> {
+        order_validation::validate_order(order, input_graph.get_num_nodes())?;
         let mut builder = FastGraphBuilder::new(input_graph);
         builder.run_contraction_with_order(input_graph, order, params);
-        Ok(builder.fast_graph)
+        Ok(builder.finish(
+            input_graph.get_weight_unit(),
+            PreparationParams::WithOrder(params.clone()),
+        ))
     }
 
-    fn run_contraction(&mut self, input_graph: &InputGraph, params: &Params) {
-        let mut preparation_graph = PreparationGraph::from_input_graph(input_graph);
-        let mut witness_search = WitnessSearch::new(self.num_nodes);
-        let mut levels = vec![0; self.num_nodes];
-        let mut queue = PriorityQueue::new();
-        for node in 0..self.num_nodes {
+    /// Returns the prepared graph along with the information how it was prepared
+    pub(crate) fn finish(
+        mut self,
+        weight_unit: Option<WeightUnit>,
+        params: PreparationParams,
+    ) -> FastGraph {
+        let mut preparation_info = PreparationInfo::new(params);
+        preparation_info.set_weight_unit(weight_unit);
+        self.fast_graph.preparation_info = preparation_info;
+        self.fast_graph
+    }
+
+    /// Contracts the nodes in the order of their priorities. The nodes are contracted group by
+    /// group, i.e. all nodes of a group with a smaller id are contracted before those of a group
+    /// with a larger id, see `try_build_with_cells`. Within the first group the leaves, i.e. the
+    /// nodes connected to at most one other node (also after contracting other leaves), are
+    /// contracted before all other nodes, because this does not require any witness searches.
+    fn run_contraction(
+        &mut self,
+        preparation_graph: PreparationGraph,
+        params: &Params,
+        groups: Vec<usize>,
+    ) -> Result<(), PreparationError> {
+        let mut contraction = Contraction::new(preparation_graph, params.clone(), groups);
+        while !self.run_contraction_step(&mut contraction, usize::MAX, &mut |_| true)? {}
+        Ok(())
+    }
+
+    /// Continues the given contraction for at most `max_nodes` nodes, where calculating the
+    /// initial priority of a node counts the same as contracting it. Returns true once all nodes
+    /// are contracted. `hook` is called after every contracted node, see `try_build_with_hook`.
+    fn run_contraction_step(
+        &mut self,
+        contraction: &mut Contraction,
+        max_nodes: usize,
+        hook: &mut dyn FnMut(&ContractedNode) -> bool,
+    ) -> Result<bool, PreparationError> {
+        let Contraction {
+            preparation_graph,
+            params,
+            groups,
+            witness_search,
+            levels,
+            queue,
+            num_initialized,
+            leaf_candidates,
+            leaves,
+            outdated,
+            rank,
+            num_shortcuts,
+            done,
+        } = contraction;
+        if *done {
+            return Ok(true);
+        }
+        let mut remaining = max_nodes;
+        // leaves never need shortcuts, so we can contract them without any witness searches. we do
+        // this before calculating the initial priorities, so the priorities of the remaining nodes
+        // already take the contracted leaves into account.
+        while let Some(node) = leaf_candidates.last().copied() {
+            if remaining == 0 {
+                return Ok(false);
+            }
+            leaf_candidates.pop();
+            if leaves[node] {
+                continue;
+            }
+            let mut neighbors = preparation_graph.out_edges[node]
+                .iter()
+                .chain(preparation_graph.in_edges[node].iter())
+                .map(|edge| edge.adj_node);
+            let neighbor = neighbors.next();
+            if neighbors.any(|adj| Some(adj) != neighbor) {
+                continue;
+            }
+            leaves[node] = true;
+            self.add_edges(preparation_graph, node, *rank);
+            preparation_graph.disconnect(node);
+            if let Some(neighbor) = neighbor {
+                levels[neighbor] = max(levels[neighbor], levels[node] + 1);
+                // the neighbor might be a leaf now
+                if groups[neighbor] == groups[node] {
+                    leaf_candidates.push(neighbor);
+                }
+            }
+            self.call_hook(hook, node, *rank, 0, *num_shortcuts)?;
+            *rank += 1;
+            remaining -= 1;
+        }
+        while *num_initialized < self.num_nodes && remaining > 0 {
+            let node = *num_initialized;
+            *num_initialized += 1;
+            if leaves[node] {
+                continue;
+            }
             let priority = node_contractor::calc_relevance(
-                &mut preparation_graph,
+                preparation_graph,
                 params,
-                &mut witness_search,
+                witness_search,
                 node,
-                0,
+                levels[node],
                 params.max_settled_nodes_initial_relevance,
             ) as Weight;
-            queue.push(node, Reverse(priority));
+            queue.push(node, Reverse((groups[node], priority)));
+            remaining -= 1;
         }
-        let mut rank = 0;
-        while !queue.is_empty() {
+        if *num_initialized < self.num_nodes {
+            return Ok(false);
+        }
+        while !queue.is_empty() && remaining > 0 {
+            if params
+                .max_shortcuts
+                .is_some_and(|max| *num_shortcuts >= max)
+            {
+                self.add_core(
+                    preparation_graph,
+                    queue,
+                    *rank,
+                    "reached the maximum number of shortcuts",
+                );
+                break;
+            }
             // This normally yields the greatest priority, but since we use Reverse, it's the
             // least.
-            let node = queue.pop().unwrap().0;
-            let mut neighbors = BTreeSet::new();
-            for out_edge in &preparation_graph.out_edges[node] {
-                neighbors.insert(out_edge.adj_node);
-                self.fast_graph.edges_fwd.push(FastGraphEdge::new(
+            let (node, priority) = loop {
+                let (node, Reverse((group, priority))) = queue.pop().unwrap();
+                if !outdated[node] {
+                    break (node, priority);
+                }
+                outdated[node] = false;
+                let priority = node_contractor::calc_relevance(
+                    preparation_graph,
+                    params,
+                    witness_search,
                     node,
-                    out_edge.adj_node,
-                    out_edge.weight,
-                    out_edge.center_node,
-                    INVALID_EDGE,
-                ));
-            }
-            self.fast_graph.first_edge_ids_fwd[rank + 1] = self.fast_graph.get_num_out_edges();
-
-            for in_edge in &preparation_graph.in_edges[node] {
-                neighbors.insert(in_edge.adj_node);
-                self.fast_graph.edges_bwd.push(FastGraphEdge::new(
+                    levels[node],
+                    params.max_settled_nodes_neighbor_relevance,
+                ) as Weight;
+                if queue
+                    .peek()
+                    .map_or(true, |(_, next)| Reverse((group, priority)) >= *next)
+                {
+                    break (node, priority);
+                }
+                queue.push(node, Reverse((group, priority)));
+            };
+            if let Some(max) = params.max_shortcuts_per_node {
+                let count = node_contractor::count_shortcuts(
+                    preparation_graph,
+                    witness_search,
                     node,
-                    in_edge.adj_node,
-                    in_edge.weight,
-                    in_edge.center_node,
-                    INVALID_EDGE,
-                ));
+                    params,
+                );
+                if count > max {
+                    // the node was deferred before and its priority was not updated since. the
+                    // queue is ordered by group first, so if the next node of the same group (if
+                    // any) was deferred as well, the whole group is deferred.
+                    let group = groups[node];
+                    let group_deferred = priority == WEIGHT_MAX
+                        && queue.peek().map_or(
+                            true,
+                            |(_, Reverse((next_group, next_priority)))| {
+                                *next_group != group || *next_priority == WEIGHT_MAX
+                            },
+                        );
+                    queue.push(node, Reverse((group, WEIGHT_MAX)));
+                    if !group_deferred {
+                        continue;
+                    }
+                    if queue.iter().all(|(_, Reverse((g, _)))| *g == group) {
+                        self.add_core(
+                            preparation_graph,
+                            queue,
+                            *rank,
+                            "the remaining nodes need too many shortcuts",
+                        );
+                        break;
+                    }
+                    // the nodes of this group are contracted together with the boundary nodes,
+                    // so we can continue with the next group
+                    let mut deferred_nodes = vec![];
+                    while queue
+                        .peek()
+                        .is_some_and(|(_, Reverse((next_group, _)))| *next_group == group)
+                    {
+                        deferred_nodes.push(queue.pop().unwrap().0);
+                    }
+                    for node in deferred_nodes {
+                        groups[node] = BOUNDARY_GROUP;
+                        outdated[node] = false;
+                        let priority = node_contractor::calc_relevance(
+                            preparation_graph,
+                            params,
+                            witness_search,
+                            node,
+                            levels[node],
+                            params.max_settled_nodes_neighbor_relevance,
+                        ) as Weight;
+                        queue.push(node, Reverse((BOUNDARY_GROUP, priority)));
+                    }
+                    continue;
+                }
             }
-            self.fast_graph.first_edge_ids_bwd[rank + 1] = self.fast_graph.get_num_in_edges();
-
-            self.fast_graph.ranks[node] = rank;
-            node_contractor::contract_node(
-                &mut preparation_graph,
-                &mut witness_search,
+            *num_shortcuts += self.add_edges(preparation_graph, node, *rank);
+            let neighbors: BTreeSet<NodeId> = preparation_graph.out_edges[node]
+                .iter()
+                .chain(preparation_graph.in_edges[node].iter())
+                .map(|edge| edge.adj_node)
+                .collect();
+            let num_added_shortcuts = node_contractor::contract_node_with_params(
+                preparation_graph,
+                witness_search,
                 node,
-                params.max_settled_nodes_contraction,
+                params,
             );
             for neighbor in neighbors {
+                if let Some(max_degree) = params.max_degree {
+                    let degree = preparation_graph.get_out_edges(neighbor).len()
+                        + preparation_graph.get_in_edges(neighbor).len();
+                    if degree > max_degree {
+                        return Err(self.describe_abort(
+                            preparation_graph,
+                            params,
+                            *rank + 1,
+                            neighbor,
+                            max_degree,
+                        ));
+                    }
+                }
                 levels[neighbor] = max(levels[neighbor], levels[node] + 1);
+                if params.lazy_priority_updates.is_some() {
+                    outdated[neighbor] = true;
+                    continue;
+                }
                 let priority = node_contractor::calc_relevance(
-                    &mut preparation_graph,
+                    preparation_graph,
                     params,
-                    &mut witness_search,
+                    witness_search,
                     neighbor,
                     levels[neighbor],
                     params.max_settled_nodes_neighbor_relevance,
                 ) as Weight;
-                queue.change_priority(&neighbor, Reverse(priority));
+                queue.change_priority(&neighbor, Reverse((groups[neighbor], priority)));
+            }
+            if let Some(interval) = params.lazy_priority_updates.filter(|i| *i > 0) {
+                if (*rank + 1) % interval == 0 {
+                    let remaining_nodes: Vec<NodeId> = queue.iter().map(|(n, _)| *n).collect();
+                    for node in remaining_nodes {
+                        outdated[node] = false;
+                        let priority = node_contractor::calc_relevance(
+                            preparation_graph,
+                            params,
+                            witness_search,
+                            node,
+                            levels[node],
+                            params.max_settled_nodes_neighbor_relevance,
+                        ) as Weight;
+                        queue.change_priority(&node, Reverse((groups[node], priority)));
+                    }
+                }
             }
             debug!(
                 "contracted node {} / {}, num edges fwd: {}, num edges bwd: {}",
-                rank + 1,
+                *rank + 1,
                 self.num_nodes,
                 self.fast_graph.get_num_out_edges(),
                 self.fast_graph.get_num_in_edges()
             );
-            rank += 1;
+            self.call_hook(hook, node, *rank, num_added_shortcuts, *num_shortcuts)?;
+            *rank += 1;
+            remaining -= 1;
         }
-        self.finish_contraction();
+        if queue.is_empty() {
+            self.finish_contraction();
+            *done = true;
+        }
+        Ok(*done)
     }
 
-    fn run_contraction_with_order(
+    /// Contracts the nodes in rounds. In each round all nodes whose priority is lower than the
+    /// priorities of all their neighbors are selected. These nodes are independent, i.e. no two of
+    /// them are adjacent, so their shortcuts can be found in parallel. To make sure that these
+    /// shortcuts stay valid when all of them are contracted at once, the witness searches must not
+    /// visit any of the selected nodes. Ties are broken by node id, so the result is the same for
+    /// any number of threads. Afterwards the priorities of all neighbors are updated in parallel.
+    ///
+    /// The resulting hierarchy usually has a few more shortcuts than the one of `run_contraction`,
+    /// because the witness searches cannot use the other selected nodes.
+    #[cfg(feature = "rayon")]
+    fn run_parallel_contraction(
         &mut self,
         input_graph: &InputGraph,
-        order: &[NodeId],
-        params: &ParamsWithOrder,
-    ) {
+        params: &Params,
+    ) -> Result<(), PreparationError> {
+        use rayon::prelude::*;
+
+        let num_nodes = self.num_nodes;
         let mut preparation_graph = PreparationGraph::from_input_graph(input_graph);
-        let mut witness_search = WitnessSearch::new(self.num_nodes);
-        for (rank, node) in order.iter().cloned().enumerate() {
-            if node >= self.num_nodes {
-                panic!("Order contains invalid node id: {}", node);
+        let mut levels = vec![0; num_nodes];
+        let mut priorities: Vec<Weight> = (0..num_nodes)
+            .into_par_iter()
+            .map_init(
+                || WitnessSearch::with_max_hops(num_nodes, params.max_witness_hops),
+                |witness_search, node| {
+                    calc_relevance_parallel(
+                        &preparation_graph,
+                        params,
+                        witness_search,
+                        node,
+                        0,
+                        params.max_settled_nodes_initial_relevance,
+                    )
+                },
+            )
+            .collect();
+        let mut remaining: Vec<NodeId> = (0..num_nodes).collect();
+        let mut selected = vec![false; num_nodes];
+        let mut rank = 0;
+        let mut num_shortcuts = 0;
+        while !remaining.is_empty() {
+            let reason = if params.max_shortcuts.is_some_and(|max| num_shortcuts >= max) {
+                Some("reached the maximum number of shortcuts")
+            } else if remaining.iter().all(|node| priorities[*node] == WEIGHT_MAX) {
+                // all remaining nodes were deferred, see below
+                Some("the remaining nodes need too many shortcuts")
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                let mut queue = PriorityQueue::new();
+                for node in &remaining {
+                    queue.push(*node, Reverse((0, priorities[*node])));
+                }
+                self.add_core(&preparation_graph, &mut queue, rank, reason);
+                break;
             }
-            for out_edge in &preparation_graph.out_edges[node] {
-                self.fast_graph.edges_fwd.push(FastGraphEdge::new(
+            let independent_nodes: Vec<NodeId> = remaining
+                .par_iter()
+                .copied()
+                .filter(|node| {
+                    get_neighbors(&preparation_graph, *node)
+                        .all(|adj| (priorities[*node], *node) < (priorities[adj], adj))
+                })
+                .collect();
+            for node in &independent_nodes {
+                selected[*node] = true;
+            }
+            let shortcuts: Vec<_> = independent_nodes
+                .par_iter()
+                .map_init(
+                    || WitnessSearch::with_max_hops(num_nodes, params.max_witness_hops),
+                    |witness_search, node| {
+                        let degree = preparation_graph.get_out_edges(*node).len()
+                            + preparation_graph.get_in_edges(*node).len();
+                        let (max_settled_nodes, max_hops) = params.get_contraction_limits(degree);
+                        witness_search.set_max_hops(max_hops);
+                        let mut witness_arcs = vec![];
+                        let shortcuts = node_contractor::find_shortcuts(
+                            &preparation_graph,
+                            witness_search,
+                            *node,
+                            max_settled_nodes,
+                            params.approximation_epsilon,
+                            &selected,
+                            Some(&mut witness_arcs),
+                        );
+                        witness_search.set_max_hops(params.max_witness_hops);
+                        (shortcuts, witness_arcs)
+                    },
+                )
+                .collect();
+
+            let mut neighbors = BTreeSet::new();
+            for (node, (shortcuts, witness_arcs)) in independent_nodes.iter().zip(shortcuts) {
+                if params
+                    .max_shortcuts_per_node
+                    .is_some_and(|max| shortcuts.len() > max)
+                {
+                    // the node stays in the graph and is selected again once its priority is
+                    // updated, or once all other nodes are contracted
+                    priorities[*node] = WEIGHT_MAX;
+                    selected[*node] = false;
+                    continue;
+                }
+                num_shortcuts += self.add_edges(&preparation_graph, *node, rank);
+                for neighbor in get_neighbors(&preparation_graph, *node) {
+                    levels[neighbor] = max(levels[neighbor], levels[*node] + 1);
+                    neighbors.insert(neighbor);
+                }
+                node_contractor::contract_node_with_shortcuts(
+                    &mut preparation_graph,
+                    *node,
+                    &shortcuts,
+                    &witness_arcs,
+                );
+                rank += 1;
+            }
+            remaining.retain(|node| !selected[*node]);
+            for node in &independent_nodes {
+                selected[*node] = false;
+            }
+
+            let neighbors: Vec<NodeId> = neighbors.into_iter().collect();
+            if let Some(max_degree) = params.max_degree {
+                for neighbor in &neighbors {
+                    let degree = preparation_graph.get_out_edges(*neighbor).len()
+                        + preparation_graph.get_in_edges(*neighbor).len();
+                    if degree > max_degree {
+                        return Err(self.describe_abort(
+                            &preparation_graph,
+                            params,
+                            rank,
+                            *neighbor,
+                            max_degree,
+                        ));
+                    }
+                }
+            }
+            let neighbor_priorities: Vec<Weight> = neighbors
+                .par_iter()
+                .map_init(
+                    || WitnessSearch::with_max_hops(num_nodes, params.max_witness_hops),
+                    |witness_search, neighbor| {
+                        calc_relevance_parallel(
+                            &preparation_graph,
+                            params,
+                            witness_search,
+                            *neighbor,
+                            levels[*neighbor],
+                            params.max_settled_nodes_neighbor_relevance,
+                        )
+                    },
+                )
+                .collect();
+            for (neighbor, priority) in neighbors.iter().zip(neighbor_priorities) {
+                priorities[*neighbor] = priority;
+            }
+            debug!(
+                "contracted {} nodes in parallel, {} / {}, num edges fwd: {}, num edges bwd: {}",
+                independent_nodes.len(),
+                rank,
+                self.num_nodes,
+                self.fast_graph.get_num_out_edges(),
+                self.fast_graph.get_num_in_edges()
+            );
+        }
+        self.finish_contraction();
+        Ok(())
+    }
+
+    /// Adds the edges of `node` to the fast graph and assigns it the given rank. Returns the
+    /// number of added shortcuts.
+    fn add_edges(
+        &mut self,
+        preparation_graph: &PreparationGraph,
+        node: NodeId,
+        rank: usize,
+    ) -> usize {
+        let mut num_shortcuts = 0;
+        for out_edge in &preparation_graph.out_edges[node] {
+            if out_edge.center_node != INVALID_NODE {
+                num_shortcuts += 1;
+            }
+            self.fast_graph.edges_fwd.push(
+                FastGraphEdge::new(
                     node,
                     out_edge.adj_node,
                     out_edge.weight,
                     out_edge.center_node,
                     INVALID_EDGE,
-                ));
-            }
-            self.fast_graph.first_edge_ids_fwd[rank + 1] = self.fast_graph.get_num_out_edges();
+                )
+                .with_input_edge_id(out_edge.input_edge_id),
+            );
+        }
+        self.fast_graph.first_edge_ids_fwd[rank + 1] = self.fast_graph.get_num_out_edges();
 
-            for in_edge in &preparation_graph.in_edges[node] {
-                self.fast_graph.edges_bwd.push(FastGraphEdge::new(
+        for in_edge in &preparation_graph.in_edges[node] {
+            if in_edge.center_node != INVALID_NODE {
+                num_shortcuts += 1;
+            }
+            self.fast_graph.edges_bwd.push(
+                FastGraphEdge::new(
                     node,
                     in_edge.adj_node,
                     in_edge.weight,
                     in_edge.center_node,
                     INVALID_EDGE,
-                ));
+                )
+                .with_input_edge_id(in_edge.input_edge_id),
+            );
+        }
+        self.fast_graph.first_edge_ids_bwd[rank + 1] = self.fast_graph.get_num_in_edges();
+
+        self.fast_graph.ranks[node] = rank;
+        num_shortcuts
+    }
+
+    /// Like `add_edges`, but for a preparation graph that only contains some of the nodes, where
+    /// `ids[node]` is the id of `node` in the fast graph. The center nodes of the shortcuts are
+    /// translated the same way, so `ids` can also contain nodes that are not part of the
+    /// preparation graph.
+    pub(crate) fn add_edges_with_ids(
+        &mut self,
+        preparation_graph: &PreparationGraph,
+        node: NodeId,
+        rank: usize,
+        ids: &[NodeId],
+    ) {
+        let id = |node: NodeId| {
+            if node == INVALID_NODE {
+                INVALID_NODE
+            } else {
+                ids[node]
+            }
+        };
+        for out_edge in &preparation_graph.out_edges[node] {
+            self.fast_graph.edges_fwd.push(
+                FastGraphEdge::new(
+                    ids[node],
+                    ids[out_edge.adj_node],
+                    out_edge.weight,
+                    id(out_edge.center_node),
+                    INVALID_EDGE,
+                )
+                .with_input_edge_id(out_edge.input_edge_id),
+            );
+        }
+        self.fast_graph.first_edge_ids_fwd[rank + 1] = self.fast_graph.get_num_out_edges();
+        for in_edge in &preparation_graph.in_edges[node] {
+            self.fast_graph.edges_bwd.push(
+                FastGraphEdge::new(
+                    ids[node],
+                    ids[in_edge.adj_node],
+                    in_edge.weight,
+                    id(in_edge.center_node),
+                    INVALID_EDGE,
+                )
+                .with_input_edge_id(in_edge.input_edge_id),
+            );
+        }
+        self.fast_graph.first_edge_ids_bwd[rank + 1] = self.fast_graph.get_num_in_edges();
+        self.fast_graph.ranks[ids[node]] = rank;
+    }
+
+    /// Assigns the remaining ranks to all nodes that are still in the queue without contracting
+    /// them. Since they are never removed from the preparation graph all edges between these core
+    /// nodes are added in both directions, i.e. the core is searched using plain Dijkstra.
+    fn add_core(
+        &mut self,
+        preparation_graph: &PreparationGraph,
+        queue: &mut PriorityQueue<NodeId, Reverse<(usize, Weight)>>,
+        first_rank: usize,
+        reason: &str,
+    ) {
+        let num_core_nodes = queue.len();
+        info!(
+            "Leaving {} of {} nodes uncontracted, because {}",
+            num_core_nodes, self.num_nodes, reason
+        );
+        let mut rank = first_rank;
+        while let Some((node, _)) = queue.pop() {
+            self.add_edges(preparation_graph, node, rank);
+            rank += 1;
+        }
+        self.fast_graph.num_core_nodes = num_core_nodes;
+    }
+
+    fn call_hook(
+        &self,
+        hook: &mut dyn FnMut(&ContractedNode) -> bool,
+        node: NodeId,
+        rank: usize,
+        num_added_shortcuts: usize,
+        num_shortcuts: usize,
+    ) -> Result<(), PreparationError> {
+        let contracted_node = ContractedNode {
+            node,
+            rank,
+            num_added_shortcuts,
+            num_shortcuts,
+            num_out_edges: self.fast_graph.get_num_out_edges(),
+            num_in_edges: self.fast_graph.get_num_in_edges(),
+        };
+        if hook(&contracted_node) {
+            Ok(())
+        } else {
+            Err(PreparationError::AbortedByHook {
+                num_contracted: rank + 1,
+                num_nodes: self.num_nodes,
+            })
+        }
+    }
+
+    fn describe_abort(
+        &self,
+        preparation_graph: &PreparationGraph,
+        params: &Params,
+        num_contracted: usize,
+        node: NodeId,
+        max_degree: usize,
+    ) -> PreparationError {
+        PreparationError::MaxDegreeExceeded {
+            num_contracted,
+            num_nodes: self.num_nodes,
+            node,
+            out_degree: preparation_graph.get_out_edges(node).len(),
+            in_degree: preparation_graph.get_in_edges(node).len(),
+            max_degree,
+            num_remaining_edges: (0..self.num_nodes)
+                .map(|n| preparation_graph.get_out_edges(n).len())
+                .sum(),
+            num_fast_graph_edges: self.fast_graph.get_num_out_edges()
+                + self.fast_graph.get_num_in_edges(),
+            max_settled_nodes_contraction: params.max_settled_nodes_contraction,
+            hierarchy_depth_factor: params.hierarchy_depth_factor,
+        }
+    }
+
+    fn run_contraction_with_order(
+        &mut self,
+        input_graph: &InputGraph,
+        order: &[NodeId],
+        params: &ParamsWithOrder,
+    ) {
+        let mut preparation_graph = PreparationGraph::from_input_graph(input_graph);
+        let mut witness_search = WitnessSearch::new(self.num_nodes);
+        for (rank, node) in order.iter().cloned().enumerate() {
+            for out_edge in &preparation_graph.out_edges[node] {
+                self.fast_graph.edges_fwd.push(
+                    FastGraphEdge::new(
+                        node,
+                        out_edge.adj_node,
+                        out_edge.weight,
+                        out_edge.center_node,
+                        INVALID_EDGE,
+                    )
+                    .with_input_edge_id(out_edge.input_edge_id),
+                );
+            }
+            self.fast_graph.first_edge_ids_fwd[rank + 1] = self.fast_graph.get_num_out_edges();
+
+            for in_edge in &preparation_graph.in_edges[node] {
+                self.fast_graph.edges_bwd.push(
+                    FastGraphEdge::new(
+                        node,
+                        in_edge.adj_node,
+                        in_edge.weight,
+                        in_edge.center_node,
+                        INVALID_EDGE,
+                    )
+                    .with_input_edge_id(in_edge.input_edge_id),
+                );
             }
             self.fast_graph.first_edge_ids_bwd[rank + 1] = self.fast_graph.get_num_in_edges();
 
@@ -210,7 +909,7 @@ impl FastGraphBuilder {
         self.finish_contraction();
     }
 
-    fn finish_contraction(&mut self) {
+    pub(crate) fn finish_contraction(&mut self) {
         for i in 0..self.num_nodes {
             for edge_id in self.fast_graph.begin_out_edges(i)..self.fast_graph.end_out_edges(i) {
                 // we temporarily stored the center node in the replaced_in_edge field. now we
@@ -268,6 +967,261 @@ impl FastGraphBuilder {
     }
 }
 
+#[cfg(feature = "rayon")]
+fn get_neighbors(
+    preparation_graph: &PreparationGraph,
+    node: NodeId,
+) -> impl Iterator<Item = NodeId> + '_ {
+    preparation_graph.out_edges[node]
+        .iter()
+        .chain(preparation_graph.in_edges[node].iter())
+        .map(|edge| edge.adj_node)
+}
+
+/// Like `node_contractor::calc_relevance`, but does not need mutable access to the graph
+#[cfg(feature = "rayon")]
+fn calc_relevance_parallel(
+    preparation_graph: &PreparationGraph,
+    params: &Params,
+    witness_search: &mut WitnessSearch,
+    node: NodeId,
+    level: NodeId,
+    max_settled_nodes: usize,
+) -> Weight {
+    let num_shortcuts = node_contractor::find_shortcuts(
+        preparation_graph,
+        witness_search,
+        node,
+        max_settled_nodes,
+        params.approximation_epsilon,
+        &[],
+        None,
+    )
+    .len();
+    node_contractor::calc_relevance_from_shortcuts(
+        preparation_graph,
+        params,
+        node,
+        level,
+        num_shortcuts,
+    ) as Weight
+}
+
+/// Prepares a graph like `FastGraphBuilder::try_build_with_params`, but step by step, such that
+/// the preparation can be interleaved with other work on the same thread, e.g. serving queries on
+/// a previously prepared graph. The resulting graph is the same as the one built in one go.
+pub struct ResumableBuilder {
+    builder: FastGraphBuilder,
+    contraction: Contraction,
+    weight_unit: Option<WeightUnit>,
+    error: Option<PreparationError>,
+}
+
+impl ResumableBuilder {
+    /// Starts the preparation of the given graph. This only copies the graph, the actual work is
+    /// done by `step`.
+    pub fn new(input_graph: &InputGraph, params: &Params) -> Self {
+        ResumableBuilder {
+            builder: FastGraphBuilder::new(input_graph),
+            contraction: Contraction::new(
+                PreparationGraph::from_input_graph(input_graph),
+                params.clone(),
+                vec![0; input_graph.get_num_nodes()],
+            ),
+            weight_unit: input_graph.get_weight_unit(),
+            error: None,
+        }
+    }
+
+    /// Continues the preparation for at most `max_nodes` nodes and returns the progress made so
+    /// far. First the leaves are contracted, then the initial priorities of the remaining nodes
+    /// are calculated and then the remaining nodes are contracted, each of which counts as one
+    /// node. Returns an error if the preparation is
+    /// aborted, see `Params::max_degree`, and the same error for all following calls.
+    pub fn step(&mut self, max_nodes: usize) -> Result<PreparationProgress, PreparationError> {
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+        if let Err(error) =
+            self.builder
+                .run_contraction_step(&mut self.contraction, max_nodes, &mut |_| true)
+        {
+            self.error = Some(error.clone());
+            return Err(error);
+        }
+        Ok(self.get_progress())
+    }
+
+    pub fn get_progress(&self) -> PreparationProgress {
+        let num_nodes = self.builder.num_nodes;
+        if self.contraction.done {
+            PreparationProgress::Done
+        } else if self.contraction.num_initialized == 0
+            && !self.contraction.leaf_candidates.is_empty()
+        {
+            PreparationProgress::ContractingLeaves {
+                num_contracted: self.contraction.rank,
+                num_nodes,
+            }
+        } else if self.contraction.num_initialized < num_nodes {
+            PreparationProgress::Initializing {
+                num_initialized: self.contraction.num_initialized,
+                num_nodes,
+            }
+        } else {
+            PreparationProgress::Contracting {
+                num_contracted: self.contraction.rank,
+                num_nodes,
+            }
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.contraction.done
+    }
+
+    /// Runs the remaining steps, if any, and returns the prepared graph.
+    pub fn finish(mut self) -> Result<FastGraph, PreparationError> {
+        self.step(usize::MAX)?;
+        let params = PreparationParams::Heuristic(self.contraction.params.clone());
+        Ok(self.builder.finish(self.weight_unit, params))
+    }
+}
+
+/// The progress of a `ResumableBuilder`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreparationProgress {
+    /// The leaves, e.g. the nodes of dead-end roads, are being contracted.
+    ContractingLeaves {
+        num_contracted: usize,
+        num_nodes: usize,
+    },
+    /// The initial priorities of the nodes are being calculated.
+    Initializing {
+        num_initialized: usize,
+        num_nodes: usize,
+    },
+    /// The nodes are being contracted.
+    Contracting {
+        num_contracted: usize,
+        num_nodes: usize,
+    },
+    /// The preparation is complete, see `ResumableBuilder::finish`.
+    Done,
+}
+
+/// Describes a node that was just contracted, see `FastGraphBuilder::try_build_with_hook`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContractedNode {
+    pub node: NodeId,
+    /// the rank of the node, i.e. the number of nodes that were contracted before it
+    pub rank: usize,
+    /// the number of shortcuts between the neighbors of the node that were needed to contract it
+    pub num_added_shortcuts: usize,
+    /// the number of shortcuts that were added so far, as limited by `Params::max_shortcuts`
+    pub num_shortcuts: usize,
+    /// the number of edges (including shortcuts) of the prepared graph so far, see
+    /// `FastGraph::get_num_out_edges`
+    pub num_out_edges: usize,
+    /// see `FastGraph::get_num_in_edges`
+    pub num_in_edges: usize,
+}
+
+/// The state of the contraction run by `FastGraphBuilder::run_contraction_step`.
+struct Contraction {
+    preparation_graph: PreparationGraph,
+    params: Params,
+    groups: Vec<usize>,
+    witness_search: WitnessSearch,
+    levels: Vec<NodeId>,
+    queue: PriorityQueue<NodeId, Reverse<(usize, Weight)>>,
+    num_initialized: usize,
+    /// the nodes that might be connected to only one other node (or none at all), e.g. the nodes
+    /// of dead-end roads, which are contracted before all other nodes. only the nodes of the first
+    /// group are considered, so the order of the groups does not change.
+    leaf_candidates: Vec<NodeId>,
+    /// the leaves that were contracted already
+    leaves: Vec<bool>,
+    /// the nodes whose priorities have to be recalculated, see `Params::lazy_priority_updates`
+    outdated: Vec<bool>,
+    rank: usize,
+    num_shortcuts: usize,
+    done: bool,
+}
+
+impl Contraction {
+    fn new(preparation_graph: PreparationGraph, params: Params, groups: Vec<usize>) -> Self {
+        let num_nodes = preparation_graph.get_num_nodes();
+        let first_group = groups.iter().min().copied();
+        let leaf_candidates = (0..num_nodes)
+            .rev()
+            .filter(|node| Some(groups[*node]) == first_group)
+            .collect();
+        let witness_search = WitnessSearch::with_max_hops(num_nodes, params.max_witness_hops);
+        Contraction {
+            preparation_graph,
+            params,
+            groups,
+            witness_search,
+            levels: vec![0; num_nodes],
+            queue: PriorityQueue::new(),
+            num_initialized: 0,
+            leaf_candidates,
+            leaves: vec![false; num_nodes],
+            outdated: vec![false; num_nodes],
+            rank: 0,
+            num_shortcuts: 0,
+            done: false,
+        }
+    }
+
+    /// Prepares the contraction of the given graph like `new` (with a single group), but reuses
+    /// the memory of the previous contraction
+    fn reset(&mut self, input_graph: &InputGraph, params: &Params) {
+        let num_nodes = input_graph.get_num_nodes();
+        if num_nodes == self.preparation_graph.get_num_nodes() {
+            self.witness_search.set_max_hops(params.max_witness_hops);
+        } else {
+            self.witness_search = WitnessSearch::with_max_hops(num_nodes, params.max_witness_hops);
+        }
+        self.preparation_graph.reset_from_input_graph(input_graph);
+        self.params = params.clone();
+        self.groups.clear();
+        self.groups.resize(num_nodes, 0);
+        self.levels.clear();
+        self.levels.resize(num_nodes, 0);
+        self.queue.clear();
+        self.num_initialized = 0;
+        self.leaf_candidates.clear();
+        self.leaf_candidates.extend((0..num_nodes).rev());
+        self.leaves.clear();
+        self.leaves.resize(num_nodes, false);
+        self.outdated.clear();
+        self.outdated.resize(num_nodes, false);
+        self.rank = 0;
+        self.num_shortcuts = 0;
+        self.done = false;
+    }
+}
+
+/// Keeps the memory used by the preparation, like the adjacency lists of the graph being
+/// contracted, the witness search and the priority queue, such that it can be reused when several
+/// graphs are prepared one after the other, e.g. the same road network with different weights.
+/// This saves allocating memory proportional to the size of the graph for every preparation, see
+/// `FastGraphBuilder::try_build_with_buffers`. The graphs do not need to have the same size, but
+/// the memory is only reused when they do.
+#[derive(Default)]
+pub struct PreparationBuffers {
+    contraction: Option<Contraction>,
+}
+
+impl PreparationBuffers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Params {
     /// Smaller values typically yield less shortcuts and a faster preparation time. The relation to
     /// query speeds is less clear. For large values that yield a much higher number of shortcuts
@@ -295,9 +1249,89 @@ pub struct Params {
     /// like ~500+ mean less shortcuts (fast graph edges), slower preparation and faster queries.
     /// Lower values mean more shortcuts, slower queries and faster preparation.
     pub max_settled_nodes_contraction: usize,
+    /// If set, the preparation is aborted as soon as a node that was not contracted yet has more
+    /// than this many edges (including shortcuts). A quickly growing number of shortcuts means
+    /// that the preparation will take extremely long, so this is used to fail with an informative
+    /// error instead. `prepare_with_params` panics in this case, use `try_prepare_with_params` to
+    /// get the error. The default is 10,000 edges, which is far more than the nodes of road
+    /// networks get even late in the preparation. Use `None` to turn the check off, e.g. for very
+    /// dense graphs where such degrees are expected.
+    #[serde(default = "default_max_degree")]
+    pub max_degree: Option<usize>,
+    /// If set, the preparation stops contracting nodes once this many shortcuts were added to the
+    /// fast graph. The remaining nodes are kept as an uncontracted core, so the preparation cannot
+    /// run out of memory on dense graphs, but queries that need to cross the core are slower. Note
+    /// that the final number of shortcuts can still be somewhat larger, because the edges of the
+    /// core can contain shortcuts as well. See `FastGraph::get_num_core_nodes`.
+    #[serde(default)]
+    pub max_shortcuts: Option<usize>,
+    /// If set, a node whose contraction would add more than this many shortcuts is put back into
+    /// the queue with the worst possible priority (within its group), so other nodes are
+    /// contracted first. It gets another chance once the priority of the node is updated. If
+    /// such a node comes up again without that, e.g. because all remaining nodes need too many
+    /// shortcuts, the remaining nodes are kept as an uncontracted core like for `max_shortcuts`.
+    /// With `prepare_with_cells` this only happens once all cells are done: the remaining nodes
+    /// of a cell are contracted together with the nodes at the cell boundaries instead.
+    /// This prevents single nodes from adding a huge number of shortcuts, which can make the
+    /// preparation of some graphs extremely slow. Counting the shortcuts requires additional
+    /// witness searches, so this makes the preparation somewhat slower. Not used by
+    /// `prepare_chunked`.
+    #[serde(default)]
+    pub max_shortcuts_per_node: Option<usize>,
+    /// If not set, the priorities of the neighbors of a contracted node are recalculated right
+    /// away. Otherwise they are only marked as outdated and recalculated once the node is about to
+    /// be contracted, and if its priority is no longer the lowest it is put back into the queue.
+    /// If this is set to a positive number `n`, the priorities of all remaining nodes are
+    /// additionally recalculated every `n` contractions. On dense graphs these lazy updates make
+    /// the preparation much faster, but they can lead to more shortcuts. Not used by
+    /// `prepare_parallel`.
+    #[serde(default)]
+    pub lazy_priority_updates: Option<usize>,
+    /// If set, the witness searches only consider paths with at most this many edges. Small values
+    /// like 3-5 bound the time spent per witness search on graphs with many edges of equal, low
+    /// weight, where a search settles many nodes before the settled node limits take effect, but
+    /// the witnesses that are missed lead to more shortcuts.
+    #[serde(default)]
+    pub max_witness_hops: Option<usize>,
+    /// If set, the nodes that have at least this many edges (including shortcuts) when they are
+    /// contracted use `max_settled_nodes_high_degree` and `max_witness_hops_high_degree` instead of
+    /// `max_settled_nodes_contraction` and `max_witness_hops` for the witness searches of their
+    /// contraction. The nodes contracted late usually have the most edges, so stricter limits for
+    /// them can save a lot of time while the limits for the many nodes contracted early can stay
+    /// generous, see `get_contraction_limits`.
+    #[serde(default)]
+    pub high_degree: Option<usize>,
+    /// The maximum number of settled nodes per witness search when contracting a node with at
+    /// least `high_degree` edges
+    #[serde(default = "default_max_settled_nodes_high_degree")]
+    pub max_settled_nodes_high_degree: usize,
+    /// The maximum number of edges of the paths considered by the witness searches when
+    /// contracting a node with at least `high_degree` edges, if set
+    #[serde(default)]
+    pub max_witness_hops_high_degree: Option<usize>,
+    /// If larger than zero, a shortcut is only added if there is no witness path that is longer
+    /// than the shortcut by at most the factor `1 + approximation_epsilon`, e.g. 0.01 accepts
+    /// witnesses that are up to 1% longer. This yields fewer shortcuts, but the queries no longer
+    /// return the shortest paths, only paths that are at most the factor `1 + approximation_epsilon`
+    /// longer. To guarantee this bound, the edges of such witnesses are never bypassed by longer
+    /// witnesses again. Methods that rely on exact shortest paths, like
+    /// `FastGraph::update_weights` and `FastGraph::prune_shortcuts`, should not be used on such
+    /// graphs.
+    #[serde(default)]
+    pub approximation_epsilon: f32,
+}
+
+fn default_max_settled_nodes_high_degree() -> usize {
+    100
+}
+
+fn default_max_degree() -> Option<usize> {
+    Some(10_000)
 }
 
 impl Params {
+    /// Creates parameters from positional values without checking them. Prefer `Params::builder`,
+    /// which uses named setters and validates the values.
     pub fn new(
         ratio: f32,
         max_settled_nodes_initial_relevance: usize,
@@ -310,14 +1344,42 @@ impl Params {
             max_settled_nodes_initial_relevance,
             max_settled_nodes_neighbor_relevance,
             max_settled_nodes_contraction,
+            max_degree: default_max_degree(),
+            max_shortcuts: None,
+            max_shortcuts_per_node: None,
+            lazy_priority_updates: None,
+            max_witness_hops: None,
+            high_degree: None,
+            max_settled_nodes_high_degree: default_max_settled_nodes_high_degree(),
+            max_witness_hops_high_degree: None,
+            approximation_epsilon: 0.0,
         }
     }
 
-    pub fn default() -> Self {
+    /// Returns the maximum number of settled nodes and the hop limit of the witness searches for
+    /// contracting a node with the given number of edges, see `high_degree`.
+    pub fn get_contraction_limits(&self, degree: usize) -> (usize, Option<usize>) {
+        if self
+            .high_degree
+            .is_some_and(|high_degree| degree >= high_degree)
+        {
+            (
+                self.max_settled_nodes_high_degree,
+                self.max_witness_hops_high_degree,
+            )
+        } else {
+            (self.max_settled_nodes_contraction, self.max_witness_hops)
+        }
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
         Params::new(0.1, 500, 100, 500)
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct ParamsWithOrder {
     /// The maximum number of settled nodes per witness search when contracting a node. Smaller
     /// values mean slower queries, more shortcuts, but a faster preparation. Note that the
@@ -333,8 +1395,10 @@ impl ParamsWithOrder {
             max_settled_nodes_contraction_with_order,
         }
     }
+}
 
-    pub fn default() -> Self {
+impl Default for ParamsWithOrder {
+    fn default() -> Self {
         ParamsWithOrder::new(100)
     }
 }
@@ -347,7 +1411,9 @@ mod tests {
     // todo: maybe move these tests and the ones in lib.rs into the 'tests' folder as integration tests
     //       see rust docs
     use crate::{
-        calc_path, create_calculator, prepare, prepare_with_order, PathCalculator, WEIGHT_MAX,
+        calc_k_paths, calc_path, calc_path_multiple_sources_and_targets, calc_path_via,
+        calc_route_with_best_order, calc_route_with_legs, create_calculator, prepare,
+        prepare_with_order, PathCalculator, WEIGHT_MAX,
     };
 
     #[test]
@@ -357,7 +1423,7 @@ mod tests {
         g.add_edge(2, 0, 9);
         g.add_edge(0, 1, 49);
         g.freeze();
-        let fast_graph = prepare_with_order(&g, &vec![0, 1, 2]).unwrap();
+        let fast_graph = prepare_with_order(&g, &[0, 1, 2]).unwrap();
         assert_path(&fast_graph, 2, 1, 58, vec![2, 0, 1]);
     }
 
@@ -368,10 +1434,43 @@ mod tests {
         g.add_edge(1, 0, 9);
         g.add_edge(0, 2, 49);
         g.freeze();
-        let fast_graph = prepare_with_order(&g, &vec![0, 1, 2]).unwrap();
+        let fast_graph = prepare_with_order(&g, &[0, 1, 2]).unwrap();
         assert_path(&fast_graph, 1, 2, 58, vec![1, 0, 2]);
     }
 
+    #[test]
+    fn reject_invalid_order() {
+        // 0 -> 1 -> 2
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 1);
+        g.freeze();
+        assert_eq!(
+            OrderError::WrongLength {
+                order_len: 2,
+                num_nodes: 3
+            },
+            prepare_with_order(&g, &[0, 1]).unwrap_err()
+        );
+        // this used to panic
+        assert_eq!(
+            OrderError::InvalidNode {
+                index: 2,
+                node: 7,
+                num_nodes: 3
+            },
+            prepare_with_order(&g, &[0, 1, 7]).unwrap_err()
+        );
+        assert_eq!(
+            OrderError::DuplicateNode {
+                index: 2,
+                first_index: 1,
+                node: 2
+            },
+            prepare_with_order(&g, &[0, 2, 2]).unwrap_err()
+        );
+    }
+
     #[test]
     fn calc_path_simple() {
         //   --->------4
@@ -385,7 +1484,7 @@ mod tests {
         g.add_edge(0, 4, 2);
         g.freeze();
 
-        let fast_graph = prepare_with_order(&g, &vec![0, 1, 2, 3, 4]).unwrap();
+        let fast_graph = prepare_with_order(&g, &[0, 1, 2, 3, 4]).unwrap();
         assert_path(&fast_graph, 0, 4, 2, vec![0, 4]);
         assert_path(&fast_graph, 4, 0, 16, vec![4, 3, 2, 1, 0]);
         assert_path(&fast_graph, 1, 4, 7, vec![1, 0, 4]);
@@ -409,10 +1508,452 @@ mod tests {
         g.add_edge(4, 2, 4);
         g.freeze();
 
-        let fast_graph = prepare_with_order(&g, &vec![0, 1, 2, 3, 4]).unwrap();
+        let fast_graph = prepare_with_order(&g, &[0, 1, 2, 3, 4]).unwrap();
         assert_path(&fast_graph, 4, 3, 15, vec![4, 2, 1, 3]);
     }
 
+    #[test]
+    fn k_paths() {
+        // 0 -> 1 -> 2 -> 3
+        // |    |    |    |
+        // 4 -> 5 -> 6 -> 7
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 1);
+        g.add_edge(2, 3, 1);
+        g.add_edge(4, 5, 2);
+        g.add_edge(5, 6, 2);
+        g.add_edge(6, 7, 2);
+        g.add_edge(0, 4, 1);
+        g.add_edge(1, 5, 1);
+        g.add_edge(2, 6, 1);
+        g.add_edge(3, 7, 1);
+        g.freeze();
+        let fast_graph = prepare(&g);
+        let paths = calc_k_paths(&fast_graph, 0, 7, 10);
+        let weights: Vec<Weight> = paths.iter().map(|p| p.get_weight()).collect();
+        assert_eq!(vec![4, 5, 6, 7], weights);
+        assert_eq!(&vec![0, 1, 2, 3, 7], paths[0].get_nodes());
+        assert_eq!(&vec![0, 1, 2, 6, 7], paths[1].get_nodes());
+        assert_eq!(&vec![0, 1, 5, 6, 7], paths[2].get_nodes());
+        assert_eq!(&vec![0, 4, 5, 6, 7], paths[3].get_nodes());
+        assert_eq!(2, calc_k_paths(&fast_graph, 0, 7, 2).len());
+        assert!(calc_k_paths(&fast_graph, 0, 7, 0).is_empty());
+        assert!(calc_k_paths(&fast_graph, 7, 0, 3).is_empty());
+        let same = calc_k_paths(&fast_graph, 5, 5, 3);
+        assert_eq!(1, same.len());
+        assert_eq!(&vec![5], same[0].get_nodes());
+    }
+
+    #[test]
+    fn route_with_legs() {
+        // 0 -> 1 -> 2 -> 3
+        //  \<-------/
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 2);
+        g.add_edge(2, 3, 3);
+        g.add_edge(2, 0, 4);
+        g.freeze();
+        let fast_graph = prepare(&g);
+        let route = calc_route_with_legs(&fast_graph, &[1, 0, 3, 3]).unwrap();
+        assert_eq!(12, route.get_total_weight());
+        let legs = route.get_legs();
+        assert_eq!(3, legs.len());
+        assert_eq!(&vec![1, 2, 0], legs[0].get_nodes());
+        assert_eq!(&vec![0, 1, 2, 3], legs[1].get_nodes());
+        assert_eq!(&vec![3], legs[2].get_nodes());
+        assert_eq!(&vec![1, 2, 0, 1, 2, 3], route.flatten().get_nodes());
+        // there is no way back from 3
+        assert!(calc_route_with_legs(&fast_graph, &[0, 3, 1]).is_none());
+
+        let path = calc_path_via(&fast_graph, &[1, 0, 3, 3]).unwrap();
+        assert_eq!(1, path.get_source());
+        assert_eq!(3, path.get_target());
+        assert_eq!(12, path.get_weight());
+        assert_eq!(&vec![1, 2, 0, 1, 2, 3], path.get_nodes());
+        assert!(calc_path_via(&fast_graph, &[0, 3, 1]).is_none());
+    }
+
+    #[test]
+    fn route_with_best_order() {
+        // 0 -> 1 -> 2 -> 3 -> 4
+        //       \<-------/
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 2);
+        g.add_edge(2, 3, 3);
+        g.add_edge(3, 4, 4);
+        g.add_edge(3, 1, 5);
+        g.freeze();
+        let fast_graph = prepare(&g);
+        let route = calc_route_with_best_order(&fast_graph, 0, &[3, 2, 1], 4).unwrap();
+        assert_eq!(10, route.get_total_weight());
+        let sources: Vec<NodeId> = route.get_legs().iter().map(|l| l.get_source()).collect();
+        assert_eq!(vec![0, 1, 2, 3], sources);
+        assert_eq!(&vec![0, 1, 2, 3, 4], route.flatten().get_nodes());
+        // round trip starting at 1
+        let route = calc_route_with_best_order(&fast_graph, 1, &[3, 2], 1).unwrap();
+        assert_eq!(10, route.get_total_weight());
+        assert_eq!(&vec![1, 2, 3, 1], route.flatten().get_nodes());
+        // no stops
+        let route = calc_route_with_best_order(&fast_graph, 0, &[], 4).unwrap();
+        assert_eq!(10, route.get_total_weight());
+        // there is no way back from 4
+        assert!(calc_route_with_best_order(&fast_graph, 0, &[4, 2], 3).is_none());
+    }
+
+    #[test]
+    fn abort_preparation() {
+        // a complete graph where each contraction adds shortcuts between all remaining nodes
+        let mut g = InputGraph::new();
+        for i in 0..8 {
+            for j in 0..8 {
+                if i != j {
+                    g.add_edge(i, j, 10 + i + j);
+                }
+            }
+        }
+        g.freeze();
+        let mut params = Params::new(0.1, 0, 0, 0);
+        params.max_degree = Some(10);
+        let result = FastGraphBuilder::try_build_with_params(&g, &params);
+        let error = result.err().unwrap();
+        assert!(
+            matches!(
+                error,
+                PreparationError::MaxDegreeExceeded {
+                    num_nodes: 8,
+                    max_degree: 10,
+                    ..
+                }
+            ),
+            "{:?}",
+            error
+        );
+        assert!(error.to_string().contains("max_degree: 10"), "{}", error);
+        params.max_degree = Some(14);
+        assert!(FastGraphBuilder::try_build_with_params(&g, &params).is_ok());
+        // the check is on by default, but does not get in the way for normal graphs
+        assert_eq!(Some(10_000), Params::default().max_degree);
+        assert!(FastGraphBuilder::try_build_with_params(&g, &Params::default()).is_ok());
+    }
+
+    #[test]
+    fn leave_core() {
+        // a complete bipartite graph, so contracting any node adds shortcuts between all its
+        // neighbors
+        let mut g = InputGraph::new();
+        for i in 0..4 {
+            for j in 4..8 {
+                g.add_edge(i, j, 1 + i + j);
+                g.add_edge(j, i, 2 + i + j);
+            }
+        }
+        g.freeze();
+        let mut params = Params::new(0.1, 0, 0, 0);
+        let full = FastGraphBuilder::build_with_params(&g, &params);
+        assert_eq!(0, full.get_num_core_nodes());
+        params.max_shortcuts = Some(5);
+        let with_core = FastGraphBuilder::build_with_params(&g, &params);
+        assert!(with_core.get_num_core_nodes() > 0);
+        assert!(with_core.get_num_core_nodes() < 8);
+        with_core.validate().unwrap();
+        let mut calc = PathCalculator::new(8);
+        for i in 0..8 {
+            for j in 0..8 {
+                assert_eq!(
+                    calc.calc_path(&full, i, j).map(|p| p.get_weight()),
+                    calc.calc_path(&with_core, i, j).map(|p| p.get_weight())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn max_shortcuts_per_node() {
+        // the same bipartite graph as in leave_core: there are witnesses via the nodes 0 and 4,
+        // so in the right order all nodes can be contracted without adding any shortcuts
+        let mut g = InputGraph::new();
+        for i in 0..4 {
+            for j in 4..8 {
+                g.add_edge(i, j, 1 + i + j);
+                g.add_edge(j, i, 2 + i + j);
+            }
+        }
+        g.freeze();
+        let full = FastGraphBuilder::build(&g);
+        let params = Params {
+            max_shortcuts_per_node: Some(0),
+            ..Params::default()
+        };
+        let without_shortcuts = FastGraphBuilder::build_with_params(&g, &params);
+        without_shortcuts.validate().unwrap();
+        assert_eq!(0, without_shortcuts.get_num_core_nodes());
+        assert!(without_shortcuts
+            .get_edges_fwd()
+            .iter()
+            .chain(without_shortcuts.get_edges_bwd())
+            .all(|e| !e.is_shortcut()));
+        let mut calc = PathCalculator::new(8);
+        for i in 0..8 {
+            for j in 0..8 {
+                assert_eq!(
+                    calc.calc_path(&full, i, j).map(|p| p.get_weight()),
+                    calc.calc_path(&without_shortcuts, i, j)
+                        .map(|p| p.get_weight())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn max_shortcuts_per_node_cycle() {
+        // 0 -> 1 -> 2 -> 3 -> 0, every node needs one shortcut, so all of them end up in the core
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 2);
+        g.add_edge(2, 3, 3);
+        g.add_edge(3, 0, 4);
+        g.freeze();
+        let mut params = Params {
+            max_shortcuts_per_node: Some(0),
+            ..Params::default()
+        };
+        let fast_graph = FastGraphBuilder::build_with_params(&g, &params);
+        fast_graph.validate().unwrap();
+        assert_eq!(4, fast_graph.get_num_core_nodes());
+        assert_path(&fast_graph, 1, 0, 9, vec![1, 2, 3, 0]);
+        params.max_shortcuts_per_node = Some(1);
+        let fast_graph = FastGraphBuilder::build_with_params(&g, &params);
+        assert_eq!(0, fast_graph.get_num_core_nodes());
+        assert_path(&fast_graph, 1, 0, 9, vec![1, 2, 3, 0]);
+    }
+
+    #[test]
+    fn max_shortcuts_per_node_cells() {
+        // 0 -> 1 -> 2 -> 3 -> 0 in cell 0 and 4 <-> 5 in cell 1. the nodes of cell 0 all need a
+        // shortcut, but this must not stop the contraction of cell 1
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 2);
+        g.add_edge(2, 3, 3);
+        g.add_edge(3, 0, 4);
+        g.add_edge_bidir(4, 5, 5);
+        g.freeze();
+        let params = Params {
+            max_shortcuts_per_node: Some(0),
+            ..Params::default()
+        };
+        let fast_graph =
+            FastGraphBuilder::try_build_with_cells(&g, &[0, 0, 0, 0, 1, 1], &params).unwrap();
+        fast_graph.validate().unwrap();
+        assert_eq!(4, fast_graph.get_num_core_nodes());
+        let ranks = fast_graph.get_ranks();
+        assert!(ranks[4] < 2 && ranks[5] < 2);
+        assert_path(&fast_graph, 1, 0, 9, vec![1, 2, 3, 0]);
+        assert_path(&fast_graph, 5, 4, 5, vec![5, 4]);
+    }
+
+    #[test]
+    fn contract_leaves_first() {
+        // 5 - 0 - 1 - 2
+        //     |   |
+        //     4 - 3 - 6 - 7
+        let mut g = InputGraph::new();
+        g.add_edge_bidir(0, 1, 1);
+        g.add_edge_bidir(1, 2, 1);
+        g.add_edge_bidir(1, 3, 1);
+        g.add_edge_bidir(3, 4, 1);
+        g.add_edge_bidir(4, 0, 2);
+        g.add_edge_bidir(0, 5, 1);
+        g.add_edge_bidir(3, 6, 1);
+        g.add_edge(6, 7, 1);
+        g.freeze();
+        let fast_graph = prepare(&g);
+        let ranks = fast_graph.get_ranks();
+        // the dead ends 2, 5, 6 and 7 are contracted before the nodes of the cycle
+        for leaf in [2, 5, 6, 7] {
+            for node in [0, 1, 3, 4] {
+                assert!(ranks[leaf] < ranks[node]);
+            }
+        }
+        assert!(calc_path(&fast_graph, 7, 5).is_none());
+        assert_path(&fast_graph, 2, 7, 4, vec![2, 1, 3, 6, 7]);
+        assert_path(&fast_graph, 5, 6, 4, vec![5, 0, 1, 3, 6]);
+        assert_path(&fast_graph, 4, 5, 3, vec![4, 0, 5]);
+    }
+
+    #[test]
+    fn resumable_builder() {
+        use rand::rngs::StdRng;
+        use rand::Rng;
+        use rand::SeedableRng;
+
+        let mut rng: StdRng = SeedableRng::from_seed([3; 32]);
+        let g = InputGraph::random(|| rng.gen(), 100, 2.0);
+        let num_nodes = g.get_num_nodes();
+        let params = Params::default();
+        let expected = FastGraphBuilder::build_with_params(&g, &params);
+
+        let mut builder = ResumableBuilder::new(&g, &params);
+        assert_eq!(
+            PreparationProgress::ContractingLeaves {
+                num_contracted: 0,
+                num_nodes
+            },
+            builder.get_progress()
+        );
+        let mut num_steps = 0;
+        let mut last_progress = builder.get_progress();
+        while !builder.is_done() {
+            let progress = builder.step(7).unwrap();
+            assert_ne!(last_progress, progress);
+            last_progress = progress;
+            num_steps += 1;
+        }
+        assert_eq!(PreparationProgress::Done, last_progress);
+        // the leaves do not need initial priorities
+        assert!(num_steps < (2 * num_nodes).div_ceil(7));
+        let fast_graph = builder.finish().unwrap();
+        fast_graph.validate().unwrap();
+        assert_eq!(expected.get_node_ordering(), fast_graph.get_node_ordering());
+        assert_eq!(expected.get_num_out_edges(), fast_graph.get_num_out_edges());
+        assert_eq!(expected.get_num_in_edges(), fast_graph.get_num_in_edges());
+    }
+
+    #[test]
+    fn resumable_builder_abort() {
+        let mut g = InputGraph::new();
+        for i in 0..8 {
+            for j in 0..8 {
+                if i != j {
+                    g.add_edge(i, j, 10 + i + j);
+                }
+            }
+        }
+        g.freeze();
+        let mut params = Params::new(0.1, 0, 0, 0);
+        params.max_degree = Some(10);
+        let mut builder = ResumableBuilder::new(&g, &params);
+        let error = builder.step(usize::MAX).err().unwrap();
+        assert!(
+            matches!(
+                error,
+                PreparationError::MaxDegreeExceeded { max_degree: 10, .. }
+            ),
+            "{:?}",
+            error
+        );
+        assert_eq!(Err(error), builder.step(1));
+        assert!(builder.finish().is_err());
+    }
+
+    #[test]
+    fn contraction_hook() {
+        use rand::rngs::StdRng;
+        use rand::Rng;
+        use rand::SeedableRng;
+
+        let mut rng: StdRng = SeedableRng::from_seed([5; 32]);
+        let g = InputGraph::random(|| rng.gen(), 100, 2.0);
+        let params = Params::default();
+        let expected = FastGraphBuilder::build_with_params(&g, &params);
+
+        let mut contracted = vec![];
+        let fast_graph = FastGraphBuilder::try_build_with_hook(&g, &params, |node| {
+            contracted.push(*node);
+            true
+        })
+        .unwrap();
+        assert_eq!(expected.get_node_ordering(), fast_graph.get_node_ordering());
+        assert_eq!(g.get_num_nodes(), contracted.len());
+        for (rank, node) in contracted.iter().enumerate() {
+            assert_eq!(rank, node.rank);
+            assert_eq!(rank, fast_graph.get_ranks()[node.node]);
+            assert_eq!(
+                fast_graph.get_first_edge_ids_fwd()[rank + 1],
+                node.num_out_edges
+            );
+            assert_eq!(
+                fast_graph.get_first_edge_ids_bwd()[rank + 1],
+                node.num_in_edges
+            );
+        }
+        assert!(contracted.iter().any(|node| node.num_added_shortcuts > 0));
+
+        // returning false aborts the preparation
+        let result = FastGraphBuilder::try_build_with_hook(&g, &params, |node| node.rank < 10);
+        assert_eq!(
+            Err(PreparationError::AbortedByHook {
+                num_contracted: 11,
+                num_nodes: 100
+            }),
+            result.map(|_| ())
+        );
+    }
+
+    #[test]
+    fn reuse_buffers() {
+        use rand::rngs::StdRng;
+        use rand::Rng;
+        use rand::SeedableRng;
+
+        let mut rng: StdRng = SeedableRng::from_seed([6; 32]);
+        let mut buffers = PreparationBuffers::new();
+        let params = Params {
+            max_shortcuts: Some(50),
+            ..Default::default()
+        };
+        // the last graph has a different number of nodes
+        for (i, num_nodes) in [80, 80, 80, 120].iter().enumerate() {
+            let g = InputGraph::random(|| rng.gen(), *num_nodes, 2.0);
+            let params = if i == 1 { &params } else { &Params::default() };
+            let expected = FastGraphBuilder::build_with_params(&g, params);
+            let fast_graph =
+                FastGraphBuilder::try_build_with_buffers(&g, params, &mut buffers).unwrap();
+            assert_eq!(expected.get_node_ordering(), fast_graph.get_node_ordering());
+            assert_eq!(expected.get_num_out_edges(), fast_graph.get_num_out_edges());
+            assert_eq!(expected.get_num_in_edges(), fast_graph.get_num_in_edges());
+            assert_eq!(
+                expected.get_num_core_nodes(),
+                fast_graph.get_num_core_nodes()
+            );
+        }
+    }
+
+    #[test]
+    fn reuse_buffers_after_abort() {
+        use rand::Rng;
+
+        // a complete graph where each contraction adds shortcuts between all remaining nodes
+        let mut complete = InputGraph::new();
+        for i in 0..8 {
+            for j in 0..8 {
+                if i != j {
+                    complete.add_edge(i, j, 10 + i + j);
+                }
+            }
+        }
+        complete.freeze();
+        let mut params = Params::new(0.1, 0, 0, 0);
+        params.max_degree = Some(10);
+        let mut buffers = PreparationBuffers::new();
+        let result = FastGraphBuilder::try_build_with_buffers(&complete, &params, &mut buffers);
+        assert!(matches!(
+            result,
+            Err(PreparationError::MaxDegreeExceeded { max_degree: 10, .. })
+        ));
+        // the buffers can still be used for the next graph
+        let mut rng = crate::tests::create_rng();
+        let g = InputGraph::random(|| rng.gen(), 50, 2.0);
+        let expected = FastGraphBuilder::build(&g);
+        let fast_graph =
+            FastGraphBuilder::try_build_with_buffers(&g, &Params::default(), &mut buffers).unwrap();
+        assert_eq!(expected.get_node_ordering(), fast_graph.get_node_ordering());
+        assert_eq!(expected.get_num_out_edges(), fast_graph.get_num_out_edges());
+    }
+
     fn assert_path(
         fast_graph: &FastGraph,
         source: NodeId,
@@ -523,6 +2064,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn multiple_sources_top_level() {
+        // 0 -> 1 -> 2 <- 5
+        // 3 -> 4 ->/
+        let mut input_graph = InputGraph::new();
+        input_graph.add_edge(0, 1, 3);
+        input_graph.add_edge(1, 2, 4);
+        input_graph.add_edge(3, 4, 2);
+        input_graph.add_edge(4, 2, 3);
+        input_graph.add_edge(5, 2, 2);
+        input_graph.freeze();
+        let fast_graph = prepare(&input_graph);
+        let path = calc_path_multiple_sources_and_targets(
+            &fast_graph,
+            vec![(1, WEIGHT_MAX), (3, 3)],
+            vec![(2, 0)],
+        )
+        .unwrap();
+        assert_eq!(3, path.get_source());
+        assert_eq!(8, path.get_weight());
+        assert_eq!(&vec![3, 4, 2], path.get_nodes());
+    }
+
     #[test]
     fn multiple_targets() {
         // 0 <- 1 <- 2
@@ -629,7 +2193,7 @@ mod tests {
         targets: Vec<(NodeId, Weight)>,
     ) {
         let fast_path =
-            path_calculator.calc_path_multiple_sources_and_targets(&fast_graph, sources, targets);
+            path_calculator.calc_path_multiple_sources_and_targets(fast_graph, sources, targets);
         assert!(fast_path.is_none(), "there should be no path");
     }
 }
@@ -0,0 +1,289 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use std::convert::TryFrom;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::fast_graph::FastGraphEdge;
+use crate::preparation_info::PreparationInfo;
+use crate::FastGraph;
+
+const MAGIC: [u8; 8] = *b"FASTPATH";
+const FORMAT_VERSION: u32 = 2;
+
+/// Representation of a `FastGraph` that can be loaded on any platform, no matter which platform
+/// it was created on. All integers are stored as u64 and `usize::MAX`, which is used to mark
+/// invalid ids, is stored as `u64::MAX`, so the data does not depend on the size of `usize`. The
+/// byte order is determined by the serialization format alone, e.g. bincode always uses little
+/// endian, so a graph serialized on x86_64 can be deserialized on aarch64 or wasm32.
+///
+/// The data starts with a header that identifies the format and its version. Data without this
+/// header, e.g. a `FastGraph` that was serialized directly, is rejected with an error when it is
+/// deserialized as `FastGraphPortable`, rather than being misinterpreted.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FastGraphPortable {
+    header: FormatHeader,
+    num_nodes: u64,
+    pub ranks: Vec<u64>,
+    pub edges_fwd: Vec<FastGraphEdgePortable>,
+    pub first_edge_ids_fwd: Vec<u64>,
+
+    pub edges_bwd: Vec<FastGraphEdgePortable>,
+    pub first_edge_ids_bwd: Vec<u64>,
+
+    pub preparation_info: PreparationInfo,
+    pub num_core_nodes: u64,
+}
+
+impl FastGraphPortable {
+    pub fn new(fast_graph: &FastGraph) -> Self {
+        FastGraphPortable {
+            header: FormatHeader {
+                magic: MAGIC,
+                version: FORMAT_VERSION,
+            },
+            num_nodes: widen(fast_graph.get_num_nodes()),
+            ranks: widen_vec(&fast_graph.ranks),
+            edges_fwd: widen_edges(&fast_graph.edges_fwd),
+            first_edge_ids_fwd: widen_vec(&fast_graph.first_edge_ids_fwd),
+            edges_bwd: widen_edges(&fast_graph.edges_bwd),
+            first_edge_ids_bwd: widen_vec(&fast_graph.first_edge_ids_bwd),
+            preparation_info: fast_graph.preparation_info.clone(),
+            num_core_nodes: widen(fast_graph.num_core_nodes),
+        }
+    }
+
+    /// Converts this representation to a `FastGraph` for the current platform. Returns an error
+    /// if any of the integers does not fit into a `usize`, e.g. when a graph with more than 2^32
+    /// edges is loaded on a 32bit platform, or if the data does not form a valid graph, see
+    /// `FastGraph::validate`.
+    pub fn try_convert_to_usize(self) -> Result<FastGraph, String> {
+        let mut g = FastGraph::new(narrow(self.num_nodes)?);
+        g.ranks = narrow_vec(&self.ranks)?;
+        g.edges_fwd = narrow_edges(&self.edges_fwd)?;
+        g.first_edge_ids_fwd = narrow_vec(&self.first_edge_ids_fwd)?;
+        g.edges_bwd = narrow_edges(&self.edges_bwd)?;
+        g.first_edge_ids_bwd = narrow_vec(&self.first_edge_ids_bwd)?;
+        g.preparation_info = self.preparation_info;
+        g.num_core_nodes = narrow(self.num_core_nodes)?;
+        g.validate().map_err(|e| e.to_string())?;
+        Ok(g)
+    }
+}
+
+/// Portable equivalent to `FastGraphEdge`, see `FastGraphPortable` docs.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct FastGraphEdgePortable {
+    pub base_node: u64,
+    pub adj_node: u64,
+    pub weight: u64,
+    pub replaced_in_edge: u64,
+    pub replaced_out_edge: u64,
+    pub input_edge_id: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FormatHeader {
+    magic: [u8; 8],
+    version: u32,
+}
+
+impl Serialize for FormatHeader {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        (self.magic, self.version).serialize(s)
+    }
+}
+
+impl<'de> Deserialize<'de> for FormatHeader {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        // the header comes first, so we stop before interpreting any other data of the wrong
+        // format
+        let (magic, version) = <([u8; 8], u32)>::deserialize(d)?;
+        if magic != MAGIC {
+            return Err(D::Error::custom(
+                "the data is not a portable graph, it might have been serialized without \
+                 `serialize_portable`, e.g. by an older version of fast_paths",
+            ));
+        }
+        if version != FORMAT_VERSION {
+            return Err(D::Error::custom(format!(
+                "the portable graph has format version {}, but only version {} is supported",
+                version, FORMAT_VERSION
+            )));
+        }
+        Ok(FormatHeader { magic, version })
+    }
+}
+
+fn widen(int: usize) -> u64 {
+    if int == usize::MAX {
+        u64::MAX
+    } else {
+        int as u64
+    }
+}
+
+fn widen_vec(vec: &[usize]) -> Vec<u64> {
+    vec.iter().map(|i| widen(*i)).collect()
+}
+
+fn widen_edges(vec: &[FastGraphEdge]) -> Vec<FastGraphEdgePortable> {
+    vec.iter()
+        .map(|edge| FastGraphEdgePortable {
+            base_node: widen(edge.base_node),
+            adj_node: widen(edge.adj_node),
+            weight: widen(edge.weight),
+            replaced_in_edge: widen(edge.replaced_in_edge),
+            replaced_out_edge: widen(edge.replaced_out_edge),
+            input_edge_id: widen(edge.input_edge_id),
+        })
+        .collect()
+}
+
+fn narrow(int: u64) -> Result<usize, String> {
+    if int == u64::MAX {
+        Ok(usize::MAX)
+    } else {
+        usize::try_from(int).map_err(|_| {
+            format!(
+                "{} does not fit into the {}bit integers of this platform",
+                int,
+                usize::BITS
+            )
+        })
+    }
+}
+
+fn narrow_vec(vec: &[u64]) -> Result<Vec<usize>, String> {
+    vec.iter().map(|i| narrow(*i)).collect()
+}
+
+fn narrow_edges(vec: &[FastGraphEdgePortable]) -> Result<Vec<FastGraphEdge>, String> {
+    vec.iter()
+        .map(|edge| {
+            Ok(FastGraphEdge {
+                base_node: narrow(edge.base_node)?,
+                adj_node: narrow(edge.adj_node)?,
+                weight: narrow(edge.weight)?,
+                replaced_in_edge: narrow(edge.replaced_in_edge)?,
+                replaced_out_edge: narrow(edge.replaced_out_edge)?,
+                input_edge_id: narrow(edge.input_edge_id)?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fast_graph32::FastGraph32;
+    use crate::input_graph::InputGraph;
+
+    use super::*;
+
+    fn create_graph() -> FastGraph {
+        // 0 -> 1 -> 2, contracting 1 first yields the shortcut 0 -> 2
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 3);
+        g.add_edge(1, 2, 4);
+        g.freeze();
+        crate::prepare_with_order(&g, &[1, 0, 2]).unwrap()
+    }
+
+    #[test]
+    fn convert_and_back() {
+        let fast_graph = create_graph();
+        let portable = FastGraphPortable::new(&fast_graph);
+        assert_eq!(u64::MAX, portable.edges_fwd[0].replaced_in_edge);
+        let converted = portable.try_convert_to_usize().unwrap();
+        assert_eq!(fast_graph.get_fingerprint(), converted.get_fingerprint());
+        assert_eq!(
+            fast_graph.get_preparation_info(),
+            converted.get_preparation_info()
+        );
+    }
+
+    #[test]
+    fn same_bytes_on_all_platforms() {
+        let fast_graph = create_graph();
+        let bytes = bincode::serialize(&FastGraphPortable::new(&fast_graph)).unwrap();
+        // these bytes must never depend on the platform the test runs on
+        #[rustfmt::skip]
+        let expected: Vec<u8> = vec![
+            // header
+            b'F', b'A', b'S', b'T', b'P', b'A', b'T', b'H', 2, 0, 0, 0,
+            // num_nodes
+            3, 0, 0, 0, 0, 0, 0, 0,
+            // ranks
+            3, 0, 0, 0, 0, 0, 0, 0,
+            1, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0,
+            2, 0, 0, 0, 0, 0, 0, 0,
+        ];
+        assert_eq!(expected, bytes[..expected.len()]);
+        let max = [255; 8];
+        // the first forward edge is the original edge 1 -> 2, which has no replaced edges and is
+        // the second edge of the input graph
+        let edges_fwd = &bytes[expected.len() + 8..];
+        assert_eq!([1, 0, 0, 0, 0, 0, 0, 0], edges_fwd[..8]);
+        assert_eq!([2, 0, 0, 0, 0, 0, 0, 0], edges_fwd[8..16]);
+        assert_eq!([4, 0, 0, 0, 0, 0, 0, 0], edges_fwd[16..24]);
+        assert_eq!(max, edges_fwd[24..32]);
+        assert_eq!(max, edges_fwd[32..40]);
+        assert_eq!([1, 0, 0, 0, 0, 0, 0, 0], edges_fwd[40..48]);
+        let portable: FastGraphPortable = bincode::deserialize(&bytes).unwrap();
+        let converted = portable.try_convert_to_usize().unwrap();
+        assert_eq!(fast_graph.get_fingerprint(), converted.get_fingerprint());
+    }
+
+    #[test]
+    fn reject_other_formats() {
+        let fast_graph = create_graph();
+        let legacy = bincode::serialize(&fast_graph).unwrap();
+        let error = bincode::deserialize::<FastGraphPortable>(&legacy).unwrap_err();
+        assert!(error.to_string().contains("not a portable graph"));
+        let legacy32 = bincode::serialize(&FastGraph32::new(&fast_graph)).unwrap();
+        let error = bincode::deserialize::<FastGraphPortable>(&legacy32).unwrap_err();
+        assert!(error.to_string().contains("not a portable graph"));
+
+        let mut bytes = bincode::serialize(&FastGraphPortable::new(&fast_graph)).unwrap();
+        bytes[8] = 3;
+        let error = bincode::deserialize::<FastGraphPortable>(&bytes).unwrap_err();
+        assert!(error.to_string().contains("format version 3"));
+    }
+
+    #[test]
+    fn reject_invalid_graph() {
+        let mut portable = FastGraphPortable::new(&create_graph());
+        portable.edges_fwd[0].adj_node = 7;
+        assert!(portable.try_convert_to_usize().is_err());
+    }
+
+    #[test]
+    fn narrow_integers() {
+        assert_eq!(Ok(5), narrow(5));
+        assert_eq!(Ok(usize::MAX), narrow(u64::MAX));
+        assert_eq!(u64::MAX, widen(usize::MAX));
+        #[cfg(target_pointer_width = "32")]
+        assert!(narrow(1 << 32).is_err());
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(Ok(1 << 32), narrow(1 << 32));
+    }
+}
@@ -0,0 +1,302 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::constants::{EdgeId, NodeId};
+use crate::fast_graph::FastGraphEdge;
+use crate::fast_graph_validation::ValidationError;
+use crate::preparation_info::PreparationInfo;
+use crate::FastGraph;
+
+/// Special graph data-structure that stores a `FastGraph` without the per-node data of isolated
+/// nodes, i.e. nodes that have no edges in the prepared graph. This is useful for graphs with
+/// sparse node ids, where most of the `ranks` and `first_edge_ids` arrays would describe empty
+/// nodes otherwise. Runs of consecutive isolated nodes are stored as a single entry.
+///
+/// Converting back to a `FastGraph` yields a graph that answers all queries exactly like the
+/// original one. Only the ranks of the isolated nodes (and thus their position in the node
+/// ordering) might differ.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FastGraphSparse {
+    num_nodes: usize,
+    /// (first node, number of nodes) for each run of consecutive isolated nodes
+    pub isolated_node_runs: Vec<(NodeId, usize)>,
+    /// the ranks of all non-isolated nodes, ordered by node id
+    pub ranks: Vec<usize>,
+    pub edges_fwd: Vec<FastGraphEdge>,
+    /// the first edge ids of all non-isolated nodes, ordered by rank, plus the total edge count
+    pub first_edge_ids_fwd: Vec<EdgeId>,
+
+    pub edges_bwd: Vec<FastGraphEdge>,
+    pub first_edge_ids_bwd: Vec<EdgeId>,
+
+    pub preparation_info: PreparationInfo,
+    pub num_core_nodes: usize,
+}
+
+impl FastGraphSparse {
+    pub fn new(fast_graph: &FastGraph) -> Self {
+        let num_nodes = fast_graph.get_num_nodes();
+        let isolated = find_isolated_nodes(fast_graph);
+        let mut isolated_node_runs: Vec<(NodeId, usize)> = vec![];
+        let mut ranks = vec![];
+        for (node, is_isolated) in isolated.iter().enumerate() {
+            if !is_isolated {
+                ranks.push(fast_graph.ranks[node]);
+                continue;
+            }
+            match isolated_node_runs.last_mut() {
+                Some((first, len)) if *first + *len == node => *len += 1,
+                _ => isolated_node_runs.push((node, 1)),
+            }
+        }
+        let mut isolated_ranks = vec![false; num_nodes];
+        for (node, is_isolated) in isolated.iter().enumerate() {
+            if *is_isolated {
+                isolated_ranks[fast_graph.ranks[node]] = true;
+            }
+        }
+        FastGraphSparse {
+            num_nodes,
+            isolated_node_runs,
+            ranks,
+            edges_fwd: fast_graph.edges_fwd.clone(),
+            first_edge_ids_fwd: compress_first_edge_ids(
+                &fast_graph.first_edge_ids_fwd,
+                &isolated_ranks,
+            ),
+            edges_bwd: fast_graph.edges_bwd.clone(),
+            first_edge_ids_bwd: compress_first_edge_ids(
+                &fast_graph.first_edge_ids_bwd,
+                &isolated_ranks,
+            ),
+            preparation_info: fast_graph.preparation_info.clone(),
+            num_core_nodes: fast_graph.num_core_nodes,
+        }
+    }
+
+    /// Like `convert_to_fast_graph`, but checks that the result is a valid `FastGraph`, see
+    /// `FastGraph::validate`. Use this for data that might be corrupted, e.g. because it was read
+    /// from a file, because otherwise invalid data leads to panics or wrong results.
+    pub fn try_convert_to_fast_graph(self) -> Result<FastGraph, ValidationError> {
+        self.check_bounds()?;
+        let graph = self.convert_to_fast_graph();
+        graph.validate()?;
+        Ok(graph)
+    }
+
+    /// Converts the sparse representation back to an actual `FastGraph`. The isolated nodes are
+    /// assigned the remaining ranks in increasing order of their node ids. The data is not
+    /// checked, see `try_convert_to_fast_graph`.
+    pub fn convert_to_fast_graph(self) -> FastGraph {
+        let mut isolated = vec![false; self.num_nodes];
+        for (first, len) in &self.isolated_node_runs {
+            isolated[*first..*first + *len].fill(true);
+        }
+        let mut isolated_ranks = vec![true; self.num_nodes];
+        for rank in &self.ranks {
+            isolated_ranks[*rank] = false;
+        }
+        let mut free_ranks = isolated_ranks
+            .iter()
+            .enumerate()
+            .filter(|(_, is_isolated)| **is_isolated)
+            .map(|(rank, _)| rank);
+        let mut non_isolated_ranks = self.ranks.iter();
+        let mut g = FastGraph::new(self.num_nodes);
+        for (node, is_isolated) in isolated.iter().enumerate() {
+            g.ranks[node] = if *is_isolated {
+                free_ranks.next()
+            } else {
+                non_isolated_ranks.next().cloned()
+            }
+            .expect("invalid sparse graph, the number of ranks does not match the number of nodes");
+        }
+        g.first_edge_ids_fwd = expand_first_edge_ids(&self.first_edge_ids_fwd, &isolated_ranks);
+        g.first_edge_ids_bwd = expand_first_edge_ids(&self.first_edge_ids_bwd, &isolated_ranks);
+        g.edges_fwd = self.edges_fwd;
+        g.edges_bwd = self.edges_bwd;
+        g.preparation_info = self.preparation_info;
+        g.num_core_nodes = self.num_core_nodes;
+        g
+    }
+
+    /// Checks everything `convert_to_fast_graph` relies on, the rest is checked by
+    /// `FastGraph::validate` afterwards
+    fn check_bounds(&self) -> Result<(), ValidationError> {
+        // the runs must be ordered, must not overlap and together with the non-isolated nodes
+        // they must cover all nodes
+        let mut end = 0;
+        let mut num_isolated: usize = 0;
+        for (first, len) in &self.isolated_node_runs {
+            if *first < end {
+                return Err(ValidationError::InvalidRanks);
+            }
+            end = first
+                .checked_add(*len)
+                .filter(|end| *end <= self.num_nodes)
+                .ok_or(ValidationError::InvalidRanks)?;
+            num_isolated += len;
+        }
+        if num_isolated + self.ranks.len() != self.num_nodes
+            || self.ranks.iter().any(|rank| *rank >= self.num_nodes)
+        {
+            return Err(ValidationError::InvalidRanks);
+        }
+        if self.first_edge_ids_fwd.len() != self.ranks.len() + 1 {
+            return Err(ValidationError::InvalidFirstEdgeIds { forward: true });
+        }
+        if self.first_edge_ids_bwd.len() != self.ranks.len() + 1 {
+            return Err(ValidationError::InvalidFirstEdgeIds { forward: false });
+        }
+        Ok(())
+    }
+}
+
+/// Isolated nodes have no edges in the prepared graph and are not the adjacent node of any edge
+fn find_isolated_nodes(fast_graph: &FastGraph) -> Vec<bool> {
+    let mut isolated: Vec<bool> = (0..fast_graph.get_num_nodes())
+        .map(|node| {
+            fast_graph.begin_out_edges(node) == fast_graph.end_out_edges(node)
+                && fast_graph.begin_in_edges(node) == fast_graph.end_in_edges(node)
+        })
+        .collect();
+    for edge in fast_graph
+        .edges_fwd
+        .iter()
+        .chain(fast_graph.edges_bwd.iter())
+    {
+        isolated[edge.adj_node] = false;
+    }
+    isolated
+}
+
+fn compress_first_edge_ids(first_edge_ids: &[EdgeId], isolated_ranks: &[bool]) -> Vec<EdgeId> {
+    let mut result: Vec<EdgeId> = isolated_ranks
+        .iter()
+        .enumerate()
+        .filter(|(_, is_isolated)| !**is_isolated)
+        .map(|(rank, _)| first_edge_ids[rank])
+        .collect();
+    result.push(first_edge_ids[isolated_ranks.len()]);
+    result
+}
+
+fn expand_first_edge_ids(compressed: &[EdgeId], isolated_ranks: &[bool]) -> Vec<EdgeId> {
+    let mut result = Vec::with_capacity(isolated_ranks.len() + 1);
+    let mut k = 0;
+    for is_isolated in isolated_ranks {
+        // isolated nodes have no edges, so their first edge id equals the one of the next node
+        result.push(compressed[k]);
+        if !is_isolated {
+            k += 1;
+        }
+    }
+    result.push(compressed[k]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{calc_path, prepare, InputGraph};
+
+    #[test]
+    fn round_trip() {
+        // only a few of the node ids in [0, 1000) are actually used
+        let mut g = InputGraph::new();
+        g.add_edge(3, 500, 6);
+        g.add_edge(500, 501, 1);
+        g.add_edge(501, 3, 4);
+        g.add_edge(501, 999, 2);
+        g.add_edge_bidir(200, 3, 3);
+        g.freeze();
+        let fast_graph = prepare(&g);
+        let sparse = FastGraphSparse::new(&fast_graph);
+        assert_eq!(
+            vec![(0, 3), (4, 196), (201, 299), (502, 497)],
+            sparse.isolated_node_runs
+        );
+        assert_eq!(5, sparse.ranks.len());
+        assert_eq!(6, sparse.first_edge_ids_fwd.len());
+        assert_eq!(6, sparse.first_edge_ids_bwd.len());
+
+        let size = bincode::serialize(&fast_graph).unwrap().len();
+        let size_sparse = bincode::serialize(&sparse).unwrap().len();
+        assert!(size_sparse * 10 < size, "{} vs. {}", size_sparse, size);
+
+        let restored = sparse.convert_to_fast_graph();
+        assert_eq!(fast_graph.get_num_nodes(), restored.get_num_nodes());
+        assert_eq!(fast_graph.edges_fwd.len(), restored.edges_fwd.len());
+        assert_eq!(fast_graph.edges_bwd.len(), restored.edges_bwd.len());
+        for node in [3, 200, 500, 501, 999] {
+            assert_eq!(fast_graph.ranks[node], restored.ranks[node]);
+        }
+        // the ranks are still a permutation of all node ids
+        let mut ranks = restored.ranks.clone();
+        ranks.sort_unstable();
+        assert_eq!((0..1000).collect::<Vec<_>>(), ranks);
+        for source in [0, 3, 200, 500, 501, 999] {
+            for target in [0, 3, 200, 500, 501, 999] {
+                assert_eq!(
+                    calc_path(&fast_graph, source, target),
+                    calc_path(&restored, source, target)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_data() {
+        let mut g = InputGraph::new();
+        g.add_edge(3, 500, 6);
+        g.add_edge(500, 501, 1);
+        g.add_edge_bidir(200, 3, 3);
+        g.freeze();
+        let sparse = FastGraphSparse::new(&prepare(&g));
+        assert!(sparse.clone().try_convert_to_fast_graph().is_ok());
+
+        let mut overlapping = sparse.clone();
+        overlapping.isolated_node_runs[1].0 -= 2;
+        let mut out_of_bounds = sparse.clone();
+        out_of_bounds.isolated_node_runs.last_mut().unwrap().1 = usize::MAX;
+        let mut duplicate_rank = sparse.clone();
+        duplicate_rank.ranks[1] = duplicate_rank.ranks[0];
+        let mut invalid_rank = sparse.clone();
+        invalid_rank.ranks[0] = 502;
+        for invalid in [overlapping, out_of_bounds, duplicate_rank, invalid_rank] {
+            assert_eq!(
+                Err(ValidationError::InvalidRanks),
+                invalid.try_convert_to_fast_graph().map(|_| ())
+            );
+        }
+
+        let mut first_edge_ids = sparse.clone();
+        first_edge_ids.first_edge_ids_bwd.pop();
+        assert_eq!(
+            Err(ValidationError::InvalidFirstEdgeIds { forward: false }),
+            first_edge_ids.try_convert_to_fast_graph().map(|_| ())
+        );
+        let mut invalid_edge = sparse;
+        invalid_edge.edges_fwd[0].adj_node = 502;
+        assert!(invalid_edge.try_convert_to_fast_graph().is_err());
+    }
+}
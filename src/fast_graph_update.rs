@@ -0,0 +1,610 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use std::collections::{BTreeSet, BinaryHeap};
+
+use crate::constants::{EdgeId, NodeId, Weight, INVALID_EDGE, WEIGHT_MAX};
+use crate::error::UpdateError;
+use crate::fast_graph::{FastGraph, FastGraphEdge};
+use crate::heap_item::HeapItem;
+
+/// The maximum number of settled nodes per witness search. If no witness is found a shortcut is
+/// added, which is always correct but might not be necessary.
+const MAX_SETTLED_NODES: usize = 100;
+
+/// The edges of a `FastGraph` as returned by `update_weights`, in the order of the `FastGraph`
+/// fields `edges_fwd`, `first_edge_ids_fwd`, `edges_bwd` and `first_edge_ids_bwd`.
+pub type UpdatedEdges = (
+    Vec<FastGraphEdge>,
+    Vec<EdgeId>,
+    Vec<FastGraphEdge>,
+    Vec<EdgeId>,
+);
+
+/// Applies the given `(from, to, weight)` changes to the original edges of `graph` and repairs the
+/// contraction hierarchy, see `FastGraph::update_weights`.
+pub fn update_weights(
+    graph: &FastGraph,
+    changes: &[(NodeId, NodeId, Weight)],
+) -> Result<UpdatedEdges, UpdateError> {
+    update(graph, changes, true)
+}
+
+/// Adds the given `(from, to, weight)` edges to `graph` and repairs the contraction hierarchy, see
+/// `FastGraph::add_edges`.
+pub fn add_edges(
+    graph: &FastGraph,
+    edges: &[(NodeId, NodeId, Weight)],
+) -> Result<UpdatedEdges, UpdateError> {
+    update(graph, edges, false)
+}
+
+fn update(
+    graph: &FastGraph,
+    changes: &[(NodeId, NodeId, Weight)],
+    reject_increases: bool,
+) -> Result<UpdatedEdges, UpdateError> {
+    if graph.get_num_core_nodes() > 0 {
+        return Err(UpdateError::CoreNodes {
+            num_core_nodes: graph.get_num_core_nodes(),
+        });
+    }
+    let mut updater = Updater::new(graph);
+    for &(from, to, weight) in changes {
+        updater.check_change(from, to, weight, reject_increases)?;
+    }
+    for &(from, to, weight) in changes {
+        updater.apply_change(from, to, weight);
+    }
+    updater.repair();
+    Ok(updater.into_edges())
+}
+
+/// An edge of the contraction hierarchy that goes from `from` to `to` and is stored at the lower
+/// ranked of the two nodes. For shortcuts `replaced_in_edge` and `replaced_out_edge` are the ids
+/// of the two edges (in `Updater::edges`) of the bypassed node, for original edges they are
+/// `INVALID_EDGE`.
+#[derive(Clone, Debug)]
+struct UpdateEdge {
+    from: NodeId,
+    to: NodeId,
+    weight: Weight,
+    replaced_in_edge: usize,
+    replaced_out_edge: usize,
+    /// see `FastGraphEdge::input_edge_id`
+    input_edge_id: EdgeId,
+}
+
+struct Updater {
+    ranks: Vec<usize>,
+    edges: Vec<UpdateEdge>,
+    /// the edges stored at each node that lead to higher ranked nodes
+    up_out_edges: Vec<Vec<usize>>,
+    /// the edges stored at each node that come from higher ranked nodes
+    up_in_edges: Vec<Vec<usize>>,
+    /// the edges that lead from each node to lower ranked nodes (and are stored there)
+    down_out_edges: Vec<Vec<usize>>,
+    /// the edges whose weight decreased or that were added and still need to be processed
+    changed: Vec<bool>,
+    /// the ranks of the nodes that have changed edges
+    dirty: BTreeSet<usize>,
+    weights: Vec<Weight>,
+    settled: Vec<bool>,
+    touched: Vec<NodeId>,
+}
+
+impl Updater {
+    fn new(graph: &FastGraph) -> Self {
+        let num_nodes = graph.get_num_nodes();
+        let num_fwd = graph.get_num_out_edges();
+        let to_index = |edge_id: EdgeId, offset: usize| {
+            if edge_id == INVALID_EDGE {
+                INVALID_EDGE
+            } else {
+                edge_id + offset
+            }
+        };
+        let mut updater = Updater {
+            ranks: graph.get_ranks().to_vec(),
+            edges: Vec::with_capacity(num_fwd + graph.get_num_in_edges()),
+            up_out_edges: vec![vec![]; num_nodes],
+            up_in_edges: vec![vec![]; num_nodes],
+            down_out_edges: vec![vec![]; num_nodes],
+            changed: vec![],
+            dirty: BTreeSet::new(),
+            weights: vec![WEIGHT_MAX; num_nodes],
+            settled: vec![false; num_nodes],
+            touched: vec![],
+        };
+        // the replaced in-edges are backward edges, which come after all forward edges
+        for edge in graph.get_edges_fwd() {
+            updater.add_edge(UpdateEdge {
+                from: edge.base_node,
+                to: edge.adj_node,
+                weight: edge.weight,
+                replaced_in_edge: to_index(edge.replaced_in_edge, num_fwd),
+                replaced_out_edge: to_index(edge.replaced_out_edge, 0),
+                input_edge_id: edge.input_edge_id,
+            });
+        }
+        for edge in graph.get_edges_bwd() {
+            updater.add_edge(UpdateEdge {
+                from: edge.adj_node,
+                to: edge.base_node,
+                weight: edge.weight,
+                replaced_in_edge: to_index(edge.replaced_in_edge, num_fwd),
+                replaced_out_edge: to_index(edge.replaced_out_edge, 0),
+                input_edge_id: edge.input_edge_id,
+            });
+        }
+        updater.changed = vec![false; updater.edges.len()];
+        updater
+    }
+
+    fn add_edge(&mut self, edge: UpdateEdge) -> usize {
+        let id = self.edges.len();
+        if self.ranks[edge.from] < self.ranks[edge.to] {
+            self.up_out_edges[edge.from].push(id);
+        } else {
+            self.up_in_edges[edge.to].push(id);
+            self.down_out_edges[edge.from].push(id);
+        }
+        self.edges.push(edge);
+        id
+    }
+
+    /// Returns the edge from `from` to `to`, either an original edge or a shortcut.
+    fn find_edge(&self, from: NodeId, to: NodeId) -> Option<usize> {
+        let found = if self.ranks[from] < self.ranks[to] {
+            self.up_out_edges[from]
+                .iter()
+                .find(|id| self.edges[**id].to == to)
+        } else {
+            self.up_in_edges[to]
+                .iter()
+                .find(|id| self.edges[**id].from == from)
+        };
+        found.copied()
+    }
+
+    fn is_shortcut(&self, id: usize) -> bool {
+        self.edges[id].replaced_in_edge != INVALID_EDGE
+    }
+
+    fn check_change(
+        &self,
+        from: NodeId,
+        to: NodeId,
+        weight: Weight,
+        reject_increases: bool,
+    ) -> Result<(), UpdateError> {
+        let num_nodes = self.ranks.len();
+        if from >= num_nodes || to >= num_nodes {
+            return Err(UpdateError::InvalidNode {
+                from,
+                to,
+                num_nodes,
+            });
+        }
+        if from == to || weight < 1 || weight == WEIGHT_MAX {
+            return Err(UpdateError::InvalidEdge { from, to, weight });
+        }
+        if !reject_increases {
+            return Ok(());
+        }
+        if let Some(id) = self.find_edge(from, to) {
+            let current = self.edges[id].weight;
+            if !self.is_shortcut(id) && weight > current {
+                return Err(UpdateError::WeightIncrease {
+                    from,
+                    to,
+                    current,
+                    weight,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_change(&mut self, from: NodeId, to: NodeId, weight: Weight) {
+        match self.find_edge(from, to) {
+            // a new edge that is not shorter than the existing one would never be used
+            Some(id) if self.edges[id].weight <= weight => {}
+            // edges that are missing because the preparation found a shorter path are simply
+            // added again, and shortcuts are replaced by the (now shorter) original edge, whose
+            // input edge id we do not know
+            Some(id) => {
+                let input_edge_id = self.edges[id].input_edge_id;
+                self.edges[id] = UpdateEdge {
+                    from,
+                    to,
+                    weight,
+                    replaced_in_edge: INVALID_EDGE,
+                    replaced_out_edge: INVALID_EDGE,
+                    input_edge_id,
+                };
+                self.mark_changed(id);
+            }
+            None => {
+                let id = self.add_edge(UpdateEdge {
+                    from,
+                    to,
+                    weight,
+                    replaced_in_edge: INVALID_EDGE,
+                    replaced_out_edge: INVALID_EDGE,
+                    input_edge_id: INVALID_EDGE,
+                });
+                self.changed.push(false);
+                self.mark_changed(id);
+            }
+        }
+    }
+
+    fn mark_changed(&mut self, id: usize) {
+        self.changed[id] = true;
+        let edge = &self.edges[id];
+        self.dirty
+            .insert(self.ranks[edge.from].min(self.ranks[edge.to]));
+    }
+
+    /// Processes the nodes with changed edges in the order of their ranks, like the contraction
+    /// did: since weights only decreased, the witnesses that were found before are still valid,
+    /// so only the paths via a changed edge can require new or shorter shortcuts.
+    fn repair(&mut self) {
+        let order = {
+            let mut order = vec![0; self.ranks.len()];
+            for (node, rank) in self.ranks.iter().enumerate() {
+                order[*rank] = node;
+            }
+            order
+        };
+        while let Some(rank) = self.dirty.pop_first() {
+            let node = order[rank];
+            let in_edges = self.up_in_edges[node].clone();
+            let out_edges = self.up_out_edges[node].clone();
+            for &in_edge in &in_edges {
+                let targets: Vec<usize> = out_edges
+                    .iter()
+                    .copied()
+                    .filter(|out_edge| self.changed[in_edge] || self.changed[*out_edge])
+                    .filter(|out_edge| self.edges[*out_edge].to != self.edges[in_edge].from)
+                    .collect();
+                if !targets.is_empty() {
+                    self.bypass(node, in_edge, &targets);
+                }
+            }
+            for id in in_edges.iter().chain(out_edges.iter()) {
+                self.changed[*id] = false;
+            }
+        }
+    }
+
+    /// Makes sure that for each of the given out-edges of `node` there is a path from the start
+    /// of `in_edge` to the end of the out-edge that is not longer than the path via `node`, by
+    /// adding or updating a shortcut if no such witness can be found.
+    fn bypass(&mut self, node: NodeId, in_edge: usize, out_edges: &[usize]) {
+        let source = self.edges[in_edge].from;
+        let max_weight = out_edges
+            .iter()
+            .map(|out_edge| self.edges[in_edge].weight + self.edges[*out_edge].weight)
+            .max()
+            .unwrap();
+        self.witness_search(source, node, max_weight);
+        for &out_edge in out_edges {
+            let target = self.edges[out_edge].to;
+            let weight = self.edges[in_edge].weight + self.edges[out_edge].weight;
+            let existing = self.find_edge(source, target);
+            if let Some(id) = existing {
+                // the weight of a shortcut via this node has to match its replaced edges, even if
+                // there is a witness now
+                let edge = &self.edges[id];
+                if edge.replaced_in_edge == in_edge && edge.replaced_out_edge == out_edge {
+                    if edge.weight > weight {
+                        self.edges[id].weight = weight;
+                        self.mark_changed(id);
+                    }
+                    continue;
+                }
+            }
+            if self.settled[target] && self.weights[target] <= weight {
+                continue;
+            }
+            let shortcut = UpdateEdge {
+                from: source,
+                to: target,
+                weight,
+                replaced_in_edge: in_edge,
+                replaced_out_edge: out_edge,
+                input_edge_id: INVALID_EDGE,
+            };
+            match existing {
+                Some(id) if self.edges[id].weight <= weight => {}
+                Some(id) => {
+                    self.edges[id] = shortcut;
+                    self.mark_changed(id);
+                }
+                None => {
+                    let id = self.add_edge(shortcut);
+                    self.changed.push(false);
+                    self.mark_changed(id);
+                }
+            }
+        }
+        self.reset_search();
+    }
+
+    /// Runs a Dijkstra search from `source` that only uses nodes with a higher rank than
+    /// `avoid_node`.
+    fn witness_search(&mut self, source: NodeId, avoid_node: NodeId, max_weight: Weight) {
+        let min_rank = self.ranks[avoid_node];
+        let mut heap = BinaryHeap::new();
+        self.weights[source] = 0;
+        self.touched.push(source);
+        heap.push(HeapItem::new(0, source));
+        let mut num_settled = 0;
+        while let Some(curr) = heap.pop() {
+            if self.settled[curr.node_id] {
+                continue;
+            }
+            if curr.weight > max_weight || num_settled >= MAX_SETTLED_NODES {
+                break;
+            }
+            self.settled[curr.node_id] = true;
+            num_settled += 1;
+            for &id in self.up_out_edges[curr.node_id]
+                .iter()
+                .chain(self.down_out_edges[curr.node_id].iter())
+            {
+                let edge = &self.edges[id];
+                if self.ranks[edge.to] <= min_rank || self.settled[edge.to] {
+                    continue;
+                }
+                let weight = curr.weight + edge.weight;
+                if weight < self.weights[edge.to] {
+                    if self.weights[edge.to] == WEIGHT_MAX {
+                        self.touched.push(edge.to);
+                    }
+                    self.weights[edge.to] = weight;
+                    heap.push(HeapItem::new(weight, edge.to));
+                }
+            }
+        }
+    }
+
+    fn reset_search(&mut self) {
+        for node in self.touched.drain(..) {
+            self.weights[node] = WEIGHT_MAX;
+            self.settled[node] = false;
+        }
+    }
+
+    fn into_edges(self) -> UpdatedEdges {
+        let num_nodes = self.ranks.len();
+        let mut order = vec![0; num_nodes];
+        for (node, rank) in self.ranks.iter().enumerate() {
+            order[*rank] = node;
+        }
+        // the new ids of the forward and backward edges
+        let mut new_ids = vec![INVALID_EDGE; self.edges.len()];
+        let mut first_edge_ids_fwd = vec![0; num_nodes + 1];
+        let mut first_edge_ids_bwd = vec![0; num_nodes + 1];
+        let (mut num_fwd, mut num_bwd) = (0, 0);
+        for (rank, node) in order.iter().enumerate() {
+            for id in &self.up_out_edges[*node] {
+                new_ids[*id] = num_fwd;
+                num_fwd += 1;
+            }
+            for id in &self.up_in_edges[*node] {
+                new_ids[*id] = num_bwd;
+                num_bwd += 1;
+            }
+            first_edge_ids_fwd[rank + 1] = num_fwd;
+            first_edge_ids_bwd[rank + 1] = num_bwd;
+        }
+        let convert = |id: &usize, base_node: NodeId, adj_node: NodeId| {
+            let edge = &self.edges[*id];
+            let new_id = |id: usize| {
+                if id == INVALID_EDGE {
+                    INVALID_EDGE
+                } else {
+                    new_ids[id]
+                }
+            };
+            FastGraphEdge::new(
+                base_node,
+                adj_node,
+                edge.weight,
+                new_id(edge.replaced_in_edge),
+                new_id(edge.replaced_out_edge),
+            )
+            .with_input_edge_id(edge.input_edge_id)
+        };
+        let edges_fwd = order
+            .iter()
+            .flat_map(|node| self.up_out_edges[*node].iter())
+            .map(|id| convert(id, self.edges[*id].from, self.edges[*id].to))
+            .collect();
+        let edges_bwd = order
+            .iter()
+            .flat_map(|node| self.up_in_edges[*node].iter())
+            .map(|id| convert(id, self.edges[*id].to, self.edges[*id].from))
+            .collect();
+        (edges_fwd, first_edge_ids_fwd, edges_bwd, first_edge_ids_bwd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::UpdateError;
+    use crate::input_graph::InputGraph;
+
+    #[test]
+    fn decrease_weights() {
+        // 0 -> 1 -> 2
+        //  \------>/
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 5);
+        g.add_edge(1, 2, 5);
+        g.add_edge(0, 2, 3);
+        g.freeze();
+        // node 1 is contracted first, and no shortcut is needed because of the edge 0 -> 2
+        let mut fast_graph = crate::prepare_with_order(&g, &[1, 0, 2]).unwrap();
+        assert_eq!(
+            3,
+            fast_graph.get_num_out_edges() + fast_graph.get_num_in_edges()
+        );
+        assert_eq!(3, crate::calc_path(&fast_graph, 0, 2).unwrap().get_weight());
+        fast_graph.update_weights(&[(0, 1, 1), (1, 2, 1)]).unwrap();
+        assert_eq!(Ok(()), fast_graph.validate());
+        let path = crate::calc_path(&fast_graph, 0, 2).unwrap();
+        assert_eq!(&vec![0, 1, 2], path.get_nodes());
+        assert_eq!(2, path.get_weight());
+        // the updated edges are still the same input edges
+        assert_eq!(Some(vec![0, 2]), path.as_input_edge_ids(&fast_graph));
+        assert_eq!(1, crate::calc_path(&fast_graph, 1, 2).unwrap().get_weight());
+        // the edge 0 -> 2 was replaced by the shortcut, but it can still be updated
+        fast_graph.update_weights(&[(0, 2, 1)]).unwrap();
+        let path = crate::calc_path(&fast_graph, 0, 2).unwrap();
+        assert_eq!(&vec![0, 2], path.get_nodes());
+        assert_eq!(1, path.get_weight());
+    }
+
+    #[test]
+    fn add_missing_edge() {
+        // 0 -> 1 -> 2
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 5);
+        g.add_edge(1, 2, 5);
+        g.freeze();
+        let mut fast_graph = crate::prepare_with_order(&g, &[0, 2, 1]).unwrap();
+        assert!(crate::calc_path(&fast_graph, 2, 0).is_none());
+        fast_graph.update_weights(&[(2, 0, 4)]).unwrap();
+        assert_eq!(Ok(()), fast_graph.validate());
+        assert_eq!(4, crate::calc_path(&fast_graph, 2, 0).unwrap().get_weight());
+        assert_eq!(9, crate::calc_path(&fast_graph, 2, 1).unwrap().get_weight());
+    }
+
+    #[test]
+    fn add_edges() {
+        // 0 -> 1 -> 2 -> 3
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 5);
+        g.add_edge(1, 2, 5);
+        g.add_edge(2, 3, 5);
+        g.freeze();
+        let mut fast_graph = crate::prepare_with_order(&g, &[0, 3, 1, 2]).unwrap();
+        assert!(crate::calc_path(&fast_graph, 3, 0).is_none());
+        // a ferry from 3 to 1, and a parallel edge that is longer than the existing one
+        fast_graph.add_edges(&[(3, 1, 2), (1, 2, 8)]).unwrap();
+        assert_eq!(Ok(()), fast_graph.validate());
+        let path = crate::calc_path(&fast_graph, 3, 2).unwrap();
+        assert_eq!(&vec![3, 1, 2], path.get_nodes());
+        assert_eq!(7, path.get_weight());
+        assert!(crate::calc_path(&fast_graph, 3, 0).is_none());
+        // a shortcut from 0 to 2
+        fast_graph.add_edges(&[(0, 2, 3)]).unwrap();
+        assert_eq!(8, crate::calc_path(&fast_graph, 0, 3).unwrap().get_weight());
+        assert_eq!(
+            Err(UpdateError::InvalidNode {
+                from: 0,
+                to: 4,
+                num_nodes: 4
+            }),
+            fast_graph.add_edges(&[(0, 4, 3)])
+        );
+    }
+
+    #[test]
+    fn reject_core_nodes() {
+        // 0 -> 1 -> 2 -> 0
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 5);
+        g.add_edge(1, 2, 5);
+        g.add_edge(2, 0, 5);
+        g.freeze();
+        let params = crate::Params {
+            max_shortcuts: Some(0),
+            ..Default::default()
+        };
+        let mut fast_graph = crate::prepare_with_params(&g, &params);
+        let num_core_nodes = fast_graph.get_num_core_nodes();
+        assert!(num_core_nodes > 0);
+        let fingerprint = fast_graph.get_fingerprint();
+        assert_eq!(
+            Err(UpdateError::CoreNodes { num_core_nodes }),
+            fast_graph.add_edges(&[(0, 2, 3)])
+        );
+        assert_eq!(
+            Err(UpdateError::CoreNodes { num_core_nodes }),
+            fast_graph.update_weights(&[(0, 1, 3)])
+        );
+        assert_eq!(fingerprint, fast_graph.get_fingerprint());
+    }
+
+    #[test]
+    fn reject_increase() {
+        // 0 -> 1 -> 2
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 5);
+        g.add_edge(1, 2, 5);
+        g.freeze();
+        let mut fast_graph = crate::prepare(&g);
+        let fingerprint = fast_graph.get_fingerprint();
+        assert_eq!(
+            Err(UpdateError::WeightIncrease {
+                from: 1,
+                to: 2,
+                current: 5,
+                weight: 6
+            }),
+            fast_graph.update_weights(&[(0, 1, 4), (1, 2, 6)])
+        );
+        assert_eq!(
+            Err(UpdateError::InvalidNode {
+                from: 0,
+                to: 3,
+                num_nodes: 3
+            }),
+            fast_graph.update_weights(&[(0, 3, 4)])
+        );
+        assert_eq!(
+            Err(UpdateError::InvalidEdge {
+                from: 0,
+                to: 0,
+                weight: 4
+            }),
+            fast_graph.update_weights(&[(0, 0, 4)])
+        );
+        assert_eq!(
+            Err(UpdateError::InvalidEdge {
+                from: 0,
+                to: 1,
+                weight: 0
+            }),
+            fast_graph.update_weights(&[(0, 1, 0)])
+        );
+        assert_eq!(fingerprint, fast_graph.get_fingerprint());
+        // unchanged weights are fine
+        fast_graph.update_weights(&[(0, 1, 5)]).unwrap();
+        assert_eq!(
+            10,
+            crate::calc_path(&fast_graph, 0, 2).unwrap().get_weight()
+        );
+    }
+}
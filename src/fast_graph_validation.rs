@@ -0,0 +1,161 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use std::error::Error;
+use std::fmt;
+
+use crate::constants::{EdgeId, INVALID_EDGE};
+use crate::fast_graph::{FastGraph, FastGraphEdge};
+
+/// Describes why the parts passed to `FastGraph::from_parts` do not form a valid `FastGraph`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The ranks are not a permutation of the node ids
+    InvalidRanks,
+    /// The first edge ids do not start at zero, are not monotonically increasing, or do not end
+    /// with the number of edges
+    InvalidFirstEdgeIds { forward: bool },
+    /// An edge does not belong to the node its position refers to, points to an invalid node, or
+    /// does not point to a node with higher rank (unless both nodes belong to the core)
+    InvalidEdge { forward: bool, edge_id: EdgeId },
+    /// A shortcut does not reference two edges that form a path with the same end points and
+    /// weight
+    InvalidShortcut { forward: bool, edge_id: EdgeId },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let direction = |forward: &bool| if *forward { "forward" } else { "backward" };
+        match self {
+            ValidationError::InvalidRanks => {
+                write!(f, "the ranks must be a permutation of the node ids")
+            }
+            ValidationError::InvalidFirstEdgeIds { forward } => write!(
+                f,
+                "the {} first edge ids must start with zero, increase monotonically and end with \
+                 the number of edges",
+                direction(forward)
+            ),
+            ValidationError::InvalidEdge { forward, edge_id } => write!(
+                f,
+                "the {} edge {} must start at the node of its rank and point to a node with \
+                 higher rank",
+                direction(forward),
+                edge_id
+            ),
+            ValidationError::InvalidShortcut { forward, edge_id } => write!(
+                f,
+                "the {} shortcut {} must replace two edges that form a path with the same end \
+                 points and weight",
+                direction(forward),
+                edge_id
+            ),
+        }
+    }
+}
+
+impl Error for ValidationError {}
+
+/// Checks all the invariants the query algorithms rely on
+pub fn validate(graph: &FastGraph) -> Result<(), ValidationError> {
+    let num_nodes = graph.get_num_nodes();
+    if graph.ranks.len() != num_nodes {
+        return Err(ValidationError::InvalidRanks);
+    }
+    let mut seen = vec![false; num_nodes];
+    for rank in &graph.ranks {
+        if *rank >= num_nodes || seen[*rank] {
+            return Err(ValidationError::InvalidRanks);
+        }
+        seen[*rank] = true;
+    }
+    validate_first_edge_ids(&graph.first_edge_ids_fwd, graph.edges_fwd.len(), num_nodes)
+        .map_err(|_| ValidationError::InvalidFirstEdgeIds { forward: true })?;
+    validate_first_edge_ids(&graph.first_edge_ids_bwd, graph.edges_bwd.len(), num_nodes)
+        .map_err(|_| ValidationError::InvalidFirstEdgeIds { forward: false })?;
+
+    // edges between the uncontracted core nodes may also point to nodes with lower rank
+    let core_rank = num_nodes.saturating_sub(graph.num_core_nodes);
+    let ordering = graph.get_node_ordering();
+    for forward in [true, false] {
+        let (edges, first_edge_ids) = if forward {
+            (&graph.edges_fwd, &graph.first_edge_ids_fwd)
+        } else {
+            (&graph.edges_bwd, &graph.first_edge_ids_bwd)
+        };
+        for (rank, node) in ordering.iter().enumerate() {
+            let begin = first_edge_ids[rank];
+            let end = first_edge_ids[rank + 1];
+            for (edge_id, edge) in edges.iter().enumerate().take(end).skip(begin) {
+                if edge.base_node != *node
+                    || edge.adj_node >= num_nodes
+                    || graph.ranks[edge.adj_node] == rank
+                    || (graph.ranks[edge.adj_node] < rank && graph.ranks[edge.adj_node] < core_rank)
+                {
+                    return Err(ValidationError::InvalidEdge { forward, edge_id });
+                }
+                if !is_valid_shortcut(graph, edge, forward) {
+                    return Err(ValidationError::InvalidShortcut { forward, edge_id });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validate_first_edge_ids(
+    first_edge_ids: &[EdgeId],
+    num_edges: usize,
+    num_nodes: usize,
+) -> Result<(), ()> {
+    if first_edge_ids.len() != num_nodes + 1
+        || first_edge_ids[0] != 0
+        || first_edge_ids[num_nodes] != num_edges
+        || first_edge_ids.windows(2).any(|pair| pair[0] > pair[1])
+    {
+        return Err(());
+    }
+    Ok(())
+}
+
+/// Checks that a shortcut replaces an in-edge and an out-edge of the same (lower ranked) center
+/// node that together connect the end points of the shortcut with the same weight. Original
+/// edges must not reference any other edges.
+fn is_valid_shortcut(graph: &FastGraph, edge: &FastGraphEdge, forward: bool) -> bool {
+    let in_edge_id = edge.replaced_in_edge;
+    let out_edge_id = edge.replaced_out_edge;
+    if in_edge_id == INVALID_EDGE || out_edge_id == INVALID_EDGE {
+        return in_edge_id == out_edge_id;
+    }
+    if in_edge_id >= graph.edges_bwd.len() || out_edge_id >= graph.edges_fwd.len() {
+        return false;
+    }
+    // forward edges point from base to adj node, backward edges from adj to base node
+    let (from, to) = if forward {
+        (edge.base_node, edge.adj_node)
+    } else {
+        (edge.adj_node, edge.base_node)
+    };
+    let in_edge = &graph.edges_bwd[in_edge_id];
+    let out_edge = &graph.edges_fwd[out_edge_id];
+    in_edge.base_node == out_edge.base_node
+        && in_edge.adj_node == from
+        && out_edge.adj_node == to
+        && in_edge.weight.checked_add(out_edge.weight) == Some(edge.weight)
+}
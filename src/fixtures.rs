@@ -0,0 +1,165 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+//! Small graphs that are hard to get right, e.g. for testing code that builds on this crate. All
+//! graphs are frozen already, and `all` returns all of them along with their names.
+
+use crate::constants::{Weight, WEIGHT_MAX};
+use crate::input_graph::InputGraph;
+
+/// Returns all the graphs of this module along with their names.
+pub fn all() -> Vec<(&'static str, InputGraph)> {
+    vec![
+        ("isolated_nodes", isolated_nodes()),
+        ("disconnected", disconnected()),
+        ("large_weights", large_weights()),
+        ("parallel_edges", parallel_edges()),
+        ("clique", clique(12)),
+        ("long_chain", long_chain(200)),
+        ("one_way_ring", one_way_ring(50)),
+        ("star", star(100)),
+        ("equal_weights_grid", equal_weights_grid(10)),
+    ]
+}
+
+/// A graph where most nodes, including the first and the last one, have no edges at all
+pub fn isolated_nodes() -> InputGraph {
+    let mut g = InputGraph::new();
+    g.add_edge(2, 5, 3);
+    g.add_edge(5, 7, 1);
+    g.add_edge_bidir(7, 2, 4);
+    g.set_min_num_nodes(10);
+    g.freeze();
+    g
+}
+
+/// Two components that are not connected to each other, one of them only in one direction
+pub fn disconnected() -> InputGraph {
+    let mut g = InputGraph::new();
+    g.add_edge_bidir(0, 1, 1);
+    g.add_edge_bidir(1, 2, 2);
+    g.add_edge_bidir(2, 0, 4);
+    g.add_edge(3, 4, 1);
+    g.add_edge(4, 5, 1);
+    g.add_edge(3, 5, 3);
+    g.freeze();
+    g
+}
+
+/// A graph whose edge weights are so large that the weights of the longest shortest paths are
+/// only a bit smaller than `WEIGHT_MAX / 2`
+pub fn large_weights() -> InputGraph {
+    const W: Weight = WEIGHT_MAX / 16;
+    let mut g = InputGraph::new();
+    for i in 0..4 {
+        g.add_edge_bidir(i, i + 1, W);
+    }
+    // these are a bit longer than the path along the chain
+    g.add_edge(0, 4, 4 * W + 1);
+    g.add_edge(4, 0, 4 * W - 1);
+    g.add_edge_bidir(1, 5, W);
+    g.add_edge_bidir(5, 3, 2);
+    g.freeze();
+    g
+}
+
+/// Parallel edges with different weights, of which only the lightest ones remain
+pub fn parallel_edges() -> InputGraph {
+    let mut g = InputGraph::new();
+    g.add_edge(0, 1, 5);
+    g.add_edge(0, 1, 3);
+    g.add_edge(0, 1, 7);
+    g.add_edge(1, 2, 2);
+    g.add_edge(1, 2, 2);
+    g.add_edge_bidir(2, 0, 6);
+    g.add_edge(2, 0, 1);
+    g.freeze();
+    g
+}
+
+/// A directed complete graph with `num_nodes` nodes, where contracting any node creates many
+/// shortcuts
+pub fn clique(num_nodes: usize) -> InputGraph {
+    let mut g = InputGraph::new();
+    for i in 0..num_nodes {
+        for j in 0..num_nodes {
+            if i != j {
+                g.add_edge(i, j, (i * 7 + j * 13) % 10 + 1);
+            }
+        }
+    }
+    g.freeze();
+    g
+}
+
+/// A bidirectional chain of `num_nodes` nodes, which leads to deeply nested shortcuts
+pub fn long_chain(num_nodes: usize) -> InputGraph {
+    let mut g = InputGraph::new();
+    for i in 1..num_nodes {
+        g.add_edge(i - 1, i, i % 3 + 1);
+        g.add_edge(i, i - 1, i % 5 + 1);
+    }
+    g.freeze();
+    g
+}
+
+/// A ring of `num_nodes` nodes that can only be traversed in one direction, so the paths between
+/// neighbors in the other direction go around the entire ring
+pub fn one_way_ring(num_nodes: usize) -> InputGraph {
+    let mut g = InputGraph::new();
+    for i in 0..num_nodes {
+        g.add_edge(i, (i + 1) % num_nodes, i % 4 + 1);
+    }
+    g.freeze();
+    g
+}
+
+/// A node that is connected to `num_leaves` other nodes, some of which can only be entered or
+/// only be left
+pub fn star(num_leaves: usize) -> InputGraph {
+    let mut g = InputGraph::new();
+    for i in 1..=num_leaves {
+        match i % 3 {
+            0 => g.add_edge(0, i, i),
+            1 => g.add_edge(i, 0, i),
+            _ => g.add_edge_bidir(0, i, i),
+        };
+    }
+    g.freeze();
+    g
+}
+
+/// A bidirectional grid with `size * size` nodes where all edges have the same weight, so there
+/// are many shortest paths between most nodes
+pub fn equal_weights_grid(size: usize) -> InputGraph {
+    let mut g = InputGraph::new();
+    for y in 0..size {
+        for x in 0..size {
+            let node = y * size + x;
+            if x + 1 < size {
+                g.add_edge_bidir(node, node + 1, 1);
+            }
+            if y + 1 < size {
+                g.add_edge_bidir(node, node + size, 1);
+            }
+        }
+    }
+    g.freeze();
+    g
+}
@@ -0,0 +1,169 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+//! Writes GeoJSON (https://geojson.org), e.g. to look at a graph or a path in a map viewer. The
+//! coordinates are given as (latitude, longitude) pairs like everywhere else in this crate, but
+//! GeoJSON stores them the other way round.
+
+use std::fmt::Write;
+
+use crate::constants::{NodeId, INVALID_EDGE_KEY};
+use crate::input_graph::Edge;
+use crate::shortest_path::ShortestPath;
+
+/// Returns a FeatureCollection with a LineString for every edge. The properties of each feature
+/// are the nodes, the weight and, if there is one, the key of the edge.
+pub(crate) fn edges_to_geojson(edges: &[Edge], coordinates: &[(f64, f64)]) -> String {
+    let features: Vec<String> = edges
+        .iter()
+        .map(|edge| {
+            let mut properties = format!(
+                r#""from":{},"to":{},"weight":{}"#,
+                edge.from, edge.to, edge.weight
+            );
+            if edge.key != INVALID_EDGE_KEY {
+                write!(properties, r#","key":{}"#, edge.key).unwrap();
+            }
+            line_string(&[edge.from, edge.to], coordinates, &properties)
+        })
+        .collect();
+    feature_collection(&features)
+}
+
+/// Returns a FeatureCollection with a LineString along the nodes of the given path, or a Point if
+/// the source and the target are the same, and no features if there is no path.
+pub(crate) fn path_to_geojson(path: &ShortestPath, coordinates: &[(f64, f64)]) -> String {
+    let nodes = path.get_nodes();
+    let properties = format!(
+        r#""source":{},"target":{},"weight":{}"#,
+        path.get_source(),
+        path.get_target(),
+        path.get_weight()
+    );
+    let features = match nodes.len() {
+        0 => vec![],
+        1 => vec![feature(
+            &format!(
+                r#"{{"type":"Point","coordinates":{}}}"#,
+                position(nodes[0], coordinates)
+            ),
+            &properties,
+        )],
+        _ => vec![line_string(nodes, coordinates, &properties)],
+    };
+    feature_collection(&features)
+}
+
+fn feature_collection(features: &[String]) -> String {
+    format!(
+        r#"{{"type":"FeatureCollection","features":[{}]}}"#,
+        features.join(",")
+    )
+}
+
+fn feature(geometry: &str, properties: &str) -> String {
+    format!(
+        r#"{{"type":"Feature","geometry":{},"properties":{{{}}}}}"#,
+        geometry, properties
+    )
+}
+
+fn line_string(nodes: &[NodeId], coordinates: &[(f64, f64)], properties: &str) -> String {
+    let positions: Vec<String> = nodes
+        .iter()
+        .map(|node| position(*node, coordinates))
+        .collect();
+    feature(
+        &format!(
+            r#"{{"type":"LineString","coordinates":[{}]}}"#,
+            positions.join(",")
+        ),
+        properties,
+    )
+}
+
+fn position(node: NodeId, coordinates: &[(f64, f64)]) -> String {
+    let (lat, lon) = coordinates.get(node).unwrap_or_else(|| {
+        panic!(
+            "there are no coordinates for node {}, only for {} nodes",
+            node,
+            coordinates.len()
+        )
+    });
+    format!("[{},{}]", lon, lat)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::input_graph::InputGraph;
+
+    use super::*;
+
+    const COORDINATES: [(f64, f64); 3] = [(52.5, 13.4), (52.51, 13.41), (-33.9, 18.42)];
+
+    #[test]
+    fn graph() {
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 5);
+        g.add_edge_with_key(2, 0, 7, 12);
+        g.freeze();
+        assert_eq!(
+            concat!(
+                r#"{"type":"FeatureCollection","features":["#,
+                r#"{"type":"Feature","geometry":{"type":"LineString","coordinates":[[13.4,52.5],[13.41,52.51]]},"properties":{"from":0,"to":1,"weight":5}},"#,
+                r#"{"type":"Feature","geometry":{"type":"LineString","coordinates":[[18.42,-33.9],[13.4,52.5]]},"properties":{"from":2,"to":0,"weight":7,"key":12}}"#,
+                r#"]}"#
+            ),
+            g.to_geojson(&COORDINATES)
+        );
+        assert_eq!(
+            r#"{"type":"FeatureCollection","features":[]}"#,
+            InputGraph::from_edges(0, vec![]).to_geojson(&[])
+        );
+    }
+
+    #[test]
+    fn paths() {
+        assert_eq!(
+            concat!(
+                r#"{"type":"FeatureCollection","features":["#,
+                r#"{"type":"Feature","geometry":{"type":"LineString","coordinates":[[18.42,-33.9],[13.4,52.5],[13.41,52.51]]},"properties":{"source":2,"target":1,"weight":12}}"#,
+                r#"]}"#
+            ),
+            ShortestPath::new(2, 1, 12, vec![2, 0, 1]).to_geojson(&COORDINATES)
+        );
+        assert_eq!(
+            concat!(
+                r#"{"type":"FeatureCollection","features":["#,
+                r#"{"type":"Feature","geometry":{"type":"Point","coordinates":[13.41,52.51]},"properties":{"source":1,"target":1,"weight":0}}"#,
+                r#"]}"#
+            ),
+            ShortestPath::singular(1).to_geojson(&COORDINATES)
+        );
+        assert_eq!(
+            r#"{"type":"FeatureCollection","features":[]}"#,
+            ShortestPath::none(0, 1).to_geojson(&COORDINATES)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "there are no coordinates for node 2")]
+    fn missing_coordinates() {
+        ShortestPath::new(0, 2, 3, vec![0, 2]).to_geojson(&COORDINATES[..2]);
+    }
+}
@@ -0,0 +1,196 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use crate::constants::{NodeId, Weight, WEIGHT_MAX};
+use crate::input_graph::InputGraph;
+
+/// The neighbours a cell of a `GridGraph` is connected to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GridConnectivity {
+    /// Cells are connected to their horizontal and vertical neighbours.
+    Four,
+    /// Cells are also connected to their diagonal neighbours.
+    Eight,
+}
+
+/// A grid of cells, e.g. for games or robot navigation, where every cell is a node and the weight
+/// of an edge is the cost of the cell it leads into. Diagonal edges cost `sqrt(2)` times as much
+/// and are only possible if both adjacent cells are passable, so paths cannot cut corners. Cells
+/// with cost `WEIGHT_MAX` cannot be entered at all.
+///
+/// The node of the cell `(x, y)` is `y * width + x`. The edges are created on the fly while
+/// preparing the graph with `prepare_grid()`, so no `InputGraph` is needed.
+pub struct GridGraph {
+    width: usize,
+    height: usize,
+    costs: Vec<Weight>,
+    connectivity: GridConnectivity,
+}
+
+impl GridGraph {
+    /// Creates a grid with the given dimensions, where `costs[y * width + x]` is the cost of the
+    /// cell `(x, y)`.
+    pub fn new(
+        width: usize,
+        height: usize,
+        costs: Vec<Weight>,
+        connectivity: GridConnectivity,
+    ) -> Self {
+        assert_eq!(
+            width * height,
+            costs.len(),
+            "there has to be one cost per cell"
+        );
+        assert!(
+            costs.iter().all(|&cost| cost > 0),
+            "cell costs must be positive"
+        );
+        GridGraph {
+            width,
+            height,
+            costs,
+            connectivity,
+        }
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get_num_nodes(&self) -> usize {
+        self.costs.len()
+    }
+
+    pub fn get_node(&self, x: usize, y: usize) -> NodeId {
+        assert!(x < self.width && y < self.height, "invalid cell");
+        y * self.width + x
+    }
+
+    /// Returns the `(x, y)` coordinates of the cell of the given node.
+    pub fn get_cell(&self, node: NodeId) -> (usize, usize) {
+        (node % self.width, node / self.width)
+    }
+
+    /// Creates an explicit `InputGraph` containing the same edges as this grid. Note that the
+    /// `InputGraph` has fewer nodes than the grid if the last cells are not connected to any other
+    /// cell.
+    pub fn to_input_graph(&self) -> InputGraph {
+        let mut input_graph = InputGraph::new();
+        self.for_each_edge(|from, to, weight| {
+            input_graph.add_edge(from, to, weight);
+        });
+        input_graph.freeze();
+        input_graph
+    }
+
+    pub(crate) fn for_each_edge<F: FnMut(NodeId, NodeId, Weight)>(&self, mut f: F) {
+        let passable = |x: isize, y: isize| {
+            x >= 0
+                && y >= 0
+                && (x as usize) < self.width
+                && (y as usize) < self.height
+                && self.costs[y as usize * self.width + x as usize] != WEIGHT_MAX
+        };
+        let straight = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+        let diagonal = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+        for y in 0..self.height as isize {
+            for x in 0..self.width as isize {
+                if !passable(x, y) {
+                    continue;
+                }
+                let from = y as usize * self.width + x as usize;
+                for &(dx, dy) in &straight {
+                    if passable(x + dx, y + dy) {
+                        let to = (y + dy) as usize * self.width + (x + dx) as usize;
+                        f(from, to, self.costs[to]);
+                    }
+                }
+                if self.connectivity == GridConnectivity::Four {
+                    continue;
+                }
+                for &(dx, dy) in &diagonal {
+                    if passable(x + dx, y + dy) && passable(x + dx, y) && passable(x, y + dy) {
+                        let to = (y + dy) as usize * self.width + (x + dx) as usize;
+                        let weight = (self.costs[to] as f64 * std::f64::consts::SQRT_2).round();
+                        f(from, to, (weight as Weight).min(WEIGHT_MAX - 1));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn four_connected() {
+        // 1 1 1
+        // 1 X 1
+        // 1 5 1
+        let x = WEIGHT_MAX;
+        let grid = GridGraph::new(
+            3,
+            3,
+            vec![1, 1, 1, 1, x, 1, 1, 5, 1],
+            GridConnectivity::Four,
+        );
+        assert_eq!(9, grid.get_num_nodes());
+        assert_eq!(5, grid.get_node(2, 1));
+        assert_eq!((2, 1), grid.get_cell(5));
+        let fast_graph = crate::prepare_grid(&grid, &crate::Params::default());
+        let path = crate::calc_path(&fast_graph, grid.get_node(0, 1), grid.get_node(2, 1)).unwrap();
+        assert_eq!(&vec![3, 0, 1, 2, 5], path.get_nodes());
+        assert_eq!(4, path.get_weight());
+        // entering the expensive cell costs 5
+        let path = crate::calc_path(&fast_graph, grid.get_node(0, 2), grid.get_node(1, 2)).unwrap();
+        assert_eq!(5, path.get_weight());
+        // the blocked cell cannot be reached
+        assert!(crate::calc_path(&fast_graph, 0, grid.get_node(1, 1)).is_none());
+    }
+
+    #[test]
+    fn eight_connected() {
+        // 2 2 2
+        // 2 2 X
+        // 2 2 2
+        let x = WEIGHT_MAX;
+        let grid = GridGraph::new(
+            3,
+            3,
+            vec![2, 2, 2, 2, 2, x, 2, 2, 2],
+            GridConnectivity::Eight,
+        );
+        let fast_graph = crate::prepare_grid(&grid, &crate::Params::default());
+        // a diagonal step costs round(2 * sqrt(2)) = 3
+        let path = crate::calc_path(&fast_graph, 0, 4).unwrap();
+        assert_eq!(&vec![0, 4], path.get_nodes());
+        assert_eq!(3, path.get_weight());
+        // going from (1, 0) to (2, 1) diagonally would cut the corner of the blocked cell, so we
+        // cannot go from (1, 1) to (2, 2) directly either
+        let path = crate::calc_path(&fast_graph, 4, 8).unwrap();
+        assert_eq!(4, path.get_weight());
+        assert_eq!(3, path.get_nodes().len());
+    }
+}
@@ -0,0 +1,225 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use std::collections::BinaryHeap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{NodeId, Weight, WEIGHT_MAX};
+use crate::fast_graph::FastGraph;
+use crate::heap_item::HeapItem;
+
+/// Hub labels built from the contraction hierarchy. Every node stores the weights to (and from)
+/// the nodes its upward search in the hierarchy can reach, so the weight between two nodes can be
+/// found by just intersecting their labels, which is much faster than a `PathCalculator` query
+/// but needs a lot more memory. Only weights can be looked up, not paths.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HubLabels {
+    fwd: Labels,
+    bwd: Labels,
+}
+
+/// The labels of all nodes, where the label of `node` is
+/// `entries[first_ids[node]..first_ids[node + 1]]`, i.e. hubs and weights sorted by hub.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Labels {
+    first_ids: Vec<usize>,
+    entries: Vec<(NodeId, Weight)>,
+}
+
+impl HubLabels {
+    /// Builds the labels by running an upward search from every node. Entries that cannot be part
+    /// of a shortest path, because the labels of the higher ranked nodes already provide a shorter
+    /// one, are left out.
+    pub fn build(graph: &FastGraph) -> Self {
+        let num_nodes = graph.get_num_nodes();
+        let mut nodes: Vec<NodeId> = (0..num_nodes).collect();
+        nodes.sort_unstable_by_key(|node| std::cmp::Reverse(graph.ranks[*node]));
+        let mut search = UpwardSearch::new(num_nodes);
+        let mut fwd = vec![vec![]; num_nodes];
+        let mut bwd = vec![vec![]; num_nodes];
+        for node in nodes {
+            // all hubs other than `node` have a higher rank, so their labels are complete already
+            // (except for the core, where we keep the entries)
+            let label = search.run(graph, node, true);
+            fwd[node] = prune(graph, node, label, |hub| &bwd[hub]);
+            let label = search.run(graph, node, false);
+            bwd[node] = prune(graph, node, label, |hub| &fwd[hub]);
+        }
+        HubLabels {
+            fwd: Labels::new(fwd),
+            bwd: Labels::new(bwd),
+        }
+    }
+
+    /// Returns the weight of the shortest path from `source` to `target`, or `None` if there is no
+    /// such path.
+    pub fn get_weight(&self, source: NodeId, target: NodeId) -> Option<Weight> {
+        assert!(source < self.get_num_nodes(), "invalid source node");
+        assert!(target < self.get_num_nodes(), "invalid target node");
+        let weight = calc_weight(self.fwd.get(source), self.bwd.get(target));
+        (weight != WEIGHT_MAX).then_some(weight)
+    }
+
+    pub fn get_num_nodes(&self) -> usize {
+        self.fwd.first_ids.len() - 1
+    }
+
+    /// The total number of entries of all forward and backward labels. Every entry takes one node
+    /// id and one weight.
+    pub fn get_num_entries(&self) -> usize {
+        self.fwd.entries.len() + self.bwd.entries.len()
+    }
+}
+
+impl Labels {
+    fn new(labels: Vec<Vec<(NodeId, Weight)>>) -> Self {
+        let mut first_ids = Vec::with_capacity(labels.len() + 1);
+        let mut entries = vec![];
+        first_ids.push(0);
+        for label in labels {
+            entries.extend(label);
+            first_ids.push(entries.len());
+        }
+        Labels { first_ids, entries }
+    }
+
+    fn get(&self, node: NodeId) -> &[(NodeId, Weight)] {
+        &self.entries[self.first_ids[node]..self.first_ids[node + 1]]
+    }
+}
+
+/// Returns the smallest sum of weights over all hubs the two labels have in common
+fn calc_weight(fwd: &[(NodeId, Weight)], bwd: &[(NodeId, Weight)]) -> Weight {
+    let (mut i, mut j) = (0, 0);
+    let mut best = WEIGHT_MAX;
+    while i < fwd.len() && j < bwd.len() {
+        match fwd[i].0.cmp(&bwd[j].0) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                best = best.min(fwd[i].1.saturating_add(bwd[j].1));
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    best
+}
+
+/// Removes the entries of the given label for which the label of the hub provides a shorter path
+fn prune<'a, F: Fn(NodeId) -> &'a Vec<(NodeId, Weight)>>(
+    graph: &FastGraph,
+    node: NodeId,
+    label: Vec<(NodeId, Weight)>,
+    get_opposite_label: F,
+) -> Vec<(NodeId, Weight)> {
+    label
+        .iter()
+        .filter(|(hub, weight)| {
+            graph.ranks[*hub] <= graph.ranks[node]
+                || calc_weight(&label, get_opposite_label(*hub)) >= *weight
+        })
+        .copied()
+        .collect()
+}
+
+/// A Dijkstra search that only follows the edges leading to higher ranked nodes
+struct UpwardSearch {
+    weights: Vec<Weight>,
+    touched: Vec<NodeId>,
+    heap: BinaryHeap<HeapItem>,
+}
+
+impl UpwardSearch {
+    fn new(num_nodes: usize) -> Self {
+        UpwardSearch {
+            weights: vec![WEIGHT_MAX; num_nodes],
+            touched: vec![],
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    /// Returns all nodes reachable from `start` (or that can reach `start` if `forward` is
+    /// false) with their weights, sorted by node id.
+    fn run(&mut self, graph: &FastGraph, start: NodeId, forward: bool) -> Vec<(NodeId, Weight)> {
+        self.weights[start] = 0;
+        self.touched.push(start);
+        self.heap.push(HeapItem::new(0, start));
+        while let Some(curr) = self.heap.pop() {
+            if curr.weight > self.weights[curr.node_id] {
+                continue;
+            }
+            let (edges, range) = if forward {
+                (
+                    &graph.edges_fwd,
+                    graph.begin_out_edges(curr.node_id)..graph.end_out_edges(curr.node_id),
+                )
+            } else {
+                (
+                    &graph.edges_bwd,
+                    graph.begin_in_edges(curr.node_id)..graph.end_in_edges(curr.node_id),
+                )
+            };
+            for edge in &edges[range] {
+                let weight = curr.weight.saturating_add(edge.weight);
+                if weight < self.weights[edge.adj_node] {
+                    if self.weights[edge.adj_node] == WEIGHT_MAX {
+                        self.touched.push(edge.adj_node);
+                    }
+                    self.weights[edge.adj_node] = weight;
+                    self.heap.push(HeapItem::new(weight, edge.adj_node));
+                }
+            }
+        }
+        let mut label: Vec<(NodeId, Weight)> = Vec::with_capacity(self.touched.len());
+        for node in self.touched.drain(..) {
+            label.push((node, self.weights[node]));
+            self.weights[node] = WEIGHT_MAX;
+        }
+        label.sort_unstable_by_key(|(node, _)| *node);
+        label
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::input_graph::InputGraph;
+
+    use super::*;
+
+    #[test]
+    fn simple() {
+        // 0 -> 1 -> 2 -> 3
+        //      ^--------/
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 2);
+        g.add_edge(2, 3, 3);
+        g.add_edge(3, 1, 4);
+        g.freeze();
+        let fast_graph = crate::prepare(&g);
+        let labels = HubLabels::build(&fast_graph);
+        assert_eq!(4, labels.get_num_nodes());
+        assert_eq!(Some(6), labels.get_weight(0, 3));
+        assert_eq!(Some(6), labels.get_weight(3, 2));
+        assert_eq!(Some(0), labels.get_weight(2, 2));
+        assert_eq!(None, labels.get_weight(1, 0));
+    }
+}
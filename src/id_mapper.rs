@@ -0,0 +1,143 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{NodeId, Weight};
+use crate::input_graph::InputGraph;
+use crate::shortest_path::ShortestPath;
+
+/// Translates sparse 64-bit node ids, like the ids of OpenStreetMap nodes, to the dense node ids
+/// used by this crate and back. Passing such ids to `InputGraph::add_edge` directly would make the
+/// graph as large as the highest id, whereas here the nodes are numbered consecutively in the
+/// order they are first seen.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct IdMapper {
+    nodes: HashMap<u64, NodeId>,
+    ids: Vec<u64>,
+}
+
+impl IdMapper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the node for the given id, and assigns the next free node to it if it is new.
+    pub fn get_or_insert(&mut self, id: u64) -> NodeId {
+        let ids = &mut self.ids;
+        *self.nodes.entry(id).or_insert_with(|| {
+            ids.push(id);
+            ids.len() - 1
+        })
+    }
+
+    /// Returns the node for the given id, or `None` if the id is not known, e.g. to find the
+    /// source and target nodes of a query.
+    pub fn get_node(&self, id: u64) -> Option<NodeId> {
+        self.nodes.get(&id).copied()
+    }
+
+    /// Returns the id of the given node.
+    pub fn get_id(&self, node: NodeId) -> u64 {
+        self.ids[node]
+    }
+
+    /// Returns the ids of the nodes of the given path.
+    pub fn get_path_ids(&self, path: &ShortestPath) -> Vec<u64> {
+        path.get_nodes()
+            .iter()
+            .map(|node| self.get_id(*node))
+            .collect()
+    }
+
+    /// Returns the number of known ids, i.e. the number of nodes.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Adds an edge between the nodes with the given ids to the graph, see `InputGraph::add_edge`.
+    /// The graph has a node for every id known to this mapper, even if no edge was added for it.
+    pub fn add_edge(
+        &mut self,
+        graph: &mut InputGraph,
+        from: u64,
+        to: u64,
+        weight: Weight,
+    ) -> usize {
+        let from = self.get_or_insert(from);
+        let to = self.get_or_insert(to);
+        let num_added = graph.add_edge(from, to, weight);
+        graph.set_min_num_nodes(self.len());
+        num_added
+    }
+
+    /// Like `add_edge`, but adds the edge in both directions, see `InputGraph::add_edge_bidir`.
+    pub fn add_edge_bidir(
+        &mut self,
+        graph: &mut InputGraph,
+        from: u64,
+        to: u64,
+        weight: Weight,
+    ) -> usize {
+        let from = self.get_or_insert(from);
+        let to = self.get_or_insert(to);
+        let num_added = graph.add_edge_bidir(from, to, weight);
+        graph.set_min_num_nodes(self.len());
+        num_added
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sparse_ids() {
+        let mut mapper = IdMapper::new();
+        let mut g = InputGraph::new();
+        assert!(mapper.is_empty());
+        mapper.add_edge(&mut g, 9_000_000_000, 17, 3);
+        mapper.add_edge_bidir(&mut g, 17, u64::MAX, 2);
+        // loop edges are skipped, but the node exists anyway
+        mapper.add_edge(&mut g, 42, 42, 1);
+        g.freeze();
+        assert_eq!(4, mapper.len());
+        assert_eq!(4, g.get_num_nodes());
+        assert_eq!(Some(0), mapper.get_node(9_000_000_000));
+        assert_eq!(Some(2), mapper.get_node(u64::MAX));
+        assert_eq!(None, mapper.get_node(5));
+        assert_eq!(17, mapper.get_id(1));
+        assert_eq!(1, mapper.get_or_insert(17));
+
+        let fast_graph = crate::prepare(&g);
+        let source = mapper.get_node(9_000_000_000).unwrap();
+        let target = mapper.get_node(u64::MAX).unwrap();
+        let path = crate::calc_path(&fast_graph, source, target).unwrap();
+        assert_eq!(5, path.get_weight());
+        assert_eq!(
+            vec![9_000_000_000, 17, u64::MAX],
+            mapper.get_path_ids(&path)
+        );
+    }
+}
@@ -21,22 +21,38 @@ use std::cmp;
 use std::fmt;
 use std::fs::File;
 use std::io::{BufRead, BufReader, BufWriter, Write};
-
-#[cfg(test)]
-use rand::rngs::StdRng;
-#[cfg(test)]
-use rand::Rng;
+use std::iter::FromIterator;
 
 use serde::{Deserialize, Serialize};
 
+use crate::connected_components;
 use crate::constants::NodeId;
 use crate::constants::Weight;
+use crate::constants::WEIGHT_MAX;
+use crate::constants::{EdgeKey, INVALID_EDGE_KEY, INVALID_NODE};
+use crate::csv_import::{self, CsvOptions};
+use crate::error::{InputError, ParseError};
+use crate::geojson;
+use crate::weight_scale::WeightScale;
+use crate::weight_unit::WeightUnit;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct InputGraph {
     edges: Vec<Edge>,
     num_nodes: usize,
+    /// see `get_max_path_weight`, this is `None` for graphs that were serialized before this was
+    /// stored, e.g. by version 1.x
+    #[serde(default)]
+    max_path_weight: Option<Weight>,
     frozen: bool,
+    #[serde(default)]
+    weight_unit: Option<WeightUnit>,
+    #[serde(default)]
+    node_penalties: Vec<Weight>,
+    #[serde(default)]
+    allow_zero_weights: bool,
+    #[serde(default)]
+    strict: bool,
 }
 
 impl InputGraph {
@@ -44,14 +60,62 @@ impl InputGraph {
         InputGraph {
             edges: Vec::new(),
             num_nodes: 0,
+            max_path_weight: None,
             frozen: false,
+            weight_unit: None,
+            node_penalties: Vec::new(),
+            allow_zero_weights: false,
+            strict: false,
+        }
+    }
+
+    /// Creates an empty graph with room for `num_edges` edges, so adding many edges does not
+    /// repeatedly grow the edge list. Note that `add_edge_bidir` adds two edges. Like in
+    /// `from_edges` the graph has at least `num_nodes` nodes, even if some of them do not get any
+    /// edges.
+    pub fn with_capacity(num_nodes: usize, num_edges: usize) -> Self {
+        let mut g = InputGraph::new();
+        g.edges.reserve_exact(num_edges);
+        g.set_min_num_nodes(num_nodes);
+        g
+    }
+
+    /// Reserves room for at least `num_edges` more edges, see `with_capacity`.
+    pub fn reserve_edges(&mut self, num_edges: usize) {
+        self.edges.reserve(num_edges);
+    }
+
+    /// Releases the memory that is not needed for the current edges, e.g. after `freeze`, which
+    /// removes duplicate edges but keeps the memory they used, or after reserving too much.
+    pub fn shrink_to_fit(&mut self) {
+        self.edges.shrink_to_fit();
+        self.node_penalties.shrink_to_fit();
+    }
+
+    /// Builds a frozen graph from the given edges. Unlike adding them one by one, this reserves the
+    /// memory for all edges up front, if the iterator knows its length. Like in `add_edge`, loops
+    /// and edges with zero weight are skipped. The graph has at least `num_nodes_hint` nodes, even
+    /// if the nodes with the highest ids do not have any edges.
+    pub fn from_edges<I: IntoIterator<Item = Edge>>(num_nodes_hint: usize, edges: I) -> Self {
+        let edges = edges.into_iter();
+        let mut g = InputGraph::with_capacity(num_nodes_hint, edges.size_hint().0);
+        for e in edges {
+            g.do_add_edge(e.from, e.to, e.weight, e.key, false);
         }
+        g.freeze();
+        g
     }
 
-    /// Builds a random input graph, mostly used for testing purposes
-    #[cfg(test)]
-    pub fn random(rng: &mut StdRng, num_nodes: usize, mean_degree: f32) -> Self {
-        InputGraph::build_random_graph(rng, num_nodes, mean_degree)
+    /// Builds a random input graph, mostly used for testing purposes. `next_u64` must return
+    /// uniformly distributed random numbers, e.g. `|| rng.next_u64()` for any generator of the
+    /// `rand` crate. The graph only depends on these numbers, so using a seeded generator yields
+    /// the same graph every time, independent of the version of `rand`: edges are added until
+    /// the graph has `mean_degree * num_nodes` edges, and each edge uses three numbers `x` for
+    /// its head, its tail and its weight, which are `random_below(x, num_nodes)` twice and
+    /// `1 + random_below(x, 99)`, where `random_below(x, n) = (x * n) >> 64` (in 128 bits).
+    #[cfg(any(test, feature = "random"))]
+    pub fn random<F: FnMut() -> u64>(next_u64: F, num_nodes: usize, mean_degree: f32) -> Self {
+        InputGraph::build_random_graph(next_u64, num_nodes, mean_degree)
     }
 
     /// Reads an input graph from a text file, using the following format:
@@ -62,6 +126,12 @@ impl InputGraph {
     /// for these instead.
     /// Mostly used for performance testing.
     pub fn from_file(filename: &str) -> Self {
+        InputGraph::try_from_file(filename).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `from_file`, but returns an error instead of panicking if the file cannot be read or
+    /// contains an invalid line.
+    pub fn try_from_file(filename: &str) -> Result<Self, ParseError> {
         InputGraph::read_from_file(filename)
     }
 
@@ -76,6 +146,28 @@ impl InputGraph {
         Ok(())
     }
 
+    /// Reads an input graph from a CSV file with one edge per line, see `CsvOptions` for the
+    /// supported layouts. The error contains the number of the first invalid line.
+    pub fn from_csv(filename: &str, options: &CsvOptions) -> Result<Self, ParseError> {
+        csv_import::read_csv_file(filename, options)
+    }
+
+    /// Like `from_csv`, but reads the CSV data from the given reader.
+    pub fn from_csv_reader<R: BufRead>(
+        reader: R,
+        options: &CsvOptions,
+    ) -> Result<Self, ParseError> {
+        csv_import::read_csv(reader, options)
+    }
+
+    /// Returns the edges of this graph as GeoJSON, i.e. a FeatureCollection with a LineString for
+    /// every edge, e.g. to look at the graph in a map viewer. `coordinates` contains the
+    /// (latitude, longitude) pair of every node. The properties of the features are the nodes, the
+    /// weight and the key (if any) of each edge.
+    pub fn to_geojson(&self, coordinates: &[(f64, f64)]) -> String {
+        geojson::edges_to_geojson(self.get_edges(), coordinates)
+    }
+
     /// Reads an input graph from a text file, using the DIMACS format:
     /// http://users.diag.uniroma1.it/challenge9/format.shtml#graph
     ///
@@ -92,6 +184,12 @@ impl InputGraph {
     ///
     /// Mostly used for performance testing.
     pub fn from_dimacs_file(filename: &str) -> Self {
+        InputGraph::try_from_dimacs_file(filename).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `from_dimacs_file`, but returns an error instead of panicking if the file cannot be
+    /// read or is invalid.
+    pub fn try_from_dimacs_file(filename: &str) -> Result<Self, ParseError> {
         InputGraph::read_from_dimacs(filename)
     }
 
@@ -111,11 +209,187 @@ impl InputGraph {
     }
 
     pub fn add_edge(&mut self, from: NodeId, to: NodeId, weight: Weight) -> usize {
-        self.do_add_edge(from, to, weight, false)
+        self.do_add_edge(from, to, weight, INVALID_EDGE_KEY, false)
     }
 
     pub fn add_edge_bidir(&mut self, from: NodeId, to: NodeId, weight: Weight) -> usize {
-        self.do_add_edge(from, to, weight, true)
+        self.do_add_edge(from, to, weight, INVALID_EDGE_KEY, true)
+    }
+
+    /// Like `add_edge`, but stores the given key along with the edge, e.g. the key of the
+    /// corresponding row in your database. The edge ids change when the graph is frozen, but the
+    /// key stays with the edge, so the edges of a calculated path can be translated back using
+    /// `ShortestPath::as_edge_keys` or `get_edge_key`. If there are parallel edges only the key
+    /// of the one that is kept is stored (see `freeze`).
+    pub fn add_edge_with_key(
+        &mut self,
+        from: NodeId,
+        to: NodeId,
+        weight: Weight,
+        key: EdgeKey,
+    ) -> usize {
+        self.do_add_edge(from, to, weight, key, false)
+    }
+
+    /// Like `add_edge_with_key`, but adds the edge in both directions, both with the same key.
+    pub fn add_edge_bidir_with_key(
+        &mut self,
+        from: NodeId,
+        to: NodeId,
+        weight: Weight,
+        key: EdgeKey,
+    ) -> usize {
+        self.do_add_edge(from, to, weight, key, true)
+    }
+
+    /// Like `add_edge`, but converts the given floating point cost to a weight first, see
+    /// `WeightScale`. Panics if the cost cannot be converted, e.g. because it is negative.
+    pub fn add_edge_f64(
+        &mut self,
+        from: NodeId,
+        to: NodeId,
+        cost: f64,
+        scale: &WeightScale,
+    ) -> usize {
+        let weight = scale
+            .to_weight(cost)
+            .unwrap_or_else(|e| panic!("Invalid cost: {}", e));
+        self.add_edge(from, to, weight)
+    }
+
+    /// Like `add_edge_f64`, but adds the edge in both directions.
+    pub fn add_edge_bidir_f64(
+        &mut self,
+        from: NodeId,
+        to: NodeId,
+        cost: f64,
+        scale: &WeightScale,
+    ) -> usize {
+        let weight = scale
+            .to_weight(cost)
+            .unwrap_or_else(|e| panic!("Invalid cost: {}", e));
+        self.add_edge_bidir(from, to, weight)
+    }
+
+    /// Like `add_edge`, but returns an error instead of skipping the edge if it is a loop or has
+    /// zero weight, or instead of panicking if the graph is frozen. Returns the number of added
+    /// edges otherwise.
+    pub fn try_add_edge(
+        &mut self,
+        from: NodeId,
+        to: NodeId,
+        weight: Weight,
+    ) -> Result<usize, InputError> {
+        self.check_edge(from, to, weight)?;
+        Ok(self.push_edge(from, to, weight, INVALID_EDGE_KEY, false))
+    }
+
+    /// Like `try_add_edge`, but stores the given key along with the edge, see `add_edge_with_key`.
+    pub fn try_add_edge_with_key(
+        &mut self,
+        from: NodeId,
+        to: NodeId,
+        weight: Weight,
+        key: EdgeKey,
+    ) -> Result<usize, InputError> {
+        self.check_edge(from, to, weight)?;
+        Ok(self.push_edge(from, to, weight, key, false))
+    }
+
+    /// Like `try_add_edge`, but adds the edge in both directions, see `add_edge_bidir`.
+    pub fn try_add_edge_bidir(
+        &mut self,
+        from: NodeId,
+        to: NodeId,
+        weight: Weight,
+    ) -> Result<usize, InputError> {
+        self.check_edge(from, to, weight)?;
+        Ok(self.push_edge(from, to, weight, INVALID_EDGE_KEY, true))
+    }
+
+    /// By default `add_edge` (and its variants) skip loops and zero weight edges with a warning.
+    /// In strict mode they panic instead, so invalid input cannot go unnoticed. To handle these
+    /// cases without panicking use `try_add_edge`.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Removes all edges for which `keep` returns false, e.g. to drop the edges of private roads.
+    /// Like adding edges this is only possible before the graph is frozen, or after `thaw`. The
+    /// number of nodes does not change.
+    pub fn retain_edges<F: FnMut(&Edge) -> bool>(&mut self, keep: F) {
+        if self.frozen {
+            panic!("Graph is frozen already, for further changes first use thaw()");
+        }
+        self.edges.retain(keep);
+    }
+
+    /// Adds all edges of the (frozen) graph `other` to this graph, shifting their node ids by
+    /// `node_offset`, e.g. to combine the graphs of several regions before the preparation. Using
+    /// this graph's number of nodes as offset keeps the nodes of both graphs apart, while a smaller
+    /// offset lets them share nodes. The edges are added like with `add_edge_with_key`, so
+    /// duplicates are removed when this graph is frozen the next time. Returns the number of added
+    /// edges.
+    pub fn merge(&mut self, other: &InputGraph, node_offset: usize) -> usize {
+        self.do_merge(other, |node| node + node_offset)
+    }
+
+    /// Like `merge`, but node `i` of `other` becomes node `node_mapping[i]` of this graph, e.g. to
+    /// connect two regions at the nodes along their border. Edges whose nodes are mapped to the
+    /// same node are skipped like loops.
+    pub fn merge_with_mapping(&mut self, other: &InputGraph, node_mapping: &[NodeId]) -> usize {
+        assert!(
+            node_mapping.len() >= other.get_num_nodes(),
+            "The node mapping has {} entries, but the merged graph has {} nodes",
+            node_mapping.len(),
+            other.get_num_nodes()
+        );
+        self.do_merge(other, |node| node_mapping[node])
+    }
+
+    fn do_merge<F: Fn(NodeId) -> NodeId>(&mut self, other: &InputGraph, map_node: F) -> usize {
+        if self.frozen {
+            panic!("Graph is frozen already, for further changes first use thaw()");
+        }
+        match (self.weight_unit, other.get_weight_unit()) {
+            (Some(a), Some(b)) if a != b => {
+                panic!(
+                    "Cannot merge graphs with different weight units: {:?} and {:?}",
+                    a, b
+                )
+            }
+            (None, unit) => self.weight_unit = unit,
+            _ => {}
+        }
+        self.edges.reserve(other.get_num_edges());
+        let num_edges = other
+            .get_edges()
+            .iter()
+            .map(|e| self.do_add_edge(map_node(e.from), map_node(e.to), e.weight, e.key, false))
+            .sum();
+        if other.get_num_nodes() > 0 {
+            self.set_min_num_nodes(map_node(other.get_num_nodes() - 1) + 1);
+        }
+        num_edges
+    }
+
+    /// Adds `penalty` to the weight of every edge entering `node`, e.g. to model the waiting time
+    /// at a traffic light or the time needed for a transfer. This way every path pays the
+    /// penalties of all its nodes except for the source. The penalties are added to the edges
+    /// that exist when the graph is frozen the next time, so afterwards `get_edges` and the
+    /// prepared graph (including its shortcuts) only contain the resulting weights.
+    pub fn add_node_penalty(&mut self, node: NodeId, penalty: Weight) {
+        if self.frozen {
+            panic!("Graph is frozen already, for further changes first use thaw()");
+        }
+        if node >= self.node_penalties.len() {
+            self.node_penalties.resize(node + 1, 0);
+        }
+        self.node_penalties[node] = self.node_penalties[node].saturating_add(penalty);
     }
 
     pub fn get_edges(&self) -> &Vec<Edge> {
@@ -123,6 +397,23 @@ impl InputGraph {
         &self.edges
     }
 
+    /// Returns the id of the edge from `from` to `to`, i.e. its index in `get_edges`, if there is
+    /// such an edge. There is at most one, because parallel edges are merged when the graph is
+    /// frozen.
+    pub fn get_edge_id(&self, from: NodeId, to: NodeId) -> Option<usize> {
+        self.check_frozen();
+        self.edges
+            .binary_search_by(|edge| edge.from.cmp(&from).then(edge.to.cmp(&to)))
+            .ok()
+    }
+
+    /// Returns the key that was given for the edge with the given id (see `get_edge_id`) when it
+    /// was added, or `None` if the edge was added without a key.
+    pub fn get_edge_key(&self, edge_id: usize) -> Option<EdgeKey> {
+        self.check_frozen();
+        Some(self.edges[edge_id].key).filter(|key| *key != INVALID_EDGE_KEY)
+    }
+
     pub fn get_num_nodes(&self) -> usize {
         self.check_frozen();
         self.num_nodes
@@ -133,12 +424,126 @@ impl InputGraph {
         self.edges.len()
     }
 
+    /// Returns an upper bound for the weight of any shortest path in this graph, i.e. the sum of
+    /// the `num_nodes - 1` largest edge weights, because a shortest path never visits a node twice.
+    /// This is `WEIGHT_MAX` if the sum does not fit into `Weight`, in which case path weights might
+    /// overflow. The bound is calculated by `freeze`, or here if the graph was deserialized from
+    /// data that does not contain it. The preparation and the queries do not choose narrower
+    /// integer types based on it, but you can use it to check whether `FastGraph16` fits your
+    /// graph.
+    pub fn get_max_path_weight(&self) -> Weight {
+        self.check_frozen();
+        self.max_path_weight
+            .unwrap_or_else(|| self.calc_max_path_weight())
+    }
+
+    /// Returns the strongly connected components of this graph, i.e. the largest sets of nodes
+    /// that can all be reached from each other. There is no path between nodes of different
+    /// components in at least one direction, so this is useful to find out why a path could not be
+    /// found, e.g. because of a one-way road that leads into a dead end. The nodes of every
+    /// component are sorted and the components are ordered by their size, largest first. Nodes
+    /// without edges form a component of their own.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<NodeId>> {
+        self.check_frozen();
+        connected_components::find_strongly_connected_components(self)
+    }
+
+    /// Returns a frozen graph that only contains the largest strongly connected component of this
+    /// graph (see `strongly_connected_components`), such that there is a path between any two of
+    /// its nodes. The nodes are numbered consecutively in the order of their ids in this graph, and
+    /// the returned vector contains the id in this graph of every node of the new one.
+    pub fn extract_largest_component(&self) -> (InputGraph, Vec<NodeId>) {
+        let nodes = self
+            .strongly_connected_components()
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        let mut new_ids = vec![INVALID_NODE; self.num_nodes];
+        for (new_id, node) in nodes.iter().enumerate() {
+            new_ids[*node] = new_id;
+        }
+        let edges = self
+            .edges
+            .iter()
+            .filter(|e| new_ids[e.from] != INVALID_NODE && new_ids[e.to] != INVALID_NODE)
+            .map(|e| Edge {
+                from: new_ids[e.from],
+                to: new_ids[e.to],
+                ..*e
+            });
+        let mut graph = InputGraph::from_edges(nodes.len(), edges);
+        graph.weight_unit = self.weight_unit;
+        (graph, nodes)
+    }
+
+    /// Returns a hash of the number of nodes and the end points of all edges, but not of their
+    /// weights, so it does not change when only the weights change. This can be used to check that
+    /// data derived from the graph, like a node ordering, belongs to it. It might change between
+    /// versions of this crate.
+    pub fn get_topology_fingerprint(&self) -> u64 {
+        self.check_frozen();
+        // FNV-1a, which unlike the hashers of the standard library is guaranteed to be stable
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut add = |value: usize| {
+            for byte in (value as u64).to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        };
+        add(self.num_nodes);
+        for edge in &self.edges {
+            add(edge.from);
+            add(edge.to);
+        }
+        hash
+    }
+
+    /// Makes sure the graph has at least the given number of nodes, even if the nodes with the
+    /// highest ids do not have any edges.
+    pub(crate) fn set_min_num_nodes(&mut self, num_nodes: usize) {
+        self.num_nodes = cmp::max(self.num_nodes, num_nodes);
+    }
+
     pub fn freeze(&mut self) {
         if self.frozen {
             panic!("Input graph is already frozen");
         }
+        self.refreeze();
+    }
+
+    /// Sets the unit of the edge weights. It is stored along with the prepared graph and the
+    /// calculated paths, see `WeightUnit`.
+    pub fn set_weight_unit(&mut self, weight_unit: WeightUnit) {
+        self.weight_unit = Some(weight_unit);
+    }
+
+    pub fn get_weight_unit(&self) -> Option<WeightUnit> {
+        self.weight_unit
+    }
+
+    /// By default edges with zero weight are skipped with a warning, like loops. Setting this to
+    /// true keeps them instead, e.g. to model free transfers between two stops. The preparation and
+    /// the queries handle zero weights (including cycles of zero weight edges) correctly, but if
+    /// there are several shortest paths between two nodes, which is more likely with zero weights,
+    /// any one of them might be returned. This only affects edges that are added afterwards, and
+    /// the weights of a prepared graph still cannot be changed to zero, see
+    /// `FastGraph::update_weights`.
+    pub fn set_allow_zero_weights(&mut self, allow_zero_weights: bool) {
+        self.allow_zero_weights = allow_zero_weights;
+    }
+
+    pub fn get_allow_zero_weights(&self) -> bool {
+        self.allow_zero_weights
+    }
+
+    /// Like `freeze`, but does not panic if the graph is frozen already. The edges are sorted and
+    /// the duplicates removed either way, so this can be called whenever the graph should be
+    /// ready for use, e.g. after adding some edges following a `thaw`.
+    pub fn refreeze(&mut self) {
+        self.apply_node_penalties();
         self.sort();
         self.remove_duplicate_edges();
+        self.max_path_weight = Some(self.calc_max_path_weight());
         self.frozen = true;
     }
 
@@ -146,12 +551,28 @@ impl InputGraph {
         self.frozen = false;
     }
 
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    fn apply_node_penalties(&mut self) {
+        if self.node_penalties.is_empty() {
+            return;
+        }
+        for edge in &mut self.edges {
+            if let Some(penalty) = self.node_penalties.get(edge.to) {
+                edge.weight = edge.weight.saturating_add(*penalty);
+            }
+        }
+        self.node_penalties.clear();
+    }
+
     fn sort(&mut self) {
         self.edges.sort_unstable_by(|a, b| {
             a.from
                 .cmp(&b.from)
                 .then(a.to.cmp(&b.to))
-                .then(a.weight.cmp(&&b.weight))
+                .then(a.weight.cmp(&b.weight))
         });
     }
 
@@ -167,14 +588,26 @@ impl InputGraph {
         }
     }
 
+    fn calc_max_path_weight(&self) -> Weight {
+        let mut weights: Vec<Weight> = self.edges.iter().map(|e| e.weight).collect();
+        let max_edges = self.num_nodes.saturating_sub(1);
+        if weights.len() > max_edges && max_edges > 0 {
+            weights.select_nth_unstable_by(max_edges - 1, |a, b| b.cmp(a));
+        }
+        weights
+            .iter()
+            .take(max_edges)
+            .try_fold(0 as Weight, |sum, w| sum.checked_add(*w))
+            .unwrap_or(WEIGHT_MAX)
+    }
+
     pub fn unit_test_output_string(&self) -> String {
-        return self
-            .edges
+        self.edges
             .iter()
             .map(|e| e.unit_test_output_string())
             .collect::<Vec<String>>()
             .join("\n")
-            + "\n";
+            + "\n"
     }
 
     fn check_frozen(&self) {
@@ -183,28 +616,54 @@ impl InputGraph {
         }
     }
 
-    fn do_add_edge(&mut self, from: NodeId, to: NodeId, weight: Weight, bidir: bool) -> usize {
-        if self.frozen {
-            panic!("Graph is frozen already, for further changes first use thaw()");
-        }
-        if from == to {
-            warn!(
-                "Loop edges are not allowed. Skipped edge! from: {}, to: {}, weight: {}",
-                from, to, weight
-            );
-            return 0;
+    fn do_add_edge(
+        &mut self,
+        from: NodeId,
+        to: NodeId,
+        weight: Weight,
+        key: EdgeKey,
+        bidir: bool,
+    ) -> usize {
+        match self.check_edge(from, to, weight) {
+            Ok(()) => self.push_edge(from, to, weight, key, bidir),
+            Err(InputError::Frozen) => {
+                panic!("Graph is frozen already, for further changes first use thaw()")
+            }
+            Err(e) if self.strict => panic!("Invalid edge in strict mode: {}", e),
+            Err(e) => {
+                warn!(
+                    "Skipped edge, because {}! from: {}, to: {}, weight: {}",
+                    e, from, to, weight
+                );
+                0
+            }
         }
-        if weight < 1 {
-            warn!(
-                "Zero weight edges are not allowed. Skipped edge! from: {}, to: {}, weight: {}",
-                from, to, weight
-            );
-            return 0;
+    }
+
+    fn check_edge(&self, from: NodeId, to: NodeId, weight: Weight) -> Result<(), InputError> {
+        if self.frozen {
+            Err(InputError::Frozen)
+        } else if from == to {
+            Err(InputError::Loop { node: from })
+        } else if weight < 1 && !self.allow_zero_weights {
+            Err(InputError::ZeroWeight { from, to })
+        } else {
+            Ok(())
         }
+    }
+
+    fn push_edge(
+        &mut self,
+        from: NodeId,
+        to: NodeId,
+        weight: Weight,
+        key: EdgeKey,
+        bidir: bool,
+    ) -> usize {
         self.num_nodes = cmp::max(self.num_nodes, cmp::max(from, to) + 1);
-        self.edges.push(Edge::new(from, to, weight));
+        self.edges.push(Edge::new(from, to, weight).with_key(key));
         if bidir {
-            self.edges.push(Edge::new(to, from, weight));
+            self.edges.push(Edge::new(to, from, weight).with_key(key));
         }
         if bidir {
             2
@@ -213,17 +672,21 @@ impl InputGraph {
         }
     }
 
-    #[cfg(test)]
-    fn build_random_graph(rng: &mut StdRng, num_nodes: usize, mean_degree: f32) -> InputGraph {
+    #[cfg(any(test, feature = "random"))]
+    fn build_random_graph<F: FnMut() -> u64>(
+        mut next_u64: F,
+        num_nodes: usize,
+        mean_degree: f32,
+    ) -> InputGraph {
         let num_edges = (mean_degree * num_nodes as f32) as usize;
         let mut result = InputGraph::new();
         let mut edge_count = 0;
         loop {
-            let head = rng.gen_range(0, num_nodes);
-            let tail = rng.gen_range(0, num_nodes);
+            let head = random_below(next_u64(), num_nodes);
+            let tail = random_below(next_u64(), num_nodes);
             // limit max weight, but otherwise allow duplicates, loops etc. to make sure clean-up
             // inside InputGraph works correctly
-            let weight = rng.gen_range(1, 100);
+            let weight = 1 + random_below(next_u64(), 99);
             edge_count += result.add_edge(tail, head, weight);
             if edge_count == num_edges {
                 break;
@@ -233,104 +696,107 @@ impl InputGraph {
         result
     }
 
-    fn read_from_file(filename: &str) -> Self {
-        let file = File::open(filename).unwrap();
-        let reader = BufReader::new(file);
+    fn read_from_file(filename: &str) -> Result<Self, ParseError> {
+        let reader = BufReader::new(File::open(filename)?);
         let mut g = InputGraph::new();
         for (index, line) in reader.lines().enumerate() {
-            let s: String = line.unwrap();
+            let s: String = line?;
             if s.starts_with("a ") {
-                let (from, to, weight) = InputGraph::read_arc_line(index, &s);
+                let (from, to, weight) =
+                    InputGraph::read_arc_line(&s).map_err(|e| invalid_line(index, &s, e))?;
                 g.add_edge(from, to, weight);
             } else {
                 continue;
             }
         }
         g.freeze();
-        g
+        Ok(g)
     }
 
-    fn read_from_dimacs(filename: &str) -> Self {
-        let file = File::open(filename).unwrap();
-        let reader = BufReader::new(file);
+    fn read_from_dimacs(filename: &str) -> Result<Self, ParseError> {
+        let reader = BufReader::new(File::open(filename)?);
         let mut g = InputGraph::new();
         let mut nodes = 0;
         let mut edges = 0;
         let mut curr_edges = 0;
         let mut found_problem_line = false;
         for (index, line) in reader.lines().enumerate() {
-            let s: String = line.unwrap();
-            if s.is_empty() || s.starts_with("c") {
+            let s: String = line?;
+            let invalid = |reason: &str| invalid_line(index, &s, reason.to_string());
+            if s.is_empty() || s.starts_with('c') {
                 continue;
-            } else if s.starts_with("p sp ") {
+            } else if let Some(problem) = s.strip_prefix("p sp ") {
                 if found_problem_line {
-                    panic!(
-                        "There should be only one problem line, but found: {} | {}",
-                        index + 1,
-                        s
-                    );
+                    return Err(invalid("there should be only one problem line"));
+                }
+                let mut split = problem.split_whitespace();
+                nodes = parse_field(&mut split, "number of nodes").map_err(|e| invalid(&e))?;
+                edges = parse_field(&mut split, "number of edges").map_err(|e| invalid(&e))?;
+                if split.next().is_some() {
+                    return Err(invalid("invalid problem line"));
                 }
-                let mut split = s[5..].split_whitespace();
-                nodes = split.next().unwrap().parse::<usize>().unwrap();
-                edges = split.next().unwrap().parse::<usize>().unwrap();
-                assert!(split.next().is_none(), "Invalid problem line: {}", s);
                 found_problem_line = true;
             } else if s.starts_with("a ") {
-                assert!(
-                    found_problem_line,
-                    "The problem line must be written before the arc lines"
-                );
-                let (from, to, weight) = InputGraph::read_arc_line(index, &s);
-                assert!(
-                    from <= nodes && to <= nodes,
-                    "Invalid nodes in line: {} | {}",
-                    index + 1,
-                    s
-                );
-                assert!(
-                    curr_edges < edges,
-                    "Too many arc lines: {}, expected: {}",
-                    curr_edges + 1,
-                    edges
-                );
-
-                assert!(
-                    from > 0 && to > 0,
-                    "Invalid arc line: {} | {}",
-                    index + 1,
-                    s
-                );
+                if !found_problem_line {
+                    return Err(invalid(
+                        "the problem line must be written before the arc lines",
+                    ));
+                }
+                let (from, to, weight) = InputGraph::read_arc_line(&s).map_err(|e| invalid(&e))?;
+                if from == 0 || to == 0 || from > nodes || to > nodes {
+                    return Err(invalid(&format!(
+                        "the nodes must be between 1 and {}",
+                        nodes
+                    )));
+                }
+                if curr_edges == edges {
+                    return Err(invalid(&format!("too many arc lines, expected: {}", edges)));
+                }
                 // we convert 1-based node IDs from DIMACS to 0-based node IDs
                 g.add_edge(from - 1, to - 1, weight);
                 curr_edges += 1;
             } else {
-                panic!(
-                    "Invalid line: {} {}\nAll non-empty lines must start with 'c', 'p' or 'a'",
-                    index, s
-                );
+                return Err(invalid(
+                    "all non-empty lines must start with 'c', 'p' or 'a'",
+                ));
             }
         }
-        assert_eq!(
-            curr_edges, edges,
-            "Not enough arc lines: {}, expected: {}",
-            curr_edges, edges
-        );
+        if curr_edges != edges {
+            return Err(ParseError::InvalidFile {
+                reason: format!("not enough arc lines: {}, expected: {}", curr_edges, edges),
+            });
+        }
         g.freeze();
-        g
+        Ok(g)
     }
 
-    fn read_arc_line(index: usize, line: &String) -> (usize, usize, usize) {
+    fn read_arc_line(line: &str) -> Result<(usize, usize, usize), String> {
         let mut split = line[2..].split_whitespace();
-        let from = split.next().unwrap().parse::<usize>().unwrap();
-        let to = split.next().unwrap().parse::<usize>().unwrap();
-        let weight = split.next().unwrap().parse::<usize>().unwrap();
-        assert!(
-            split.next().is_none(),
-            "Invalid arc line: {} | {}",
-            index + 1,
-            line
-        );
-        (from, to, weight)
+        let from = parse_field(&mut split, "from")?;
+        let to = parse_field(&mut split, "to")?;
+        let weight = parse_field(&mut split, "weight")?;
+        if split.next().is_some() {
+            return Err("an arc line must have exactly three fields".to_string());
+        }
+        Ok((from, to, weight))
+    }
+}
+
+fn parse_field<'a, I: Iterator<Item = &'a str>>(
+    split: &mut I,
+    name: &str,
+) -> Result<usize, String> {
+    let field = split.next().ok_or_else(|| format!("missing {}", name))?;
+    field
+        .parse::<usize>()
+        .map_err(|e| format!("invalid {} '{}': {}", name, field, e))
+}
+
+fn invalid_line(index: usize, line: &str, reason: String) -> ParseError {
+    ParseError::InvalidLine {
+        line_number: index + 1,
+        line: line.to_string(),
+        reason,
     }
 }
 
@@ -340,6 +806,13 @@ impl fmt::Debug for InputGraph {
     }
 }
 
+/// Builds a frozen graph from the given edges, see `InputGraph::from_edges`.
+impl FromIterator<Edge> for InputGraph {
+    fn from_iter<I: IntoIterator<Item = Edge>>(edges: I) -> Self {
+        InputGraph::from_edges(0, edges)
+    }
+}
+
 impl Default for InputGraph {
     fn default() -> Self {
         Self::new()
@@ -351,18 +824,43 @@ pub struct Edge {
     pub from: NodeId,
     pub to: NodeId,
     pub weight: Weight,
+    /// The key given in `InputGraph::add_edge_with_key`, or `INVALID_EDGE_KEY`
+    #[serde(default = "invalid_edge_key")]
+    pub key: EdgeKey,
+}
+
+fn invalid_edge_key() -> EdgeKey {
+    INVALID_EDGE_KEY
 }
 
 impl Edge {
     pub fn new(from: NodeId, to: NodeId, weight: Weight) -> Edge {
-        Edge { from, to, weight }
+        Edge {
+            from,
+            to,
+            weight,
+            key: INVALID_EDGE_KEY,
+        }
+    }
+
+    pub fn with_key(mut self, key: EdgeKey) -> Edge {
+        self.key = key;
+        self
     }
 
     pub fn unit_test_output_string(&self) -> String {
-        return format!("g.add_edge({}, {}, {});", self.from, self.to, self.weight);
+        format!("g.add_edge({}, {}, {});", self.from, self.to, self.weight)
     }
 }
 
+/// Maps a uniformly distributed random number to the range `[0, bound)`, see
+/// `InputGraph::random`. We use our own arithmetic rather than the `rand` crate, so the results
+/// stay the same for all versions of `rand`.
+#[cfg(any(test, feature = "random"))]
+pub(crate) fn random_below(x: u64, bound: usize) -> usize {
+    ((x as u128 * bound as u128) >> 64) as usize
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -400,6 +898,63 @@ mod tests {
         g.add_edge(2, 5, 4);
     }
 
+    #[test]
+    #[should_panic]
+    fn panic_if_frozen_retain_edges() {
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 3);
+        g.freeze();
+        g.retain_edges(|_| true);
+    }
+
+    #[test]
+    fn retain_edges() {
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 3);
+        g.add_edge_bidir(1, 2, 5);
+        g.add_edge(2, 4, 8);
+        g.retain_edges(|e| e.weight != 5);
+        g.freeze();
+        assert_eq!(2, g.get_num_edges());
+        assert_eq!(5, g.get_num_nodes());
+        g.thaw();
+        g.retain_edges(|e| e.from != 0);
+        g.freeze();
+        assert_eq!(1, g.get_num_edges());
+        assert_eq!(2, g.get_edges()[0].from);
+    }
+
+    #[test]
+    fn node_penalties() {
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 3);
+        g.add_edge(1, 2, 4);
+        g.add_edge(2, 1, 5);
+        g.add_node_penalty(1, 10);
+        g.add_node_penalty(1, 2);
+        g.add_node_penalty(5, 7);
+        g.freeze();
+        let weights = |g: &InputGraph| -> Vec<(NodeId, NodeId, Weight)> {
+            g.get_edges()
+                .iter()
+                .map(|e| (e.from, e.to, e.weight))
+                .collect()
+        };
+        assert_eq!(vec![(0, 1, 15), (1, 2, 4), (2, 1, 17)], weights(&g));
+        // the penalties are only applied once
+        g.refreeze();
+        assert_eq!(vec![(0, 1, 15), (1, 2, 4), (2, 1, 17)], weights(&g));
+        g.thaw();
+        g.add_edge(2, 0, 1);
+        g.add_node_penalty(0, 1);
+        g.freeze();
+        assert_eq!(
+            vec![(0, 1, 15), (1, 2, 4), (2, 0, 2), (2, 1, 17)],
+            weights(&g)
+        );
+        assert_eq!(3, g.get_num_nodes());
+    }
+
     #[test]
     fn freeze_and_thaw() {
         let mut g = InputGraph::new();
@@ -414,6 +969,27 @@ mod tests {
         assert_eq!(1, g.get_edges()[0].weight);
     }
 
+    #[test]
+    fn refreeze() {
+        let mut g = InputGraph::new();
+        assert!(!g.is_frozen());
+        g.add_edge(0, 5, 10);
+        g.refreeze();
+        assert!(g.is_frozen());
+        // refreezing a frozen graph does not panic
+        g.refreeze();
+        assert_eq!(1, g.get_num_edges());
+        g.thaw();
+        assert!(!g.is_frozen());
+        g.add_edge(0, 5, 3);
+        g.add_edge(2, 0, 4);
+        g.refreeze();
+        assert_eq!(2, g.get_num_edges());
+        assert_eq!(3, g.get_edges()[0].weight);
+        assert_eq!(2, g.get_edges()[1].from);
+        assert_eq!(7, g.get_max_path_weight());
+    }
+
     #[test]
     fn num_nodes() {
         let mut g = InputGraph::new();
@@ -424,6 +1000,34 @@ mod tests {
         assert_eq!(12, g.get_num_nodes());
     }
 
+    #[test]
+    fn max_path_weight() {
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 5);
+        g.add_edge(1, 2, 7);
+        g.add_edge(2, 0, 3);
+        g.add_edge(0, 2, 9);
+        g.freeze();
+        // no shortest path has more than two edges
+        assert_eq!(16, g.get_max_path_weight());
+        g.thaw();
+        g.add_edge(2, 3, WEIGHT_MAX - 1);
+        g.freeze();
+        assert_eq!(WEIGHT_MAX, g.get_max_path_weight());
+    }
+
+    #[test]
+    fn max_path_weight_missing() {
+        // graphs serialized by version 1.x do not contain the bound
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 5);
+        g.add_edge(1, 2, 7);
+        g.add_edge(2, 0, 3);
+        g.freeze();
+        g.max_path_weight = None;
+        assert_eq!(12, g.get_max_path_weight());
+    }
+
     #[test]
     fn skips_loops() {
         let mut g = InputGraph::new();
@@ -444,6 +1048,52 @@ mod tests {
         assert_eq!(2, g.get_num_edges());
     }
 
+    #[test]
+    fn allows_zero_weight_edges() {
+        let mut g = InputGraph::new();
+        g.set_allow_zero_weights(true);
+        g.add_edge(0, 1, 5);
+        g.add_edge(1, 2, 0);
+        g.add_edge_bidir(2, 3, 0);
+        g.freeze();
+        assert_eq!(4, g.get_num_edges());
+    }
+
+    #[test]
+    fn try_add_edge() {
+        let mut g = InputGraph::new();
+        assert_eq!(Ok(1), g.try_add_edge(0, 1, 5));
+        assert_eq!(Ok(2), g.try_add_edge_bidir(1, 2, 3));
+        assert_eq!(Err(InputError::Loop { node: 4 }), g.try_add_edge(4, 4, 2));
+        assert_eq!(
+            Err(InputError::ZeroWeight { from: 2, to: 3 }),
+            g.try_add_edge_bidir(2, 3, 0)
+        );
+        g.set_allow_zero_weights(true);
+        assert_eq!(Ok(1), g.try_add_edge(2, 3, 0));
+        g.freeze();
+        assert_eq!(Err(InputError::Frozen), g.try_add_edge(0, 2, 1));
+        assert_eq!(4, g.get_num_nodes());
+        assert_eq!(4, g.get_num_edges());
+    }
+
+    #[test]
+    #[should_panic(expected = "loop edges are not allowed")]
+    fn panic_if_strict_add_loop() {
+        let mut g = InputGraph::new();
+        g.set_strict(true);
+        g.add_edge(0, 1, 3);
+        g.add_edge(2, 2, 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "zero weight edges are not allowed")]
+    fn panic_if_strict_add_zero_weight_edge() {
+        let mut g = InputGraph::new();
+        g.set_strict(true);
+        g.add_edge_bidir(0, 1, 0);
+    }
+
     #[test]
     fn skips_duplicate_edges() {
         let mut g = InputGraph::new();
@@ -486,4 +1136,212 @@ mod tests {
             .collect::<Vec<Weight>>();
         assert_eq!(vec![45, 43, 87, 75, 88, 5], weights);
     }
+
+    fn read_dimacs(content: &str) -> Result<InputGraph, ParseError> {
+        let filename = "input_graph_test.gr";
+        std::fs::write(filename, content).unwrap();
+        let result = InputGraph::try_from_dimacs_file(filename);
+        std::fs::remove_file(filename).unwrap();
+        result
+    }
+
+    #[test]
+    fn try_from_dimacs_file() {
+        let g = read_dimacs("c comment\np sp 3 2\na 1 2 5\n\na 3 1 4\n").unwrap();
+        assert_eq!(3, g.get_num_nodes());
+        assert_eq!(
+            "g.add_edge(0, 1, 5);\ng.add_edge(2, 0, 4);\n",
+            g.unit_test_output_string()
+        );
+
+        let invalid = |content: &str| read_dimacs(content).unwrap_err().to_string();
+        assert_eq!(
+            "line 3: invalid weight 'x': invalid digit found in string | a 1 3 x",
+            invalid("p sp 3 2\na 1 2 5\na 1 3 x\n")
+        );
+        assert_eq!(
+            "line 2: the nodes must be between 1 and 3 | a 0 2 5",
+            invalid("p sp 3 1\na 0 2 5\n")
+        );
+        assert_eq!(
+            "line 1: the problem line must be written before the arc lines | a 1 2 5",
+            invalid("a 1 2 5\np sp 3 1\n")
+        );
+        assert_eq!(
+            "line 3: too many arc lines, expected: 1 | a 2 3 5",
+            invalid("p sp 3 1\na 1 2 5\na 2 3 5\n")
+        );
+        assert_eq!(
+            "line 2: all non-empty lines must start with 'c', 'p' or 'a' | x",
+            invalid("p sp 3 1\nx\n")
+        );
+        assert_eq!(
+            "line 1: missing number of edges | p sp 3",
+            invalid("p sp 3\n")
+        );
+        assert_eq!(
+            "not enough arc lines: 1, expected: 2",
+            invalid("p sp 3 2\na 1 2 5\n")
+        );
+    }
+
+    #[test]
+    fn try_from_file() {
+        let filename = "input_graph_test.txt";
+        std::fs::write(filename, "a 0 1 5\nanything else\na 1 2 3 4\n").unwrap();
+        let result = InputGraph::try_from_file(filename);
+        std::fs::remove_file(filename).unwrap();
+        assert_eq!(
+            "line 3: an arc line must have exactly three fields | a 1 2 3 4",
+            result.unwrap_err().to_string()
+        );
+        match InputGraph::try_from_file("does_not_exist.txt") {
+            Err(ParseError::Io(e)) => assert_eq!(std::io::ErrorKind::NotFound, e.kind()),
+            _ => panic!("expected an io error"),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "could not read the file")]
+    fn panic_if_file_does_not_exist() {
+        InputGraph::from_file("does_not_exist.txt");
+    }
+
+    #[test]
+    fn merge() {
+        let mut a = InputGraph::new();
+        a.add_edge(0, 1, 3);
+        a.add_edge(1, 2, 4);
+        a.set_weight_unit(WeightUnit::seconds());
+        a.freeze();
+        let mut b = InputGraph::new();
+        b.add_edge_with_key(0, 1, 5, 42);
+        b.add_edge(2, 0, 6);
+        b.set_min_num_nodes(4);
+        b.freeze();
+
+        let mut g = InputGraph::new();
+        assert_eq!(2, g.merge(&a, 0));
+        assert_eq!(2, g.merge(&b, 3));
+        g.freeze();
+        assert_eq!(7, g.get_num_nodes());
+        assert_eq!(
+            "g.add_edge(0, 1, 3);\ng.add_edge(1, 2, 4);\ng.add_edge(3, 4, 5);\ng.add_edge(5, 3, 6);\n",
+            g.unit_test_output_string()
+        );
+        assert_eq!(Some(42), g.get_edge_key(2));
+        assert_eq!(Some(WeightUnit::seconds()), g.get_weight_unit());
+
+        // the first two nodes of a and b are now nodes 1 and 2, for the parallel edges only the
+        // lightest is kept
+        g.thaw();
+        assert_eq!(2, g.merge_with_mapping(&a, &[1, 2, 7]));
+        g.merge_with_mapping(&b, &[1, 2, 8, 9]);
+        g.freeze();
+        assert_eq!(10, g.get_num_nodes());
+        assert_eq!(
+            "g.add_edge(0, 1, 3);\ng.add_edge(1, 2, 3);\ng.add_edge(2, 7, 4);\ng.add_edge(3, 4, 5);\ng.add_edge(5, 3, 6);\ng.add_edge(8, 1, 6);\n",
+            g.unit_test_output_string()
+        );
+
+        // nodes mapped to the same node yield a loop, which is skipped
+        g.thaw();
+        assert_eq!(0, g.merge_with_mapping(&a, &[4, 4, 4]));
+    }
+
+    #[test]
+    #[should_panic(expected = "different weight units")]
+    fn panic_if_merge_different_weight_units() {
+        let mut a = InputGraph::new();
+        a.set_weight_unit(WeightUnit::seconds());
+        a.freeze();
+        let mut g = InputGraph::new();
+        g.set_weight_unit(WeightUnit::meters());
+        g.merge(&a, 0);
+    }
+
+    #[test]
+    fn random_only_depends_on_numbers() {
+        // a simple sequence of numbers instead of a random number generator, so we can check
+        // that the graph does not change when the numbers are the same
+        let mut x: u64 = 0;
+        let mut next_u64 = || {
+            x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            x
+        };
+        let g = InputGraph::random(&mut next_u64, 5, 1.0);
+        // the fifth edge is a duplicate of one of these
+        let edges: Vec<_> = g
+            .get_edges()
+            .iter()
+            .map(|e| (e.from, e.to, e.weight))
+            .collect();
+        assert_eq!(vec![(0, 2, 71), (1, 3, 85), (3, 0, 27), (4, 1, 56)], edges);
+    }
+
+    #[test]
+    fn capacity() {
+        let mut g = InputGraph::with_capacity(5, 100);
+        assert!(g.edges.capacity() >= 100);
+        g.add_edge_bidir(0, 1, 3);
+        g.add_edge(0, 1, 4);
+        g.reserve_edges(1000);
+        assert!(g.edges.capacity() >= 1003);
+        g.freeze();
+        g.shrink_to_fit();
+        assert!(g.edges.capacity() < 1003);
+        assert_eq!(5, g.get_num_nodes());
+        assert_eq!(
+            "g.add_edge(0, 1, 3);\ng.add_edge(1, 0, 3);\n",
+            g.unit_test_output_string()
+        );
+    }
+
+    #[test]
+    fn from_edges() {
+        let edges = vec![
+            Edge::new(3, 2, 90),
+            Edge::new(1, 3, 43).with_key(7),
+            Edge::new(3, 2, 88),
+            Edge::new(2, 2, 1),
+            Edge::new(0, 2, 0),
+        ];
+        let g = InputGraph::from_edges(10, edges.clone());
+        assert!(g.is_frozen());
+        assert_eq!(10, g.get_num_nodes());
+        assert_eq!(
+            "g.add_edge(1, 3, 43);\ng.add_edge(3, 2, 88);\n",
+            g.unit_test_output_string()
+        );
+        assert_eq!(Some(7), g.get_edge_key(0));
+
+        let g: InputGraph = edges.into_iter().collect();
+        assert_eq!(4, g.get_num_nodes());
+        assert_eq!(2, g.get_num_edges());
+    }
+
+    #[test]
+    fn extract_largest_component() {
+        // 0 -> 1 <-> 2 <-> 4, 3 <-> 5
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge_bidir_with_key(1, 2, 2, 12);
+        g.add_edge_bidir(2, 4, 3);
+        g.add_edge_bidir(3, 5, 4);
+        g.set_weight_unit(WeightUnit::seconds());
+        g.freeze();
+        let (largest, nodes) = g.extract_largest_component();
+        assert_eq!(vec![1, 2, 4], nodes);
+        assert_eq!(3, largest.get_num_nodes());
+        assert_eq!(
+            "g.add_edge(0, 1, 2);\ng.add_edge(1, 0, 2);\ng.add_edge(1, 2, 3);\ng.add_edge(2, 1, 3);\n",
+            largest.unit_test_output_string()
+        );
+        assert_eq!(Some(12), largest.get_edge_key(0));
+        assert_eq!(Some(WeightUnit::seconds()), largest.get_weight_unit());
+
+        let (empty, nodes) = InputGraph::from_edges(0, vec![]).extract_largest_component();
+        assert!(nodes.is_empty());
+        assert_eq!(0, empty.get_num_nodes());
+    }
 }
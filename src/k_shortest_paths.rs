@@ -0,0 +1,212 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashSet};
+
+use crate::constants::{NodeId, Weight, INVALID_NODE, WEIGHT_MAX};
+use crate::fast_graph::FastGraph;
+use crate::heap_item::HeapItem;
+use crate::shortest_path::ShortestPath;
+use crate::valid_flags::ValidFlags;
+
+/// Finds up to `k` loopless paths from `source` to `target` in order of increasing weight using
+/// Yen's algorithm. The first path must be the shortest path, which we get from the usual query.
+///
+/// Yen's algorithm needs to exclude nodes and edges when searching for deviations ('spur paths')
+/// from the previously found paths. This does not work with the shortcuts of the contraction
+/// hierarchy, because a shortcut that would be needed might not exist when its witness path uses
+/// an excluded node. Therefore the spur paths are calculated using Dijkstra's algorithm on the
+/// original (non-shortcut) edges of the prepared graph.
+pub fn calc_k_paths(graph: &FastGraph, shortest_path: ShortestPath, k: usize) -> Vec<ShortestPath> {
+    let mut result = vec![];
+    if k == 0 {
+        return result;
+    }
+    let target = shortest_path.get_target();
+    let mut spur_search = SpurSearch::new(graph);
+    let mut candidates = BinaryHeap::new();
+    let mut known_paths: HashSet<Vec<NodeId>> = HashSet::new();
+    known_paths.insert(shortest_path.get_nodes().clone());
+    result.push(shortest_path);
+    while result.len() < k {
+        let prev_nodes = result[result.len() - 1].get_nodes().clone();
+        let mut root_weight = 0;
+        for j in 0..prev_nodes.len() - 1 {
+            let spur_node = prev_nodes[j];
+            let root = &prev_nodes[..=j];
+            let mut excluded_edges = HashSet::new();
+            for path in &result {
+                let nodes = path.get_nodes();
+                if nodes.len() > j + 1 && &nodes[..=j] == root {
+                    excluded_edges.insert((nodes[j], nodes[j + 1]));
+                }
+            }
+            if let Some((spur_weight, spur_nodes)) =
+                spur_search.calc_path(spur_node, target, &root[..j], &excluded_edges)
+            {
+                let mut nodes = root[..j].to_vec();
+                nodes.extend(spur_nodes);
+                if known_paths.insert(nodes.clone()) {
+                    candidates.push(Candidate {
+                        weight: root_weight + spur_weight,
+                        nodes,
+                    });
+                }
+            }
+            root_weight += spur_search.get_edge_weight(spur_node, prev_nodes[j + 1]);
+        }
+        match candidates.pop() {
+            Some(candidate) => result.push(ShortestPath::new(
+                candidate.nodes[0],
+                target,
+                candidate.weight,
+                candidate.nodes,
+            )),
+            None => break,
+        }
+    }
+    result
+}
+
+/// Dijkstra's algorithm on the original edges of a `FastGraph` that allows excluding some nodes
+/// and edges
+struct SpurSearch {
+    out_edges: Vec<Vec<(NodeId, Weight)>>,
+    weights: Vec<Weight>,
+    parents: Vec<NodeId>,
+    valid_flags: ValidFlags,
+    excluded_nodes: ValidFlags,
+    heap: BinaryHeap<HeapItem>,
+}
+
+impl SpurSearch {
+    fn new(graph: &FastGraph) -> Self {
+        let num_nodes = graph.get_num_nodes();
+        let mut out_edges = vec![vec![]; num_nodes];
+        for edge in &graph.edges_fwd {
+            if !edge.is_shortcut() {
+                out_edges[edge.base_node].push((edge.adj_node, edge.weight));
+            }
+        }
+        for edge in &graph.edges_bwd {
+            if !edge.is_shortcut() {
+                out_edges[edge.adj_node].push((edge.base_node, edge.weight));
+            }
+        }
+        SpurSearch {
+            out_edges,
+            weights: vec![WEIGHT_MAX; num_nodes],
+            parents: vec![INVALID_NODE; num_nodes],
+            valid_flags: ValidFlags::new(num_nodes),
+            excluded_nodes: ValidFlags::new(num_nodes),
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    fn get_edge_weight(&self, from: NodeId, to: NodeId) -> Weight {
+        self.out_edges[from]
+            .iter()
+            .find(|(adj, _)| *adj == to)
+            .map(|(_, weight)| *weight)
+            .expect("paths must only use original edges")
+    }
+
+    fn calc_path(
+        &mut self,
+        start: NodeId,
+        end: NodeId,
+        excluded_nodes: &[NodeId],
+        excluded_edges: &HashSet<(NodeId, NodeId)>,
+    ) -> Option<(Weight, Vec<NodeId>)> {
+        self.excluded_nodes.invalidate_all();
+        for node in excluded_nodes {
+            self.excluded_nodes.set_valid(*node);
+        }
+        self.valid_flags.invalidate_all();
+        self.heap.clear();
+        self.update_node(start, 0, INVALID_NODE);
+        self.heap.push(HeapItem::new(0, start));
+        while let Some(curr) = self.heap.pop() {
+            if curr.weight > self.get_weight(curr.node_id) {
+                continue;
+            }
+            if curr.node_id == end {
+                let mut nodes = vec![end];
+                let mut node = end;
+                while self.parents[node] != INVALID_NODE {
+                    node = self.parents[node];
+                    nodes.push(node);
+                }
+                nodes.reverse();
+                return Some((curr.weight, nodes));
+            }
+            for i in 0..self.out_edges[curr.node_id].len() {
+                let (adj, edge_weight) = self.out_edges[curr.node_id][i];
+                if self.excluded_nodes.is_valid(adj)
+                    || excluded_edges.contains(&(curr.node_id, adj))
+                {
+                    continue;
+                }
+                let weight = curr.weight + edge_weight;
+                if weight < self.get_weight(adj) {
+                    self.update_node(adj, weight, curr.node_id);
+                    self.heap.push(HeapItem::new(weight, adj));
+                }
+            }
+        }
+        None
+    }
+
+    fn update_node(&mut self, node: NodeId, weight: Weight, parent: NodeId) {
+        self.valid_flags.set_valid(node);
+        self.weights[node] = weight;
+        self.parents[node] = parent;
+    }
+
+    fn get_weight(&self, node: NodeId) -> Weight {
+        if self.valid_flags.is_valid(node) {
+            self.weights[node]
+        } else {
+            WEIGHT_MAX
+        }
+    }
+}
+
+/// A candidate path, ordered such that the path with the smallest weight is on top of the heap
+#[derive(Eq, PartialEq)]
+struct Candidate {
+    weight: Weight,
+    nodes: Vec<NodeId>,
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Candidate) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Candidate) -> Ordering {
+        self.weight
+            .cmp(&other.weight)
+            .then_with(|| self.nodes.cmp(&other.nodes))
+            .reverse()
+    }
+}
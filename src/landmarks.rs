@@ -0,0 +1,228 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use std::collections::BinaryHeap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{NodeId, Weight, WEIGHT_MAX};
+use crate::fast_graph::FastGraph;
+use crate::heap_item::HeapItem;
+
+/// The shortest path weights from and to a few selected nodes (landmarks), which yield lower
+/// bounds for the weight between any two nodes using the triangle inequality (ALT). A
+/// `PathCalculator` can use these bounds to prune its searches, see
+/// `PathCalculator::set_landmarks`. This helps most for long queries, but costs two weights per
+/// node and landmark.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Landmarks {
+    num_nodes: usize,
+    graph_fingerprint: u64,
+    nodes: Vec<NodeId>,
+    /// `weights_from[i * num_nodes + node]` is the weight from the i-th landmark to `node`
+    weights_from: Vec<Weight>,
+    /// `weights_to[i * num_nodes + node]` is the weight from `node` to the i-th landmark
+    weights_to: Vec<Weight>,
+}
+
+impl Landmarks {
+    /// Selects `num_landmarks` landmarks that are far away from each other and calculates their
+    /// weights. Every landmark requires two Dijkstra searches on the entire graph.
+    pub fn new(graph: &FastGraph, num_landmarks: usize) -> Self {
+        let adjacency = Adjacency::new(graph);
+        let num_nodes = graph.get_num_nodes();
+        let mut landmarks = Landmarks {
+            num_nodes,
+            graph_fingerprint: graph.get_fingerprint(),
+            nodes: vec![],
+            weights_from: vec![],
+            weights_to: vec![],
+        };
+        if num_nodes == 0 {
+            return landmarks;
+        }
+        // we start with the node that is farthest away from an arbitrary node, and then keep
+        // adding the node whose minimum weight to all the previous landmarks is the largest
+        let mut min_weights = adjacency.calc_weights(0, true);
+        for _ in 0..num_landmarks.min(num_nodes) {
+            let next = (0..num_nodes)
+                .filter(|node| !landmarks.nodes.contains(node))
+                .max_by_key(|node| {
+                    let weight = min_weights[*node];
+                    // unreachable nodes are the least useful landmarks
+                    (weight != WEIGHT_MAX, weight)
+                })
+                .unwrap();
+            landmarks.add(&adjacency, next);
+            let weights_from = &landmarks.weights_from[landmarks.weights_from.len() - num_nodes..];
+            for (min_weight, weight) in min_weights.iter_mut().zip(weights_from) {
+                *min_weight = (*min_weight).min(*weight);
+            }
+        }
+        landmarks
+    }
+
+    /// Like `new`, but uses the given nodes as landmarks.
+    pub fn with_nodes(graph: &FastGraph, nodes: &[NodeId]) -> Self {
+        let adjacency = Adjacency::new(graph);
+        let mut landmarks = Landmarks {
+            num_nodes: graph.get_num_nodes(),
+            graph_fingerprint: graph.get_fingerprint(),
+            nodes: vec![],
+            weights_from: vec![],
+            weights_to: vec![],
+        };
+        for node in nodes {
+            assert!(*node < landmarks.num_nodes, "invalid landmark");
+            landmarks.add(&adjacency, *node);
+        }
+        landmarks
+    }
+
+    pub fn get_nodes(&self) -> &[NodeId] {
+        &self.nodes
+    }
+
+    /// Returns true if these landmarks were calculated for the given graph.
+    pub fn is_compatible(&self, graph: &FastGraph) -> bool {
+        self.num_nodes == graph.get_num_nodes() && self.graph_fingerprint == graph.get_fingerprint()
+    }
+
+    /// Returns a lower bound for the weight of the shortest path from `from` to `to`, or
+    /// `WEIGHT_MAX` if it is certain that there is no such path.
+    pub fn calc_lower_bound(&self, from: NodeId, to: NodeId) -> Weight {
+        let mut bound = 0;
+        for i in 0..self.nodes.len() {
+            let offset = i * self.num_nodes;
+            // landmark -> from -> to is not shorter than landmark -> to
+            let (l_from, l_to) = (
+                self.weights_from[offset + from],
+                self.weights_from[offset + to],
+            );
+            if l_from != WEIGHT_MAX {
+                if l_to == WEIGHT_MAX {
+                    return WEIGHT_MAX;
+                }
+                bound = bound.max(l_to.saturating_sub(l_from));
+            }
+            // from -> to -> landmark is not shorter than from -> landmark
+            let (from_l, to_l) = (self.weights_to[offset + from], self.weights_to[offset + to]);
+            if to_l != WEIGHT_MAX {
+                if from_l == WEIGHT_MAX {
+                    return WEIGHT_MAX;
+                }
+                bound = bound.max(from_l.saturating_sub(to_l));
+            }
+        }
+        bound
+    }
+
+    fn add(&mut self, adjacency: &Adjacency, node: NodeId) {
+        self.nodes.push(node);
+        self.weights_from.extend(adjacency.calc_weights(node, true));
+        self.weights_to.extend(adjacency.calc_weights(node, false));
+    }
+}
+
+/// The original edges of the graph in both directions
+pub(crate) struct Adjacency {
+    pub(crate) out_edges: Vec<Vec<(NodeId, Weight)>>,
+    pub(crate) in_edges: Vec<Vec<(NodeId, Weight)>>,
+}
+
+impl Adjacency {
+    pub(crate) fn new(graph: &FastGraph) -> Self {
+        let num_nodes = graph.get_num_nodes();
+        let mut out_edges = vec![vec![]; num_nodes];
+        let mut in_edges = vec![vec![]; num_nodes];
+        for node in 0..num_nodes {
+            for edge in graph.get_original_out_edges(node) {
+                out_edges[edge.from].push((edge.to, edge.weight));
+                in_edges[edge.to].push((edge.from, edge.weight));
+            }
+        }
+        Adjacency {
+            out_edges,
+            in_edges,
+        }
+    }
+
+    /// Returns the weights of the shortest paths from `source` to all nodes (or from all nodes to
+    /// `source` if `forward` is false).
+    pub(crate) fn calc_weights(&self, source: NodeId, forward: bool) -> Vec<Weight> {
+        let edges = if forward {
+            &self.out_edges
+        } else {
+            &self.in_edges
+        };
+        let mut weights = vec![WEIGHT_MAX; edges.len()];
+        let mut heap = BinaryHeap::new();
+        weights[source] = 0;
+        heap.push(HeapItem::new(0, source));
+        while let Some(curr) = heap.pop() {
+            if curr.weight > weights[curr.node_id] {
+                continue;
+            }
+            for (adj, edge_weight) in &edges[curr.node_id] {
+                let weight = curr.weight.saturating_add(*edge_weight);
+                if weight < weights[*adj] {
+                    weights[*adj] = weight;
+                    heap.push(HeapItem::new(weight, *adj));
+                }
+            }
+        }
+        weights
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::input_graph::InputGraph;
+
+    use super::*;
+
+    #[test]
+    fn lower_bounds() {
+        // 0 -> 1 -> 2 -> 3    4
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 2);
+        g.add_edge(2, 3, 3);
+        g.add_edge(4, 3, 1);
+        g.freeze();
+        let fast_graph = crate::prepare(&g);
+        let landmarks = Landmarks::with_nodes(&fast_graph, &[0, 3]);
+        assert_eq!(&[0, 3], landmarks.get_nodes());
+        assert!(landmarks.is_compatible(&fast_graph));
+        // the landmarks lie on the path, so the bounds are exact
+        assert_eq!(6, landmarks.calc_lower_bound(0, 3));
+        assert_eq!(5, landmarks.calc_lower_bound(1, 3));
+        assert_eq!(2, landmarks.calc_lower_bound(1, 2));
+        // 0 can reach 1 but not 4
+        assert_eq!(WEIGHT_MAX, landmarks.calc_lower_bound(1, 4));
+        // 1 can reach 3, but 0 cannot
+        assert_eq!(WEIGHT_MAX, landmarks.calc_lower_bound(3, 0));
+        assert_eq!(0, landmarks.calc_lower_bound(2, 2));
+
+        let landmarks = Landmarks::new(&fast_graph, 2);
+        assert_eq!(2, landmarks.get_nodes().len());
+        let landmarks = Landmarks::new(&fast_graph, 10);
+        assert_eq!(5, landmarks.get_nodes().len());
+    }
+}
@@ -20,35 +20,135 @@
 #[macro_use]
 extern crate log;
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+pub use crate::arc_flags::ArcFlags;
+pub use crate::blocked_elements::BlockedElements;
+pub use crate::calculator_pool::{CalculatorPool, PooledCalculator};
+pub use crate::cancellation::{CancellationToken, Cancelled};
+#[cfg(feature = "ch_import")]
+pub use crate::ch_import::{from_ch_edges, from_ch_file, ChEdge};
+pub use crate::chain_compression::ChainCompression;
 pub use crate::constants::*;
+pub use crate::csv_import::{CsvColumn, CsvOptions};
+pub use crate::customizable_graph::CustomizableGraph;
+pub use crate::edge_based_graph::{EdgeBasedGraph, TurnCosts};
+pub use crate::edge_payloads::EdgePayloads;
+pub use crate::edge_point::EdgePoint;
+pub use crate::error::{
+    CustomizationError, Error, InputError, OverlayError, ParseError, PreparationError, UpdateError,
+};
 pub use crate::fast_graph::FastGraph;
+pub use crate::fast_graph::FastGraphEdge;
+pub use crate::fast_graph16::FastGraph16;
 pub use crate::fast_graph32::FastGraph32;
 pub use crate::fast_graph_builder::FastGraphBuilder;
 pub use crate::fast_graph_builder::Params;
 pub use crate::fast_graph_builder::ParamsWithOrder;
+pub use crate::fast_graph_builder::{
+    ContractedNode, PreparationBuffers, PreparationProgress, ResumableBuilder,
+};
+pub use crate::fast_graph_portable::{FastGraphEdgePortable, FastGraphPortable};
+pub use crate::fast_graph_sparse::FastGraphSparse;
+pub use crate::fast_graph_validation::ValidationError;
+pub use crate::grid_graph::{GridConnectivity, GridGraph};
+pub use crate::hub_labels::HubLabels;
+pub use crate::id_mapper::IdMapper;
 pub use crate::input_graph::Edge;
 pub use crate::input_graph::InputGraph;
+pub use crate::landmarks::Landmarks;
+pub use crate::memory_estimate::estimate_preparation_memory;
+#[cfg(feature = "snapping")]
+pub use crate::node_locations::NodeLocations;
+pub use crate::node_ordering::{read_node_ordering, write_node_ordering};
+pub use crate::order_validation::OrderError;
+#[cfg(feature = "osm")]
+pub use crate::osm::{CarProfile, OsmGraph, OsmRoute, OsmRouter};
+pub use crate::overlay_graph::OverlayGraph;
+pub use crate::param_description::{ParamDescription, ParamValue};
+pub use crate::params_builder::ParamsBuilder;
+pub use crate::path_calculator::DirectionBias;
 pub use crate::path_calculator::PathCalculator;
+pub use crate::preparation_info::PreparationInfo;
+pub use crate::preparation_info::PreparationParams;
+pub use crate::query_stats::{QueryStats, SearchDirection, SettledNode};
+pub use crate::query_verifier::{Discrepancy, QueryVerifier};
+pub use crate::route_with_legs::RouteWithLegs;
 pub use crate::shortest_path::ShortestPath;
+pub use crate::stop_order::MAX_STOPS;
+pub use crate::subgraph_view::SubgraphView;
+pub use crate::weight_scale::WeightScale;
+pub use crate::weight_unit::{BaseUnit, WeightUnit};
 
+mod arc_flags;
+mod blocked_elements;
+mod calculator_pool;
+mod cancellation;
+#[cfg(feature = "ch_import")]
+mod ch_import;
+mod chain_compression;
+mod chunked_preparation;
+mod connected_components;
 mod constants;
-#[cfg(test)]
+mod csv_import;
+mod customizable_graph;
 mod dijkstra;
+mod edge_based_graph;
+mod edge_payloads;
+mod edge_point;
+mod error;
 mod fast_graph;
+mod fast_graph16;
 mod fast_graph32;
 mod fast_graph_builder;
+mod fast_graph_portable;
+mod fast_graph_sparse;
+mod fast_graph_update;
+mod fast_graph_validation;
+#[cfg(any(test, feature = "fixtures"))]
+pub mod fixtures;
 #[cfg(test)]
 mod floyd_warshall;
+mod geojson;
+mod grid_graph;
 mod heap_item;
+mod hub_labels;
+mod id_mapper;
 mod input_graph;
+mod k_shortest_paths;
+mod landmarks;
+mod memory_estimate;
 mod node_contractor;
+#[cfg(feature = "snapping")]
+mod node_locations;
+mod node_ordering;
+mod order_validation;
+mod original_edge_search;
+#[cfg(feature = "osm")]
+mod osm;
+#[cfg(feature = "osm")]
+mod osm_pbf;
+mod overlay_graph;
+mod param_description;
+mod params_builder;
 mod path_calculator;
 mod preparation_graph;
+mod preparation_info;
+mod query_stats;
+mod query_verifier;
+mod route_with_legs;
+mod shortcut_pruning;
 mod shortest_path;
+mod stop_order;
+mod subgraph_view;
 mod valid_flags;
+mod weight_overrides;
+mod weight_scale;
+mod weight_unit;
 mod witness_search;
+mod zone_penalty;
 
 /// Prepares the given `InputGraph` for fast shortest path calculations.
 pub fn prepare(input_graph: &InputGraph) -> FastGraph {
@@ -60,11 +160,133 @@ pub fn prepare_with_params(input_graph: &InputGraph, params: &Params) -> FastGra
     FastGraphBuilder::build_with_params(input_graph, params)
 }
 
+/// Like `prepare_with_params()`, but returns an error rather than panicking if the preparation is
+/// aborted, see `Params::max_degree`.
+pub fn try_prepare_with_params(
+    input_graph: &InputGraph,
+    params: &Params,
+) -> Result<FastGraph, PreparationError> {
+    FastGraphBuilder::try_build_with_params(input_graph, params)
+}
+
+/// Like `try_prepare_with_params()`, but calls `hook` after every contracted node, and aborts the
+/// preparation with an error if it returns false, see `FastGraphBuilder::try_build_with_hook`.
+pub fn prepare_with_hook<F>(
+    input_graph: &InputGraph,
+    params: &Params,
+    hook: F,
+) -> Result<FastGraph, PreparationError>
+where
+    F: FnMut(&ContractedNode) -> bool,
+{
+    FastGraphBuilder::try_build_with_hook(input_graph, params, hook)
+}
+
+/// Like `try_prepare_with_params()`, but reuses the memory kept in `buffers` from previous
+/// preparations, which is faster when many graphs of the same size are prepared one after the
+/// other, see `PreparationBuffers`. The buffers can still be used after a preparation returned a
+/// `PreparationError`.
+pub fn prepare_with_buffers(
+    input_graph: &InputGraph,
+    params: &Params,
+    buffers: &mut PreparationBuffers,
+) -> Result<FastGraph, PreparationError> {
+    FastGraphBuilder::try_build_with_buffers(input_graph, params, buffers)
+}
+
+/// Like `prepare_with_params()`, but contracts sets of independent nodes in parallel using the
+/// rayon thread pool. The result is deterministic, i.e. the serialized graph is byte-identical for
+/// the same input graph and parameters, no matter how many threads are used, but it differs
+/// slightly from the one of `prepare_with_params()`. Panics if the preparation is aborted, see
+/// `Params::max_degree`.
+#[cfg(feature = "rayon")]
+pub fn prepare_parallel(input_graph: &InputGraph, params: &Params) -> FastGraph {
+    FastGraphBuilder::build_parallel(input_graph, params)
+}
+
+/// Like `try_prepare_with_params()`, but contracts the graph cell by cell to improve the memory
+/// locality, where `cells[node]` is the cell of each node, see `FastGraphBuilder::try_build_with_cells`
+/// and `calc_grid_cells()`.
+pub fn prepare_with_cells(
+    input_graph: &InputGraph,
+    cells: &[usize],
+    params: &Params,
+) -> Result<FastGraph, PreparationError> {
+    FastGraphBuilder::try_build_with_cells(input_graph, cells, params)
+}
+
+/// Prepares a graph with `num_nodes` nodes without ever holding all of its edges in memory, where
+/// `cells[node]` is the cell of each node and `load_cell_edges(cell)` returns all edges that start
+/// or end at a node of the given cell, e.g. read from a file per cell. The edges between two cells
+/// are therefore returned twice, once for each cell.
+///
+/// The cells are processed one after the other: the edges of a cell are loaded, its inner nodes,
+/// i.e. the nodes that are only connected to nodes of the same cell, are contracted and only the
+/// edges and shortcuts between its boundary nodes are kept. Finally the boundary nodes of all cells
+/// are contracted. So apart from the `FastGraph` that is built, the memory usage is bounded by the
+/// size of the largest cell and the number of edges between boundary nodes, which is small for
+/// cells that are obtained from a graph partition. Since the witness searches cannot leave a cell
+/// there are usually more shortcuts than with `prepare_with_params`. Only the priority factors and
+/// the settled node limits of `params` are used.
+pub fn prepare_chunked<F>(
+    num_nodes: usize,
+    cells: &[usize],
+    load_cell_edges: F,
+    params: &Params,
+) -> Result<FastGraph, String>
+where
+    F: FnMut(usize) -> Result<Vec<Edge>, String>,
+{
+    chunked_preparation::build_chunked(num_nodes, cells, load_cell_edges, params)
+}
+
+/// Prepares the implicit grid graph described by `grid` without creating an `InputGraph` first,
+/// see `GridGraph`.
+pub fn prepare_grid(grid: &GridGraph, params: &Params) -> FastGraph {
+    FastGraphBuilder::build_grid(grid, params)
+}
+
+/// Assigns the given coordinates to the cells of a grid with the given cell size, e.g. to use them
+/// for `prepare_with_cells()`. The coordinates can be e.g. (longitude, latitude) pairs or
+/// projected coordinates, one pair per node.
+pub fn calc_grid_cells(coordinates: &[(f64, f64)], cell_size: f64) -> Vec<usize> {
+    assert!(
+        cell_size.is_finite() && cell_size > 0.0,
+        "the cell size must be positive, but was: {}",
+        cell_size
+    );
+    let min_x = coordinates
+        .iter()
+        .map(|c| c.0)
+        .fold(f64::INFINITY, f64::min);
+    let min_y = coordinates
+        .iter()
+        .map(|c| c.1)
+        .fold(f64::INFINITY, f64::min);
+    let max_x = coordinates
+        .iter()
+        .map(|c| c.0)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let num_columns = ((max_x - min_x) / cell_size) as usize + 1;
+    coordinates
+        .iter()
+        .map(|(x, y)| {
+            let column = ((x - min_x) / cell_size) as usize;
+            let row = ((y - min_y) / cell_size) as usize;
+            row * num_columns + column
+        })
+        .collect()
+}
+
 /// Prepares the given input graph using a fixed node ordering, which can be any permutation
 /// of the node ids. This can be used to speed up the graph preparation if you have done
 /// it for a similar graph with an equal number of nodes. For example if you have changed some
-/// of the edge weights only.
-pub fn prepare_with_order(input_graph: &InputGraph, order: &[NodeId]) -> Result<FastGraph, String> {
+/// of the edge weights only. Returns an error describing the first problem (and its position) if
+/// the order is not a permutation of the node ids, see `OrderError`.
+pub fn prepare_with_order(
+    input_graph: &InputGraph,
+    order: &[NodeId],
+) -> Result<FastGraph, OrderError> {
     FastGraphBuilder::build_with_order(input_graph, order)
 }
 
@@ -73,7 +295,7 @@ pub fn prepare_with_order_with_params(
     input_graph: &InputGraph,
     order: &[NodeId],
     params: &ParamsWithOrder,
-) -> Result<FastGraph, String> {
+) -> Result<FastGraph, OrderError> {
     FastGraphBuilder::build_with_order_with_params(input_graph, order, params)
 }
 
@@ -83,19 +305,200 @@ pub fn calc_path(fast_graph: &FastGraph, source: NodeId, target: NodeId) -> Opti
     calc.calc_path(fast_graph, source, target)
 }
 
+/// Like `calc_path()`, but returns an error instead of panicking if `source` or `target` is not a
+/// node of the graph.
+pub fn try_calc_path(
+    fast_graph: &FastGraph,
+    source: NodeId,
+    target: NodeId,
+) -> Result<Option<ShortestPath>, Error> {
+    let mut calc = PathCalculator::new(fast_graph.get_num_nodes());
+    calc.try_calc_path(fast_graph, source, target)
+}
+
+/// Calculates the weight of the shortest path from `source` to `target`, without calculating
+/// the nodes of the path.
+pub fn calc_weight(fast_graph: &FastGraph, source: NodeId, target: NodeId) -> Option<Weight> {
+    let mut calc = PathCalculator::new(fast_graph.get_num_nodes());
+    calc.calc_weight(fast_graph, source, target)
+}
+
 /// Calculates the shortest path from any of the `sources` to any of the `targets`.
 ///
 /// The path returned will be the one with minimum weight among all possible paths between the sources
 /// and targets. The sources and targets can also be assigned an initial weight. In this case the
 /// path returned will be the one that minimizes start_weight + path-weight + target_weight. The
-/// weight of the path also includes start_weight and target_weight.
+/// weight of the path also includes start_weight and target_weight. See
+/// `PathCalculator::calc_path_multiple_sources_and_targets` for details.
 pub fn calc_path_multiple_sources_and_targets(
     fast_graph: &FastGraph,
     sources: Vec<(NodeId, Weight)>,
-    target: Vec<(NodeId, Weight)>,
+    targets: Vec<(NodeId, Weight)>,
+) -> Option<ShortestPath> {
+    let mut calc = PathCalculator::new(fast_graph.get_num_nodes());
+    calc.calc_path_multiple_sources_and_targets(fast_graph, sources, targets)
+}
+
+/// Like `calc_path_multiple_sources_and_targets`, but only calculates the weight, so the shortcuts
+/// of the path are never unpacked.
+pub fn calc_weight_multiple_sources_and_targets(
+    fast_graph: &FastGraph,
+    sources: Vec<(NodeId, Weight)>,
+    targets: Vec<(NodeId, Weight)>,
+) -> Option<Weight> {
+    let mut calc = PathCalculator::new(fast_graph.get_num_nodes());
+    calc.calc_weight_multiple_sources_and_targets(fast_graph, sources, targets)
+}
+
+/// Calculates the weight of the shortest path from any of the `sources` (each with an initial
+/// weight) to `target`, see `calc_weight_multiple_sources_and_targets`. Like the other functions
+/// here this allocates a new `PathCalculator`, which takes O(n) time and memory for a graph with
+/// n nodes, on every call. When calling this in a loop, create a `PathCalculator` once and use
+/// `PathCalculator::calc_weight_multiple_sources_and_targets` instead.
+pub fn calc_weight_multiple_endpoints(
+    fast_graph: &FastGraph,
+    sources: Vec<(NodeId, Weight)>,
+    target: NodeId,
+) -> Option<Weight> {
+    calc_weight_multiple_sources_and_targets(fast_graph, sources, vec![(target, 0)])
+}
+
+/// Calculates the weight of the shortest path from `source` to any of the `targets` (each with
+/// an initial weight), see `calc_weight_multiple_endpoints`, which also explains how to avoid
+/// allocating a new `PathCalculator` on every call.
+pub fn calc_weight_multiple_target_endpoints(
+    fast_graph: &FastGraph,
+    source: NodeId,
+    targets: Vec<(NodeId, Weight)>,
+) -> Option<Weight> {
+    calc_weight_multiple_sources_and_targets(fast_graph, vec![(source, 0)], targets)
+}
+
+/// Creates `num_queries` random `(source, target)` pairs for a graph with `num_nodes` nodes, e.g.
+/// for benchmarks. Like for `InputGraph::random` the pairs only depend on the numbers returned
+/// by `next_u64`: each pair uses two of them, which are mapped to node ids the same way.
+#[cfg(any(test, feature = "random"))]
+pub fn random_queries<F: FnMut() -> u64>(
+    mut next_u64: F,
+    num_nodes: usize,
+    num_queries: usize,
+) -> Vec<(NodeId, NodeId)> {
+    (0..num_queries)
+        .map(|_| {
+            let source = input_graph::random_below(next_u64(), num_nodes);
+            let target = input_graph::random_below(next_u64(), num_nodes);
+            (source, target)
+        })
+        .collect()
+}
+
+/// Calculates the shortest paths for all the given `(source, target)` pairs in parallel, using one
+/// `PathCalculator` per worker thread of the rayon thread pool. The results are in the same order
+/// as the given pairs.
+#[cfg(feature = "rayon")]
+pub fn calc_paths_parallel(
+    fast_graph: &FastGraph,
+    pairs: &[(NodeId, NodeId)],
+) -> Vec<Option<ShortestPath>> {
+    use rayon::prelude::*;
+    pairs
+        .par_iter()
+        .map_init(
+            || create_calculator(fast_graph),
+            |calc, (source, target)| calc.calc_path(fast_graph, *source, *target),
+        )
+        .collect()
+}
+
+/// Calculates up to `k` loopless paths from `source` to `target` in order of increasing weight,
+/// see `PathCalculator::calc_k_paths`.
+pub fn calc_k_paths(
+    fast_graph: &FastGraph,
+    source: NodeId,
+    target: NodeId,
+    k: usize,
+) -> Vec<ShortestPath> {
+    let mut calc = PathCalculator::new(fast_graph.get_num_nodes());
+    calc.calc_k_paths(fast_graph, source, target, k)
+}
+
+/// Calculates the shortest path between two points that lie somewhere on the edges of the graph,
+/// see `EdgePoint` and `PathCalculator::calc_path_between_edge_points`.
+pub fn calc_path_between_edge_points(
+    fast_graph: &FastGraph,
+    source: &EdgePoint,
+    target: &EdgePoint,
+) -> Option<ShortestPath> {
+    let mut calc = PathCalculator::new(fast_graph.get_num_nodes());
+    calc.calc_path_between_edge_points(fast_graph, source, target)
+}
+
+/// Calculates the shortest path from `source` to `target` where `penalty` is added for every edge
+/// connecting two nodes with different `zones`, e.g. to avoid entering or leaving a congestion
+/// charge zone. `zones[node]` is the zone of each node. The weight of the returned path includes
+/// the penalties. This does not use the shortcuts of the prepared graph and is therefore much
+/// slower than `calc_path`.
+pub fn calc_path_with_zone_penalty(
+    fast_graph: &FastGraph,
+    source: NodeId,
+    target: NodeId,
+    zones: &[usize],
+    penalty: Weight,
 ) -> Option<ShortestPath> {
+    zone_penalty::calc_path(fast_graph, source, target, zones, penalty)
+}
+
+/// Calculates the shortest path from `source` to `target` that does not use any of the `blocked`
+/// nodes or edges, e.g. to avoid closed roads without preparing the graph again. This does not use
+/// the shortcuts of the prepared graph and is therefore much slower than `calc_path`.
+pub fn calc_path_avoiding(
+    fast_graph: &FastGraph,
+    source: NodeId,
+    target: NodeId,
+    blocked: &BlockedElements,
+) -> Option<ShortestPath> {
+    blocked_elements::calc_path(fast_graph, source, target, blocked)
+}
+
+/// Calculates the shortest path from `source` to `target` where the weights of the edges given in
+/// `overrides` (identified by their `(from, to)` nodes) are replaced, e.g. to take live traffic
+/// into account without preparing the graph again. Edges with weight `WEIGHT_MAX` are not used.
+/// This does not use the shortcuts of the prepared graph and is therefore much slower than
+/// `calc_path`.
+pub fn calc_path_with_weight_overrides(
+    fast_graph: &FastGraph,
+    source: NodeId,
+    target: NodeId,
+    overrides: &HashMap<(NodeId, NodeId), Weight>,
+) -> Option<ShortestPath> {
+    weight_overrides::calc_path(fast_graph, source, target, overrides)
+}
+
+/// Calculates the route from `start` to `end` that visits all the given `stops` in the best
+/// possible order, see `PathCalculator::calc_route_with_best_order`.
+pub fn calc_route_with_best_order(
+    fast_graph: &FastGraph,
+    start: NodeId,
+    stops: &[NodeId],
+    end: NodeId,
+) -> Option<RouteWithLegs> {
+    let mut calc = PathCalculator::new(fast_graph.get_num_nodes());
+    calc.calc_route_with_best_order(fast_graph, start, stops, end)
+}
+
+/// Calculates a route visiting all the given `waypoints` in the given order. The route contains
+/// one leg for each pair of consecutive waypoints. Returns `None` if any of the legs cannot be
+/// found.
+pub fn calc_route_with_legs(fast_graph: &FastGraph, waypoints: &[NodeId]) -> Option<RouteWithLegs> {
+    let mut calc = PathCalculator::new(fast_graph.get_num_nodes());
+    calc.calc_route_with_legs(fast_graph, waypoints)
+}
+
+/// Calculates the shortest path visiting all the given `waypoints` in order. The nodes where two
+/// consecutive legs meet are only included once and the weight is the sum of all legs' weights.
+pub fn calc_path_via(fast_graph: &FastGraph, waypoints: &[NodeId]) -> Option<ShortestPath> {
     let mut calc = PathCalculator::new(fast_graph.get_num_nodes());
-    calc.calc_path_multiple_sources_and_targets(fast_graph, sources, target)
+    calc.calc_path_via(fast_graph, waypoints)
 }
 
 /// Creates a `PathCalculator` that can be used to run many shortest path calculations in a row.
@@ -122,16 +525,74 @@ pub fn serialize_32<S: Serializer>(fg: &FastGraph, s: S) -> Result<S::Ok, S::Err
 /// When deserializing a `FastGraph` in a larger struct, use `#[serde(deserialize_with =
 /// "fast_paths::deserialize_32`)]` to transform the graph from a 32-bit representation to the
 /// current platform's supported size. This is necessary when serializing on a 64-bit system and
-/// deserializing on a 32-bit system, such as WASM.
+/// deserializing on a 32-bit system, such as WASM. Deserialization fails if the data does not
+/// form a valid graph, see `FastGraph32::try_convert_to_usize`.
 pub fn deserialize_32<'de, D: Deserializer<'de>>(d: D) -> Result<FastGraph, D::Error> {
     let fg32 = <FastGraph32>::deserialize(d)?;
-    Ok(fg32.convert_to_usize())
+    fg32.try_convert_to_usize()
+        .map_err(serde::de::Error::custom)
+}
+
+/// When serializing a `FastGraph` in a larger struct, use `#[serde(serialize_with =
+/// "fast_paths::serialize_16`)]` to transform the graph to a representation with 32-bit ids and
+/// 16-bit weights, see `FastGraph16`. Serialization fails if the graph exceeds these limits.
+pub fn serialize_16<S: Serializer>(fg: &FastGraph, s: S) -> Result<S::Ok, S::Error> {
+    FastGraph16::try_new(fg)
+        .map_err(serde::ser::Error::custom)?
+        .serialize(s)
+}
+
+/// When deserializing a `FastGraph` in a larger struct, use `#[serde(deserialize_with =
+/// "fast_paths::deserialize_16`)]` to restore a graph that was serialized using `serialize_16`.
+/// Deserialization fails if the data does not form a valid graph, see
+/// `FastGraph16::try_convert_to_usize`.
+pub fn deserialize_16<'de, D: Deserializer<'de>>(d: D) -> Result<FastGraph, D::Error> {
+    let fg16 = <FastGraph16>::deserialize(d)?;
+    fg16.try_convert_to_usize()
+        .map_err(serde::de::Error::custom)
+}
+
+/// When serializing a `FastGraph` in a larger struct, use `#[serde(serialize_with =
+/// "fast_paths::serialize_portable`)]` to make sure the graph can be loaded on any platform,
+/// regardless of its pointer width and byte order, see `FastGraphPortable`.
+pub fn serialize_portable<S: Serializer>(fg: &FastGraph, s: S) -> Result<S::Ok, S::Error> {
+    FastGraphPortable::new(fg).serialize(s)
+}
+
+/// When deserializing a `FastGraph` in a larger struct, use `#[serde(deserialize_with =
+/// "fast_paths::deserialize_portable`)]` to restore a graph that was serialized using
+/// `serialize_portable`. Deserialization fails with an error if the data was serialized
+/// differently, if the graph is too large for the current platform or if it is not valid.
+pub fn deserialize_portable<'de, D: Deserializer<'de>>(d: D) -> Result<FastGraph, D::Error> {
+    let portable = <FastGraphPortable>::deserialize(d)?;
+    portable
+        .try_convert_to_usize()
+        .map_err(serde::de::Error::custom)
+}
+
+/// When serializing a `FastGraph` in a larger struct, use `#[serde(serialize_with =
+/// "fast_paths::serialize_sparse`)]` to leave out the data of nodes that have no edges, see
+/// `FastGraphSparse`. This can reduce the size considerably for graphs with sparse node ids.
+pub fn serialize_sparse<S: Serializer>(fg: &FastGraph, s: S) -> Result<S::Ok, S::Error> {
+    FastGraphSparse::new(fg).serialize(s)
+}
+
+/// When deserializing a `FastGraph` in a larger struct, use `#[serde(deserialize_with =
+/// "fast_paths::deserialize_sparse`)]` to restore a graph that was serialized using
+/// `serialize_sparse`. Deserialization fails if the data does not form a valid graph, see
+/// `FastGraphSparse::try_convert_to_fast_graph`.
+pub fn deserialize_sparse<'de, D: Deserializer<'de>>(d: D) -> Result<FastGraph, D::Error> {
+    let sparse = <FastGraphSparse>::deserialize(d)?;
+    sparse
+        .try_convert_to_fast_graph()
+        .map_err(serde::de::Error::custom)
 }
 
 #[cfg(test)]
 mod tests {
     use std::error::Error;
     use std::fs::{remove_file, File};
+    use std::sync::Arc;
     use std::time::SystemTime;
 
     use rand::rngs::StdRng;
@@ -156,15 +617,52 @@ mod tests {
         }
     }
 
+    #[test]
+    fn calc_weight_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const NUM_QUERIES: usize = 200;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            let fast_graph = prepare(&input_graph);
+            let mut path_calculator = create_calculator(&fast_graph);
+            let mut fw = FloydWarshall::new(input_graph.get_num_nodes());
+            fw.prepare(&input_graph);
+            for _ in 0..NUM_QUERIES {
+                let source = rng.gen_range(0, input_graph.get_num_nodes());
+                let target = rng.gen_range(0, input_graph.get_num_nodes());
+                let weight = path_calculator.calc_weight(&fast_graph, source, target);
+                assert_eq!(
+                    path_calculator
+                        .calc_path(&fast_graph, source, target)
+                        .map(|p| p.get_weight()),
+                    weight
+                );
+                assert_eq!(
+                    fw.calc_weight(source, target),
+                    weight.unwrap_or(WEIGHT_MAX),
+                    "\nNo agreement for query from: {} to: {}\n Failing graph:\n{:?}",
+                    source,
+                    target,
+                    input_graph
+                );
+            }
+        }
+    }
+
     fn run_test_on_random_graph() {
         const NUM_NODES: usize = 50;
         const NUM_QUERIES: usize = 1_000;
         const MEAN_DEGREE: f32 = 2.0;
 
         let mut rng = create_rng();
-        let input_graph = InputGraph::random(&mut rng, NUM_NODES, MEAN_DEGREE);
+        let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
         debug!("random graph: \n {:?}", input_graph);
         let fast_graph = prepare(&input_graph);
+        assert_eq!(Ok(()), fast_graph.validate());
         let mut path_calculator = create_calculator(&fast_graph);
 
         let dijkstra_graph = PreparationGraph::from_input_graph(&input_graph);
@@ -184,6 +682,22 @@ mod tests {
                 .calc_path(&dijkstra_graph, source, target)
                 .unwrap_or(ShortestPath::none(source, target));
             let weight_fast = path_fast.get_weight();
+            if source % 10 == 0 {
+                let zones = vec![0; input_graph.get_num_nodes()];
+                assert_eq!(
+                    calc_path_with_zone_penalty(&fast_graph, source, target, &zones, 10)
+                        .map(|p| p.get_weight())
+                        .unwrap_or(WEIGHT_MAX),
+                    weight_fast
+                );
+            }
+            let limit = rng.gen_range(0, 300);
+            assert_eq!(
+                path_calculator
+                    .calc_path_with_limit(&fast_graph, source, target, limit)
+                    .map(|p| p.get_weight()),
+                Some(weight_fast).filter(|w| *w <= limit)
+            );
             let weight_dijkstra = path_dijkstra.get_weight();
             let weight_fw = fw.calc_weight(source, target);
             assert_eq!(
@@ -212,6 +726,67 @@ mod tests {
         }
     }
 
+    #[test]
+    fn weight_with_multiple_sources_or_targets() {
+        // 0 -> 1 -> 2 -> 3
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 2);
+        g.add_edge(2, 3, 3);
+        g.freeze();
+        let fast_graph = prepare(&g);
+        assert_eq!(
+            Some(5),
+            calc_weight_multiple_endpoints(&fast_graph, vec![(0, 10), (1, 0)], 3)
+        );
+        assert_eq!(
+            Some(7),
+            calc_weight_multiple_target_endpoints(&fast_graph, 0, vec![(2, 4), (3, 1)])
+        );
+        assert_eq!(
+            None,
+            calc_weight_multiple_target_endpoints(&fast_graph, 3, vec![(0, 0), (1, 0)])
+        );
+        assert_eq!(
+            Some(2),
+            calc_weight_multiple_sources_and_targets(&fast_graph, vec![(1, 2)], vec![(1, 0)])
+        );
+    }
+
+    #[test]
+    fn calc_weight_with_multiple_sources_and_targets_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const NUM_QUERIES: usize = 200;
+            const MEAN_DEGREE: f32 = 2.0;
+            const NUM_SOURCES: usize = 3;
+            const NUM_TARGETS: usize = 3;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            let fast_graph = prepare(&input_graph);
+            let mut path_calculator = create_calculator(&fast_graph);
+            for _ in 0..NUM_QUERIES {
+                let sources =
+                    gen_weighted_nodes(&mut rng, input_graph.get_num_nodes(), NUM_SOURCES);
+                let targets =
+                    gen_weighted_nodes(&mut rng, input_graph.get_num_nodes(), NUM_TARGETS);
+                let fast_path = path_calculator.calc_path_multiple_sources_and_targets(
+                    &fast_graph,
+                    sources.clone(),
+                    targets.clone(),
+                );
+                let fast_weight = path_calculator.calc_weight_multiple_sources_and_targets(
+                    &fast_graph,
+                    sources,
+                    targets,
+                );
+                assert_eq!(fast_path.map(|p| p.get_weight()), fast_weight);
+            }
+        }
+    }
+
     #[test]
     fn routing_with_multiple_sources_and_targets_on_random_graph() {
         const REPEATS: usize = 20;
@@ -223,7 +798,7 @@ mod tests {
             const NUM_TARGETS: usize = 3;
 
             let mut rng = create_rng();
-            let input_graph = InputGraph::random(&mut rng, NUM_NODES, MEAN_DEGREE);
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
             debug!("random graph: \n {:?}", input_graph);
             let fast_graph = prepare(&input_graph);
             let mut path_calculator = create_calculator(&fast_graph);
@@ -302,7 +877,7 @@ mod tests {
                     .collect();
 
                 assert!(
-                    matching_dijkstras.len() > 0,
+                    !matching_dijkstras.is_empty(),
                     "There has to be at least one Dijkstra path with source,target and weight equal to fast_path"
                 );
 
@@ -332,52 +907,1856 @@ mod tests {
         }
     }
 
-    fn gen_weighted_nodes(rng: &mut StdRng, max_node: usize, num: usize) -> Vec<(NodeId, Weight)> {
-        (0..num)
-            .map(|_| {
-                (
-                    rng.gen_range(0, max_node),
-                    // sometimes use max weight
-                    if rng.gen_range(0, 100) < 3 {
-                        WEIGHT_MAX
-                    } else {
-                        rng.gen_range(0, 100)
-                    },
-                )
-            })
-            .collect()
+    #[test]
+    fn one_to_many_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const NUM_QUERIES: usize = 100;
+            const MEAN_DEGREE: f32 = 2.0;
+            const NUM_TARGETS: usize = 10;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            debug!("random graph: \n {:?}", input_graph);
+            let fast_graph = prepare(&input_graph);
+            let mut path_calculator = create_calculator(&fast_graph);
+
+            let mut fw = FloydWarshall::new(input_graph.get_num_nodes());
+            fw.prepare(&input_graph);
+
+            for _ in 0..NUM_QUERIES {
+                let source = rng.gen_range(0, input_graph.get_num_nodes());
+                let targets: Vec<NodeId> = (0..NUM_TARGETS)
+                    .map(|_| rng.gen_range(0, input_graph.get_num_nodes()))
+                    .collect();
+                let weights =
+                    path_calculator.calc_weights_one_to_many(&fast_graph, source, &targets);
+                let expected: Vec<Weight> = targets
+                    .iter()
+                    .map(|target| fw.calc_weight(source, *target))
+                    .collect();
+                assert_eq!(
+                    expected, weights,
+                    "\nNo agreement for one-to-many query from: {} to: {:?}\
+                     \n Failing graph:\n{:?}",
+                    source, targets, input_graph
+                );
+            }
+        }
     }
 
     #[test]
-    fn save_to_and_load_from_disk() {
-        let mut g = InputGraph::new();
-        g.add_edge(0, 5, 6);
-        g.add_edge(5, 2, 1);
-        g.add_edge(2, 3, 4);
-        g.freeze();
-        let fast_graph = prepare(&g);
-        save_to_disk(&fast_graph, "example.fp").expect("writing to disk failed");
-        let loaded = load_from_disk("example.fp").unwrap();
-        remove_file("example.fp").expect("deleting file failed");
-        assert_eq!(fast_graph.get_num_nodes(), loaded.get_num_nodes());
-        assert_eq!(fast_graph.get_num_in_edges(), loaded.get_num_in_edges());
-        assert_eq!(fast_graph.get_num_out_edges(), loaded.get_num_out_edges());
+    fn weights_multiple_sources_and_targets_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const NUM_QUERIES: usize = 20;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            let fast_graph = prepare(&input_graph);
+            let mut path_calculator = create_calculator(&fast_graph);
+            let mut fw = FloydWarshall::new(input_graph.get_num_nodes());
+            fw.prepare(&input_graph);
+
+            for _ in 0..NUM_QUERIES {
+                let sources = gen_weighted_nodes(&mut rng, input_graph.get_num_nodes(), 4);
+                let targets = gen_weighted_nodes(&mut rng, input_graph.get_num_nodes(), 6);
+                let weights = path_calculator.calc_weights_multiple_sources_and_targets(
+                    &fast_graph,
+                    &sources,
+                    &targets,
+                );
+                assert_eq!(sources.len(), weights.len());
+                for (i, (source, source_weight)) in sources.iter().enumerate() {
+                    for (j, (target, target_weight)) in targets.iter().enumerate() {
+                        let weight = fw.calc_weight(*source, *target);
+                        let expected = if weight == WEIGHT_MAX
+                            || *source_weight == WEIGHT_MAX
+                            || *target_weight == WEIGHT_MAX
+                        {
+                            WEIGHT_MAX
+                        } else {
+                            source_weight + weight + target_weight
+                        };
+                        assert_eq!(expected, weights[i][j]);
+                    }
+                }
+                // the best combination is the one found by the single path query
+                let best = weights.iter().flatten().min().cloned().unwrap();
+                let path = path_calculator.calc_path_multiple_sources_and_targets(
+                    &fast_graph,
+                    sources,
+                    targets,
+                );
+                assert_eq!(best, path.map_or(WEIGHT_MAX, |p| p.get_weight()));
+            }
+        }
     }
 
     #[test]
-    fn save_to_and_load_from_disk_32() {
-        let mut g = InputGraph::new();
-        g.add_edge(0, 5, 6);
-        g.add_edge(5, 2, 1);
-        g.add_edge(2, 3, 4);
-        g.freeze();
-        let fast_graph = prepare(&g);
-        save_to_disk32(&fast_graph, "example32.fp").expect("writing to disk failed");
-        let loaded = load_from_disk32("example32.fp").unwrap();
-        remove_file("example32.fp").expect("deleting file failed");
-        assert_eq!(fast_graph.get_num_nodes(), loaded.get_num_nodes());
-        assert_eq!(fast_graph.get_num_in_edges(), loaded.get_num_in_edges());
-        assert_eq!(fast_graph.get_num_out_edges(), loaded.get_num_out_edges());
+    fn k_paths_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 30;
+            const NUM_QUERIES: usize = 50;
+            const MEAN_DEGREE: f32 = 3.0;
+            const K: usize = 5;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            debug!("random graph: \n {:?}", input_graph);
+            let fast_graph = prepare(&input_graph);
+            let mut path_calculator = create_calculator(&fast_graph);
+            let edge_weights: std::collections::HashMap<(NodeId, NodeId), Weight> = input_graph
+                .get_edges()
+                .iter()
+                .map(|e| ((e.from, e.to), e.weight))
+                .collect();
+
+            for _ in 0..NUM_QUERIES {
+                let source = rng.gen_range(0, input_graph.get_num_nodes());
+                let target = rng.gen_range(0, input_graph.get_num_nodes());
+                let paths = path_calculator.calc_k_paths(&fast_graph, source, target, K);
+                let shortest = calc_weight(&fast_graph, source, target);
+                assert_eq!(shortest, paths.first().map(|p| p.get_weight()));
+                assert!(paths.len() <= K);
+                let mut distinct = std::collections::HashSet::new();
+                for window in paths.windows(2) {
+                    assert!(window[0].get_weight() <= window[1].get_weight());
+                }
+                for path in &paths {
+                    let nodes = path.get_nodes();
+                    assert_eq!(source, nodes[0]);
+                    assert_eq!(target, nodes[nodes.len() - 1]);
+                    assert!(distinct.insert(nodes.clone()), "duplicate path {:?}", nodes);
+                    let loopless: std::collections::HashSet<_> = nodes.iter().collect();
+                    assert_eq!(nodes.len(), loopless.len(), "path has loops {:?}", nodes);
+                    let weight: Weight = nodes
+                        .windows(2)
+                        .map(|pair| edge_weights[&(pair[0], pair[1])])
+                        .sum();
+                    assert_eq!(weight, path.get_weight());
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_preparation_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            debug!("random graph: \n {:?}", input_graph);
+            let params = Params::default();
+            let fast_graph = prepare_parallel(&input_graph, &params);
+            fast_graph.validate().unwrap();
+            for num_threads in [1, 3] {
+                let other = rayon::ThreadPoolBuilder::new()
+                    .num_threads(num_threads)
+                    .build()
+                    .unwrap()
+                    .install(|| prepare_parallel(&input_graph, &params));
+                assert_eq!(
+                    bincode::serialize(&fast_graph).unwrap(),
+                    bincode::serialize(&other).unwrap(),
+                    "the result must not depend on the number of threads"
+                );
+            }
+
+            let mut path_calculator = create_calculator(&fast_graph);
+            let mut fw = FloydWarshall::new(input_graph.get_num_nodes());
+            fw.prepare(&input_graph);
+            for source in 0..input_graph.get_num_nodes() {
+                for target in 0..input_graph.get_num_nodes() {
+                    let path = path_calculator.calc_path(&fast_graph, source, target);
+                    assert_eq!(
+                        fw.calc_weight(source, target),
+                        path.map_or(WEIGHT_MAX, |p| p.get_weight()),
+                        "\nNo agreement for query from: {} to: {}\n Failing graph:\n{:?}",
+                        source,
+                        target,
+                        input_graph
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn zero_weights_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let mut input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            input_graph.thaw();
+            input_graph.set_allow_zero_weights(true);
+            // some of these form cycles of zero weight edges
+            for _ in 0..NUM_NODES {
+                let from = rng.gen_range(0, NUM_NODES);
+                let to = rng.gen_range(0, NUM_NODES);
+                input_graph.add_edge(from, to, 0);
+            }
+            input_graph.freeze();
+            debug!("random graph: \n {:?}", input_graph);
+            let mut fw = FloydWarshall::new(input_graph.get_num_nodes());
+            fw.prepare(&input_graph);
+            #[allow(unused_mut)]
+            let mut fast_graphs = vec![
+                prepare(&input_graph),
+                prepare_with_order(
+                    &input_graph,
+                    &(0..input_graph.get_num_nodes()).collect::<Vec<_>>(),
+                )
+                .unwrap(),
+            ];
+            #[cfg(feature = "rayon")]
+            fast_graphs.push(prepare_parallel(&input_graph, &Params::default()));
+            for fast_graph in fast_graphs {
+                fast_graph.validate().unwrap();
+                let mut path_calculator = create_calculator(&fast_graph);
+                for source in 0..input_graph.get_num_nodes() {
+                    for target in 0..input_graph.get_num_nodes() {
+                        let path = path_calculator.calc_path(&fast_graph, source, target);
+                        assert_eq!(
+                            fw.calc_weight(source, target),
+                            path.as_ref().map_or(WEIGHT_MAX, |p| p.get_weight()),
+                            "\nNo agreement for query from: {} to: {}\n Failing graph:\n{:?}",
+                            source,
+                            target,
+                            input_graph
+                        );
+                        if let Some(path) = path {
+                            let weight: Weight = path
+                                .get_nodes()
+                                .windows(2)
+                                .map(|pair| {
+                                    let id = input_graph.get_edge_id(pair[0], pair[1]).unwrap();
+                                    input_graph.get_edges()[id].weight
+                                })
+                                .sum();
+                            assert_eq!(path.get_weight(), weight);
+                            // zero weight cycles are never part of a path
+                            let mut nodes = path.get_nodes().clone();
+                            nodes.sort_unstable();
+                            nodes.dedup();
+                            assert_eq!(path.get_nodes().len(), nodes.len());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_paths_on_random_graph() {
+        let mut rng = create_rng();
+        let input_graph = InputGraph::random(|| rng.gen(), 100, 2.0);
+        let fast_graph = prepare(&input_graph);
+        // the random graph can have less than 100 nodes
+        let pairs = random_queries(|| rng.gen(), input_graph.get_num_nodes(), 1_000);
+        let paths = calc_paths_parallel(&fast_graph, &pairs);
+        assert_eq!(pairs.len(), paths.len());
+        let mut path_calculator = create_calculator(&fast_graph);
+        for ((source, target), path) in pairs.iter().zip(paths) {
+            assert_eq!(
+                path_calculator.calc_path(&fast_graph, *source, *target),
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn chain_compression_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const NUM_QUERIES: usize = 200;
+
+            let mut rng = create_rng();
+            // sparse graphs with many chains
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, 1.2);
+            let compression = ChainCompression::new(&input_graph);
+            let fast_graph = prepare(compression.get_compressed_graph());
+            let mut path_calculator = create_calculator(&fast_graph);
+            let mut fw = FloydWarshall::new(input_graph.get_num_nodes());
+            fw.prepare(&input_graph);
+            let edge_weights: std::collections::HashMap<(NodeId, NodeId), Weight> = input_graph
+                .get_edges()
+                .iter()
+                .map(|e| ((e.from, e.to), e.weight))
+                .collect();
+
+            for _ in 0..NUM_QUERIES {
+                let source = rng.gen_range(0, input_graph.get_num_nodes());
+                let target = rng.gen_range(0, input_graph.get_num_nodes());
+                let path = compression.calc_path(&mut path_calculator, &fast_graph, source, target);
+                let weight_fw = fw.calc_weight(source, target);
+                assert_eq!(
+                    weight_fw,
+                    path.as_ref().map_or(WEIGHT_MAX, |p| p.get_weight()),
+                    "\nNo agreement for routing query from: {} to: {}\
+                     \n Failing graph:\n{:?}",
+                    source,
+                    target,
+                    input_graph
+                );
+                if let Some(path) = path {
+                    let nodes = path.get_nodes();
+                    assert_eq!(source, nodes[0]);
+                    assert_eq!(target, nodes[nodes.len() - 1]);
+                    let weight: Weight = nodes
+                        .windows(2)
+                        .map(|pair| edge_weights[&(pair[0], pair[1])])
+                        .sum();
+                    assert_eq!(weight_fw, weight);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn edge_based_graph_on_random_graph() {
+        const REPEATS: usize = 10;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 30;
+            const NUM_QUERIES: usize = 100;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, 2.0);
+            let num_nodes = input_graph.get_num_nodes();
+            let edges = input_graph.get_edges();
+            let mut turn_costs = TurnCosts::new();
+            for e in edges {
+                for f in edges.iter().filter(|f| f.from == e.to) {
+                    match rng.gen_range(0, 4) {
+                        0 => turn_costs.restrict_turn(e.from, e.to, f.to),
+                        1 => turn_costs.set_turn_cost(e.from, e.to, f.to, rng.gen_range(1, 10)),
+                        _ => {}
+                    }
+                }
+            }
+            let edge_based_graph = EdgeBasedGraph::new(&input_graph, &turn_costs);
+            let fast_graph = prepare(edge_based_graph.get_expanded_graph());
+            let mut path_calculator = create_calculator(&fast_graph);
+            let mut fw = FloydWarshall::new(edges.len());
+            fw.prepare(edge_based_graph.get_expanded_graph());
+            let edge_ids: std::collections::HashMap<(NodeId, NodeId), usize> = edges
+                .iter()
+                .enumerate()
+                .map(|(i, e)| ((e.from, e.to), i))
+                .collect();
+
+            for _ in 0..NUM_QUERIES {
+                let source = rng.gen_range(0, num_nodes);
+                let target = rng.gen_range(0, num_nodes);
+                let path =
+                    edge_based_graph.calc_path(&mut path_calculator, &fast_graph, source, target);
+                let mut weight_fw = if source == target { 0 } else { WEIGHT_MAX };
+                for (i, e) in edges.iter().enumerate().filter(|(_, e)| e.from == source) {
+                    for (j, _) in edges.iter().enumerate().filter(|(_, f)| f.to == target) {
+                        let weight = fw.calc_weight(i, j);
+                        if weight != WEIGHT_MAX {
+                            weight_fw = weight_fw.min(e.weight + weight);
+                        }
+                    }
+                }
+                assert_eq!(
+                    weight_fw,
+                    path.as_ref().map_or(WEIGHT_MAX, |p| p.get_weight()),
+                    "\nNo agreement for routing query from: {} to: {}\
+                     \n Failing graph:\n{:?}",
+                    source,
+                    target,
+                    input_graph
+                );
+                if let Some(path) = path {
+                    let nodes = path.get_nodes();
+                    assert_eq!(source, nodes[0]);
+                    assert_eq!(target, nodes[nodes.len() - 1]);
+                    let mut weight: Weight = nodes
+                        .windows(2)
+                        .map(|pair| edges[edge_ids[&(pair[0], pair[1])]].weight)
+                        .sum();
+                    for turn in nodes.windows(3) {
+                        let cost = turn_costs.get_turn_cost(turn[0], turn[1], turn[2]);
+                        assert_ne!(WEIGHT_MAX, cost, "the path contains a forbidden turn");
+                        weight += cost;
+                    }
+                    assert_eq!(weight_fw, weight);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reachable_nodes_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const NUM_QUERIES: usize = 50;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            debug!("random graph: \n {:?}", input_graph);
+            let fast_graph = prepare(&input_graph);
+            let mut path_calculator = create_calculator(&fast_graph);
+            let mut fw = FloydWarshall::new(input_graph.get_num_nodes());
+            fw.prepare(&input_graph);
+
+            for _ in 0..NUM_QUERIES {
+                let source = rng.gen_range(0, input_graph.get_num_nodes());
+                let max_weight = rng.gen_range(0, 300);
+                let reachable =
+                    path_calculator.calc_reachable_nodes(&fast_graph, source, max_weight);
+                let expected: Vec<(NodeId, Weight)> = (0..input_graph.get_num_nodes())
+                    .map(|node| (node, fw.calc_weight(source, node)))
+                    .filter(|(_, weight)| *weight <= max_weight)
+                    .collect();
+                assert_eq!(
+                    expected, reachable,
+                    "\nNo agreement for reachable nodes from: {} within: {}\
+                     \n Failing graph:\n{:?}",
+                    source, max_weight, input_graph
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn lazy_priority_updates_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const NUM_QUERIES: usize = 50;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            let params = Params {
+                lazy_priority_updates: Some(rng.gen_range(0, 10)),
+                max_shortcuts: if rng.gen() { Some(5) } else { None },
+                ..Params::default()
+            };
+            let fast_graph = prepare_with_params(&input_graph, &params);
+            fast_graph.validate().unwrap();
+            let mut path_calculator = create_calculator(&fast_graph);
+            let mut fw = FloydWarshall::new(input_graph.get_num_nodes());
+            fw.prepare(&input_graph);
+            for _ in 0..NUM_QUERIES {
+                let source = rng.gen_range(0, input_graph.get_num_nodes());
+                let target = rng.gen_range(0, input_graph.get_num_nodes());
+                assert_eq!(
+                    fw.calc_weight(source, target),
+                    path_calculator
+                        .calc_weight(&fast_graph, source, target)
+                        .unwrap_or(WEIGHT_MAX),
+                    "\nNo agreement for query from: {} to: {}\n Failing graph:\n{:?}",
+                    source,
+                    target,
+                    input_graph
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn core_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const NUM_QUERIES: usize = 50;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            debug!("random graph: \n {:?}", input_graph);
+            let params = Params {
+                max_shortcuts: Some(rng.gen_range(0, 10)),
+                ..Params::default()
+            };
+            let fast_graph = prepare_with_params(&input_graph, &params);
+            fast_graph.validate().unwrap();
+            let mut path_calculator = create_calculator(&fast_graph);
+            let mut fw = FloydWarshall::new(input_graph.get_num_nodes());
+            fw.prepare(&input_graph);
+
+            for _ in 0..NUM_QUERIES {
+                let source = rng.gen_range(0, input_graph.get_num_nodes());
+                let target = rng.gen_range(0, input_graph.get_num_nodes());
+                let expected = fw.calc_weight(source, target);
+                let path = path_calculator.calc_path(&fast_graph, source, target);
+                assert_eq!(
+                    expected,
+                    path.as_ref().map_or(WEIGHT_MAX, |p| p.get_weight()),
+                    "\nNo agreement for query from: {} to: {}\n Failing graph:\n{:?}",
+                    source,
+                    target,
+                    input_graph
+                );
+                if let Some(path) = path {
+                    let nodes = path.get_nodes();
+                    assert_eq!(source, nodes[0]);
+                    assert_eq!(target, nodes[nodes.len() - 1]);
+                }
+                let weights =
+                    path_calculator.calc_weights_one_to_many(&fast_graph, source, &[target]);
+                assert_eq!(vec![expected], weights);
+            }
+        }
+    }
+
+    #[test]
+    fn witness_hops_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            debug!("random graph: \n {:?}", input_graph);
+            let params = Params {
+                max_witness_hops: Some(rng.gen_range(1, 4)),
+                ..Params::default()
+            };
+            let fast_graph = prepare_with_params(&input_graph, &params);
+            fast_graph.validate().unwrap();
+            let mut path_calculator = create_calculator(&fast_graph);
+            let mut fw = FloydWarshall::new(input_graph.get_num_nodes());
+            fw.prepare(&input_graph);
+            for source in 0..input_graph.get_num_nodes() {
+                for target in 0..input_graph.get_num_nodes() {
+                    let path = path_calculator.calc_path(&fast_graph, source, target);
+                    assert_eq!(
+                        fw.calc_weight(source, target),
+                        path.map_or(WEIGHT_MAX, |p| p.get_weight()),
+                        "\nNo agreement for query from: {} to: {}\n Failing graph:\n{:?}",
+                        source,
+                        target,
+                        input_graph
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn high_degree_limits_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const MEAN_DEGREE: f32 = 3.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            debug!("random graph: \n {:?}", input_graph);
+            let params = Params {
+                high_degree: Some(rng.gen_range(2, 8)),
+                max_settled_nodes_high_degree: rng.gen_range(0, 5),
+                max_witness_hops_high_degree: Some(rng.gen_range(1, 3)),
+                ..Params::default()
+            };
+            let fast_graph = prepare_with_params(&input_graph, &params);
+            fast_graph.validate().unwrap();
+            let mut path_calculator = create_calculator(&fast_graph);
+            let mut fw = FloydWarshall::new(input_graph.get_num_nodes());
+            fw.prepare(&input_graph);
+            for source in 0..input_graph.get_num_nodes() {
+                for target in 0..input_graph.get_num_nodes() {
+                    let path = path_calculator.calc_path(&fast_graph, source, target);
+                    assert_eq!(
+                        fw.calc_weight(source, target),
+                        path.map_or(WEIGHT_MAX, |p| p.get_weight()),
+                        "\nNo agreement for query from: {} to: {}\n Failing graph:\n{:?}",
+                        source,
+                        target,
+                        input_graph
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn max_shortcuts_per_node_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const MEAN_DEGREE: f32 = 3.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            debug!("random graph: \n {:?}", input_graph);
+            let params = Params {
+                max_shortcuts_per_node: Some(rng.gen_range(0, 4)),
+                ..Params::default()
+            };
+            #[allow(unused_mut)]
+            let mut fast_graphs = vec![prepare_with_params(&input_graph, &params)];
+            #[cfg(feature = "rayon")]
+            fast_graphs.push(prepare_parallel(&input_graph, &params));
+            let mut fw = FloydWarshall::new(input_graph.get_num_nodes());
+            fw.prepare(&input_graph);
+            for fast_graph in &fast_graphs {
+                fast_graph.validate().unwrap();
+                let mut path_calculator = create_calculator(fast_graph);
+                for source in 0..input_graph.get_num_nodes() {
+                    for target in 0..input_graph.get_num_nodes() {
+                        let path = path_calculator.calc_path(fast_graph, source, target);
+                        assert_eq!(
+                            fw.calc_weight(source, target),
+                            path.map_or(WEIGHT_MAX, |p| p.get_weight()),
+                            "\nNo agreement for query from: {} to: {}\n Failing graph:\n{:?}",
+                            source,
+                            target,
+                            input_graph
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn approximation_on_random_graph() {
+        const REPEATS: usize = 20;
+        const EPSILON: f32 = 0.1;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const MEAN_DEGREE: f32 = 3.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            debug!("random graph: \n {:?}", input_graph);
+            let params = Params {
+                approximation_epsilon: EPSILON,
+                ..Params::default()
+            };
+            #[allow(unused_mut)]
+            let mut fast_graphs = vec![prepare_with_params(&input_graph, &params)];
+            #[cfg(feature = "rayon")]
+            fast_graphs.push(prepare_parallel(&input_graph, &params));
+            let mut fw = FloydWarshall::new(input_graph.get_num_nodes());
+            fw.prepare(&input_graph);
+            for fast_graph in &fast_graphs {
+                fast_graph.validate().unwrap();
+                let mut path_calculator = create_calculator(fast_graph);
+                for source in 0..input_graph.get_num_nodes() {
+                    for target in 0..input_graph.get_num_nodes() {
+                        let weight_fw = fw.calc_weight(source, target);
+                        let path = path_calculator.calc_path(fast_graph, source, target);
+                        let weight = path.map_or(WEIGHT_MAX, |p| p.get_weight());
+                        if weight_fw == WEIGHT_MAX {
+                            assert_eq!(WEIGHT_MAX, weight);
+                            continue;
+                        }
+                        assert!(
+                            weight >= weight_fw
+                                && weight as f32 <= weight_fw as f32 * (1.0 + EPSILON),
+                            "\nPath too long from: {} to: {}: {} vs. {}\n Failing graph:\n{:?}",
+                            source,
+                            target,
+                            weight,
+                            weight_fw,
+                            input_graph
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cells_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const NUM_QUERIES: usize = 50;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            let num_nodes = input_graph.get_num_nodes();
+            let cells: Vec<usize> = (0..num_nodes).map(|_| rng.gen_range(0, 3)).collect();
+            let fast_graph = prepare_with_cells(&input_graph, &cells, &Params::default()).unwrap();
+            fast_graph.validate().unwrap();
+
+            // the interior nodes come first and the nodes of each cell have consecutive ranks
+            let mut boundary = vec![false; num_nodes];
+            for edge in input_graph.get_edges() {
+                if cells[edge.from] != cells[edge.to] {
+                    boundary[edge.from] = true;
+                    boundary[edge.to] = true;
+                }
+            }
+            let order = get_node_ordering(&fast_graph);
+            let groups: Vec<Option<usize>> = order
+                .iter()
+                .map(|node| (!boundary[*node]).then_some(cells[*node]))
+                .collect();
+            let mut expected = groups.clone();
+            expected.sort_by_key(|group| group.unwrap_or(usize::MAX));
+            assert_eq!(expected, groups);
+
+            let mut path_calculator = create_calculator(&fast_graph);
+            let mut fw = FloydWarshall::new(num_nodes);
+            fw.prepare(&input_graph);
+            for _ in 0..NUM_QUERIES {
+                let source = rng.gen_range(0, num_nodes);
+                let target = rng.gen_range(0, num_nodes);
+                let path = path_calculator.calc_path(&fast_graph, source, target);
+                assert_eq!(
+                    fw.calc_weight(source, target),
+                    path.as_ref().map_or(WEIGHT_MAX, |p| p.get_weight()),
+                    "\nNo agreement for query from: {} to: {}\n Failing graph:\n{:?}",
+                    source,
+                    target,
+                    input_graph
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn cells_with_max_shortcuts_per_node_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const NUM_QUERIES: usize = 50;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            let num_nodes = input_graph.get_num_nodes();
+            let cells: Vec<usize> = (0..num_nodes).map(|_| rng.gen_range(0, 3)).collect();
+            let params = Params {
+                max_shortcuts_per_node: Some(1),
+                ..Params::default()
+            };
+            let fast_graph = prepare_with_cells(&input_graph, &cells, &params).unwrap();
+            fast_graph.validate().unwrap();
+
+            let mut path_calculator = create_calculator(&fast_graph);
+            let mut fw = FloydWarshall::new(num_nodes);
+            fw.prepare(&input_graph);
+            for _ in 0..NUM_QUERIES {
+                let source = rng.gen_range(0, num_nodes);
+                let target = rng.gen_range(0, num_nodes);
+                let path = path_calculator.calc_path(&fast_graph, source, target);
+                assert_eq!(
+                    fw.calc_weight(source, target),
+                    path.as_ref().map_or(WEIGHT_MAX, |p| p.get_weight()),
+                    "\nNo agreement for query from: {} to: {}\n Failing graph:\n{:?}",
+                    source,
+                    target,
+                    input_graph
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn grid_graph_on_random_grids() {
+        const REPEATS: usize = 20;
+        const NUM_QUERIES: usize = 50;
+        let mut rng = create_rng();
+        for _ in 0..REPEATS {
+            let width = rng.gen_range(2, 10);
+            let height = rng.gen_range(1, 10);
+            let mut costs: Vec<Weight> = (0..width * height)
+                .map(|_| {
+                    if rng.gen_range(0, 5) == 0 {
+                        WEIGHT_MAX
+                    } else {
+                        rng.gen_range(1, 10)
+                    }
+                })
+                .collect();
+            // make sure the last node is connected so the input graph has as many nodes as the grid
+            costs[width * height - 1] = 1;
+            costs[width * height - 2] = 1;
+            let connectivity = if rng.gen() {
+                GridConnectivity::Four
+            } else {
+                GridConnectivity::Eight
+            };
+            let grid = GridGraph::new(width, height, costs, connectivity);
+            let fast_graph = prepare_grid(&grid, &Params::default());
+            fast_graph.validate().unwrap();
+
+            let input_graph = grid.to_input_graph();
+            let num_nodes = grid.get_num_nodes();
+            let mut path_calculator = create_calculator(&fast_graph);
+            let mut fw = FloydWarshall::new(num_nodes);
+            fw.prepare(&input_graph);
+            for _ in 0..NUM_QUERIES {
+                let source = rng.gen_range(0, num_nodes);
+                let target = rng.gen_range(0, num_nodes);
+                let path = path_calculator.calc_path(&fast_graph, source, target);
+                assert_eq!(
+                    fw.calc_weight(source, target),
+                    path.as_ref().map_or(WEIGHT_MAX, |p| p.get_weight()),
+                    "\nNo agreement for query from: {} to: {}\n Failing graph:\n{:?}",
+                    source,
+                    target,
+                    input_graph
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn grid_cells() {
+        let coordinates = [(0.0, 0.0), (1.5, 0.2), (0.9, 2.1), (2.0, 2.0), (-1.0, 0.5)];
+        // four columns from -1 to 2
+        assert_eq!(vec![1, 2, 9, 11, 0], calc_grid_cells(&coordinates, 1.0));
+        assert_eq!(vec![0; 5], calc_grid_cells(&coordinates, 10.0));
+        assert!(calc_grid_cells(&[], 1.0).is_empty());
+        let mut rng = create_rng();
+        assert_eq!(
+            Some(PreparationError::CellCountMismatch {
+                num_cells: 1,
+                num_nodes: 5
+            }),
+            prepare_with_cells(
+                &InputGraph::random(|| rng.gen(), 5, 2.0),
+                &[0],
+                &Params::default()
+            )
+            .err()
+        );
+    }
+
+    #[test]
+    fn edge_points_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const NUM_QUERIES: usize = 50;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            let fast_graph = prepare(&input_graph);
+            let mut path_calculator = create_calculator(&fast_graph);
+            let mut fw = FloydWarshall::new(input_graph.get_num_nodes());
+            fw.prepare(&input_graph);
+            let edges = input_graph.get_edges();
+
+            for _ in 0..NUM_QUERIES {
+                let mut random_point = || {
+                    let edge = edges[rng.gen_range(0, edges.len())];
+                    let fraction = rng.gen_range(0, 11) as f64 / 10.0;
+                    let offset = (fraction * edge.weight as f64).round() as Weight;
+                    (
+                        EdgePoint::new(edge.from, edge.to, edge.weight, fraction),
+                        edge,
+                        offset,
+                    )
+                };
+                let (source, source_edge, source_offset) = random_point();
+                let (target, target_edge, target_offset) = random_point();
+                let mut expected = WEIGHT_MAX;
+                let weight = fw.calc_weight(source_edge.to, target_edge.from);
+                if weight != WEIGHT_MAX {
+                    expected = source_edge.weight - source_offset + weight + target_offset;
+                }
+                if (source_edge.from, source_edge.to) == (target_edge.from, target_edge.to)
+                    && source_offset <= target_offset
+                {
+                    expected = expected.min(target_offset - source_offset);
+                }
+                let path =
+                    path_calculator.calc_path_between_edge_points(&fast_graph, &source, &target);
+                assert_eq!(
+                    expected,
+                    path.as_ref().map_or(WEIGHT_MAX, |p| p.get_weight()),
+                    "\nNo agreement for query from: {:?} to: {:?}\n Failing graph:\n{:?}",
+                    source,
+                    target,
+                    input_graph
+                );
+                if let Some(path) = path {
+                    let nodes = path.get_nodes();
+                    assert_eq!(input_graph.get_num_nodes(), nodes[0]);
+                    assert_eq!(input_graph.get_num_nodes() + 1, nodes[nodes.len() - 1]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn max_settled_nodes_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const NUM_QUERIES: usize = 50;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            let fast_graph = prepare(&input_graph);
+            let mut path_calculator = create_calculator(&fast_graph);
+            let mut fw = FloydWarshall::new(input_graph.get_num_nodes());
+            fw.prepare(&input_graph);
+
+            for _ in 0..NUM_QUERIES {
+                let source = rng.gen_range(0, input_graph.get_num_nodes());
+                let target = rng.gen_range(0, input_graph.get_num_nodes());
+                path_calculator.set_max_settled_nodes(Some(rng.gen_range(1, 10)));
+                let expected = fw.calc_weight(source, target);
+                match path_calculator.calc_path(&fast_graph, source, target) {
+                    Some(path) => {
+                        assert!(path.get_weight() >= expected);
+                        assert_eq!(
+                            path.is_exact(),
+                            !path_calculator.get_stats().is_settled_node_limit_reached()
+                        );
+                        if path.is_exact() {
+                            assert_eq!(expected, path.get_weight());
+                        }
+                    }
+                    None => assert!(
+                        expected == WEIGHT_MAX
+                            || path_calculator.get_stats().is_settled_node_limit_reached()
+                    ),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn original_edges_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            debug!("random graph: \n {:?}", input_graph);
+            let params = Params {
+                max_shortcuts: if rng.gen() { Some(5) } else { None },
+                ..Params::default()
+            };
+            let fast_graph = prepare_with_params(&input_graph, &params);
+            let mut fw = FloydWarshall::new(input_graph.get_num_nodes());
+            fw.prepare(&input_graph);
+            let to_tuple = |e: &Edge| (e.from, e.to, e.weight);
+            let input_edges: Vec<(NodeId, NodeId, Weight)> =
+                input_graph.get_edges().iter().map(to_tuple).collect();
+
+            for node in 0..input_graph.get_num_nodes() {
+                let mut out_edges: Vec<_> = fast_graph
+                    .get_original_out_edges(node)
+                    .iter()
+                    .map(to_tuple)
+                    .collect();
+                let mut in_edges: Vec<_> = fast_graph
+                    .get_original_in_edges(node)
+                    .iter()
+                    .map(to_tuple)
+                    .collect();
+                for edge in &input_edges {
+                    // edges that are shortest paths are never replaced by shortcuts
+                    if edge.2 != fw.calc_weight(edge.0, edge.1) {
+                        continue;
+                    }
+                    if edge.0 == node {
+                        assert!(out_edges.contains(edge), "{:?}", edge);
+                    }
+                    if edge.1 == node {
+                        assert!(in_edges.contains(edge), "{:?}", edge);
+                    }
+                }
+                for edge in out_edges.iter().chain(in_edges.iter()) {
+                    assert!(input_edges.contains(edge), "{:?}", edge);
+                }
+                let num_out_edges = out_edges.len();
+                out_edges.sort_unstable();
+                out_edges.dedup();
+                assert_eq!(num_out_edges, out_edges.len());
+                let num_in_edges = in_edges.len();
+                in_edges.sort_unstable();
+                in_edges.dedup();
+                assert_eq!(num_in_edges, in_edges.len());
+
+                let mut expected_neighbors: Vec<NodeId> = out_edges
+                    .iter()
+                    .map(|e| e.1)
+                    .chain(in_edges.iter().map(|e| e.0))
+                    .collect();
+                expected_neighbors.sort_unstable();
+                expected_neighbors.dedup();
+                assert_eq!(expected_neighbors, fast_graph.get_original_neighbors(node));
+            }
+        }
+    }
+
+    #[test]
+    fn input_edge_ids_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            debug!("random graph: \n {:?}", input_graph);
+            let num_nodes = input_graph.get_num_nodes();
+            let params = Params {
+                max_shortcuts: if rng.gen() { Some(5) } else { None },
+                ..Params::default()
+            };
+            let order: Vec<NodeId> = (0..num_nodes).collect();
+            let mut pruned =
+                prepare_with_order_with_params(&input_graph, &order, &ParamsWithOrder::new(0))
+                    .unwrap();
+            pruned.prune_shortcuts();
+            let mut fast_graphs = vec![
+                prepare_with_params(&input_graph, &params),
+                prepare_with_order(&input_graph, &order).unwrap(),
+                pruned,
+                CustomizableGraph::new(&input_graph, &order)
+                    .unwrap()
+                    .customize(&input_graph)
+                    .unwrap(),
+            ];
+            let fast_graph32 = FastGraph32::new(&fast_graphs[0]).convert_to_usize();
+            fast_graphs.push(fast_graph32);
+            #[cfg(feature = "rayon")]
+            fast_graphs.push(prepare_parallel(&input_graph, &params));
+            for fast_graph in &fast_graphs {
+                let mut path_calculator = create_calculator(fast_graph);
+                for source in 0..num_nodes {
+                    for target in 0..num_nodes {
+                        let path = match path_calculator.calc_path(fast_graph, source, target) {
+                            Some(path) => path,
+                            None => continue,
+                        };
+                        assert_eq!(
+                            path.as_edge_ids(&input_graph),
+                            path.as_input_edge_ids(fast_graph),
+                            "\nWrong edge ids for path from: {} to: {}\n Failing graph:\n{:?}",
+                            source,
+                            target,
+                            input_graph
+                        );
+                    }
+                }
+                // expanding an edge yields the ids of the edges it consists of
+                for edge_id in 0..fast_graph.get_num_out_edges() {
+                    let edges = fast_graph.expand_edge_fwd(edge_id);
+                    let ids = fast_graph.expand_edge_fwd_to_input_ids(edge_id);
+                    let expected: Vec<EdgeId> = edges
+                        .iter()
+                        .map(|e| input_graph.get_edge_id(e.from, e.to).unwrap())
+                        .collect();
+                    assert_eq!(expected, ids);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn subgraph_view_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const NUM_QUERIES: usize = 50;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            let fast_graph = std::sync::Arc::new(prepare(&input_graph));
+            let num_nodes = input_graph.get_num_nodes();
+            let mut remaining = InputGraph::new();
+            let view = SubgraphView::new(fast_graph.clone(), |edge| {
+                let allowed = rng.gen_range(0, 5) != 0;
+                if allowed {
+                    remaining.add_edge(edge.from, edge.to, edge.weight);
+                }
+                allowed
+            });
+            remaining.set_min_num_nodes(num_nodes);
+            remaining.freeze();
+            let mut fw = FloydWarshall::new(num_nodes);
+            fw.prepare(&remaining);
+
+            for _ in 0..NUM_QUERIES {
+                let source = rng.gen_range(0, num_nodes);
+                let target = rng.gen_range(0, num_nodes);
+                let path = view.calc_path(source, target);
+                assert_eq!(
+                    fw.calc_weight(source, target),
+                    path.as_ref().map_or(WEIGHT_MAX, |p| p.get_weight()),
+                    "\nNo agreement for query from: {} to: {}\n Failing graph:\n{:?}",
+                    source,
+                    target,
+                    input_graph
+                );
+                if let Some(path) = path {
+                    assert!(path
+                        .get_nodes()
+                        .windows(2)
+                        .all(|pair| view.is_allowed(pair[0], pair[1])));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn avoiding_blocked_elements_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const NUM_QUERIES: usize = 50;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            debug!("random graph: \n {:?}", input_graph);
+            let fast_graph = prepare(&input_graph);
+            let num_nodes = input_graph.get_num_nodes();
+            let mut blocked = BlockedElements::new();
+            for _ in 0..5 {
+                blocked.block_node(rng.gen_range(0, num_nodes));
+            }
+            let mut remaining = InputGraph::new();
+            for node in 0..num_nodes {
+                for edge in fast_graph.get_original_out_edges(node) {
+                    if rng.gen_range(0, 10) == 0 {
+                        blocked.block_edge(edge.from, edge.to);
+                    } else if !blocked.is_node_blocked(edge.from)
+                        && !blocked.is_node_blocked(edge.to)
+                    {
+                        remaining.add_edge(edge.from, edge.to, edge.weight);
+                    }
+                }
+            }
+            remaining.set_min_num_nodes(num_nodes);
+            remaining.freeze();
+            let mut fw = FloydWarshall::new(num_nodes);
+            fw.prepare(&remaining);
+
+            for _ in 0..NUM_QUERIES {
+                let source = rng.gen_range(0, num_nodes);
+                let target = rng.gen_range(0, num_nodes);
+                let expected = if blocked.is_node_blocked(source) || blocked.is_node_blocked(target)
+                {
+                    WEIGHT_MAX
+                } else {
+                    fw.calc_weight(source, target)
+                };
+                let path = calc_path_avoiding(&fast_graph, source, target, &blocked);
+                assert_eq!(
+                    expected,
+                    path.as_ref().map_or(WEIGHT_MAX, |p| p.get_weight()),
+                    "\nNo agreement for query from: {} to: {}\n Failing graph:\n{:?}",
+                    source,
+                    target,
+                    input_graph
+                );
+                if let Some(path) = path {
+                    assert!(path
+                        .get_nodes()
+                        .iter()
+                        .all(|n| !blocked.is_node_blocked(*n)));
+                    assert!(path
+                        .get_nodes()
+                        .windows(2)
+                        .all(|pair| !blocked.is_edge_blocked(pair[0], pair[1])));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn weight_overrides_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const NUM_QUERIES: usize = 50;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            debug!("random graph: \n {:?}", input_graph);
+            let fast_graph = prepare(&input_graph);
+            let num_nodes = input_graph.get_num_nodes();
+            let mut overrides = HashMap::new();
+            let mut changed = InputGraph::new();
+            for node in 0..num_nodes {
+                for edge in fast_graph.get_original_out_edges(node) {
+                    let weight = match rng.gen_range(0, 10) {
+                        0 => WEIGHT_MAX,
+                        1..=3 => rng.gen_range(1, 100),
+                        _ => edge.weight,
+                    };
+                    if weight != edge.weight {
+                        overrides.insert((edge.from, edge.to), weight);
+                    }
+                    if weight != WEIGHT_MAX {
+                        changed.add_edge(edge.from, edge.to, weight);
+                    }
+                }
+            }
+            changed.set_min_num_nodes(num_nodes);
+            changed.freeze();
+            let mut fw = FloydWarshall::new(num_nodes);
+            fw.prepare(&changed);
+
+            for _ in 0..NUM_QUERIES {
+                let source = rng.gen_range(0, num_nodes);
+                let target = rng.gen_range(0, num_nodes);
+                let path = calc_path_with_weight_overrides(&fast_graph, source, target, &overrides);
+                assert_eq!(
+                    fw.calc_weight(source, target),
+                    path.map_or(WEIGHT_MAX, |p| p.get_weight()),
+                    "\nNo agreement for query from: {} to: {}\n Failing graph:\n{:?}",
+                    source,
+                    target,
+                    input_graph
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn direction_bias_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const NUM_QUERIES: usize = 50;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            debug!("random graph: \n {:?}", input_graph);
+            let fast_graph = prepare(&input_graph);
+            let mut fw = FloydWarshall::new(input_graph.get_num_nodes());
+            fw.prepare(&input_graph);
+            let biases = [
+                DirectionBias::Balanced,
+                DirectionBias::Auto,
+                DirectionBias::Factor(100.0),
+                DirectionBias::Factor(0.01),
+            ];
+            let mut calculators: Vec<PathCalculator> = biases
+                .iter()
+                .map(|bias| {
+                    let mut calc = create_calculator(&fast_graph);
+                    calc.set_direction_bias(*bias);
+                    calc
+                })
+                .collect();
+            let mut num_settled_fwd = vec![0; biases.len()];
+
+            for _ in 0..NUM_QUERIES {
+                let source = rng.gen_range(0, input_graph.get_num_nodes());
+                let target = rng.gen_range(0, input_graph.get_num_nodes());
+                for (i, calc) in calculators.iter_mut().enumerate() {
+                    let weight = calc
+                        .calc_path(&fast_graph, source, target)
+                        .map_or(WEIGHT_MAX, |p| p.get_weight());
+                    assert_eq!(
+                        fw.calc_weight(source, target),
+                        weight,
+                        "\nNo agreement for query from: {} to: {} with bias: {:?}\
+                         \n Failing graph:\n{:?}",
+                        source,
+                        target,
+                        biases[i],
+                        input_graph
+                    );
+                    num_settled_fwd[i] += calc.get_stats().get_num_settled_fwd();
+                }
+            }
+            assert!(num_settled_fwd[2] >= num_settled_fwd[3]);
+        }
+    }
+
+    #[test]
+    fn warm_start_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const NUM_SOURCES: usize = 20;
+            const NUM_TARGETS: usize = 20;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            debug!("random graph: \n {:?}", input_graph);
+            let fast_graph = prepare(&input_graph);
+            let mut path_calculator = create_calculator(&fast_graph);
+            let mut fw = FloydWarshall::new(input_graph.get_num_nodes());
+            fw.prepare(&input_graph);
+            let dijkstra_graph = PreparationGraph::from_input_graph(&input_graph);
+            let mut dijkstra = Dijkstra::new(input_graph.get_num_nodes());
+
+            for _ in 0..NUM_SOURCES {
+                let source = rng.gen_range(0, input_graph.get_num_nodes());
+                for _ in 0..NUM_TARGETS {
+                    let target = rng.gen_range(0, input_graph.get_num_nodes());
+                    let path = if rng.gen() {
+                        path_calculator.calc_path_warm_start(&fast_graph, source, target)
+                    } else {
+                        path_calculator.set_source(&fast_graph, source);
+                        path_calculator.calc_path_to(&fast_graph, target)
+                    }
+                    .unwrap_or(ShortestPath::none(source, target));
+                    assert_eq!(fw.calc_weight(source, target), path.get_weight());
+                    if path.is_found() {
+                        assert_eq!(
+                            dijkstra.calc_path(&dijkstra_graph, source, target),
+                            Some(path.clone())
+                        );
+                        assert_eq!(source, path.get_nodes()[0]);
+                        assert_eq!(target, path.get_nodes()[path.get_nodes().len() - 1]);
+                    }
+                    // other queries in between must not break the warm start
+                    if rng.gen_range(0, 10) == 0 {
+                        path_calculator.calc_path(&fast_graph, target, source);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn nearest_targets_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const NUM_QUERIES: usize = 20;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            let num_nodes = input_graph.get_num_nodes();
+            let fast_graph = prepare(&input_graph);
+            let mut path_calculator = create_calculator(&fast_graph);
+            let mut fw = FloydWarshall::new(num_nodes);
+            fw.prepare(&input_graph);
+            for _ in 0..NUM_QUERIES {
+                let source = rng.gen_range(0, num_nodes);
+                let num_candidates = rng.gen_range(0, 20);
+                let candidates: Vec<NodeId> = (0..num_candidates)
+                    .map(|_| rng.gen_range(0, num_nodes))
+                    .collect();
+                let k = rng.gen_range(0, 6);
+                let mut expected: Vec<(NodeId, Weight)> = candidates
+                    .iter()
+                    .map(|c| (*c, fw.calc_weight(source, *c)))
+                    .filter(|(_, w)| *w != WEIGHT_MAX)
+                    .collect();
+                expected.sort_by_key(|(_, w)| *w);
+                expected.truncate(k);
+                let nearest =
+                    path_calculator.calc_nearest_targets(&fast_graph, source, &candidates, k);
+                assert_eq!(expected, nearest);
+            }
+        }
+    }
+
+    #[test]
+    fn landmarks_on_random_graph() {
+        const REPEATS: usize = 20;
+        let mut num_pruned = 0;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const NUM_QUERIES: usize = 50;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            let num_nodes = input_graph.get_num_nodes();
+            let fast_graph = prepare(&input_graph);
+            let landmarks = Arc::new(Landmarks::new(&fast_graph, rng.gen_range(1, 5)));
+            let mut path_calculator = create_calculator(&fast_graph);
+            path_calculator.set_landmarks(Some(landmarks.clone()));
+            let mut fw = FloydWarshall::new(num_nodes);
+            fw.prepare(&input_graph);
+            for _ in 0..NUM_QUERIES {
+                let source = rng.gen_range(0, num_nodes);
+                let target = rng.gen_range(0, num_nodes);
+                let weight = fw.calc_weight(source, target);
+                let bound = landmarks.calc_lower_bound(source, target);
+                assert!(bound <= weight);
+                if weight == WEIGHT_MAX {
+                    assert!(path_calculator
+                        .calc_path(&fast_graph, source, target)
+                        .is_none());
+                } else {
+                    let path = path_calculator
+                        .calc_path(&fast_graph, source, target)
+                        .unwrap();
+                    assert_eq!(weight, path.get_weight());
+                    assert_eq!(source, path.get_source());
+                    assert_eq!(target, path.get_target());
+                }
+                num_pruned += path_calculator.get_stats().get_num_pruned();
+            }
+        }
+        assert!(num_pruned > 0);
+    }
+
+    #[test]
+    fn hub_labels_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            let num_nodes = input_graph.get_num_nodes();
+            // sometimes use a core, which leads to larger labels
+            let params = Params {
+                max_shortcuts: if rng.gen_range(0, 2) == 0 {
+                    None
+                } else {
+                    Some(rng.gen_range(0, 10))
+                },
+                ..Params::default()
+            };
+            let fast_graph = prepare_with_params(&input_graph, &params);
+            let hub_labels = HubLabels::build(&fast_graph);
+            let mut fw = FloydWarshall::new(num_nodes);
+            fw.prepare(&input_graph);
+            for source in 0..num_nodes {
+                for target in 0..num_nodes {
+                    let expected = fw.calc_weight(source, target);
+                    assert_eq!(
+                        (expected != WEIGHT_MAX).then_some(expected),
+                        hub_labels.get_weight(source, target)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn arc_flags_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const NUM_QUERIES: usize = 50;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            let num_nodes = input_graph.get_num_nodes();
+            // sometimes use a core, whose edges are flagged as well
+            let params = Params {
+                max_shortcuts: if rng.gen_range(0, 2) == 0 {
+                    None
+                } else {
+                    Some(rng.gen_range(0, 10))
+                },
+                ..Params::default()
+            };
+            let fast_graph = prepare_with_params(&input_graph, &params);
+            let regions = if rng.gen_range(0, 2) == 0 {
+                ArcFlags::partition(&fast_graph, rng.gen_range(1, 10))
+            } else {
+                (0..num_nodes).map(|_| rng.gen_range(0, 5)).collect()
+            };
+            let arc_flags = Arc::new(ArcFlags::new(&fast_graph, &regions));
+            let mut path_calculator = create_calculator(&fast_graph);
+            path_calculator.set_arc_flags(Some(arc_flags));
+            let mut fw = FloydWarshall::new(num_nodes);
+            fw.prepare(&input_graph);
+            for _ in 0..NUM_QUERIES {
+                let source = rng.gen_range(0, num_nodes);
+                let target = rng.gen_range(0, num_nodes);
+                let weight = fw.calc_weight(source, target);
+                let path = path_calculator.calc_path(&fast_graph, source, target);
+                assert_eq!(weight, path.as_ref().map_or(WEIGHT_MAX, |p| p.get_weight()));
+                if let Some(path) = path {
+                    assert_eq!(source, path.get_nodes()[0]);
+                    assert_eq!(target, *path.get_nodes().last().unwrap());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn overlay_graph_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const NUM_QUERIES: usize = 50;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            let small = rng.gen_range(1, 10);
+            let cells = OverlayGraph::partition(&input_graph, &[small, small + 10, 30]);
+            let mut overlay_graph = OverlayGraph::new(&input_graph, &cells).unwrap();
+            check_overlay_graph(&mut rng, &overlay_graph, &input_graph, NUM_QUERIES);
+
+            // the same graph with new weights
+            let mut new_graph = InputGraph::new();
+            for edge in input_graph.get_edges() {
+                new_graph.add_edge(edge.from, edge.to, rng.gen_range(1, 100));
+            }
+            new_graph.freeze();
+            let weights: Vec<Weight> = new_graph.get_edges().iter().map(|e| e.weight).collect();
+            overlay_graph.customize(&weights).unwrap();
+            check_overlay_graph(&mut rng, &overlay_graph, &new_graph, NUM_QUERIES);
+        }
+    }
+
+    #[test]
+    fn customizable_graph_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const NUM_QUERIES: usize = 50;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            let order = get_node_ordering(&prepare(&input_graph));
+            let customizable_graph = CustomizableGraph::new(&input_graph, &order).unwrap();
+            for _ in 0..3 {
+                // the same edges with new weights
+                let mut new_graph = InputGraph::new();
+                for edge in input_graph.get_edges() {
+                    new_graph.add_edge(edge.from, edge.to, rng.gen_range(1, 100));
+                }
+                new_graph.freeze();
+                let fast_graph = customizable_graph.customize(&new_graph).unwrap();
+                let mut path_calculator = create_calculator(&fast_graph);
+                let mut fw = FloydWarshall::new(new_graph.get_num_nodes());
+                fw.prepare(&new_graph);
+                for _ in 0..NUM_QUERIES {
+                    let source = rng.gen_range(0, new_graph.get_num_nodes());
+                    let target = rng.gen_range(0, new_graph.get_num_nodes());
+                    let path = path_calculator.calc_path(&fast_graph, source, target);
+                    assert_eq!(
+                        fw.calc_weight(source, target),
+                        path.as_ref().map_or(WEIGHT_MAX, |p| p.get_weight())
+                    );
+                    if let Some(path) = path {
+                        let nodes = path.get_nodes();
+                        assert_eq!(source, nodes[0]);
+                        assert_eq!(target, *nodes.last().unwrap());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn update_weights_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const NUM_QUERIES: usize = 100;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let mut input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            let mut fast_graph = prepare(&input_graph);
+            for _ in 0..3 {
+                // decrease the weights of some edges
+                let mut new_graph = InputGraph::new();
+                let mut changes = vec![];
+                for edge in input_graph.get_edges() {
+                    let mut weight = edge.weight;
+                    if weight > 1 && rng.gen_bool(0.2) {
+                        weight = rng.gen_range(1, weight);
+                        changes.push((edge.from, edge.to, weight));
+                    }
+                    new_graph.add_edge(edge.from, edge.to, weight);
+                }
+                new_graph.freeze();
+                fast_graph.update_weights(&changes).unwrap();
+                assert_eq!(Ok(()), fast_graph.validate());
+                let mut path_calculator = create_calculator(&fast_graph);
+                let mut fw = FloydWarshall::new(new_graph.get_num_nodes());
+                fw.prepare(&new_graph);
+                for _ in 0..NUM_QUERIES {
+                    let source = rng.gen_range(0, new_graph.get_num_nodes());
+                    let target = rng.gen_range(0, new_graph.get_num_nodes());
+                    let path = path_calculator.calc_path(&fast_graph, source, target);
+                    assert_eq!(
+                        fw.calc_weight(source, target),
+                        path.as_ref().map_or(WEIGHT_MAX, |p| p.get_weight())
+                    );
+                    if let Some(path) = path {
+                        let nodes = path.get_nodes();
+                        assert_eq!(source, nodes[0]);
+                        assert_eq!(target, *nodes.last().unwrap());
+                    }
+                }
+                input_graph = new_graph;
+            }
+        }
+    }
+
+    #[test]
+    fn add_edges_on_random_graph() {
+        const REPEATS: usize = 20;
+        for _ in 0..REPEATS {
+            const NUM_NODES: usize = 50;
+            const NUM_QUERIES: usize = 100;
+            const MEAN_DEGREE: f32 = 2.0;
+
+            let mut rng = create_rng();
+            let mut input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
+            let mut fast_graph = prepare(&input_graph);
+            let num_nodes = input_graph.get_num_nodes();
+            for _ in 0..3 {
+                let mut new_edges = vec![];
+                input_graph.thaw();
+                for _ in 0..5 {
+                    let from = rng.gen_range(0, num_nodes);
+                    let to = rng.gen_range(0, num_nodes);
+                    if from != to {
+                        let weight = rng.gen_range(1, 100);
+                        new_edges.push((from, to, weight));
+                        input_graph.add_edge(from, to, weight);
+                    }
+                }
+                input_graph.freeze();
+                fast_graph.add_edges(&new_edges).unwrap();
+                assert_eq!(Ok(()), fast_graph.validate());
+                let mut path_calculator = create_calculator(&fast_graph);
+                let mut fw = FloydWarshall::new(num_nodes);
+                fw.prepare(&input_graph);
+                for _ in 0..NUM_QUERIES {
+                    let source = rng.gen_range(0, num_nodes);
+                    let target = rng.gen_range(0, num_nodes);
+                    let path = path_calculator.calc_path(&fast_graph, source, target);
+                    assert_eq!(
+                        fw.calc_weight(source, target),
+                        path.as_ref().map_or(WEIGHT_MAX, |p| p.get_weight())
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_overlay_graph(
+        rng: &mut StdRng,
+        overlay_graph: &OverlayGraph,
+        input_graph: &InputGraph,
+        num_queries: usize,
+    ) {
+        let mut fw = FloydWarshall::new(input_graph.get_num_nodes());
+        fw.prepare(input_graph);
+        for _ in 0..num_queries {
+            let source = rng.gen_range(0, overlay_graph.get_num_nodes());
+            let target = rng.gen_range(0, overlay_graph.get_num_nodes());
+            let weight = fw.calc_weight(source, target);
+            let path = overlay_graph.calc_path(source, target);
+            assert_eq!(weight, path.as_ref().map_or(WEIGHT_MAX, |p| p.get_weight()));
+            assert_eq!(
+                weight,
+                overlay_graph
+                    .calc_weight(source, target)
+                    .unwrap_or(WEIGHT_MAX)
+            );
+            if let Some(path) = path {
+                let nodes = path.get_nodes();
+                assert_eq!(source, nodes[0]);
+                assert_eq!(target, *nodes.last().unwrap());
+                let sum: Weight = nodes
+                    .windows(2)
+                    .map(|pair| {
+                        let edge_id = input_graph.get_edge_id(pair[0], pair[1]).unwrap();
+                        input_graph.get_edges()[edge_id].weight
+                    })
+                    .sum();
+                assert_eq!(weight, sum);
+            }
+        }
+    }
+
+    #[test]
+    fn routing_on_fixtures() {
+        for (name, input_graph) in fixtures::all() {
+            let num_nodes = input_graph.get_num_nodes();
+            let fast_graph = prepare(&input_graph);
+            fast_graph.validate().unwrap();
+            let mut path_calculator = create_calculator(&fast_graph);
+            let mut fw = FloydWarshall::new(num_nodes);
+            fw.prepare(&input_graph);
+            for source in 0..num_nodes {
+                for target in 0..num_nodes {
+                    let expected = fw.calc_weight(source, target);
+                    let path = path_calculator.calc_path(&fast_graph, source, target);
+                    assert_eq!(
+                        expected,
+                        path.as_ref().map_or(WEIGHT_MAX, |p| p.get_weight()),
+                        "wrong weight from {} to {} for fixture {}",
+                        source,
+                        target,
+                        name
+                    );
+                    if let Some(path) = path {
+                        assert_eq!(source, path.get_nodes()[0]);
+                        assert_eq!(target, *path.get_nodes().last().unwrap());
+                    }
+                }
+            }
+        }
+    }
+
+    fn gen_weighted_nodes(rng: &mut StdRng, max_node: usize, num: usize) -> Vec<(NodeId, Weight)> {
+        (0..num)
+            .map(|_| {
+                (
+                    rng.gen_range(0, max_node),
+                    // sometimes use max weight
+                    if rng.gen_range(0, 100) < 3 {
+                        WEIGHT_MAX
+                    } else {
+                        rng.gen_range(0, 100)
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn save_to_and_load_from_disk() {
+        let mut g = InputGraph::new();
+        g.add_edge(0, 5, 6);
+        g.add_edge(5, 2, 1);
+        g.add_edge(2, 3, 4);
+        g.freeze();
+        let fast_graph = prepare(&g);
+        save_to_disk(&fast_graph, "example.fp").expect("writing to disk failed");
+        let loaded = load_from_disk("example.fp").unwrap();
+        remove_file("example.fp").expect("deleting file failed");
+        assert_eq!(fast_graph.get_num_nodes(), loaded.get_num_nodes());
+        assert_eq!(fast_graph.get_num_in_edges(), loaded.get_num_in_edges());
+        assert_eq!(fast_graph.get_num_out_edges(), loaded.get_num_out_edges());
+    }
+
+    #[test]
+    fn fingerprint_after_save_and_load() {
+        let mut g = InputGraph::new();
+        g.add_edge(0, 5, 6);
+        g.add_edge(5, 2, 1);
+        g.add_edge(2, 3, 4);
+        g.freeze();
+        let fast_graph = prepare(&g);
+        save_to_disk(&fast_graph, "fingerprint.fp").expect("writing to disk failed");
+        let loaded = load_from_disk("fingerprint.fp").unwrap();
+        remove_file("fingerprint.fp").expect("deleting file failed");
+        assert_eq!(fast_graph.get_fingerprint(), loaded.get_fingerprint());
+        save_to_disk32(&fast_graph, "fingerprint32.fp").expect("writing to disk failed");
+        let loaded = load_from_disk32("fingerprint32.fp").unwrap();
+        remove_file("fingerprint32.fp").expect("deleting file failed");
+        assert_eq!(fast_graph.get_fingerprint(), loaded.get_fingerprint());
+    }
+
+    #[test]
+    fn deserialize_sparse_rejects_invalid_data() {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            #[serde(deserialize_with = "deserialize_sparse")]
+            graph: FastGraph,
+        }
+        let mut g = InputGraph::new();
+        g.add_edge(0, 5, 6);
+        g.add_edge(5, 2, 1);
+        g.freeze();
+        let fast_graph = prepare(&g);
+        let mut sparse = FastGraphSparse::new(&fast_graph);
+        let bytes = bincode::serialize(&sparse).unwrap();
+        let loaded: Wrapper = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(fast_graph.get_fingerprint(), loaded.graph.get_fingerprint());
+
+        sparse.ranks[0] = 100;
+        let bytes = bincode::serialize(&sparse).unwrap();
+        let error = bincode::deserialize::<Wrapper>(&bytes).err().unwrap();
+        assert_eq!(ValidationError::InvalidRanks.to_string(), error.to_string());
+    }
+
+    #[test]
+    fn save_to_and_load_from_disk_32() {
+        let mut g = InputGraph::new();
+        g.add_edge(0, 5, 6);
+        g.add_edge(5, 2, 1);
+        g.add_edge(2, 3, 4);
+        g.freeze();
+        let fast_graph = prepare(&g);
+        save_to_disk32(&fast_graph, "example32.fp").expect("writing to disk failed");
+        let loaded = load_from_disk32("example32.fp").unwrap();
+        remove_file("example32.fp").expect("deleting file failed");
+        assert_eq!(fast_graph.get_num_nodes(), loaded.get_num_nodes());
+        assert_eq!(fast_graph.get_num_in_edges(), loaded.get_num_in_edges());
+        assert_eq!(fast_graph.get_num_out_edges(), loaded.get_num_out_edges());
+    }
+
+    #[test]
+    fn preparation_info() {
+        let mut g = InputGraph::new();
+        g.add_edge(0, 5, 6);
+        g.add_edge(5, 2, 1);
+        g.add_edge(2, 3, 4);
+        g.set_weight_unit(WeightUnit::milliseconds());
+        g.freeze();
+        let params = Params::new(0.5, 100, 10, 100);
+        let mut fast_graph = prepare_with_params(&g, &params);
+        assert_eq!(
+            Some(WeightUnit::milliseconds()),
+            fast_graph.get_weight_unit()
+        );
+        let path = calc_path(&fast_graph, 0, 3).unwrap();
+        assert_eq!(Some(WeightUnit::milliseconds()), path.get_weight_unit());
+        let info = fast_graph.get_preparation_info();
+        assert_eq!(&PreparationParams::Heuristic(params), info.get_params());
+        assert_eq!(env!("CARGO_PKG_VERSION"), info.get_crate_version());
+        assert_eq!(None, info.get_created_at());
+        fast_graph.set_created_at(1_700_000_000);
+
+        let order = get_node_ordering(&fast_graph);
+        let with_order = prepare_with_order(&g, &order).unwrap();
+        assert_eq!(
+            &PreparationParams::WithOrder(ParamsWithOrder::default()),
+            with_order.get_preparation_info().get_params()
+        );
+
+        // the info survives serialization, also when using the 32bit representation
+        save_to_disk(&fast_graph, "example_info.fp").expect("writing to disk failed");
+        let loaded = load_from_disk("example_info.fp").unwrap();
+        remove_file("example_info.fp").expect("deleting file failed");
+        assert_eq!(
+            fast_graph.get_preparation_info(),
+            loaded.get_preparation_info()
+        );
+        assert_eq!(
+            Some(1_700_000_000),
+            loaded.get_preparation_info().get_created_at()
+        );
+        save_to_disk32(&fast_graph, "example_info32.fp").expect("writing to disk failed");
+        let loaded32 = load_from_disk32("example_info32.fp").unwrap();
+        remove_file("example_info32.fp").expect("deleting file failed");
+        assert_eq!(
+            fast_graph.get_preparation_info(),
+            loaded32.get_preparation_info()
+        );
     }
 
     #[test]
@@ -388,7 +2767,7 @@ mod tests {
         // Repeat a few times to reduce test flakiness.
         for _ in 0..10 {
             let mut rng = create_rng();
-            let input_graph = InputGraph::random(&mut rng, NUM_NODES, MEAN_DEGREE);
+            let input_graph = InputGraph::random(|| rng.gen(), NUM_NODES, MEAN_DEGREE);
             let serialized1 = bincode::serialize(&prepare(&input_graph)).unwrap();
             let serialized2 = bincode::serialize(&prepare(&input_graph)).unwrap();
             if serialized1 != serialized2 {
@@ -407,8 +2786,8 @@ mod tests {
         run_performance_test(
             &InputGraph::from_file("meta/test_maps/bremen_dist.gr"),
             &Params::new(0.1, 500, 2, 50),
-            845493338,
-            30265,
+            844882371,
+            30568,
         )
     }
 
@@ -422,8 +2801,8 @@ mod tests {
         run_performance_test(
             &InputGraph::from_file("meta/test_maps/bremen_time.gr"),
             &Params::new(0.1, 100, 2, 100),
-            88104267255,
-            30265,
+            88019943073,
+            30568,
         );
     }
 
@@ -436,8 +2815,8 @@ mod tests {
         run_performance_test(
             &InputGraph::from_file("meta/test_maps/graph_ballard.gr"),
             &Params::new(0.1, 100, 3, 100),
-            28409159409,
-            14992,
+            28489079005,
+            14873,
         );
     }
 
@@ -450,8 +2829,8 @@ mod tests {
         run_performance_test(
             &InputGraph::from_file("meta/test_maps/graph_23rd.gr"),
             &Params::new(0.1, 100, 3, 100),
-            19438403873,
-            20421,
+            19481720699,
+            20457,
         );
     }
 
@@ -464,8 +2843,8 @@ mod tests {
         run_performance_test(
             &InputGraph::from_file("meta/test_maps/south_seattle_car.gr"),
             &Params::new(0.1, 100, 10, 100),
-            77479396,
-            30805,
+            78536913,
+            30915,
         );
     }
 
@@ -479,8 +2858,8 @@ mod tests {
             &InputGraph::from_file("meta/test_maps/bremen_dist.gr"),
             &Params::default(),
             &ParamsWithOrder::default(),
-            845493338,
-            30265,
+            844882371,
+            30568,
         );
     }
 
@@ -494,8 +2873,8 @@ mod tests {
             &InputGraph::from_file("meta/test_maps/south_seattle_car.gr"),
             &Params::new(0.1, 100, 10, 100),
             &ParamsWithOrder::new(100),
-            77479396,
-            30805,
+            78536913,
+            30915,
         );
     }
 
@@ -508,7 +2887,7 @@ mod tests {
         let mut fast_graph = FastGraph::new(1);
         prepare_algo(
             &mut |input_graph| fast_graph = prepare_with_params(input_graph, params),
-            &input_graph,
+            input_graph,
         );
         print_fast_graph_stats(&fast_graph);
         let mut path_calculator = PathCalculator::new(fast_graph.get_num_nodes());
@@ -540,7 +2919,7 @@ mod tests {
                 fast_graph =
                     prepare_with_order_with_params(input_graph, &order, params_with_order).unwrap()
             },
-            &input_graph,
+            input_graph,
         );
         print_fast_graph_stats(&fast_graph);
         let mut path_calculator = PathCalculator::new(fast_graph.get_num_nodes());
@@ -573,7 +2952,7 @@ mod tests {
     {
         let mut time = Stopwatch::new();
         time.start();
-        preparation(&input_graph);
+        preparation(input_graph);
         time.stop();
         println!(
             "number of nodes (input graph) ..... {}",
@@ -603,9 +2982,7 @@ mod tests {
         let mut checksum = 0;
         let mut num_not_found = 0;
         let mut time = Stopwatch::new();
-        for _i in 0..num_queries {
-            let source = rng.gen_range(0, num_nodes);
-            let target = rng.gen_range(0, num_nodes);
+        for (source, target) in random_queries(|| rng.gen(), num_nodes, num_queries) {
             time.start();
             let path = calc_path(source, target);
             time.stop();
@@ -629,7 +3006,9 @@ mod tests {
         );
     }
 
-    fn create_rng() -> StdRng {
+    /// Creates a random number generator with a new seed, which is logged so failing tests can be
+    /// reproduced using `create_rng_with_seed`. Also used by the tests of the other modules.
+    pub(crate) fn create_rng() -> StdRng {
         let seed = create_seed();
         create_rng_with_seed(seed)
     }
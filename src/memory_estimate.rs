@@ -0,0 +1,108 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use std::mem::size_of;
+
+use crate::constants::{NodeId, Weight};
+use crate::fast_graph::FastGraphEdge;
+use crate::fast_graph_builder::Params;
+use crate::input_graph::InputGraph;
+use crate::preparation_graph::Arc;
+
+/// The number of shortcuts per input edge we assume. Road networks usually need less than one
+/// shortcut per edge, but denser graphs can need a lot more.
+const SHORTCUTS_PER_EDGE: f64 = 1.0;
+
+/// The number of edges (and shortcuts) we reserve for each node in the preparation graph, see
+/// `PreparationGraph::new`
+const INITIAL_CAPACITY: usize = 3;
+
+/// The bytes per node of the priority queue of the contraction: an entry of its index map, a hash
+/// table slot and two positions in its heap
+const QUEUE_BYTES_PER_NODE: usize =
+    size_of::<(u64, NodeId, (usize, Weight))>() + 3 * size_of::<usize>();
+
+/// Estimates how many bytes the preparation of the given graph using `prepare_with_params` will
+/// need at its peak, including the `FastGraph` it returns, but not the input graph itself. The
+/// number of shortcuts cannot be known before the preparation, so we assume one shortcut per
+/// input edge, unless `Params::max_shortcuts` is smaller. The estimate is usually somewhat too
+/// high for road networks, but can be much too low for dense graphs. Use
+/// `FastGraph::memory_usage_bytes` to find the actual size of a prepared graph.
+pub fn estimate_preparation_memory(input_graph: &InputGraph, params: &Params) -> usize {
+    let num_nodes = input_graph.get_num_nodes();
+    let num_edges = input_graph.get_num_edges();
+    let mut num_shortcuts = (num_edges as f64 * SHORTCUTS_PER_EDGE) as usize;
+    if let Some(max_shortcuts) = params.max_shortcuts {
+        num_shortcuts = num_shortcuts.min(max_shortcuts);
+    }
+
+    // every edge and shortcut is stored as an out-edge and an in-edge. the edges of a node grow
+    // beyond the initial capacity by doubling it, and they keep their capacity after the node is
+    // contracted, so we assume twice as many edges as there will be in total
+    let num_arcs = 2 * (INITIAL_CAPACITY * num_nodes).max(2 * (num_edges + num_shortcuts));
+    let preparation_graph = 2 * num_nodes * size_of::<Vec<Arc>>() + num_arcs * size_of::<Arc>();
+
+    // the witness search data, the levels, groups, outdated flags, leaf candidates and the
+    // priority queue of the contraction
+    let witness_search =
+        num_nodes * (size_of::<(bool, Weight, usize, NodeId)>() + size_of::<u32>());
+    let contraction = num_nodes
+        * (size_of::<NodeId>() + size_of::<usize>() + size_of::<bool>() + size_of::<NodeId>())
+        + num_nodes * QUEUE_BYTES_PER_NODE;
+
+    // every edge and shortcut ends up as a forward or a backward edge of the fast graph, but the
+    // edge vectors can have up to twice the capacity they need
+    let fast_graph = 3 * num_nodes * size_of::<usize>()
+        + 2 * (num_edges + num_shortcuts) * size_of::<FastGraphEdge>();
+
+    preparation_graph + witness_search + contraction + fast_graph
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{prepare_with_params, InputGraph, Params};
+
+    use super::*;
+
+    #[test]
+    fn estimate_memory() {
+        use rand::Rng;
+
+        let mut rng = crate::tests::create_rng();
+        let g = InputGraph::random(|| rng.gen(), 500, 2.0);
+        let params = Params::default();
+        let estimate = estimate_preparation_memory(&g, &params);
+        let fast_graph = prepare_with_params(&g, &params);
+        let actual = fast_graph.memory_usage_bytes();
+        assert!(actual > g.get_num_edges() * size_of::<FastGraphEdge>());
+        assert!(estimate > actual, "{} vs. {}", estimate, actual);
+        assert!(estimate < 20 * actual, "{} vs. {}", estimate, actual);
+
+        // the index of the original edges is included once it is built
+        fast_graph.get_original_out_edges(0);
+        assert!(fast_graph.memory_usage_bytes() > actual);
+
+        // limiting the number of shortcuts lowers the estimate
+        let params = Params {
+            max_shortcuts: Some(10),
+            ..Params::default()
+        };
+        assert!(estimate_preparation_memory(&g, &params) < estimate);
+    }
+}
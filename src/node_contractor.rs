@@ -18,7 +18,7 @@
  */
 
 use crate::constants::NodeId;
-use crate::constants::Weight;
+use crate::constants::{Weight, WEIGHT_MAX};
 use crate::fast_graph_builder::Params;
 use crate::preparation_graph::PreparationGraph;
 use crate::witness_search::WitnessSearch;
@@ -31,10 +31,76 @@ pub fn contract_node(
     node: NodeId,
     max_settled_nodes: usize,
 ) {
-    handle_shortcuts(graph, witness_search, node, add_shortcut, max_settled_nodes);
+    handle_shortcuts(
+        graph,
+        witness_search,
+        node,
+        add_shortcut,
+        max_settled_nodes,
+        0.0,
+        false,
+    );
     graph.disconnect(node);
 }
 
+/// Like `contract_node`, but uses the witness search limits of `params` for the current number
+/// of edges of `node`, see `Params::get_contraction_limits`, and accepts witnesses that are up to
+/// `Params::approximation_epsilon` longer than the shortcuts. Returns the number of shortcuts that
+/// were added (or replaced longer edges).
+pub fn contract_node_with_params(
+    graph: &mut PreparationGraph,
+    witness_search: &mut WitnessSearch,
+    node: NodeId,
+    params: &Params,
+) -> usize {
+    let degree = graph.get_out_edges(node).len() + graph.get_in_edges(node).len();
+    let (max_settled_nodes, max_hops) = params.get_contraction_limits(degree);
+    witness_search.set_max_hops(max_hops);
+    let mut num_shortcuts = 0;
+    handle_shortcuts(
+        graph,
+        witness_search,
+        node,
+        |graph, shortcut| {
+            add_shortcut(graph, shortcut);
+            num_shortcuts += 1;
+        },
+        max_settled_nodes,
+        params.approximation_epsilon,
+        true,
+    );
+    graph.disconnect(node);
+    witness_search.set_max_hops(params.max_witness_hops);
+    num_shortcuts
+}
+
+/// Returns the number of shortcuts `contract_node_with_params` would add to contract `node`,
+/// see `Params::max_shortcuts_per_node`
+pub fn count_shortcuts(
+    graph: &mut PreparationGraph,
+    witness_search: &mut WitnessSearch,
+    node: NodeId,
+    params: &Params,
+) -> usize {
+    let degree = graph.get_out_edges(node).len() + graph.get_in_edges(node).len();
+    let (max_settled_nodes, max_hops) = params.get_contraction_limits(degree);
+    witness_search.set_max_hops(max_hops);
+    let mut num_shortcuts = 0;
+    handle_shortcuts(
+        graph,
+        witness_search,
+        node,
+        |_graph, _shortcut| {
+            num_shortcuts += 1;
+        },
+        max_settled_nodes,
+        params.approximation_epsilon,
+        false,
+    );
+    witness_search.set_max_hops(params.max_witness_hops);
+    num_shortcuts
+}
+
 pub fn calc_relevance(
     graph: &mut PreparationGraph,
     params: &Params,
@@ -52,41 +118,220 @@ pub fn calc_relevance(
             num_shortcuts += 1;
         },
         max_settled_nodes,
+        params.approximation_epsilon,
+        false,
     );
+    calc_relevance_from_shortcuts(graph, params, node, level, num_shortcuts)
+}
+
+/// Calculates the relevance of `node` given the number of shortcuts its contraction would add
+pub fn calc_relevance_from_shortcuts(
+    graph: &PreparationGraph,
+    params: &Params,
+    node: NodeId,
+    level: NodeId,
+    num_shortcuts: usize,
+) -> f32 {
     let num_edges = graph.get_out_edges(node).len() + graph.get_in_edges(node).len();
     let relevance = (params.hierarchy_depth_factor * level as f32)
         + (params.edge_quotient_factor * num_shortcuts as f32 + 1.0) / (num_edges as f32 + 1.0);
     relevance * 1000.0
 }
 
+/// Finds the shortcuts that are needed to contract `node` without modifying the graph. The
+/// witness searches never visit the nodes marked in `blocked`, which allows finding the shortcuts
+/// of several nodes that are contracted at the same time. See `handle_shortcuts` for `epsilon`,
+/// the arcs that need to be protected are added to `witness_arcs`, if given.
+#[cfg(feature = "rayon")]
+pub fn find_shortcuts(
+    graph: &PreparationGraph,
+    witness_search: &mut WitnessSearch,
+    node: NodeId,
+    max_settled_nodes: usize,
+    epsilon: f32,
+    blocked: &[bool],
+    mut witness_arcs: Option<&mut Vec<(NodeId, NodeId)>>,
+) -> Vec<Shortcut> {
+    let mut search = ShortcutSearch::new(max_settled_nodes, epsilon);
+    let mut shortcuts = vec![];
+    for i in 0..graph.in_edges[node].len() {
+        for outcome in search.run(graph, witness_search, node, i, blocked) {
+            match *outcome {
+                Outcome::Shortcut(shortcut) => shortcuts.push(shortcut),
+                Outcome::Witness { out_node, protect } => {
+                    if let (true, Some(witness_arcs)) = (protect, witness_arcs.as_deref_mut()) {
+                        witness_search.add_witness_arcs(out_node, witness_arcs);
+                    }
+                }
+            }
+        }
+    }
+    shortcuts
+}
+
+/// Adds the given shortcuts (see `find_shortcuts`), protects the given witness arcs and removes
+/// all edges incident to `node`
+#[cfg(feature = "rayon")]
+pub fn contract_node_with_shortcuts(
+    graph: &mut PreparationGraph,
+    node: NodeId,
+    shortcuts: &[Shortcut],
+    witness_arcs: &[(NodeId, NodeId)],
+) {
+    for shortcut in shortcuts {
+        add_shortcut(graph, *shortcut);
+    }
+    for (from, to) in witness_arcs {
+        graph.protect_arc(*from, *to);
+    }
+    graph.disconnect(node);
+}
+
+/// Calls `handle_shortcut` for every shortcut that is needed to contract `node`. There is one
+/// witness search per in-neighbor of `node`, which looks for witnesses to all out-neighbors at
+/// once, see `WitnessSearch::find_max_weights`.
+///
+/// Witnesses that are longer than the shortcut they replace, but by at most the factor
+/// `1 + epsilon`, are accepted as well, unless one of the two edges of the shortcut is protected.
+/// If `protect_witnesses` is set, the edges of such witnesses are protected, as well as those of
+/// the witnesses and shortcuts that replace protected edges. This way each part of a shortest
+/// path is replaced by a longer path at most once, so the paths found by the queries are never
+/// longer than the shortest paths by more than the factor `1 + epsilon`.
 pub fn handle_shortcuts<F>(
     graph: &mut PreparationGraph,
     witness_search: &mut WitnessSearch,
     node: NodeId,
     mut handle_shortcut: F,
     max_settled_nodes: usize,
+    epsilon: f32,
+    protect_witnesses: bool,
 ) where
     F: FnMut(&mut PreparationGraph, Shortcut),
 {
+    let mut search = ShortcutSearch::new(max_settled_nodes, epsilon);
+    let mut witness_arcs = vec![];
     for i in 0..graph.in_edges[node].len() {
-        let in_node = graph.in_edges[node][i].adj_node;
-        witness_search.init(in_node, node);
-        for j in 0..graph.out_edges[node].len() {
-            let weight = graph.in_edges[node][i].weight + graph.out_edges[node][j].weight;
-            let out_node = graph.out_edges[node][j].adj_node;
-            // no need to find the actual weight of a witness path as long as we can be sure
-            // that there is some witness with weight smaller or equal to the removed direct
-            // path
-            let max_witness_weight =
-                witness_search.find_max_weight(graph, out_node, weight, max_settled_nodes);
-            if max_witness_weight <= weight {
-                continue;
+        // handling the outcomes never changes the edges of `node` or whether they are protected,
+        // so the outcomes for all out-neighbors can be determined up front
+        for outcome in search.run(graph, witness_search, node, i, &[]) {
+            match *outcome {
+                Outcome::Shortcut(shortcut) => handle_shortcut(graph, shortcut),
+                Outcome::Witness { out_node, protect } => {
+                    if protect && protect_witnesses {
+                        witness_search.add_witness_arcs(out_node, &mut witness_arcs);
+                        for (from, to) in witness_arcs.drain(..) {
+                            graph.protect_arc(from, to);
+                        }
+                    }
+                }
             }
-            handle_shortcut(graph, Shortcut::new(in_node, out_node, node, weight))
         }
     }
 }
 
+/// The outcome of the witness search for the path `in_node -> node -> out_node`
+#[derive(Copy, Clone)]
+enum Outcome {
+    /// No witness was found, so the shortcut is needed
+    Shortcut(Shortcut),
+    /// A witness was found. Its arcs need to be protected if `protect` is true, see
+    /// `handle_shortcuts`
+    Witness { out_node: NodeId, protect: bool },
+}
+
+/// Runs the witness searches that `handle_shortcuts` and `find_shortcuts` have in common and
+/// keeps the memory they need between the searches
+struct ShortcutSearch {
+    max_settled_nodes: usize,
+    epsilon: f32,
+    targets: Vec<(NodeId, Weight)>,
+    weights: Vec<Weight>,
+    outcomes: Vec<Outcome>,
+}
+
+impl ShortcutSearch {
+    fn new(max_settled_nodes: usize, epsilon: f32) -> Self {
+        ShortcutSearch {
+            max_settled_nodes,
+            epsilon,
+            targets: vec![],
+            weights: vec![],
+            outcomes: vec![],
+        }
+    }
+
+    /// Searches witnesses from the `in_index`-th in-neighbor of `node` to all out-neighbors of
+    /// `node`, without visiting the nodes marked in `blocked`. Returns one outcome per
+    /// out-neighbor.
+    fn run(
+        &mut self,
+        graph: &PreparationGraph,
+        witness_search: &mut WitnessSearch,
+        node: NodeId,
+        in_index: usize,
+        blocked: &[bool],
+    ) -> &[Outcome] {
+        let epsilon = self.epsilon;
+        let in_node = graph.in_edges[node][in_index].adj_node;
+        let in_weight = graph.in_edges[node][in_index].weight;
+        witness_search.init(in_node, node);
+        self.targets.clear();
+        self.targets
+            .extend(graph.out_edges[node].iter().map(|out_edge| {
+                let weight = in_weight + out_edge.weight;
+                let protected =
+                    epsilon > 0.0 && is_protected_pair(graph, in_node, node, out_edge.adj_node);
+                (out_edge.adj_node, calc_limit(weight, epsilon, protected))
+            }));
+        // no need to find the actual weight of a witness path as long as we can be sure
+        // that there is some witness with weight smaller or equal to the removed direct
+        // path (plus the slack)
+        witness_search.find_max_weights(
+            graph,
+            &self.targets,
+            self.max_settled_nodes,
+            blocked,
+            &mut self.weights,
+        );
+        self.outcomes.clear();
+        for (j, (out_node, limit)) in self.targets.iter().enumerate() {
+            let weight = in_weight + graph.out_edges[node][j].weight;
+            let protected = epsilon > 0.0 && is_protected_pair(graph, in_node, node, *out_node);
+            let witness_weight = self.weights[j];
+            self.outcomes.push(if witness_weight <= *limit {
+                Outcome::Witness {
+                    out_node: *out_node,
+                    protect: epsilon > 0.0 && (protected || witness_weight > weight),
+                }
+            } else {
+                Outcome::Shortcut(Shortcut {
+                    protected,
+                    ..Shortcut::new(in_node, *out_node, node, weight)
+                })
+            });
+        }
+        &self.outcomes
+    }
+}
+
+/// Returns true if the edge `from -> node` or the edge `node -> to` is protected, see
+/// `handle_shortcuts`
+fn is_protected_pair(graph: &PreparationGraph, from: NodeId, node: NodeId, to: NodeId) -> bool {
+    graph.is_protected(from, node) || graph.is_protected(node, to)
+}
+
+/// Returns the largest weight a witness may have to replace a shortcut with the given weight
+fn calc_limit(weight: Weight, epsilon: f32, protected: bool) -> Weight {
+    if epsilon <= 0.0 || protected {
+        weight
+    } else {
+        // WEIGHT_MAX means that no witness was found
+        weight
+            .saturating_add((weight as f64 * epsilon as f64) as Weight)
+            .min(WEIGHT_MAX - 1)
+    }
+}
+
 fn add_shortcut(graph: &mut PreparationGraph, shortcut: Shortcut) {
     graph.add_or_reduce_edge(
         shortcut.from,
@@ -94,6 +339,10 @@ fn add_shortcut(graph: &mut PreparationGraph, shortcut: Shortcut) {
         shortcut.weight,
         shortcut.center_node,
     );
+    // even if there already is a shorter edge, it takes the place of the shortcut
+    if shortcut.protected {
+        graph.protect_arc(shortcut.from, shortcut.to);
+    }
 }
 
 #[derive(Eq, PartialEq, Debug, Copy, Clone)]
@@ -102,6 +351,8 @@ pub struct Shortcut {
     to: NodeId,
     center_node: NodeId,
     weight: Weight,
+    /// true if the shortcut replaces a protected edge, see `handle_shortcuts`
+    protected: bool,
 }
 
 impl Shortcut {
@@ -111,6 +362,7 @@ impl Shortcut {
             to,
             center_node,
             weight,
+            protected: false,
         }
     }
 }
@@ -166,7 +418,7 @@ mod tests {
         let _shortcuts = calc_shortcuts(&mut g, 1);
         // performance: there is no need for a shortcut 0->1->2, because there is already the
         // (required) shortcut 3->1->2
-        let _expected_shortcuts = vec![Shortcut::new(3, 2, 1, 2)];
+        let _expected_shortcuts = [Shortcut::new(3, 2, 1, 2)];
         // todo: handle this case for better performance (less shortcuts)
         //        assert_eq!(expected_shortcuts, handler.shortcuts);
     }
@@ -270,6 +522,8 @@ mod tests {
             node,
             |_g, shortcut| shortcuts.push(shortcut),
             usize::MAX,
+            0.0,
+            false,
         );
         shortcuts
     }
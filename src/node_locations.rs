@@ -0,0 +1,114 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use rstar::primitives::GeomWithData;
+use rstar::RTree;
+
+use crate::constants::NodeId;
+
+type IndexedNode = GeomWithData<[f64; 2], NodeId>;
+
+/// Stores the coordinates of the nodes along with a spatial index, so that locations can be
+/// snapped to the closest node, e.g. to find the source and target nodes of a query. Distances are
+/// calculated using an equirectangular projection, which is accurate for the short distances
+/// snapping is used for, but not for locations that are far apart or close to the poles.
+pub struct NodeLocations {
+    coordinates: Vec<(f64, f64)>,
+    tree: RTree<IndexedNode>,
+}
+
+impl NodeLocations {
+    /// Creates the index for the given (latitude, longitude) pairs in degrees, one per node.
+    pub fn new(coordinates: Vec<(f64, f64)>) -> Self {
+        NodeLocations::with_filter(coordinates, |_| true)
+    }
+
+    /// Like `new`, but only the nodes for which `include` returns true can be snapped to, e.g. to
+    /// leave out nodes without edges.
+    pub fn with_filter<F: Fn(NodeId) -> bool>(coordinates: Vec<(f64, f64)>, include: F) -> Self {
+        let nodes = coordinates
+            .iter()
+            .enumerate()
+            .filter(|(node, _)| include(*node))
+            .map(|(node, (lat, lon))| IndexedNode::new(project(*lat, *lon), node))
+            .collect();
+        NodeLocations {
+            coordinates,
+            tree: RTree::bulk_load(nodes),
+        }
+    }
+
+    pub fn get_num_nodes(&self) -> usize {
+        self.coordinates.len()
+    }
+
+    /// Returns the (latitude, longitude) pair of the given node.
+    pub fn get_coordinates(&self, node: NodeId) -> (f64, f64) {
+        self.coordinates[node]
+    }
+
+    /// Returns the node that is closest to the given location, or `None` if there are no nodes.
+    pub fn snap(&self, lat: f64, lon: f64) -> Option<NodeId> {
+        self.tree
+            .nearest_neighbor(&project(lat, lon))
+            .map(|node| node.data)
+    }
+
+    /// Returns the nodes within the given distance (in degrees of latitude) of the given location,
+    /// ordered by their distance.
+    pub fn snap_within(&self, lat: f64, lon: f64, max_distance: f64) -> Vec<NodeId> {
+        let point = project(lat, lon);
+        self.tree
+            .nearest_neighbor_iter_with_distance_2(&point)
+            .take_while(|(_, distance_2)| *distance_2 <= max_distance * max_distance)
+            .map(|(node, _)| node.data)
+            .collect()
+    }
+}
+
+fn project(lat: f64, lon: f64) -> [f64; 2] {
+    [lon * lat.to_radians().cos(), lat]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snap() {
+        let locations = NodeLocations::new(vec![
+            (52.52, 13.40),
+            (52.51, 13.38),
+            (48.14, 11.58),
+            (52.53, 13.41),
+        ]);
+        assert_eq!(4, locations.get_num_nodes());
+        assert_eq!((48.14, 11.58), locations.get_coordinates(2));
+        assert_eq!(Some(0), locations.snap(52.521, 13.401));
+        assert_eq!(Some(2), locations.snap(48.0, 11.0));
+        assert_eq!(Some(1), locations.snap(52.0, 13.0));
+        assert_eq!(vec![0, 3], locations.snap_within(52.522, 13.402, 0.015));
+        assert!(locations.snap_within(50.0, 12.0, 0.1).is_empty());
+        // node 0 is excluded
+        let coordinates = vec![(52.52, 13.40), (52.51, 13.38)];
+        let locations = NodeLocations::with_filter(coordinates, |node| node != 0);
+        assert_eq!(Some(1), locations.snap(52.52, 13.40));
+        assert_eq!(None, NodeLocations::new(vec![]).snap(52.52, 13.40));
+    }
+}
@@ -0,0 +1,209 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+//! Storing node orderings separately from the prepared graph, e.g. to calculate the ordering on a
+//! powerful machine and run `prepare_with_order` on devices that only have the input graph.
+//!
+//! The format is plain text, so it is independent of the platform:
+//!
+//! ```text
+//! fast_paths node ordering 1
+//! nodes <num_nodes>
+//! fingerprint <topology fingerprint of the input graph, hexadecimal>
+//! <one node id per line, starting with the lowest ranked node>
+//! ```
+
+use std::io::{BufRead, Write};
+
+use crate::constants::NodeId;
+use crate::input_graph::InputGraph;
+
+const HEADER: &str = "fast_paths node ordering";
+const VERSION: u32 = 1;
+
+/// Writes the node ordering `order` (as returned by `FastGraph::get_node_ordering`) of the given
+/// input graph, see the module documentation for the format.
+pub fn write_node_ordering<W: Write>(
+    writer: &mut W,
+    input_graph: &InputGraph,
+    order: &[NodeId],
+) -> Result<(), String> {
+    if order.len() != input_graph.get_num_nodes() {
+        return Err(format!(
+            "the node ordering has {} nodes, but the input graph has {}",
+            order.len(),
+            input_graph.get_num_nodes()
+        ));
+    }
+    let mut write = || -> std::io::Result<()> {
+        writeln!(writer, "{} {}", HEADER, VERSION)?;
+        writeln!(writer, "nodes {}", order.len())?;
+        writeln!(
+            writer,
+            "fingerprint {:016x}",
+            input_graph.get_topology_fingerprint()
+        )?;
+        for node in order {
+            writeln!(writer, "{}", node)?;
+        }
+        Ok(())
+    };
+    write().map_err(|e| e.to_string())
+}
+
+/// Reads a node ordering that was written using `write_node_ordering`. Returns an error if the
+/// data is malformed, was written by a newer version of this crate or does not belong to the
+/// given input graph, i.e. its number of nodes or its topology fingerprint differ (the weights of
+/// the edges may differ).
+pub fn read_node_ordering<R: BufRead>(
+    reader: R,
+    input_graph: &InputGraph,
+) -> Result<Vec<NodeId>, String> {
+    let mut lines = reader.lines();
+    let mut next_line = |what: &str| -> Result<String, String> {
+        match lines.next() {
+            Some(line) => line.map_err(|e| e.to_string()),
+            None => Err(format!("unexpected end of data, expected {}", what)),
+        }
+    };
+
+    let header = next_line("header")?;
+    let version = header
+        .strip_prefix(HEADER)
+        .and_then(|version| version.trim().parse::<u32>().ok())
+        .ok_or_else(|| "not a node ordering".to_string())?;
+    if version != VERSION {
+        return Err(format!(
+            "unsupported node ordering format version {}",
+            version
+        ));
+    }
+    let num_nodes = parse_field(&next_line("number of nodes")?, "nodes", 10)?;
+    if num_nodes != input_graph.get_num_nodes() as u64 {
+        return Err(format!(
+            "the node ordering has {} nodes, but the input graph has {}",
+            num_nodes,
+            input_graph.get_num_nodes()
+        ));
+    }
+    let fingerprint = parse_field(&next_line("fingerprint")?, "fingerprint", 16)?;
+    if fingerprint != input_graph.get_topology_fingerprint() {
+        return Err("the node ordering belongs to another graph".to_string());
+    }
+
+    let num_nodes = input_graph.get_num_nodes();
+    let mut order = Vec::with_capacity(num_nodes);
+    let mut seen = vec![false; num_nodes];
+    for _ in 0..num_nodes {
+        let line = next_line("node id")?;
+        let node: NodeId = line
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid node id: {}", line))?;
+        if node >= num_nodes || seen[node] {
+            return Err(format!("invalid or duplicate node id: {}", node));
+        }
+        seen[node] = true;
+        order.push(node);
+    }
+    Ok(order)
+}
+
+fn parse_field(line: &str, name: &str, radix: u32) -> Result<u64, String> {
+    line.strip_prefix(name)
+        .and_then(|value| u64::from_str_radix(value.trim(), radix).ok())
+        .ok_or_else(|| format!("expected {}, but found: {}", name, line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_graph() -> InputGraph {
+        // 0 -> 1 -> 2
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 3);
+        g.add_edge(1, 2, 4);
+        g.freeze();
+        g
+    }
+
+    #[test]
+    fn write_and_read() {
+        let g = create_graph();
+        let mut data = vec![];
+        write_node_ordering(&mut data, &g, &[2, 0, 1]).unwrap();
+        let text = String::from_utf8(data.clone()).unwrap();
+        assert!(text.starts_with("fast_paths node ordering 1\nnodes 3\nfingerprint "));
+        assert!(text.ends_with("\n2\n0\n1\n"));
+        assert_eq!(vec![2, 0, 1], read_node_ordering(&data[..], &g).unwrap());
+
+        // only the weights changed
+        let mut other = InputGraph::new();
+        other.add_edge(0, 1, 5);
+        other.add_edge(1, 2, 1);
+        other.freeze();
+        assert_eq!(
+            vec![2, 0, 1],
+            read_node_ordering(&data[..], &other).unwrap()
+        );
+    }
+
+    #[test]
+    fn reject_invalid_data() {
+        let g = create_graph();
+        assert!(write_node_ordering(&mut vec![], &g, &[0, 1]).is_err());
+        let mut data = vec![];
+        write_node_ordering(&mut data, &g, &[2, 0, 1]).unwrap();
+        let text = String::from_utf8(data).unwrap();
+        let read = |text: &str| read_node_ordering(text.as_bytes(), &g);
+
+        assert_eq!(Err("not a node ordering".to_string()), read("a 0 1 3\n"));
+        assert_eq!(
+            Err("unsupported node ordering format version 2".to_string()),
+            read(&text.replace("ordering 1", "ordering 2"))
+        );
+        assert_eq!(
+            Err("unexpected end of data, expected node id".to_string()),
+            read(text.trim_end_matches("1\n"))
+        );
+        assert_eq!(
+            Err("invalid or duplicate node id: 2".to_string()),
+            read(&text.replace("\n0\n", "\n2\n"))
+        );
+
+        // a graph with another edge
+        let mut other = InputGraph::new();
+        other.add_edge(0, 1, 3);
+        other.add_edge(2, 1, 4);
+        other.freeze();
+        assert_eq!(
+            Err("the node ordering belongs to another graph".to_string()),
+            read_node_ordering(text.as_bytes(), &other)
+        );
+        // a graph with another number of nodes
+        let mut other = InputGraph::new();
+        other.add_edge(0, 1, 3);
+        other.freeze();
+        assert_eq!(
+            Err("the node ordering has 3 nodes, but the input graph has 2".to_string()),
+            read_node_ordering(text.as_bytes(), &other)
+        );
+    }
+}
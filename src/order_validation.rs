@@ -0,0 +1,157 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use std::fmt;
+
+use crate::constants::{NodeId, INVALID_NODE};
+
+/// Describes why a node ordering passed to `prepare_with_order` is not a permutation of the node
+/// ids of the graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderError {
+    /// The order has the node id `node` at position `index`, but the graph only has `num_nodes`
+    /// nodes
+    InvalidNode {
+        index: usize,
+        node: NodeId,
+        num_nodes: usize,
+    },
+    /// The order has the node id `node` at position `index`, but it already occurred at position
+    /// `first_index`
+    DuplicateNode {
+        index: usize,
+        first_index: usize,
+        node: NodeId,
+    },
+    /// The order contains `order_len` node ids, but the graph has `num_nodes` nodes, i.e. some of
+    /// the nodes are missing or there are too many
+    WrongLength { order_len: usize, num_nodes: usize },
+}
+
+impl fmt::Display for OrderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OrderError::InvalidNode {
+                index,
+                node,
+                num_nodes,
+            } => write!(
+                f,
+                "invalid node {} at index {} of the order, the graph only has {} nodes",
+                node, index, num_nodes
+            ),
+            OrderError::DuplicateNode {
+                index,
+                first_index,
+                node,
+            } => write!(
+                f,
+                "the node {} occurs at index {} of the order, but also at index {} before, the \
+                 order must be a permutation of the node ids",
+                node, index, first_index
+            ),
+            OrderError::WrongLength {
+                order_len,
+                num_nodes,
+            } => write!(
+                f,
+                "the order contains {} nodes, but the graph has {} nodes",
+                order_len, num_nodes
+            ),
+        }
+    }
+}
+
+impl std::error::Error for OrderError {}
+
+/// Checks that `order` is a permutation of the ids of `num_nodes` nodes. Invalid and duplicate
+/// node ids are reported before a wrong length, because they point to the position where the
+/// order goes wrong.
+pub fn validate_order(order: &[NodeId], num_nodes: usize) -> Result<(), OrderError> {
+    let mut indices = vec![INVALID_NODE; num_nodes];
+    for (index, node) in order.iter().cloned().enumerate() {
+        if node >= num_nodes {
+            return Err(OrderError::InvalidNode {
+                index,
+                node,
+                num_nodes,
+            });
+        }
+        if indices[node] != INVALID_NODE {
+            return Err(OrderError::DuplicateNode {
+                index,
+                first_index: indices[node],
+                node,
+            });
+        }
+        indices[node] = index;
+    }
+    if order.len() != num_nodes {
+        return Err(OrderError::WrongLength {
+            order_len: order.len(),
+            num_nodes,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate() {
+        assert_eq!(Ok(()), validate_order(&[], 0));
+        assert_eq!(Ok(()), validate_order(&[2, 0, 1], 3));
+        assert_eq!(
+            Err(OrderError::InvalidNode {
+                index: 1,
+                node: 3,
+                num_nodes: 3
+            }),
+            validate_order(&[2, 3, 1], 3)
+        );
+        assert_eq!(
+            Err(OrderError::DuplicateNode {
+                index: 2,
+                first_index: 0,
+                node: 2
+            }),
+            validate_order(&[2, 0, 2], 3)
+        );
+        assert_eq!(
+            Err(OrderError::WrongLength {
+                order_len: 2,
+                num_nodes: 3
+            }),
+            validate_order(&[2, 0], 3)
+        );
+        // too many nodes always means there is an invalid or duplicate node
+        assert_eq!(
+            Err(OrderError::DuplicateNode {
+                index: 3,
+                first_index: 1,
+                node: 0
+            }),
+            validate_order(&[2, 0, 1, 0], 3)
+        );
+        let error = validate_order(&[0, 5], 2).unwrap_err().to_string();
+        assert!(error.contains("index 1"), "{}", error);
+    }
+}
@@ -0,0 +1,98 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use std::collections::BinaryHeap;
+
+use crate::constants::{NodeId, Weight, INVALID_NODE, WEIGHT_MAX};
+use crate::fast_graph::FastGraph;
+use crate::heap_item::HeapItem;
+use crate::input_graph::Edge;
+use crate::shortest_path::ShortestPath;
+
+/// Runs Dijkstra's algorithm from `start` to `end` on the original (non-shortcut) edges of the
+/// prepared graph, where `edge_weight` yields the weight to use for each edge, or `None` if the
+/// edge must not be used. This is needed whenever the query changes the edge weights in a way the
+/// contraction hierarchy was not prepared for, so it is much slower than the usual query.
+///
+/// Note that edges of the input graph for which the preparation found a shorter path between the
+/// same two nodes can be dropped from the prepared graph, so such edges are never used here.
+pub fn calc_path<F>(
+    graph: &FastGraph,
+    start: NodeId,
+    end: NodeId,
+    edge_weight: F,
+) -> Option<ShortestPath>
+where
+    F: Fn(&Edge) -> Option<Weight>,
+{
+    calc_path_with_positions(graph, start, end, |edge, _| edge_weight(edge))
+}
+
+/// Like `calc_path`, but `edge_weight` also receives the position of each edge in
+/// `FastGraph::get_original_out_edges(edge.from)`, which identifies the edge even if there are
+/// several edges between the same two nodes.
+pub fn calc_path_with_positions<F>(
+    graph: &FastGraph,
+    start: NodeId,
+    end: NodeId,
+    edge_weight: F,
+) -> Option<ShortestPath>
+where
+    F: Fn(&Edge, usize) -> Option<Weight>,
+{
+    let num_nodes = graph.get_num_nodes();
+    assert!(start < num_nodes, "invalid start node");
+    assert!(end < num_nodes, "invalid end node");
+    let mut weights = vec![WEIGHT_MAX; num_nodes];
+    let mut parents = vec![INVALID_NODE; num_nodes];
+    let mut heap = BinaryHeap::new();
+    weights[start] = 0;
+    heap.push(HeapItem::new(0, start));
+    while let Some(curr) = heap.pop() {
+        if curr.weight > weights[curr.node_id] {
+            continue;
+        }
+        if curr.node_id == end {
+            let mut nodes = vec![end];
+            let mut node = end;
+            while parents[node] != INVALID_NODE {
+                node = parents[node];
+                nodes.push(node);
+            }
+            nodes.reverse();
+            return Some(ShortestPath::new(start, end, curr.weight, nodes));
+        }
+        for (position, edge) in graph
+            .get_original_out_edges(curr.node_id)
+            .iter()
+            .enumerate()
+        {
+            let weight = match edge_weight(edge, position) {
+                Some(weight) => curr.weight.saturating_add(weight),
+                None => continue,
+            };
+            if weight < weights[edge.to] {
+                weights[edge.to] = weight;
+                parents[edge.to] = curr.node_id;
+                heap.push(HeapItem::new(weight, edge.to));
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,543 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use crate::constants::{NodeId, Weight};
+use crate::fast_graph::FastGraph;
+use crate::input_graph::InputGraph;
+use crate::node_locations::NodeLocations;
+use crate::osm_pbf;
+use crate::path_calculator::PathCalculator;
+use crate::shortest_path::ShortestPath;
+use crate::weight_unit::WeightUnit;
+
+const EARTH_RADIUS_METERS: f64 = 6_371_000.0;
+
+/// The speeds used for the different kinds of roads, see `OsmRouter`. Roads whose `highway` tag
+/// has no speed are not used.
+#[derive(Debug, Clone)]
+pub struct CarProfile {
+    speeds: HashMap<String, f64>,
+}
+
+impl CarProfile {
+    /// Creates a profile without any speeds, see `set_speed`.
+    pub fn new() -> Self {
+        CarProfile {
+            speeds: HashMap::new(),
+        }
+    }
+
+    /// Sets the speed in km/h for roads with the given `highway` tag, e.g. `residential`.
+    pub fn set_speed(&mut self, highway: &str, speed: f64) {
+        assert!(speed > 0.0, "speeds must be positive");
+        self.speeds.insert(highway.to_string(), speed);
+    }
+
+    /// Returns the speed in km/h for roads with the given `highway` tag, or `None` if they are not
+    /// used.
+    pub fn get_speed(&self, highway: &str) -> Option<f64> {
+        self.speeds.get(highway).copied()
+    }
+}
+
+impl Default for CarProfile {
+    fn default() -> Self {
+        let mut profile = CarProfile::new();
+        for (highway, speed) in [
+            ("motorway", 100.0),
+            ("motorway_link", 60.0),
+            ("trunk", 80.0),
+            ("trunk_link", 50.0),
+            ("primary", 65.0),
+            ("primary_link", 45.0),
+            ("secondary", 55.0),
+            ("secondary_link", 40.0),
+            ("tertiary", 45.0),
+            ("tertiary_link", 35.0),
+            ("unclassified", 35.0),
+            ("residential", 30.0),
+            ("living_street", 6.0),
+            ("service", 15.0),
+        ] {
+            profile.set_speed(highway, speed);
+        }
+        profile
+    }
+}
+
+/// The road network of an OpenStreetMap extract as an `InputGraph`, along with the coordinates and
+/// the OSM id of every node. The roads are turned into edges according to a `CarProfile`, and the
+/// weights are travel times in milliseconds, based on the speed of each road or its `maxspeed`
+/// tag, if that is lower. One-way roads only get an edge in their direction of travel. Access
+/// restrictions are respected only as far as the `access`, `motor_vehicle` and `motorcar` tags go,
+/// and turn restrictions are ignored.
+///
+/// Both the OSM XML format (`.osm`) and the PBF format (`.osm.pbf`) are supported.
+pub struct OsmGraph {
+    input_graph: InputGraph,
+    coordinates: Vec<(f64, f64)>,
+    osm_node_ids: Vec<i64>,
+}
+
+impl OsmGraph {
+    /// Reads the OSM XML file with the given name, see `from_reader`.
+    pub fn from_file(filename: &str, profile: &CarProfile) -> Result<Self, String> {
+        let file = File::open(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        OsmGraph::from_reader(BufReader::new(file), profile)
+    }
+
+    /// Reads OSM XML data and turns the road network it contains into a graph.
+    pub fn from_reader<R: BufRead>(reader: R, profile: &CarProfile) -> Result<Self, String> {
+        Ok(OsmGraph::from_data(&OsmData::read(reader)?, profile))
+    }
+
+    /// Reads the OSM PBF file with the given name, see `from_pbf_reader`.
+    pub fn from_pbf_file(filename: &str, profile: &CarProfile) -> Result<Self, String> {
+        let file = File::open(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        OsmGraph::from_pbf_reader(BufReader::new(file), profile)
+    }
+
+    /// Reads OSM PBF data and turns the road network it contains into a graph. Only uncompressed
+    /// and zlib compressed blocks are supported, which covers the extracts offered by the common
+    /// download sites.
+    pub fn from_pbf_reader<R: Read>(reader: R, profile: &CarProfile) -> Result<Self, String> {
+        Ok(OsmGraph::from_data(&osm_pbf::read(reader)?, profile))
+    }
+
+    fn from_data(data: &OsmData, profile: &CarProfile) -> Self {
+        let mut node_ids: HashMap<i64, NodeId> = HashMap::new();
+        let mut osm_node_ids = vec![];
+        let mut coordinates = vec![];
+        let mut input_graph = InputGraph::new();
+        for way in &data.ways {
+            let speed = match way.get_speed(profile) {
+                Some(speed) => speed,
+                None => continue,
+            };
+            let (forward, backward) = way.get_directions();
+            for pair in way.nodes.windows(2) {
+                let (from, to) = match (data.nodes.get(&pair[0]), data.nodes.get(&pair[1])) {
+                    (Some(from), Some(to)) => (*from, *to),
+                    // the extract might contain ways whose nodes are cut off at its boundary
+                    _ => continue,
+                };
+                let mut get_node = |osm_id: i64, coords: (f64, f64)| {
+                    *node_ids.entry(osm_id).or_insert_with(|| {
+                        osm_node_ids.push(osm_id);
+                        coordinates.push(coords);
+                        coordinates.len() - 1
+                    })
+                };
+                let from_node = get_node(pair[0], from);
+                let to_node = get_node(pair[1], to);
+                let millis = calc_distance(from, to) / (speed / 3.6) * 1000.0;
+                let weight = (millis.round() as Weight).max(1);
+                if forward {
+                    input_graph.add_edge(from_node, to_node, weight);
+                }
+                if backward {
+                    input_graph.add_edge(to_node, from_node, weight);
+                }
+            }
+        }
+        input_graph.set_min_num_nodes(coordinates.len());
+        input_graph.set_weight_unit(WeightUnit::milliseconds());
+        input_graph.freeze();
+        info!(
+            "Read {} nodes and {} edges from OSM data",
+            input_graph.get_num_nodes(),
+            input_graph.get_num_edges()
+        );
+        OsmGraph {
+            input_graph,
+            coordinates,
+            osm_node_ids,
+        }
+    }
+
+    pub fn get_input_graph(&self) -> &InputGraph {
+        &self.input_graph
+    }
+
+    /// Returns the (latitude, longitude) pairs of all nodes.
+    pub fn get_coordinates(&self) -> &[(f64, f64)] {
+        &self.coordinates
+    }
+
+    /// Returns the id of the OSM node that corresponds to the given node of the graph.
+    pub fn get_osm_node_id(&self, node: NodeId) -> i64 {
+        self.osm_node_ids[node]
+    }
+}
+
+/// Finds routes between coordinates on the road network of an OpenStreetMap extract. This wires up
+/// all the steps that are needed for this: the extract is read, the roads are turned into an
+/// `InputGraph` according to a `CarProfile` (see `OsmGraph`), the graph is prepared, and the start
+/// and end coordinates of each query are snapped to the closest nodes. This is a starting point
+/// rather than a complete routing engine.
+pub struct OsmRouter {
+    fast_graph: FastGraph,
+    locations: NodeLocations,
+    osm_node_ids: Vec<i64>,
+    calculator: PathCalculator,
+}
+
+impl OsmRouter {
+    /// Reads the OSM XML file with the given name, see `from_reader`.
+    pub fn from_file(filename: &str, profile: &CarProfile) -> Result<Self, String> {
+        Ok(OsmRouter::from_graph(OsmGraph::from_file(
+            filename, profile,
+        )?))
+    }
+
+    /// Reads OSM XML data and prepares the road network it contains for routing.
+    pub fn from_reader<R: BufRead>(reader: R, profile: &CarProfile) -> Result<Self, String> {
+        Ok(OsmRouter::from_graph(OsmGraph::from_reader(
+            reader, profile,
+        )?))
+    }
+
+    /// Reads the OSM PBF file with the given name and prepares the road network it contains for
+    /// routing, see `OsmGraph::from_pbf_reader`.
+    pub fn from_pbf_file(filename: &str, profile: &CarProfile) -> Result<Self, String> {
+        Ok(OsmRouter::from_graph(OsmGraph::from_pbf_file(
+            filename, profile,
+        )?))
+    }
+
+    /// Prepares the given road network for routing.
+    pub fn from_graph(graph: OsmGraph) -> Self {
+        let fast_graph = crate::prepare(&graph.input_graph);
+        let calculator = PathCalculator::new(fast_graph.get_num_nodes());
+        OsmRouter {
+            fast_graph,
+            locations: NodeLocations::new(graph.coordinates),
+            osm_node_ids: graph.osm_node_ids,
+            calculator,
+        }
+    }
+
+    pub fn get_fast_graph(&self) -> &FastGraph {
+        &self.fast_graph
+    }
+
+    pub fn get_node_locations(&self) -> &NodeLocations {
+        &self.locations
+    }
+
+    /// Returns the id of the OSM node that corresponds to the given node of the graph.
+    pub fn get_osm_node_id(&self, node: NodeId) -> i64 {
+        self.osm_node_ids[node]
+    }
+
+    /// Calculates the fastest route between the nodes closest to the given (latitude, longitude)
+    /// pairs, or `None` if there is no such route.
+    pub fn calc_route(&mut self, from: (f64, f64), to: (f64, f64)) -> Option<OsmRoute> {
+        let source = self.locations.snap(from.0, from.1)?;
+        let target = self.locations.snap(to.0, to.1)?;
+        let path = self
+            .calculator
+            .calc_path(&self.fast_graph, source, target)?;
+        let coordinates: Vec<(f64, f64)> = path
+            .get_nodes()
+            .iter()
+            .map(|node| self.locations.get_coordinates(*node))
+            .collect();
+        let distance = coordinates
+            .windows(2)
+            .map(|pair| calc_distance(pair[0], pair[1]))
+            .sum();
+        Some(OsmRoute {
+            path,
+            coordinates,
+            distance,
+        })
+    }
+}
+
+/// A route calculated by `OsmRouter::calc_route`.
+#[derive(Debug, Clone)]
+pub struct OsmRoute {
+    path: ShortestPath,
+    coordinates: Vec<(f64, f64)>,
+    distance: f64,
+}
+
+impl OsmRoute {
+    /// Returns the path between the snapped nodes, whose weight is the travel time in milliseconds.
+    pub fn get_path(&self) -> &ShortestPath {
+        &self.path
+    }
+
+    /// Returns the (latitude, longitude) pairs of the nodes of the route.
+    pub fn get_coordinates(&self) -> &[(f64, f64)] {
+        &self.coordinates
+    }
+
+    /// Returns the length of the route in meters.
+    pub fn get_distance(&self) -> f64 {
+        self.distance
+    }
+}
+
+/// The nodes (with their coordinates) and ways of an OSM extract
+pub(crate) struct OsmData {
+    pub(crate) nodes: HashMap<i64, (f64, f64)>,
+    pub(crate) ways: Vec<Way>,
+}
+
+#[derive(Default)]
+pub(crate) struct Way {
+    pub(crate) nodes: Vec<i64>,
+    pub(crate) tags: HashMap<String, String>,
+}
+
+impl Way {
+    fn get_speed(&self, profile: &CarProfile) -> Option<f64> {
+        let tag = |key: &str| self.tags.get(key).map(|v| v.as_str());
+        if ["access", "motor_vehicle", "motorcar"]
+            .iter()
+            .any(|key| matches!(tag(key), Some("no") | Some("private")))
+        {
+            return None;
+        }
+        let speed = profile.get_speed(tag("highway")?)?;
+        match tag("maxspeed").and_then(|maxspeed| maxspeed.parse::<f64>().ok()) {
+            Some(maxspeed) if maxspeed > 0.0 && maxspeed < speed => Some(maxspeed),
+            _ => Some(speed),
+        }
+    }
+
+    /// Returns whether the way can be used in its own direction and in the opposite direction.
+    fn get_directions(&self) -> (bool, bool) {
+        let tag = |key: &str| self.tags.get(key).map(|v| v.as_str());
+        match tag("oneway") {
+            Some("yes") | Some("1") | Some("true") => (true, false),
+            Some("-1") | Some("reverse") => (false, true),
+            Some("no") => (true, true),
+            _ => {
+                let implied = matches!(tag("highway"), Some("motorway"))
+                    || matches!(tag("junction"), Some("roundabout"));
+                (true, !implied)
+            }
+        }
+    }
+}
+
+impl OsmData {
+    fn read<R: BufRead>(reader: R) -> Result<Self, String> {
+        let mut reader = Reader::from_reader(reader);
+        let mut buf = vec![];
+        let mut data = OsmData {
+            nodes: HashMap::new(),
+            ways: vec![],
+        };
+        let mut way: Option<Way> = None;
+        loop {
+            let event = reader
+                .read_event_into(&mut buf)
+                .map_err(|e| format!("Invalid OSM data: {}", e))?;
+            match event {
+                // a way without nodes
+                Event::Empty(element) if element.name().as_ref() == b"way" => {}
+                Event::Start(element) | Event::Empty(element) => match element.name().as_ref() {
+                    b"node" => {
+                        let id = parse_attribute(&element, b"id")?;
+                        let lat = parse_attribute(&element, b"lat")?;
+                        let lon = parse_attribute(&element, b"lon")?;
+                        data.nodes.insert(id, (lat, lon));
+                    }
+                    b"way" => way = Some(Way::default()),
+                    b"nd" => {
+                        if let Some(way) = way.as_mut() {
+                            way.nodes.push(parse_attribute(&element, b"ref")?);
+                        }
+                    }
+                    b"tag" => {
+                        if let Some(way) = way.as_mut() {
+                            way.tags.insert(
+                                get_attribute(&element, b"k")?,
+                                get_attribute(&element, b"v")?,
+                            );
+                        }
+                    }
+                    _ => {}
+                },
+                Event::End(element) if element.name().as_ref() == b"way" => {
+                    data.ways.extend(way.take());
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+        Ok(data)
+    }
+}
+
+fn get_attribute(element: &BytesStart, key: &[u8]) -> Result<String, String> {
+    let name = String::from_utf8_lossy(element.name().as_ref()).to_string();
+    let attribute = element
+        .try_get_attribute(key)
+        .map_err(|e| format!("Invalid OSM data: {}", e))?
+        .ok_or_else(|| {
+            format!(
+                "Invalid OSM data: missing attribute {} of {}",
+                String::from_utf8_lossy(key),
+                name
+            )
+        })?;
+    let value = attribute
+        .unescape_value()
+        .map_err(|e| format!("Invalid OSM data: {}", e))?;
+    Ok(value.to_string())
+}
+
+fn parse_attribute<T: std::str::FromStr>(element: &BytesStart, key: &[u8]) -> Result<T, String> {
+    let value = get_attribute(element, key)?;
+    value.parse().map_err(|_| {
+        format!(
+            "Invalid OSM data: could not parse attribute {}: {}",
+            String::from_utf8_lossy(key),
+            value
+        )
+    })
+}
+
+/// Returns the great-circle distance in meters between two (latitude, longitude) pairs.
+fn calc_distance(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lat1, lon1) = (from.0.to_radians(), from.1.to_radians());
+    let (lat2, lon2) = (to.0.to_radians(), to.1.to_radians());
+    let a = ((lat2 - lat1) / 2.0).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_METERS * a.sqrt().asin()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 1 --- 2 --- 3      a residential street from 1 to 3, a footway from 3 to 4,
+    //       |     |      a one-way primary road from 2 via 5 to 3, and a private
+    //       5 --- 4      service road from 5 to 4
+    const OSM: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<osm version="0.6">
+  <node id="1" lat="52.5000" lon="13.4000"/>
+  <node id="2" lat="52.5000" lon="13.4010"/>
+  <node id="3" lat="52.5000" lon="13.4020"/>
+  <node id="4" lat="52.4990" lon="13.4020"/>
+  <node id="5" lat="52.4990" lon="13.4010"/>
+  <way id="10">
+    <nd ref="1"/>
+    <nd ref="2"/>
+    <nd ref="3"/>
+    <tag k="highway" v="residential"/>
+    <tag k="name" v="Main &amp; Co Street"/>
+  </way>
+  <way id="11">
+    <nd ref="3"/>
+    <nd ref="4"/>
+    <tag k="highway" v="footway"/>
+  </way>
+  <way id="12">
+    <nd ref="2"/>
+    <nd ref="5"/>
+    <nd ref="3"/>
+    <nd ref="99"/>
+    <tag k="highway" v="primary"/>
+    <tag k="oneway" v="yes"/>
+  </way>
+  <way id="13">
+    <nd ref="5"/>
+    <nd ref="4"/>
+    <tag k="highway" v="service"/>
+    <tag k="access" v="private"/>
+  </way>
+</osm>"#;
+
+    #[test]
+    fn route_between_coordinates() {
+        let mut router = OsmRouter::from_reader(OSM.as_bytes(), &CarProfile::default()).unwrap();
+        // node 4 is not reachable by car, and node 99 is missing
+        assert_eq!(4, router.get_fast_graph().get_num_nodes());
+        assert_eq!(
+            Some(WeightUnit::milliseconds()),
+            router.get_fast_graph().get_weight_unit()
+        );
+
+        let route = router
+            .calc_route((52.5001, 13.3999), (52.5001, 13.4021))
+            .unwrap();
+        let osm_ids: Vec<i64> = route
+            .get_path()
+            .get_nodes()
+            .iter()
+            .map(|node| router.get_osm_node_id(*node))
+            .collect();
+        assert_eq!(vec![1, 2, 3], osm_ids);
+        assert_eq!(
+            &[(52.5, 13.4), (52.5, 13.401), (52.5, 13.402)],
+            route.get_coordinates()
+        );
+        // 0.002 degrees of longitude at this latitude are about 136m, which takes about 16s at
+        // 30km/h
+        assert!(
+            (route.get_distance() - 135.8).abs() < 0.5,
+            "{}",
+            route.get_distance()
+        );
+        assert!((route.get_path().get_weight() as i64 - 16_295).abs() < 100);
+
+        // the primary road is faster, but it is a one-way road
+        let route = router
+            .calc_route((52.5001, 13.4021), (52.4989, 13.4011))
+            .unwrap();
+        let osm_ids: Vec<i64> = route
+            .get_path()
+            .get_nodes()
+            .iter()
+            .map(|node| router.get_osm_node_id(*node))
+            .collect();
+        assert_eq!(vec![3, 2, 5], osm_ids);
+    }
+
+    #[test]
+    fn invalid_data() {
+        let result = OsmRouter::from_reader(
+            r#"<osm><node id="1" lat="x" lon="13.4"/></osm>"#.as_bytes(),
+            &CarProfile::default(),
+        );
+        assert!(result.err().unwrap().contains("lat"));
+        let result = OsmRouter::from_reader(
+            r#"<osm><node id="1" lon="13.4"/></osm>"#.as_bytes(),
+            &CarProfile::default(),
+        );
+        assert!(result
+            .err()
+            .unwrap()
+            .contains("missing attribute lat of node"));
+    }
+}
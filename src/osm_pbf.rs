@@ -0,0 +1,574 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+//! Reads the OpenStreetMap PBF format, see https://wiki.openstreetmap.org/wiki/PBF_Format. Only
+//! the parts needed for routing are decoded, i.e. the coordinates of the nodes, and the nodes and
+//! tags of the ways. The protocol buffer messages are simple enough to decode them by hand.
+
+use std::collections::HashMap;
+use std::io::{ErrorKind, Read};
+
+use miniz_oxide::inflate::decompress_to_vec_zlib_with_limit;
+
+use crate::osm::{OsmData, Way};
+
+/// The maximum sizes of the headers and blobs allowed by the specification
+const MAX_BLOB_HEADER_SIZE: usize = 64 * 1024;
+const MAX_BLOB_SIZE: usize = 32 * 1024 * 1024;
+const SUPPORTED_FEATURES: [&str; 2] = ["OsmSchema-V0.6", "DenseNodes"];
+
+pub(crate) fn read<R: Read>(mut reader: R) -> Result<OsmData, String> {
+    let mut data = OsmData {
+        nodes: HashMap::new(),
+        ways: vec![],
+    };
+    let mut found_header = false;
+    while let Some(header_size) = read_size(&mut reader)? {
+        if header_size > MAX_BLOB_HEADER_SIZE {
+            return Err(invalid(format!("blob header too large: {}", header_size)));
+        }
+        let (blob_type, blob_size) = read_blob_header(&read_bytes(&mut reader, header_size)?)?;
+        if blob_size > MAX_BLOB_SIZE {
+            return Err(invalid(format!("blob too large: {}", blob_size)));
+        }
+        let block = read_blob(&read_bytes(&mut reader, blob_size)?)?;
+        match blob_type.as_str() {
+            "OSMHeader" => {
+                check_header_block(&block)?;
+                found_header = true;
+            }
+            "OSMData" if !found_header => {
+                return Err(invalid("the first block must be the header"));
+            }
+            "OSMData" => read_primitive_block(&block, &mut data)?,
+            // unknown blob types are to be skipped according to the specification
+            _ => {}
+        }
+    }
+    Ok(data)
+}
+
+fn invalid<S: AsRef<str>>(message: S) -> String {
+    format!("Invalid OSM PBF data: {}", message.as_ref())
+}
+
+/// Reads the size of the next blob header, or returns `None` at the end of the file.
+fn read_size<R: Read>(reader: &mut R) -> Result<Option<usize>, String> {
+    let mut bytes = [0; 4];
+    let mut num_read = 0;
+    while num_read < bytes.len() {
+        match reader.read(&mut bytes[num_read..]) {
+            Ok(0) if num_read == 0 => return Ok(None),
+            Ok(0) => return Err(invalid("unexpected end of file")),
+            Ok(n) => num_read += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+    Ok(Some(u32::from_be_bytes(bytes) as usize))
+}
+
+fn read_bytes<R: Read>(reader: &mut R, size: usize) -> Result<Vec<u8>, String> {
+    let mut bytes = vec![0; size];
+    reader.read_exact(&mut bytes).map_err(|e| match e.kind() {
+        ErrorKind::UnexpectedEof => invalid("unexpected end of file"),
+        _ => e.to_string(),
+    })?;
+    Ok(bytes)
+}
+
+/// Returns the type and the size of the blob described by the given `BlobHeader`.
+fn read_blob_header(bytes: &[u8]) -> Result<(String, usize), String> {
+    let mut blob_type = None;
+    let mut blob_size = None;
+    for_each_field(bytes, |field, value| {
+        match (field, value) {
+            (1, Value::Bytes(b)) => blob_type = Some(String::from_utf8_lossy(b).to_string()),
+            (3, Value::Varint(v)) => blob_size = Some(v as usize),
+            _ => {}
+        }
+        Ok(())
+    })?;
+    match (blob_type, blob_size) {
+        (Some(blob_type), Some(blob_size)) => Ok((blob_type, blob_size)),
+        _ => Err(invalid("incomplete blob header")),
+    }
+}
+
+/// Returns the uncompressed content of the given `Blob`.
+fn read_blob(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mut raw = None;
+    let mut raw_size = MAX_BLOB_SIZE;
+    let mut zlib_data = None;
+    let mut compression = None;
+    for_each_field(bytes, |field, value| {
+        match (field, value) {
+            (1, Value::Bytes(b)) => raw = Some(b),
+            (2, Value::Varint(v)) => raw_size = v as usize,
+            (3, Value::Bytes(b)) => zlib_data = Some(b),
+            (4, _) => compression = Some("lzma"),
+            (5, _) => compression = Some("bzip2"),
+            (6, _) => compression = Some("lz4"),
+            (7, _) => compression = Some("zstd"),
+            _ => {}
+        }
+        Ok(())
+    })?;
+    match (raw, zlib_data, compression) {
+        (Some(raw), _, _) => Ok(raw.to_vec()),
+        (_, Some(zlib_data), _) => decompress_to_vec_zlib_with_limit(zlib_data, raw_size)
+            .map_err(|e| invalid(format!("could not decompress blob: {:?}", e.status))),
+        (_, _, Some(compression)) => Err(invalid(format!(
+            "{} compression is not supported, only zlib",
+            compression
+        ))),
+        _ => Err(invalid("empty blob")),
+    }
+}
+
+/// Makes sure the `HeaderBlock` does not require any features we do not support.
+fn check_header_block(bytes: &[u8]) -> Result<(), String> {
+    for_each_field(bytes, |field, value| match (field, value) {
+        (4, Value::Bytes(feature)) => {
+            let feature = String::from_utf8_lossy(feature);
+            if SUPPORTED_FEATURES.contains(&feature.as_ref()) {
+                Ok(())
+            } else {
+                Err(invalid(format!(
+                    "unsupported required feature: {}",
+                    feature
+                )))
+            }
+        }
+        _ => Ok(()),
+    })
+}
+
+/// Adds the nodes and ways of the given `PrimitiveBlock` to `data`.
+fn read_primitive_block(bytes: &[u8], data: &mut OsmData) -> Result<(), String> {
+    let mut strings = vec![];
+    let mut groups = vec![];
+    let mut granularity = 100;
+    let mut lat_offset = 0;
+    let mut lon_offset = 0;
+    for_each_field(bytes, |field, value| {
+        match (field, value) {
+            (1, Value::Bytes(table)) => for_each_field(table, |field, value| {
+                if let (1, Value::Bytes(s)) = (field, value) {
+                    strings.push(String::from_utf8_lossy(s).to_string());
+                }
+                Ok(())
+            })?,
+            (2, Value::Bytes(group)) => groups.push(group),
+            (17, Value::Varint(v)) => granularity = v as i64,
+            (19, Value::Varint(v)) => lat_offset = v as i64,
+            (20, Value::Varint(v)) => lon_offset = v as i64,
+            _ => {}
+        }
+        Ok(())
+    })?;
+    let to_degrees = |value: i64, offset: i64| 1e-9 * (offset + granularity * value) as f64;
+    let to_coordinates =
+        |lat: i64, lon: i64| (to_degrees(lat, lat_offset), to_degrees(lon, lon_offset));
+    let get_string = |index: u64| {
+        strings
+            .get(index as usize)
+            .ok_or_else(|| invalid(format!("invalid string index: {}", index)))
+    };
+    for group in groups {
+        for_each_field(group, |field, value| {
+            match (field, value) {
+                (1, Value::Bytes(node)) => {
+                    let (mut id, mut lat, mut lon) = (0, 0, 0);
+                    for_each_field(node, |field, value| {
+                        match (field, value) {
+                            (1, Value::Varint(v)) => id = zigzag(v),
+                            (8, Value::Varint(v)) => lat = zigzag(v),
+                            (9, Value::Varint(v)) => lon = zigzag(v),
+                            _ => {}
+                        }
+                        Ok(())
+                    })?;
+                    data.nodes.insert(id, to_coordinates(lat, lon));
+                }
+                (2, Value::Bytes(dense)) => {
+                    let (mut ids, mut lats, mut lons) = (vec![], vec![], vec![]);
+                    for_each_field(dense, |field, value| {
+                        match field {
+                            1 => read_packed(value, &mut ids)?,
+                            8 => read_packed(value, &mut lats)?,
+                            9 => read_packed(value, &mut lons)?,
+                            _ => {}
+                        }
+                        Ok(())
+                    })?;
+                    if ids.len() != lats.len() || ids.len() != lons.len() {
+                        return Err(invalid("dense nodes with missing coordinates"));
+                    }
+                    // all values are delta encoded
+                    let (mut id, mut lat, mut lon) = (0, 0, 0);
+                    for i in 0..ids.len() {
+                        id += zigzag(ids[i]);
+                        lat += zigzag(lats[i]);
+                        lon += zigzag(lons[i]);
+                        data.nodes.insert(id, to_coordinates(lat, lon));
+                    }
+                }
+                (3, Value::Bytes(way)) => {
+                    let (mut keys, mut values, mut refs) = (vec![], vec![], vec![]);
+                    for_each_field(way, |field, value| {
+                        match field {
+                            2 => read_packed(value, &mut keys)?,
+                            3 => read_packed(value, &mut values)?,
+                            8 => read_packed(value, &mut refs)?,
+                            _ => {}
+                        }
+                        Ok(())
+                    })?;
+                    if keys.len() != values.len() {
+                        return Err(invalid("way with a different number of keys and values"));
+                    }
+                    let mut result = Way::default();
+                    let mut node = 0;
+                    for delta in refs {
+                        node += zigzag(delta);
+                        result.nodes.push(node);
+                    }
+                    for (key, value) in keys.iter().zip(values.iter()) {
+                        result
+                            .tags
+                            .insert(get_string(*key)?.clone(), get_string(*value)?.clone());
+                    }
+                    data.ways.push(result);
+                }
+                _ => {}
+            }
+            Ok(())
+        })?;
+    }
+    Ok(())
+}
+
+/// The value of a protocol buffer field. Fixed size values are not used by the PBF format, so
+/// their content is ignored.
+enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+/// Calls `f` with the number and the value of every field of the given message.
+fn for_each_field<'a, F>(mut bytes: &'a [u8], mut f: F) -> Result<(), String>
+where
+    F: FnMut(u32, Value<'a>) -> Result<(), String>,
+{
+    while !bytes.is_empty() {
+        let key = read_varint(&mut bytes)?;
+        let value = match key & 7 {
+            0 => Value::Varint(read_varint(&mut bytes)?),
+            1 => skip(&mut bytes, 8)?,
+            2 => {
+                let len = read_varint(&mut bytes)? as usize;
+                if len > bytes.len() {
+                    return Err(invalid("truncated message"));
+                }
+                let (value, rest) = bytes.split_at(len);
+                bytes = rest;
+                Value::Bytes(value)
+            }
+            5 => skip(&mut bytes, 4)?,
+            wire_type => return Err(invalid(format!("unsupported wire type: {}", wire_type))),
+        };
+        f((key >> 3) as u32, value)?;
+    }
+    Ok(())
+}
+
+fn skip<'a>(bytes: &mut &'a [u8], len: usize) -> Result<Value<'a>, String> {
+    if len > bytes.len() {
+        return Err(invalid("truncated message"));
+    }
+    *bytes = &bytes[len..];
+    Ok(Value::Fixed)
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Result<u64, String> {
+    let mut result = 0;
+    for shift in (0..64).step_by(7) {
+        let (byte, rest) = bytes
+            .split_first()
+            .ok_or_else(|| invalid("truncated varint"))?;
+        *bytes = rest;
+        result |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(result);
+        }
+    }
+    Err(invalid("varint too long"))
+}
+
+/// Adds the values of a repeated varint field, which are usually packed into a single field.
+fn read_packed(value: Value, values: &mut Vec<u64>) -> Result<(), String> {
+    match value {
+        Value::Varint(v) => values.push(v),
+        Value::Bytes(mut bytes) => {
+            while !bytes.is_empty() {
+                values.push(read_varint(&mut bytes)?);
+            }
+        }
+        Value::Fixed => return Err(invalid("unexpected fixed size value")),
+    }
+    Ok(())
+}
+
+/// Decodes the signed integers of the `sint64` fields
+fn zigzag(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use miniz_oxide::deflate::compress_to_vec_zlib;
+
+    use crate::osm::{CarProfile, OsmGraph, OsmRouter};
+
+    use super::*;
+
+    fn varint(mut value: u64, out: &mut Vec<u8>) {
+        while value >= 0x80 {
+            out.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    fn varint_field(field: u64, value: u64, out: &mut Vec<u8>) {
+        varint(field << 3, out);
+        varint(value, out);
+    }
+
+    fn bytes_field(field: u64, bytes: &[u8], out: &mut Vec<u8>) {
+        varint((field << 3) | 2, out);
+        varint(bytes.len() as u64, out);
+        out.extend_from_slice(bytes);
+    }
+
+    fn packed(values: &[u64]) -> Vec<u8> {
+        let mut out = vec![];
+        for value in values {
+            varint(*value, &mut out);
+        }
+        out
+    }
+
+    fn sint(value: i64) -> u64 {
+        ((value << 1) ^ (value >> 63)) as u64
+    }
+
+    /// Encodes the given values as `sint64` deltas, like the dense nodes and the node references
+    /// of the ways
+    fn deltas(values: &[i64]) -> Vec<u64> {
+        let mut prev = 0;
+        values
+            .iter()
+            .map(|v| {
+                let delta = sint(v - prev);
+                prev = *v;
+                delta
+            })
+            .collect()
+    }
+
+    fn blob(blob_type: &str, block: &[u8], compress: bool) -> Vec<u8> {
+        let mut blob = vec![];
+        if compress {
+            varint_field(2, block.len() as u64, &mut blob);
+            bytes_field(3, &compress_to_vec_zlib(block, 6), &mut blob);
+        } else {
+            bytes_field(1, block, &mut blob);
+        }
+        let mut header = vec![];
+        bytes_field(1, blob_type.as_bytes(), &mut header);
+        varint_field(3, blob.len() as u64, &mut header);
+        let mut out = (header.len() as u32).to_be_bytes().to_vec();
+        out.extend(header);
+        out.extend(blob);
+        out
+    }
+
+    fn header_block(features: &[&str]) -> Vec<u8> {
+        let mut out = vec![];
+        for feature in features {
+            bytes_field(4, feature.as_bytes(), &mut out);
+        }
+        out
+    }
+
+    /// The same network as in the tests of `osm`:
+    ///
+    /// 1 --- 2 --- 3      a residential street from 1 to 3, a footway from 3 to 4,
+    ///       |     |      a one-way primary road from 2 via 5 to 3, and a private
+    ///       5 --- 4      service road from 5 to 4
+    ///
+    /// Nodes 1 to 3 are stored as dense nodes, 4 and 5 as plain nodes.
+    fn data_block() -> Vec<u8> {
+        let strings = [
+            "",
+            "highway",
+            "residential",
+            "footway",
+            "primary",
+            "oneway",
+            "yes",
+            "service",
+            "access",
+            "private",
+        ];
+        let mut table = vec![];
+        for s in strings {
+            bytes_field(1, s.as_bytes(), &mut table);
+        }
+        // with a granularity of 100 nanodegrees
+        let coordinate = |degrees: f64| (degrees * 1e7).round() as i64;
+
+        let mut group = vec![];
+        let mut dense = vec![];
+        bytes_field(1, &packed(&deltas(&[1, 2, 3])), &mut dense);
+        let lats = [52.5, 52.5, 52.5].map(coordinate);
+        bytes_field(8, &packed(&deltas(&lats)), &mut dense);
+        let lons = [13.4, 13.401, 13.402].map(coordinate);
+        bytes_field(9, &packed(&deltas(&lons)), &mut dense);
+        bytes_field(2, &dense, &mut group);
+        for (id, lat, lon) in [(4, 52.499, 13.402), (5, 52.499, 13.401)] {
+            let mut node = vec![];
+            varint_field(1, sint(id), &mut node);
+            varint_field(8, sint(coordinate(lat)), &mut node);
+            varint_field(9, sint(coordinate(lon)), &mut node);
+            bytes_field(1, &node, &mut group);
+        }
+        let ways = [
+            (10, vec![1, 2, 3], vec![(1, 2)]),
+            (11, vec![3, 4], vec![(1, 3)]),
+            (12, vec![2, 5, 3, 99], vec![(1, 4), (5, 6)]),
+            (13, vec![5, 4], vec![(1, 7), (8, 9)]),
+        ];
+        for (id, refs, tags) in ways {
+            let mut way = vec![];
+            varint_field(1, id, &mut way);
+            let keys: Vec<u64> = tags.iter().map(|(k, _)| *k).collect();
+            let values: Vec<u64> = tags.iter().map(|(_, v)| *v).collect();
+            bytes_field(2, &packed(&keys), &mut way);
+            bytes_field(3, &packed(&values), &mut way);
+            bytes_field(8, &packed(&deltas(&refs)), &mut way);
+            bytes_field(3, &way, &mut group);
+        }
+
+        let mut block = vec![];
+        bytes_field(1, &table, &mut block);
+        bytes_field(2, &group, &mut block);
+        varint_field(17, 100, &mut block);
+        block
+    }
+
+    fn pbf(compress: bool) -> Vec<u8> {
+        let mut out = blob(
+            "OSMHeader",
+            &header_block(&["OsmSchema-V0.6", "DenseNodes"]),
+            compress,
+        );
+        out.extend(blob("OSMData", &data_block(), compress));
+        out
+    }
+
+    #[test]
+    fn read_pbf() {
+        for compress in [false, true] {
+            let data = read(pbf(compress).as_slice()).unwrap();
+            assert_eq!(5, data.nodes.len());
+            let (lat, lon) = data.nodes[&4];
+            assert!((lat - 52.499).abs() < 1e-9 && (lon - 13.402).abs() < 1e-9);
+            assert_eq!(4, data.ways.len());
+            assert_eq!(vec![2, 5, 3, 99], data.ways[2].nodes);
+            assert_eq!(
+                Some("yes"),
+                data.ways[2].tags.get("oneway").map(|s| s.as_str())
+            );
+        }
+    }
+
+    #[test]
+    fn route_on_pbf() {
+        let graph =
+            OsmGraph::from_pbf_reader(pbf(true).as_slice(), &CarProfile::default()).unwrap();
+        // node 4 is not reachable by car, and node 99 is missing
+        assert_eq!(4, graph.get_input_graph().get_num_nodes());
+        assert_eq!(4, graph.get_coordinates().len());
+        let mut router = OsmRouter::from_graph(graph);
+        let route = router
+            .calc_route((52.5001, 13.4021), (52.4989, 13.4011))
+            .unwrap();
+        let osm_ids: Vec<i64> = route
+            .get_path()
+            .get_nodes()
+            .iter()
+            .map(|node| router.get_osm_node_id(*node))
+            .collect();
+        assert_eq!(vec![3, 2, 5], osm_ids);
+    }
+
+    #[test]
+    fn invalid_pbf() {
+        let error = |bytes: Vec<u8>| read(bytes.as_slice()).err().unwrap();
+        let data = pbf(false);
+        assert!(error(data[..data.len() - 1].to_vec()).contains("unexpected end of file"));
+        assert!(error(data[..2].to_vec()).contains("unexpected end of file"));
+        assert!(error(blob("OSMData", &data_block(), false)).contains("header"));
+        let mut data = blob(
+            "OSMHeader",
+            &header_block(&["HistoricalInformation"]),
+            false,
+        );
+        assert!(error(data.clone()).contains("unsupported required feature"));
+        data = blob("OSMHeader", &header_block(&[]), false);
+        let mut lz4 = vec![];
+        bytes_field(6, &[1, 2, 3], &mut lz4);
+        let mut header = vec![];
+        bytes_field(1, b"OSMData", &mut header);
+        varint_field(3, lz4.len() as u64, &mut header);
+        data.extend((header.len() as u32).to_be_bytes());
+        data.extend(header);
+        data.extend(lz4);
+        assert!(error(data).contains("lz4 compression is not supported"));
+        // unknown blob types are skipped
+        let mut data = pbf(false);
+        data.extend(blob("Other", &[1, 2, 3], false));
+        assert!(read(data.as_slice()).is_ok());
+    }
+
+    #[test]
+    fn zigzag_and_varints() {
+        for value in [0, 1, -1, 150, -150, i64::MAX, i64::MIN] {
+            let mut bytes = vec![];
+            varint(sint(value), &mut bytes);
+            let mut slice = bytes.as_slice();
+            assert_eq!(value, zigzag(read_varint(&mut slice).unwrap()));
+            assert!(slice.is_empty());
+        }
+        assert!(read_varint(&mut [0x80].as_slice()).is_err());
+    }
+}
@@ -0,0 +1,564 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{NodeId, Weight, INVALID_NODE, WEIGHT_MAX};
+use crate::error::OverlayError;
+use crate::heap_item::HeapItem;
+use crate::input_graph::InputGraph;
+use crate::shortest_path::ShortestPath;
+
+/// An alternative to the contraction hierarchy for graphs whose weights change frequently, e.g.
+/// because of live traffic (customizable route planning). The nodes are divided into cells on
+/// several levels, where every cell of a level lies within a single cell of the next level. This
+/// partition only depends on the topology of the graph, so it has to be calculated only once. For
+/// every cell the overlay stores the weights between the nodes at its boundary, and calculating
+/// these weights for new edge weights (`customize`) is much faster than preparing the graph from
+/// scratch.
+///
+/// Queries are slower than those of a `FastGraph`, because they run Dijkstra's algorithm on the
+/// overlay of the cells that contain neither the source nor the target.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OverlayGraph {
+    num_nodes: usize,
+    first_out: Vec<usize>,
+    heads: Vec<NodeId>,
+    weights: Vec<Weight>,
+    levels: Vec<Level>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Level {
+    /// the cell of every node, the cell ids are `0..num_cells`
+    cells: Vec<usize>,
+    /// the boundary nodes of cell `c` are `boundary_nodes[first_boundary[c]..first_boundary[c + 1]]`
+    first_boundary: Vec<usize>,
+    boundary_nodes: Vec<NodeId>,
+    /// the position of every node among the boundary nodes of its cell, or `usize::MAX`
+    boundary_index: Vec<usize>,
+    /// the weights between the `b` boundary nodes of cell `c` are stored row by row in
+    /// `clique_weights[first_weight[c]..first_weight[c] + b * b]`
+    first_weight: Vec<usize>,
+    clique_weights: Vec<Weight>,
+}
+
+#[derive(Clone, Copy)]
+struct Label {
+    weight: Weight,
+    parent: NodeId,
+    /// the level of the arc from the parent to this node, zero for original edges
+    arc_level: usize,
+}
+
+impl OverlayGraph {
+    /// Builds the overlay for the given graph, where `cells[level][node]` is the cell of every
+    /// node on each level, e.g. obtained using `partition`. The cells of a level have to be
+    /// nested in those of the next level. The overlay is customized using the weights of the
+    /// given graph.
+    pub fn new(input_graph: &InputGraph, cells: &[Vec<usize>]) -> Result<Self, OverlayError> {
+        let num_nodes = input_graph.get_num_nodes();
+        for (level, level_cells) in cells.iter().enumerate() {
+            if level_cells.len() != num_nodes {
+                return Err(OverlayError::CellCountMismatch {
+                    level,
+                    num_cells: level_cells.len(),
+                    num_nodes,
+                });
+            }
+        }
+        for level in 1..cells.len() {
+            let mut parents = HashMap::new();
+            for (node, (cell, next_cell)) in cells[level - 1].iter().zip(&cells[level]).enumerate()
+            {
+                if *parents.entry(cell).or_insert(next_cell) != next_cell {
+                    return Err(OverlayError::CellsNotNested {
+                        level: level - 1,
+                        node,
+                    });
+                }
+            }
+        }
+        let edges = input_graph.get_edges();
+        let mut first_out = vec![0; num_nodes + 1];
+        for edge in edges {
+            first_out[edge.from + 1] += 1;
+        }
+        for node in 0..num_nodes {
+            first_out[node + 1] += first_out[node];
+        }
+        let mut overlay_graph = OverlayGraph {
+            num_nodes,
+            first_out,
+            heads: edges.iter().map(|edge| edge.to).collect(),
+            weights: vec![WEIGHT_MAX; edges.len()],
+            levels: cells
+                .iter()
+                .map(|level_cells| Level::new(input_graph, level_cells))
+                .collect(),
+        };
+        let weights: Vec<Weight> = edges.iter().map(|edge| edge.weight).collect();
+        overlay_graph.customize(&weights)?;
+        Ok(overlay_graph)
+    }
+
+    /// Divides the nodes of the given graph into cells of at most `max_cell_sizes[level]` nodes
+    /// on every level, such that the cells of a level are nested in those of the next level. The
+    /// cells are grown from one node to its neighbors, so they are connected, but the number of
+    /// boundary nodes can be much larger than for a dedicated graph partitioner.
+    pub fn partition(input_graph: &InputGraph, max_cell_sizes: &[usize]) -> Vec<Vec<usize>> {
+        assert!(
+            max_cell_sizes.windows(2).all(|w| w[0] < w[1])
+                && max_cell_sizes.first().map_or(true, |size| *size > 0),
+            "the maximum cell sizes have to be positive and increasing, but were: {:?}",
+            max_cell_sizes
+        );
+        let num_nodes = input_graph.get_num_nodes();
+        // on every level we merge the cells of the previous level, starting with single nodes
+        let mut units: Vec<usize> = (0..num_nodes).collect();
+        let mut unit_sizes = vec![1; num_nodes];
+        let mut cells = Vec::with_capacity(max_cell_sizes.len());
+        for max_cell_size in max_cell_sizes {
+            let mut neighbors = vec![vec![]; unit_sizes.len()];
+            for edge in input_graph.get_edges() {
+                let (from, to) = (units[edge.from], units[edge.to]);
+                if from != to {
+                    neighbors[from].push(to);
+                    neighbors[to].push(from);
+                }
+            }
+            let groups = grow_groups(&neighbors, &unit_sizes, *max_cell_size);
+            let num_groups = groups.iter().max().map_or(0, |g| g + 1);
+            let mut group_sizes = vec![0; num_groups];
+            for (unit, group) in groups.iter().enumerate() {
+                group_sizes[*group] += unit_sizes[unit];
+            }
+            units = units.iter().map(|unit| groups[*unit]).collect();
+            unit_sizes = group_sizes;
+            cells.push(units.clone());
+        }
+        cells
+    }
+
+    /// Updates the overlay for new edge weights, where `weights[edge_id]` is the weight of every
+    /// edge of the graph the overlay was built for, see `InputGraph::get_edge_id`. Edges with
+    /// weight `WEIGHT_MAX` are not used at all.
+    pub fn customize(&mut self, weights: &[Weight]) -> Result<(), OverlayError> {
+        if weights.len() != self.weights.len() {
+            return Err(OverlayError::WeightCountMismatch {
+                num_weights: weights.len(),
+                num_edges: self.weights.len(),
+            });
+        }
+        self.weights.copy_from_slice(weights);
+        for level in 1..=self.levels.len() {
+            // the weights of a level are calculated using the overlay of the level below
+            let cells = &self.levels[level - 1];
+            let mut clique_weights = Vec::with_capacity(*cells.first_weight.last().unwrap());
+            for cell in 0..cells.first_boundary.len() - 1 {
+                let boundary_nodes = &cells.boundary_nodes
+                    [cells.first_boundary[cell]..cells.first_boundary[cell + 1]];
+                for source in boundary_nodes {
+                    let labels = self.search(*source, None, |node| {
+                        (cells.cells[node] == cell).then_some(level - 1)
+                    });
+                    clique_weights.extend(
+                        boundary_nodes
+                            .iter()
+                            .map(|target| labels.get(target).map_or(WEIGHT_MAX, |l| l.weight)),
+                    );
+                }
+            }
+            self.levels[level - 1].clique_weights = clique_weights;
+        }
+        Ok(())
+    }
+
+    /// Returns the current weights of the edges, see `customize`.
+    pub fn get_weights(&self) -> &[Weight] {
+        &self.weights
+    }
+
+    pub fn get_num_nodes(&self) -> usize {
+        self.num_nodes
+    }
+
+    pub fn get_num_levels(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Returns the number of boundary nodes on the given level, which determines the time needed
+    /// for queries and customization.
+    pub fn get_num_boundary_nodes(&self, level: usize) -> usize {
+        self.levels[level].boundary_nodes.len()
+    }
+
+    /// Calculates the shortest path from `source` to `target` using the current weights.
+    pub fn calc_path(&self, source: NodeId, target: NodeId) -> Option<ShortestPath> {
+        let labels = self.search(source, Some(target), |node| {
+            Some(self.get_query_level(source, target, node))
+        });
+        let weight = labels.get(&target)?.weight;
+        let mut nodes = vec![source];
+        for (from, to, arc_level) in trace_arcs(&labels, source, target) {
+            self.unpack(from, to, arc_level, &mut nodes);
+        }
+        Some(ShortestPath::new(source, target, weight, nodes))
+    }
+
+    /// Like `calc_path`, but only calculates the weight, so the arcs of the overlay are never
+    /// unpacked.
+    pub fn calc_weight(&self, source: NodeId, target: NodeId) -> Option<Weight> {
+        let labels = self.search(source, Some(target), |node| {
+            Some(self.get_query_level(source, target, node))
+        });
+        labels.get(&target).map(|label| label.weight)
+    }
+
+    /// Returns the highest level on which the given node lies in a different cell than both the
+    /// source and the target, or zero if there is none.
+    fn get_query_level(&self, source: NodeId, target: NodeId, node: NodeId) -> usize {
+        (1..=self.levels.len())
+            .rev()
+            .find(|level| {
+                let cells = &self.levels[level - 1].cells;
+                cells[node] != cells[source] && cells[node] != cells[target]
+            })
+            .unwrap_or(0)
+    }
+
+    /// Runs Dijkstra's algorithm starting from `source`, where `get_level` returns the level of
+    /// the arcs that are used at each node, or `None` for nodes that must not be visited. The
+    /// search stops once `target` is settled.
+    fn search<F: Fn(NodeId) -> Option<usize>>(
+        &self,
+        source: NodeId,
+        target: Option<NodeId>,
+        get_level: F,
+    ) -> HashMap<NodeId, Label> {
+        let mut labels = HashMap::new();
+        let mut heap = BinaryHeap::new();
+        labels.insert(
+            source,
+            Label {
+                weight: 0,
+                parent: INVALID_NODE,
+                arc_level: 0,
+            },
+        );
+        heap.push(HeapItem::new(0, source));
+        while let Some(curr) = heap.pop() {
+            if curr.weight > labels[&curr.node_id].weight {
+                continue;
+            }
+            if Some(curr.node_id) == target {
+                break;
+            }
+            let level = match get_level(curr.node_id) {
+                Some(level) => level,
+                None => continue,
+            };
+            self.for_each_arc(level, curr.node_id, |adj, weight, arc_level| {
+                if adj != source && get_level(adj).is_none() {
+                    return;
+                }
+                let weight = curr.weight + weight;
+                let label = Label {
+                    weight,
+                    parent: curr.node_id,
+                    arc_level,
+                };
+                match labels.entry(adj) {
+                    Entry::Occupied(mut e) => {
+                        if weight < e.get().weight {
+                            e.insert(label);
+                            heap.push(HeapItem::new(weight, adj));
+                        }
+                    }
+                    Entry::Vacant(e) => {
+                        e.insert(label);
+                        heap.push(HeapItem::new(weight, adj));
+                    }
+                }
+            });
+        }
+        labels
+    }
+
+    /// Calls `f` with the adjacent node, the weight and the level of all the arcs of the overlay
+    /// of the given level leaving `node`. On level zero these are the original edges. On the
+    /// other levels these are the arcs between the boundary nodes of the node's cell and the
+    /// original edges leaving the cell.
+    fn for_each_arc<F: FnMut(NodeId, Weight, usize)>(&self, level: usize, node: NodeId, mut f: F) {
+        for edge_id in self.first_out[node]..self.first_out[node + 1] {
+            let adj = self.heads[edge_id];
+            let weight = self.weights[edge_id];
+            if weight != WEIGHT_MAX
+                && (level == 0
+                    || self.levels[level - 1].cells[adj] != self.levels[level - 1].cells[node])
+            {
+                f(adj, weight, 0);
+            }
+        }
+        if level == 0 {
+            return;
+        }
+        let cells = &self.levels[level - 1];
+        let cell = cells.cells[node];
+        let index = cells.boundary_index[node];
+        debug_assert_ne!(index, usize::MAX, "only boundary nodes have overlay arcs");
+        let boundary_nodes =
+            &cells.boundary_nodes[cells.first_boundary[cell]..cells.first_boundary[cell + 1]];
+        let row = cells.first_weight[cell] + index * boundary_nodes.len();
+        for (i, adj) in boundary_nodes.iter().enumerate() {
+            let weight = cells.clique_weights[row + i];
+            if i != index && weight != WEIGHT_MAX {
+                f(*adj, weight, level);
+            }
+        }
+    }
+
+    /// Appends the nodes of the arc from `from` to `to` (excluding `from`) to `nodes`.
+    fn unpack(&self, from: NodeId, to: NodeId, arc_level: usize, nodes: &mut Vec<NodeId>) {
+        if arc_level == 0 {
+            nodes.push(to);
+            return;
+        }
+        let cells = &self.levels[arc_level - 1].cells;
+        let labels = self.search(from, Some(to), |node| {
+            (cells[node] == cells[from]).then_some(arc_level - 1)
+        });
+        for (from, to, arc_level) in trace_arcs(&labels, from, to) {
+            self.unpack(from, to, arc_level, nodes);
+        }
+    }
+}
+
+impl Level {
+    fn new(input_graph: &InputGraph, cells: &[usize]) -> Self {
+        // we use consecutive cell ids, no matter which ids were given
+        let mut ids = HashMap::new();
+        let cells: Vec<usize> = cells
+            .iter()
+            .map(|cell| {
+                let num_cells = ids.len();
+                *ids.entry(*cell).or_insert(num_cells)
+            })
+            .collect();
+        let num_cells = ids.len();
+        let mut is_boundary = vec![false; cells.len()];
+        for edge in input_graph.get_edges() {
+            if cells[edge.from] != cells[edge.to] {
+                is_boundary[edge.from] = true;
+                is_boundary[edge.to] = true;
+            }
+        }
+        let mut first_boundary = vec![0; num_cells + 1];
+        for node in (0..cells.len()).filter(|n| is_boundary[*n]) {
+            first_boundary[cells[node] + 1] += 1;
+        }
+        for cell in 0..num_cells {
+            first_boundary[cell + 1] += first_boundary[cell];
+        }
+        let mut boundary_nodes = vec![INVALID_NODE; first_boundary[num_cells]];
+        let mut boundary_index = vec![usize::MAX; cells.len()];
+        let mut next = first_boundary.clone();
+        for node in (0..cells.len()).filter(|n| is_boundary[*n]) {
+            let cell = cells[node];
+            boundary_nodes[next[cell]] = node;
+            boundary_index[node] = next[cell] - first_boundary[cell];
+            next[cell] += 1;
+        }
+        let mut first_weight = vec![0; num_cells + 1];
+        for cell in 0..num_cells {
+            let num_boundary_nodes = first_boundary[cell + 1] - first_boundary[cell];
+            first_weight[cell + 1] = first_weight[cell] + num_boundary_nodes * num_boundary_nodes;
+        }
+        Level {
+            cells,
+            first_boundary,
+            boundary_nodes,
+            boundary_index,
+            first_weight,
+            clique_weights: vec![],
+        }
+    }
+}
+
+/// Returns the arcs `(from, to, arc_level)` of the path from `source` to `target` found by a
+/// search.
+fn trace_arcs(
+    labels: &HashMap<NodeId, Label>,
+    source: NodeId,
+    target: NodeId,
+) -> Vec<(NodeId, NodeId, usize)> {
+    let mut arcs = vec![];
+    let mut node = target;
+    while node != source {
+        let label = labels[&node];
+        arcs.push((label.parent, node, label.arc_level));
+        node = label.parent;
+    }
+    arcs.reverse();
+    arcs
+}
+
+/// Assigns every unit to a group of connected units whose sizes add up to at most `max_size`.
+fn grow_groups(neighbors: &[Vec<usize>], sizes: &[usize], max_size: usize) -> Vec<usize> {
+    let mut groups = vec![usize::MAX; sizes.len()];
+    let mut num_groups = 0;
+    let mut queue = VecDeque::new();
+    for start in 0..sizes.len() {
+        if groups[start] != usize::MAX {
+            continue;
+        }
+        groups[start] = num_groups;
+        let mut size = sizes[start];
+        queue.clear();
+        queue.push_back(start);
+        while let Some(unit) = queue.pop_front() {
+            for adj in &neighbors[unit] {
+                if groups[*adj] == usize::MAX && size + sizes[*adj] <= max_size {
+                    groups[*adj] = num_groups;
+                    size += sizes[*adj];
+                    queue.push_back(*adj);
+                }
+            }
+        }
+        num_groups += 1;
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn customize() {
+        // 0 -> 1 -> 2 -> 3 -> 4 -> 5
+        //       \-> 6 -> 7 ->/
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 1);
+        g.add_edge(2, 3, 1);
+        g.add_edge(3, 4, 1);
+        g.add_edge(4, 5, 1);
+        g.add_edge(1, 6, 2);
+        g.add_edge(6, 7, 2);
+        g.add_edge(7, 4, 2);
+        g.freeze();
+        let cells = vec![vec![0, 0, 1, 1, 2, 2, 3, 3], vec![0, 0, 1, 1, 0, 0, 1, 1]];
+        let mut overlay_graph = OverlayGraph::new(&g, &cells).unwrap();
+        assert_eq!(2, overlay_graph.get_num_levels());
+        let path = overlay_graph.calc_path(0, 5).unwrap();
+        assert_eq!(&vec![0, 1, 2, 3, 4, 5], path.get_nodes());
+        assert_eq!(5, path.get_weight());
+        assert_eq!(Some(3), overlay_graph.calc_weight(2, 5));
+        assert!(overlay_graph.calc_path(5, 0).is_none());
+        assert_eq!(
+            Some(ShortestPath::singular(3)),
+            overlay_graph.calc_path(3, 3)
+        );
+
+        // a traffic jam between 2 and 3
+        let mut weights = overlay_graph.get_weights().to_vec();
+        weights[g.get_edge_id(2, 3).unwrap()] = 10;
+        overlay_graph.customize(&weights).unwrap();
+        let path = overlay_graph.calc_path(0, 5).unwrap();
+        assert_eq!(&vec![0, 1, 6, 7, 4, 5], path.get_nodes());
+        assert_eq!(8, path.get_weight());
+
+        // the road is closed
+        weights[g.get_edge_id(2, 3).unwrap()] = WEIGHT_MAX;
+        weights[g.get_edge_id(6, 7).unwrap()] = WEIGHT_MAX;
+        overlay_graph.customize(&weights).unwrap();
+        assert!(overlay_graph.calc_path(0, 5).is_none());
+        assert_eq!(Some(1), overlay_graph.calc_weight(0, 1));
+        assert_eq!(
+            Err(OverlayError::WeightCountMismatch {
+                num_weights: 3,
+                num_edges: weights.len()
+            }),
+            overlay_graph.customize(&[1, 2, 3])
+        );
+    }
+
+    #[test]
+    fn invalid_cells() {
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 1);
+        g.freeze();
+        assert_eq!(
+            Some(OverlayError::CellCountMismatch {
+                level: 0,
+                num_cells: 2,
+                num_nodes: 3
+            }),
+            OverlayGraph::new(&g, &[vec![0, 1]]).err()
+        );
+        // cell 0 of the first level does not lie within a single cell of the second one
+        assert_eq!(
+            Some(OverlayError::CellsNotNested { level: 0, node: 1 }),
+            OverlayGraph::new(&g, &[vec![0, 0, 1], vec![0, 1, 1]]).err()
+        );
+        assert!(OverlayGraph::new(&g, &[vec![0, 0, 1], vec![0, 0, 0]]).is_ok());
+        // no levels at all works as well, but then we just run Dijkstra
+        let overlay_graph = OverlayGraph::new(&g, &[]).unwrap();
+        assert_eq!(Some(2), overlay_graph.calc_weight(0, 2));
+    }
+
+    #[test]
+    fn partition() {
+        // a 10x10 grid
+        let mut g = InputGraph::new();
+        for row in 0..10 {
+            for column in 0..10 {
+                let node = row * 10 + column;
+                if column < 9 {
+                    g.add_edge_bidir(node, node + 1, 1);
+                }
+                if row < 9 {
+                    g.add_edge_bidir(node, node + 10, 1);
+                }
+            }
+        }
+        g.freeze();
+        let cells = OverlayGraph::partition(&g, &[5, 20, 50]);
+        assert_eq!(3, cells.len());
+        for (level, max_cell_size) in [5, 20, 50].iter().enumerate() {
+            let mut sizes = HashMap::new();
+            for cell in &cells[level] {
+                *sizes.entry(*cell).or_insert(0) += 1;
+            }
+            assert!(sizes.values().all(|size| size <= max_cell_size));
+            assert!(sizes.len() >= 100 / max_cell_size);
+        }
+        let overlay_graph = OverlayGraph::new(&g, &cells).unwrap();
+        assert_eq!(Some(18), overlay_graph.calc_weight(0, 99));
+        assert_eq!(
+            19,
+            overlay_graph.calc_path(90, 9).unwrap().get_nodes().len()
+        );
+    }
+}
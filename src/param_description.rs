@@ -0,0 +1,351 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use serde::{Deserialize, Serialize};
+
+use crate::fast_graph_builder::{Params, ParamsWithOrder};
+
+/// The value of a preparation parameter, see `Params::describe`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ParamValue {
+    Float(f32),
+    Integer(usize),
+    /// An integer that can also be left unset to disable the corresponding feature
+    OptionalInteger(Option<usize>),
+}
+
+/// Describes one of the preparation parameters, e.g. so they can be shown in a user interface or
+/// config values can be checked without knowing about the individual parameters.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ParamDescription {
+    /// The name of the field of `Params` or `ParamsWithOrder`
+    pub name: &'static str,
+    /// What the parameter does and how it affects the preparation and the queries
+    pub effect: &'static str,
+    /// The smallest valid value
+    pub min: f64,
+    /// The largest valid value, if there is any
+    pub max: Option<f64>,
+    pub default: ParamValue,
+}
+
+impl ParamDescription {
+    /// Checks that `value` has the type of this parameter and is within its valid range.
+    pub fn check(&self, value: ParamValue) -> Result<(), String> {
+        let number = match (self.default, value) {
+            (ParamValue::Float(_), ParamValue::Float(v)) if v.is_finite() => v as f64,
+            (ParamValue::Integer(_), ParamValue::Integer(v)) => v as f64,
+            (ParamValue::OptionalInteger(_), ParamValue::OptionalInteger(None)) => return Ok(()),
+            (ParamValue::OptionalInteger(_), ParamValue::OptionalInteger(Some(v))) => v as f64,
+            _ => {
+                return Err(format!(
+                    "Invalid value for {}: {:?}, expected a value like {:?}",
+                    self.name, value, self.default
+                ))
+            }
+        };
+        if number < self.min || self.max.is_some_and(|max| number > max) {
+            return Err(format!(
+                "Invalid value for {}: {:?}, it must be in the range {}..={}",
+                self.name,
+                value,
+                self.min,
+                self.max.map_or(String::new(), |max| max.to_string())
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Params {
+    /// Describes all the parameters along with their valid ranges and default values, in the
+    /// order they are declared.
+    pub fn describe() -> Vec<ParamDescription> {
+        let default = Params::default();
+        vec![
+            ParamDescription {
+                name: "hierarchy_depth_factor",
+                effect: "Smaller values typically yield less shortcuts and a faster preparation. \
+                         Values between 0 and 1 are worth trying.",
+                min: 0.0,
+                max: None,
+                default: ParamValue::Float(default.hierarchy_depth_factor),
+            },
+            ParamDescription {
+                name: "edge_quotient_factor",
+                effect: "Only its size relative to hierarchy_depth_factor matters, so it can be \
+                         kept at 1.",
+                min: 0.0,
+                max: None,
+                default: ParamValue::Float(default.edge_quotient_factor),
+            },
+            ParamDescription {
+                name: "max_settled_nodes_initial_relevance",
+                effect: "The maximum number of nodes settled by each witness search when the \
+                         initial node priorities are calculated.",
+                min: 0.0,
+                max: None,
+                default: ParamValue::Integer(default.max_settled_nodes_initial_relevance),
+            },
+            ParamDescription {
+                name: "max_settled_nodes_neighbor_relevance",
+                effect: "The maximum number of nodes settled by each witness search when the \
+                         priorities of the neighbors of a contracted node are updated. Higher \
+                         values mean less shortcuts and faster queries, but a slower preparation.",
+                min: 0.0,
+                max: None,
+                default: ParamValue::Integer(default.max_settled_nodes_neighbor_relevance),
+            },
+            ParamDescription {
+                name: "max_settled_nodes_contraction",
+                effect: "The maximum number of nodes settled by each witness search when a node \
+                         is contracted. Higher values mean less shortcuts and faster queries, but \
+                         a slower preparation.",
+                min: 0.0,
+                max: None,
+                default: ParamValue::Integer(default.max_settled_nodes_contraction),
+            },
+            ParamDescription {
+                name: "max_degree",
+                effect: "If set, the preparation fails once a node that was not contracted yet \
+                         has more edges than this, instead of taking extremely long.",
+                min: 0.0,
+                max: None,
+                default: ParamValue::OptionalInteger(default.max_degree),
+            },
+            ParamDescription {
+                name: "max_shortcuts",
+                effect: "If set, the preparation stops contracting nodes once this many shortcuts \
+                         were added and leaves the remaining nodes as an uncontracted core.",
+                min: 0.0,
+                max: None,
+                default: ParamValue::OptionalInteger(default.max_shortcuts),
+            },
+            ParamDescription {
+                name: "max_shortcuts_per_node",
+                effect: "If set, nodes whose contraction would add more shortcuts are postponed, \
+                         and if only such nodes remain they are left as an uncontracted core.",
+                min: 0.0,
+                max: None,
+                default: ParamValue::OptionalInteger(default.max_shortcuts_per_node),
+            },
+            ParamDescription {
+                name: "lazy_priority_updates",
+                effect: "If set, the priorities of the neighbors of a contracted node are only \
+                         recalculated when they are about to be contracted, and if positive the \
+                         priorities of all nodes are recalculated every this many contractions.",
+                min: 0.0,
+                max: None,
+                default: ParamValue::OptionalInteger(default.lazy_priority_updates),
+            },
+            ParamDescription {
+                name: "max_witness_hops",
+                effect: "If set, the witness searches only consider paths with at most this many \
+                         edges. This bounds the time per witness search, but can add more \
+                         shortcuts.",
+                min: 1.0,
+                max: None,
+                default: ParamValue::OptionalInteger(default.max_witness_hops),
+            },
+            ParamDescription {
+                name: "high_degree",
+                effect: "If set, the nodes with at least this many edges use \
+                         max_settled_nodes_high_degree and max_witness_hops_high_degree for the \
+                         witness searches of their contraction.",
+                min: 0.0,
+                max: None,
+                default: ParamValue::OptionalInteger(default.high_degree),
+            },
+            ParamDescription {
+                name: "max_settled_nodes_high_degree",
+                effect: "The maximum number of nodes settled by each witness search when a node \
+                         with at least high_degree edges is contracted.",
+                min: 0.0,
+                max: None,
+                default: ParamValue::Integer(default.max_settled_nodes_high_degree),
+            },
+            ParamDescription {
+                name: "max_witness_hops_high_degree",
+                effect: "If set, the witness searches only consider paths with at most this many \
+                         edges when a node with at least high_degree edges is contracted.",
+                min: 1.0,
+                max: None,
+                default: ParamValue::OptionalInteger(default.max_witness_hops_high_degree),
+            },
+            ParamDescription {
+                name: "approximation_epsilon",
+                effect: "Witness paths that are longer than a shortcut by at most this fraction \
+                         make the shortcut unnecessary. Values above zero yield fewer shortcuts \
+                         and a faster preparation, but only approximate shortest paths.",
+                min: 0.0,
+                max: None,
+                default: ParamValue::Float(default.approximation_epsilon),
+            },
+        ]
+    }
+
+    /// Returns the value of the parameter with the given name, see `describe`.
+    pub fn get_value(&self, name: &str) -> Option<ParamValue> {
+        Some(match name {
+            "hierarchy_depth_factor" => ParamValue::Float(self.hierarchy_depth_factor),
+            "edge_quotient_factor" => ParamValue::Float(self.edge_quotient_factor),
+            "max_settled_nodes_initial_relevance" => {
+                ParamValue::Integer(self.max_settled_nodes_initial_relevance)
+            }
+            "max_settled_nodes_neighbor_relevance" => {
+                ParamValue::Integer(self.max_settled_nodes_neighbor_relevance)
+            }
+            "max_settled_nodes_contraction" => {
+                ParamValue::Integer(self.max_settled_nodes_contraction)
+            }
+            "max_degree" => ParamValue::OptionalInteger(self.max_degree),
+            "max_shortcuts" => ParamValue::OptionalInteger(self.max_shortcuts),
+            "max_shortcuts_per_node" => ParamValue::OptionalInteger(self.max_shortcuts_per_node),
+            "lazy_priority_updates" => ParamValue::OptionalInteger(self.lazy_priority_updates),
+            "max_witness_hops" => ParamValue::OptionalInteger(self.max_witness_hops),
+            "approximation_epsilon" => ParamValue::Float(self.approximation_epsilon),
+            "high_degree" => ParamValue::OptionalInteger(self.high_degree),
+            "max_settled_nodes_high_degree" => {
+                ParamValue::Integer(self.max_settled_nodes_high_degree)
+            }
+            "max_witness_hops_high_degree" => {
+                ParamValue::OptionalInteger(self.max_witness_hops_high_degree)
+            }
+            _ => return None,
+        })
+    }
+
+    /// Sets the parameter with the given name, or returns an error if there is no such parameter
+    /// or the value is invalid, see `describe`.
+    pub fn set_value(&mut self, name: &str, value: ParamValue) -> Result<(), String> {
+        let description = Params::describe()
+            .into_iter()
+            .find(|d| d.name == name)
+            .ok_or_else(|| format!("Unknown parameter: {}", name))?;
+        description.check(value)?;
+        match (name, value) {
+            ("hierarchy_depth_factor", ParamValue::Float(v)) => self.hierarchy_depth_factor = v,
+            ("edge_quotient_factor", ParamValue::Float(v)) => self.edge_quotient_factor = v,
+            ("max_settled_nodes_initial_relevance", ParamValue::Integer(v)) => {
+                self.max_settled_nodes_initial_relevance = v
+            }
+            ("max_settled_nodes_neighbor_relevance", ParamValue::Integer(v)) => {
+                self.max_settled_nodes_neighbor_relevance = v
+            }
+            ("max_settled_nodes_contraction", ParamValue::Integer(v)) => {
+                self.max_settled_nodes_contraction = v
+            }
+            ("max_degree", ParamValue::OptionalInteger(v)) => self.max_degree = v,
+            ("max_shortcuts", ParamValue::OptionalInteger(v)) => self.max_shortcuts = v,
+            ("max_shortcuts_per_node", ParamValue::OptionalInteger(v)) => {
+                self.max_shortcuts_per_node = v
+            }
+            ("lazy_priority_updates", ParamValue::OptionalInteger(v)) => {
+                self.lazy_priority_updates = v
+            }
+            ("max_witness_hops", ParamValue::OptionalInteger(v)) => self.max_witness_hops = v,
+            ("approximation_epsilon", ParamValue::Float(v)) => self.approximation_epsilon = v,
+            ("high_degree", ParamValue::OptionalInteger(v)) => self.high_degree = v,
+            ("max_settled_nodes_high_degree", ParamValue::Integer(v)) => {
+                self.max_settled_nodes_high_degree = v
+            }
+            ("max_witness_hops_high_degree", ParamValue::OptionalInteger(v)) => {
+                self.max_witness_hops_high_degree = v
+            }
+            _ => unreachable!("the value was checked above"),
+        }
+        Ok(())
+    }
+}
+
+impl ParamsWithOrder {
+    /// Describes all the parameters, see `Params::describe`.
+    pub fn describe() -> Vec<ParamDescription> {
+        vec![ParamDescription {
+            name: "max_settled_nodes_contraction_with_order",
+            effect: "The maximum number of nodes settled by each witness search when a node is \
+                     contracted. Should usually be the same as the max_settled_nodes_contraction \
+                     used to obtain the node ordering.",
+            min: 0.0,
+            max: None,
+            default: ParamValue::Integer(
+                ParamsWithOrder::default().max_settled_nodes_contraction_with_order,
+            ),
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describe_all_params() {
+        let params = Params::default();
+        let descriptions = Params::describe();
+        assert_eq!(14, descriptions.len());
+        for description in &descriptions {
+            assert_eq!(
+                Some(description.default),
+                params.get_value(description.name)
+            );
+            assert!(description.check(description.default).is_ok());
+        }
+        // the descriptions must cover all fields
+        let debug = format!("{:?}", params);
+        assert_eq!(descriptions.len(), debug.matches(": ").count());
+        for description in &descriptions {
+            assert!(debug.contains(description.name), "{}", description.name);
+        }
+        assert_eq!(1, ParamsWithOrder::describe().len());
+    }
+
+    #[test]
+    fn set_values() {
+        let mut params = Params::default();
+        params
+            .set_value("hierarchy_depth_factor", ParamValue::Float(0.5))
+            .unwrap();
+        params
+            .set_value("max_shortcuts", ParamValue::OptionalInteger(Some(100)))
+            .unwrap();
+        assert_eq!(0.5, params.hierarchy_depth_factor);
+        assert_eq!(Some(100), params.max_shortcuts);
+        assert_eq!(
+            Some(ParamValue::OptionalInteger(Some(100))),
+            params.get_value("max_shortcuts")
+        );
+        assert!(params
+            .set_value("hierarchy_depth_factor", ParamValue::Float(-1.0))
+            .unwrap_err()
+            .contains("range"));
+        assert!(params
+            .set_value("hierarchy_depth_factor", ParamValue::Integer(1))
+            .is_err());
+        assert!(params
+            .set_value("max_degree", ParamValue::Float(f32::NAN))
+            .is_err());
+        assert!(params
+            .set_value("no_such_param", ParamValue::Integer(1))
+            .unwrap_err()
+            .contains("Unknown parameter"));
+        assert_eq!(None, params.get_value("no_such_param"));
+        assert_eq!(0.5, params.hierarchy_depth_factor);
+    }
+}
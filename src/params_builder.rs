@@ -0,0 +1,227 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use crate::fast_graph_builder::Params;
+
+/// Builds `Params` using named setters, starting from `Params::default()`. Unlike `Params::new`
+/// and setting the fields directly, `build` checks the values, see `Params::validate`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamsBuilder {
+    params: Params,
+}
+
+impl ParamsBuilder {
+    pub fn new() -> Self {
+        ParamsBuilder {
+            params: Params::default(),
+        }
+    }
+
+    /// See `Params::hierarchy_depth_factor`
+    pub fn hierarchy_depth_factor(mut self, value: f32) -> Self {
+        self.params.hierarchy_depth_factor = value;
+        self
+    }
+
+    /// See `Params::edge_quotient_factor`
+    pub fn edge_quotient_factor(mut self, value: f32) -> Self {
+        self.params.edge_quotient_factor = value;
+        self
+    }
+
+    /// See `Params::max_settled_nodes_initial_relevance`
+    pub fn max_settled_nodes_initial_relevance(mut self, value: usize) -> Self {
+        self.params.max_settled_nodes_initial_relevance = value;
+        self
+    }
+
+    /// See `Params::max_settled_nodes_neighbor_relevance`
+    pub fn max_settled_nodes_neighbor_relevance(mut self, value: usize) -> Self {
+        self.params.max_settled_nodes_neighbor_relevance = value;
+        self
+    }
+
+    /// See `Params::max_settled_nodes_contraction`
+    pub fn max_settled_nodes_contraction(mut self, value: usize) -> Self {
+        self.params.max_settled_nodes_contraction = value;
+        self
+    }
+
+    /// See `Params::max_degree`
+    pub fn max_degree(mut self, value: usize) -> Self {
+        self.params.max_degree = Some(value);
+        self
+    }
+
+    /// See `Params::max_shortcuts`
+    pub fn max_shortcuts(mut self, value: usize) -> Self {
+        self.params.max_shortcuts = Some(value);
+        self
+    }
+
+    /// See `Params::max_shortcuts_per_node`
+    pub fn max_shortcuts_per_node(mut self, value: usize) -> Self {
+        self.params.max_shortcuts_per_node = Some(value);
+        self
+    }
+
+    /// See `Params::lazy_priority_updates`
+    pub fn lazy_priority_updates(mut self, interval: usize) -> Self {
+        self.params.lazy_priority_updates = Some(interval);
+        self
+    }
+
+    /// See `Params::max_witness_hops`
+    pub fn max_witness_hops(mut self, value: usize) -> Self {
+        self.params.max_witness_hops = Some(value);
+        self
+    }
+
+    /// Uses the given limits for the contraction of nodes with at least `degree` edges, see
+    /// `Params::high_degree`.
+    pub fn high_degree_limits(
+        mut self,
+        degree: usize,
+        max_settled_nodes: usize,
+        max_hops: Option<usize>,
+    ) -> Self {
+        self.params.high_degree = Some(degree);
+        self.params.max_settled_nodes_high_degree = max_settled_nodes;
+        self.params.max_witness_hops_high_degree = max_hops;
+        self
+    }
+
+    /// See `Params::approximation_epsilon`
+    pub fn approximation_epsilon(mut self, value: f32) -> Self {
+        self.params.approximation_epsilon = value;
+        self
+    }
+
+    /// Returns the parameters, or an error if they are invalid, see `Params::validate`.
+    pub fn build(self) -> Result<Params, String> {
+        self.params.validate()?;
+        Ok(self.params)
+    }
+}
+
+impl Default for ParamsBuilder {
+    fn default() -> Self {
+        ParamsBuilder::new()
+    }
+}
+
+impl Params {
+    /// Returns a builder with named setters that validates the parameters, see `ParamsBuilder`.
+    pub fn builder() -> ParamsBuilder {
+        ParamsBuilder::new()
+    }
+
+    /// Checks that all parameters are within the ranges given by `Params::describe` and that they
+    /// make sense together. Returns an error describing the first problem otherwise.
+    pub fn validate(&self) -> Result<(), String> {
+        for description in Params::describe() {
+            let value = self
+                .get_value(description.name)
+                .expect("all described parameters have a value");
+            description.check(value)?;
+        }
+        if self.hierarchy_depth_factor == 0.0 && self.edge_quotient_factor == 0.0 {
+            return Err(String::from(
+                "hierarchy_depth_factor and edge_quotient_factor must not both be zero, \
+                 otherwise all nodes have the same priority",
+            ));
+        }
+        if self.max_degree == Some(0) {
+            return Err(String::from(
+                "max_degree must be positive, otherwise the preparation fails for any graph with \
+                 edges",
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_params() {
+        assert_eq!(Ok(Params::default()), Params::builder().build());
+        let params = Params::builder()
+            .hierarchy_depth_factor(0.5)
+            .max_settled_nodes_neighbor_relevance(10)
+            .max_degree(100)
+            .lazy_priority_updates(0)
+            .build()
+            .unwrap();
+        let mut expected = Params::new(0.5, 500, 10, 500);
+        expected.max_degree = Some(100);
+        expected.lazy_priority_updates = Some(0);
+        assert_eq!(expected, params);
+    }
+
+    #[test]
+    fn high_degree_limits() {
+        let params = Params::builder()
+            .max_settled_nodes_contraction(50)
+            .max_witness_hops(10)
+            .high_degree_limits(5, 20, Some(3))
+            .build()
+            .unwrap();
+        assert_eq!((50, Some(10)), params.get_contraction_limits(0));
+        assert_eq!((50, Some(10)), params.get_contraction_limits(4));
+        assert_eq!((20, Some(3)), params.get_contraction_limits(5));
+        assert_eq!((20, Some(3)), params.get_contraction_limits(100));
+        // without a threshold all nodes use the same limits
+        assert_eq!(
+            (500, None),
+            Params::default().get_contraction_limits(usize::MAX)
+        );
+    }
+
+    #[test]
+    fn reject_invalid_params() {
+        assert!(Params::builder()
+            .hierarchy_depth_factor(-0.1)
+            .build()
+            .unwrap_err()
+            .contains("hierarchy_depth_factor"));
+        assert!(Params::builder()
+            .edge_quotient_factor(f32::INFINITY)
+            .build()
+            .is_err());
+        assert!(Params::builder()
+            .hierarchy_depth_factor(0.0)
+            .edge_quotient_factor(0.0)
+            .build()
+            .unwrap_err()
+            .contains("same priority"));
+        assert!(Params::builder()
+            .max_degree(0)
+            .build()
+            .unwrap_err()
+            .contains("max_degree"));
+        // only one of them being zero is fine
+        assert!(Params::builder()
+            .hierarchy_depth_factor(0.0)
+            .build()
+            .is_ok());
+    }
+}
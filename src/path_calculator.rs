@@ -17,18 +17,49 @@
  * under the License.
  */
 
-use std::collections::BinaryHeap;
+use std::cmp::{max, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::Arc;
 
+use crate::arc_flags::ArcFlags;
+use crate::cancellation::{CancellationToken, Cancelled};
 use crate::constants::Weight;
 use crate::constants::INVALID_EDGE;
 use crate::constants::INVALID_NODE;
 use crate::constants::WEIGHT_MAX;
 use crate::constants::{EdgeId, NodeId};
+use crate::edge_point::{self, EdgePoint};
+use crate::error::Error;
 use crate::fast_graph::FastGraph;
 use crate::heap_item::HeapItem;
+use crate::k_shortest_paths;
+use crate::landmarks::Landmarks;
+use crate::query_stats::{QueryStats, SearchDirection, SettledNode};
+use crate::route_with_legs::RouteWithLegs;
 use crate::shortest_path::ShortestPath;
+use crate::stop_order;
 use crate::valid_flags::ValidFlags;
 
+/// The number of settled nodes after which cancellable queries check their token
+const CANCELLATION_CHECK_INTERVAL: usize = 256;
+
+/// Controls how the bidirectional search divides the work between the forward and the backward
+/// search. This does not change the results, only the number of nodes each search settles.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DirectionBias {
+    /// Both searches advance with the same radius, i.e. we always continue with the search whose
+    /// next node is closer to its start. This is the default.
+    Balanced,
+    /// The forward search is continued as long as its radius is at most this factor times the
+    /// radius of the backward search. Values larger than one favor the forward search, smaller
+    /// values favor the backward search.
+    Factor(f64),
+    /// Uses the ratio between the number of backward and forward edges of the graph as factor, so
+    /// the search that has to look at fewer edges per node does more of the work. This can help
+    /// for graphs that are much denser in one direction.
+    Auto,
+}
+
 pub struct PathCalculator {
     num_nodes: usize,
     data_fwd: Vec<Data>,
@@ -37,6 +68,19 @@ pub struct PathCalculator {
     valid_flags_bwd: ValidFlags,
     heap_fwd: BinaryHeap<HeapItem>,
     heap_bwd: BinaryHeap<HeapItem>,
+    sweep_nodes: Vec<NodeId>,
+    warm_start: Option<WarmStart>,
+    stats: QueryStats,
+    direction_bias: DirectionBias,
+    max_settled_nodes: Option<usize>,
+    record_search_space: bool,
+    search_space: Vec<SettledNode>,
+    landmarks: Option<Arc<Landmarks>>,
+    arc_flags: Option<Arc<ArcFlags>>,
+    /// the regions of the source and target of the current query, see `set_arc_flags`
+    arc_flag_regions: Option<(usize, usize)>,
+    /// the source and target whose lower bounds are used to prune the current query
+    landmark_query: Option<(NodeId, NodeId)>,
 }
 
 impl PathCalculator {
@@ -49,7 +93,129 @@ impl PathCalculator {
             valid_flags_bwd: ValidFlags::new(num_nodes),
             heap_fwd: BinaryHeap::new(),
             heap_bwd: BinaryHeap::new(),
+            sweep_nodes: Vec::new(),
+            warm_start: None,
+            stats: QueryStats::default(),
+            direction_bias: DirectionBias::Balanced,
+            max_settled_nodes: None,
+            record_search_space: false,
+            search_space: Vec::new(),
+            landmarks: None,
+            arc_flags: None,
+            arc_flag_regions: None,
+            landmark_query: None,
+        }
+    }
+
+    /// Prepares this calculator for queries on the given graph and returns it, e.g.
+    /// `calc.for_graph(&graph).calc_path(&graph, 0, 5)`. The internal buffers grow or shrink if
+    /// the number of nodes differs from the graph the calculator was used with before, so a single
+    /// calculator can be used for many graphs of different sizes. Resizing is not free, so if you
+    /// keep switching between a few large graphs it is still faster to use one calculator each.
+    pub fn for_graph(&mut self, graph: &FastGraph) -> &mut Self {
+        let num_nodes = graph.get_num_nodes();
+        if num_nodes != self.num_nodes {
+            self.resize(num_nodes);
+        }
+        self
+    }
+
+    fn resize(&mut self, num_nodes: usize) {
+        self.num_nodes = num_nodes;
+        for data in [&mut self.data_fwd, &mut self.data_bwd] {
+            data.resize_with(num_nodes, Data::new);
+            data.shrink_to_fit();
+        }
+        self.valid_flags_fwd = ValidFlags::new(num_nodes);
+        self.valid_flags_bwd = ValidFlags::new(num_nodes);
+        self.heap_fwd.clear();
+        self.heap_bwd.clear();
+        self.warm_start = None;
+    }
+
+    /// Restores the settings of a new calculator, i.e. removes the limit on the settled nodes, the
+    /// direction bias, the landmarks and the arc flags, disables recording the search space and
+    /// discards the forward search stored by `set_source`. The buffers are kept.
+    pub(crate) fn reset_settings(&mut self) {
+        self.warm_start = None;
+        self.direction_bias = DirectionBias::Balanced;
+        self.max_settled_nodes = None;
+        self.set_record_search_space(false);
+        self.landmarks = None;
+        self.arc_flags = None;
+    }
+
+    /// Sets how the work is divided between the forward and the backward search for all following
+    /// queries, see `DirectionBias`.
+    pub fn set_direction_bias(&mut self, direction_bias: DirectionBias) {
+        if let DirectionBias::Factor(factor) = direction_bias {
+            assert!(
+                factor.is_finite() && factor > 0.0,
+                "the direction bias factor must be positive, but was: {}",
+                factor
+            );
         }
+        self.direction_bias = direction_bias;
+    }
+
+    pub fn get_direction_bias(&self) -> DirectionBias {
+        self.direction_bias
+    }
+
+    /// Limits the number of nodes each of the two searches may settle, to bound the time a query
+    /// takes. Once a search reaches the limit it stops, and the query returns the best path found
+    /// so far, which is not necessarily the shortest one, see `ShortestPath::is_exact`. This
+    /// applies to all following queries that use the bidirectional search, e.g. `calc_path` or
+    /// `calc_weight`, but not to `calc_weights_one_to_many` or `calc_reachable_nodes`. `None`
+    /// removes the limit.
+    pub fn set_max_settled_nodes(&mut self, max_settled_nodes: Option<usize>) {
+        self.max_settled_nodes = max_settled_nodes;
+    }
+
+    pub fn get_max_settled_nodes(&self) -> Option<usize> {
+        self.max_settled_nodes
+    }
+
+    /// Enables or disables recording the nodes settled by the following queries, e.g. to visualize
+    /// their search spaces, see `get_search_space`. This makes the queries slower.
+    pub fn set_record_search_space(&mut self, record_search_space: bool) {
+        self.record_search_space = record_search_space;
+        self.search_space.clear();
+    }
+
+    /// Returns the nodes settled by the most recent query in the order they were settled, if
+    /// recording was enabled using `set_record_search_space`. Like `get_stats` this only covers
+    /// the last search of queries that consist of several searches.
+    pub fn get_search_space(&self) -> &[SettledNode] {
+        &self.search_space
+    }
+
+    /// Returns the statistics of the most recent query. Queries that consist of several searches,
+    /// like `calc_path_via` or `calc_k_paths`, only report the last of these searches.
+    pub fn get_stats(&self) -> &QueryStats {
+        &self.stats
+    }
+
+    /// Sets the landmarks whose lower bounds are used to prune the searches of all following
+    /// queries with a single source and target, see `Landmarks`. Nodes from which the target
+    /// cannot be reached with a smaller weight than the best path found so far (and vice versa)
+    /// are not expanded, see `QueryStats::get_num_pruned`. The results do not change.
+    pub fn set_landmarks(&mut self, landmarks: Option<Arc<Landmarks>>) {
+        self.landmarks = landmarks;
+    }
+
+    pub fn get_landmarks(&self) -> Option<&Arc<Landmarks>> {
+        self.landmarks.as_ref()
+    }
+
+    /// Sets the arc flags that are used to skip edges in all following queries with a single
+    /// source and target, see `ArcFlags`. The results do not change.
+    pub fn set_arc_flags(&mut self, arc_flags: Option<Arc<ArcFlags>>) {
+        self.arc_flags = arc_flags;
+    }
+
+    pub fn get_arc_flags(&self) -> Option<&Arc<ArcFlags>> {
+        self.arc_flags.as_ref()
     }
 
     pub fn calc_path(
@@ -61,12 +227,314 @@ impl PathCalculator {
         self.calc_path_multiple_sources_and_targets(graph, vec![(start, 0)], vec![(end, 0)])
     }
 
+    /// Like `calc_path`, but returns an error instead of panicking if the calculator does not fit
+    /// the graph or the nodes do not exist.
+    pub fn try_calc_path(
+        &mut self,
+        graph: &FastGraph,
+        start: NodeId,
+        end: NodeId,
+    ) -> Result<Option<ShortestPath>, Error> {
+        self.check_query(graph, [start, end])?;
+        Ok(self.calc_path(graph, start, end))
+    }
+
+    /// Like `calc_path_multiple_sources_and_targets`, but returns an error instead of panicking if
+    /// the calculator does not fit the graph or any of the nodes does not exist. Empty `starts` or
+    /// `ends` simply yield no path.
+    pub fn try_calc_path_multiple_sources_and_targets(
+        &mut self,
+        graph: &FastGraph,
+        starts: Vec<(NodeId, Weight)>,
+        ends: Vec<(NodeId, Weight)>,
+    ) -> Result<Option<ShortestPath>, Error> {
+        self.check_query(
+            graph,
+            starts.iter().chain(ends.iter()).map(|(node, _)| *node),
+        )?;
+        if starts.is_empty() || ends.is_empty() {
+            return Ok(None);
+        }
+        Ok(self.calc_path_multiple_sources_and_targets(graph, starts, ends))
+    }
+
+    /// Calculates the shortest path from any of the `starts` to any of the `ends`. Each start and
+    /// end node comes with an initial weight, e.g. the cost of getting from some location to the
+    /// node, and the returned path is the one minimizing
+    /// `start_weight + path weight + end_weight`. The weight of the returned path includes both
+    /// initial weights, and its source and target are the chosen start and end nodes.
+    ///
+    /// Nodes that are given more than once only use their smallest initial weight, and nodes with
+    /// initial weight `WEIGHT_MAX` are ignored. A node that is both a start and an end yields a
+    /// path consisting of this node only, if the sum of its initial weights is minimal.
     pub fn calc_path_multiple_sources_and_targets(
         &mut self,
         graph: &FastGraph,
         starts: Vec<(NodeId, Weight)>,
         ends: Vec<(NodeId, Weight)>,
     ) -> Option<ShortestPath> {
+        let (meeting_node, best_weight) = self.run_search(graph, starts, ends, WEIGHT_MAX);
+        self.build_path(graph, meeting_node, best_weight)
+    }
+
+    /// Calculates the shortest path from `start` to `end` like `calc_path`, but only if its weight
+    /// is at most `max_weight`. The search is stopped as soon as it is clear that there is no such
+    /// path, which is much faster than calculating the path first and checking its weight
+    /// afterwards, at least if `max_weight` is small compared to the weight of the path.
+    pub fn calc_path_with_limit(
+        &mut self,
+        graph: &FastGraph,
+        start: NodeId,
+        end: NodeId,
+        max_weight: Weight,
+    ) -> Option<ShortestPath> {
+        let (meeting_node, best_weight) =
+            self.run_search(graph, vec![(start, 0)], vec![(end, 0)], max_weight);
+        self.build_path(graph, meeting_node, best_weight)
+    }
+
+    /// Calculates the shortest path from `start` to `end` like `calc_path`, but re-uses the
+    /// forward search of the previous call of this method if `start` (and the graph) did not
+    /// change. This is a shortcut for calling `set_source` followed by `calc_path_to`.
+    pub fn calc_path_warm_start(
+        &mut self,
+        graph: &FastGraph,
+        start: NodeId,
+        end: NodeId,
+    ) -> Option<ShortestPath> {
+        self.reset_stats();
+        self.run_source_search(graph, start);
+        self.run_target_search(graph, end)
+    }
+
+    /// Runs the forward search from `source` and stores it, such that the following calls of
+    /// `calc_path_to` only need to run the backward search for each target. This is much faster
+    /// when calculating many paths from the same source, e.g. when re-routing a vehicle to
+    /// different targets. Nothing is done if the forward search from `source` is already stored.
+    ///
+    /// The forward search explores the entire upward search space of `source`, so this is slower
+    /// than the forward part of `calc_path`. Calling any other method of this calculator (except
+    /// `calc_path_to`, `calc_path_warm_start` and `calc_nearest_targets`) discards the stored
+    /// forward search. The stored search is also not used for a graph with a different
+    /// fingerprint (see `FastGraph::get_fingerprint`), e.g. after `FastGraph::update_weights`.
+    pub fn set_source(&mut self, graph: &FastGraph, source: NodeId) {
+        self.reset_stats();
+        self.run_source_search(graph, source);
+    }
+
+    /// Returns the source set by `set_source`, unless the stored forward search was discarded.
+    pub fn get_source(&self) -> Option<NodeId> {
+        self.warm_start.as_ref().map(|warm_start| warm_start.source)
+    }
+
+    /// Calculates the shortest path from the source set by `set_source` to `end`. Panics if no
+    /// source is set or if the source was set for another graph, or before the graph was
+    /// modified.
+    pub fn calc_path_to(&mut self, graph: &FastGraph, end: NodeId) -> Option<ShortestPath> {
+        let source = self
+            .get_source()
+            .expect("no source is set, call set_source first");
+        assert!(
+            self.warm_start.as_ref() == Some(&WarmStart::new(graph, source)),
+            "the source was set for another graph"
+        );
+        self.reset_stats();
+        self.run_target_search(graph, end)
+    }
+
+    fn run_source_search(&mut self, graph: &FastGraph, source: NodeId) {
+        assert_eq!(
+            graph.get_num_nodes(),
+            self.num_nodes,
+            "given graph has invalid node count"
+        );
+        assert!(source < self.num_nodes, "invalid start node");
+        let warm_start = WarmStart::new(graph, source);
+        if self.warm_start.as_ref() != Some(&warm_start) {
+            self.run_upward_search_fwd(graph, source, WEIGHT_MAX);
+            self.warm_start = Some(warm_start);
+        }
+    }
+
+    fn run_target_search(&mut self, graph: &FastGraph, end: NodeId) -> Option<ShortestPath> {
+        assert!(end < self.num_nodes, "invalid end node");
+        self.landmark_query = None;
+        self.arc_flag_regions = None;
+        self.heap_bwd.clear();
+        self.valid_flags_bwd.invalidate_all();
+        let mut best_weight = WEIGHT_MAX;
+        let mut meeting_node = INVALID_NODE;
+        self.update_node_bwd(end, 0, end, INVALID_EDGE);
+        self.heap_bwd.push(HeapItem::new(0, end));
+        self.stats.num_heap_pushes += 1;
+        // the forward search is complete, so we only need to continue the backward search until
+        // it cannot yield a better meeting point
+        while self
+            .heap_bwd
+            .peek()
+            .is_some_and(|item| item.weight <= best_weight)
+        {
+            self.settle_next_bwd(graph, &mut best_weight, &mut meeting_node);
+        }
+        self.stats.meeting_node = (meeting_node != INVALID_NODE).then_some(meeting_node);
+        self.build_path(graph, meeting_node, best_weight)
+    }
+
+    /// Like `run_target_search`, but only returns the weight, and only if it is smaller than
+    /// `max_weight`.
+    fn run_target_weight_search(
+        &mut self,
+        graph: &FastGraph,
+        end: NodeId,
+        max_weight: Weight,
+    ) -> Option<Weight> {
+        assert!(end < self.num_nodes, "invalid end node");
+        self.landmark_query = None;
+        self.arc_flag_regions = None;
+        self.heap_bwd.clear();
+        self.valid_flags_bwd.invalidate_all();
+        let mut best_weight = max_weight;
+        let mut meeting_node = INVALID_NODE;
+        self.update_node_bwd(end, 0, end, INVALID_EDGE);
+        self.heap_bwd.push(HeapItem::new(0, end));
+        self.stats.num_heap_pushes += 1;
+        // the weight of any path we can still find is at least the weight of the next node in the
+        // heap, so we stop once it is not smaller than the current best weight
+        while self
+            .heap_bwd
+            .peek()
+            .is_some_and(|item| item.weight < best_weight)
+        {
+            self.settle_next_bwd(graph, &mut best_weight, &mut meeting_node);
+        }
+        self.stats.meeting_node = (meeting_node != INVALID_NODE).then_some(meeting_node);
+        (meeting_node != INVALID_NODE).then_some(best_weight)
+    }
+
+    fn build_path(
+        &mut self,
+        graph: &FastGraph,
+        meeting_node: NodeId,
+        best_weight: Weight,
+    ) -> Option<ShortestPath> {
+        if meeting_node == INVALID_NODE {
+            None
+        } else {
+            assert!(best_weight < WEIGHT_MAX);
+            let nodes = self.extract_nodes(graph, meeting_node);
+            assert!(!nodes.is_empty());
+            Some(
+                ShortestPath::new(nodes[0], nodes[nodes.len() - 1], best_weight, nodes)
+                    .with_graph_fingerprint(Some(graph.get_fingerprint()))
+                    .with_weight_unit(graph.get_weight_unit())
+                    .with_exact(!self.stats.settled_node_limit_reached),
+            )
+        }
+    }
+
+    /// Calculates the weight of the shortest path from `start` to `end`. This is faster than
+    /// `calc_path` when only the weight is needed, because the shortcuts of the path are never
+    /// unpacked.
+    pub fn calc_weight(&mut self, graph: &FastGraph, start: NodeId, end: NodeId) -> Option<Weight> {
+        let (meeting_node, best_weight) =
+            self.run_search(graph, vec![(start, 0)], vec![(end, 0)], WEIGHT_MAX);
+        if meeting_node == INVALID_NODE {
+            None
+        } else {
+            Some(best_weight)
+        }
+    }
+
+    /// Like `calc_path_multiple_sources_and_targets`, but only calculates the weight, which is
+    /// faster because the shortcuts of the path are never unpacked.
+    pub fn calc_weight_multiple_sources_and_targets(
+        &mut self,
+        graph: &FastGraph,
+        starts: Vec<(NodeId, Weight)>,
+        ends: Vec<(NodeId, Weight)>,
+    ) -> Option<Weight> {
+        let (meeting_node, best_weight) = self.run_search(graph, starts, ends, WEIGHT_MAX);
+        if meeting_node == INVALID_NODE {
+            None
+        } else {
+            Some(best_weight)
+        }
+    }
+
+    /// Like `calc_weight`, but returns an error instead of panicking if the calculator does not fit
+    /// the graph or the nodes do not exist.
+    pub fn try_calc_weight(
+        &mut self,
+        graph: &FastGraph,
+        start: NodeId,
+        end: NodeId,
+    ) -> Result<Option<Weight>, Error> {
+        self.check_query(graph, [start, end])?;
+        Ok(self.calc_weight(graph, start, end))
+    }
+
+    /// Calculates the shortest path from `start` to `end` like `calc_path`, but stops the search
+    /// and returns `Err(Cancelled)` once the given token is cancelled or its timeout has passed.
+    pub fn calc_path_cancellable(
+        &mut self,
+        graph: &FastGraph,
+        start: NodeId,
+        end: NodeId,
+        token: &CancellationToken,
+    ) -> Result<Option<ShortestPath>, Cancelled> {
+        let (meeting_node, best_weight) = self.run_search_cancellable(
+            graph,
+            vec![(start, 0)],
+            vec![(end, 0)],
+            WEIGHT_MAX,
+            Some(token),
+        )?;
+        Ok(self.build_path(graph, meeting_node, best_weight))
+    }
+
+    /// Checks the conditions the queries assert on
+    fn check_query<I: IntoIterator<Item = NodeId>>(
+        &self,
+        graph: &FastGraph,
+        nodes: I,
+    ) -> Result<(), Error> {
+        if graph.get_num_nodes() != self.num_nodes {
+            return Err(Error::NodeCountMismatch {
+                graph_nodes: graph.get_num_nodes(),
+                calculator_nodes: self.num_nodes,
+            });
+        }
+        match nodes.into_iter().find(|node| *node >= self.num_nodes) {
+            Some(node) => Err(Error::InvalidNode {
+                node,
+                num_nodes: self.num_nodes,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn run_search(
+        &mut self,
+        graph: &FastGraph,
+        starts: Vec<(NodeId, Weight)>,
+        ends: Vec<(NodeId, Weight)>,
+        max_weight: Weight,
+    ) -> (NodeId, Weight) {
+        self.run_search_cancellable(graph, starts, ends, max_weight, None)
+            .expect("a search without a cancellation token cannot be cancelled")
+    }
+
+    /// Runs the bidirectional search and returns the meeting node and the weight of the shortest
+    /// path. The meeting node is `INVALID_NODE` if no path with weight `max_weight` or less was
+    /// found.
+    fn run_search_cancellable(
+        &mut self,
+        graph: &FastGraph,
+        starts: Vec<(NodeId, Weight)>,
+        ends: Vec<(NodeId, Weight)>,
+        max_weight: Weight,
+        token: Option<&CancellationToken>,
+    ) -> Result<(NodeId, Weight), Cancelled> {
         assert_eq!(
             graph.get_num_nodes(),
             self.num_nodes,
@@ -80,6 +548,29 @@ impl PathCalculator {
         for (end_node, _) in &ends {
             assert!(*end_node < self.num_nodes, "invalid end node");
         }
+        self.warm_start = None;
+        self.reset_stats();
+        self.landmark_query = None;
+        if let (Some(landmarks), [(start, _)], [(end, _)]) =
+            (&self.landmarks, &starts[..], &ends[..])
+        {
+            assert!(
+                landmarks.is_compatible(graph),
+                "the landmarks were calculated for another graph"
+            );
+            self.landmark_query = Some((*start, *end));
+        }
+        self.arc_flag_regions = None;
+        if let (Some(arc_flags), [(start, _)], [(end, _)]) =
+            (&self.arc_flags, &starts[..], &ends[..])
+        {
+            assert!(
+                arc_flags.is_compatible(graph),
+                "the arc flags were calculated for another graph"
+            );
+            let regions = arc_flags.get_regions();
+            self.arc_flag_regions = Some((regions[*start], regions[*end]));
+        }
         self.heap_fwd.clear();
         self.heap_bwd.clear();
         self.valid_flags_fwd.invalidate_all();
@@ -108,6 +599,7 @@ impl PathCalculator {
                 // later
                 self.update_node_fwd(node, weight, node, INVALID_EDGE);
                 self.heap_fwd.push(HeapItem::new(weight, node));
+                self.stats.num_heap_pushes += 1;
             }
         }
         for (node, weight) in ends {
@@ -115,99 +607,561 @@ impl PathCalculator {
                 // ... same here
                 self.update_node_bwd(node, weight, node, INVALID_EDGE);
                 self.heap_bwd.push(HeapItem::new(weight, node));
+                self.stats.num_heap_pushes += 1;
             }
         }
 
+        let bias_factor = match self.direction_bias {
+            DirectionBias::Balanced => None,
+            DirectionBias::Factor(factor) => Some(factor),
+            DirectionBias::Auto => Some(
+                graph.get_num_in_edges().max(1) as f64 / graph.get_num_out_edges().max(1) as f64,
+            ),
+        };
+        let mut num_settled: usize = 0;
         loop {
-            if self.heap_fwd.is_empty() && self.heap_bwd.is_empty() {
-                break;
+            // checking the token is not free (it might even need to read the clock), so we only do
+            // it every now and then
+            if num_settled % CANCELLATION_CHECK_INTERVAL == 0
+                && token.is_some_and(|token| token.is_cancelled())
+            {
+                return Err(Cancelled);
             }
-            loop {
-                if self.heap_fwd.is_empty() {
-                    break;
+            num_settled += 1;
+            // by default we always continue with the search whose next node is closer to its
+            // start. this balances the search radii and typically reduces the number of settled
+            // nodes when one of the two searches is much more expensive than the other
+            let bound = best_weight.min(max_weight);
+            let mut fwd_done = self
+                .heap_fwd
+                .peek()
+                .map_or(true, |item| item.weight > bound);
+            let mut bwd_done = self
+                .heap_bwd
+                .peek()
+                .map_or(true, |item| item.weight > bound);
+            if let Some(max_settled_nodes) = self.max_settled_nodes {
+                if !fwd_done && self.stats.num_settled_fwd >= max_settled_nodes {
+                    fwd_done = true;
+                    self.stats.settled_node_limit_reached = true;
                 }
-                let curr = self.heap_fwd.pop().unwrap();
-                if self.is_settled_fwd(curr.node_id) {
-                    continue;
+                if !bwd_done && self.stats.num_settled_bwd >= max_settled_nodes {
+                    bwd_done = true;
+                    self.stats.settled_node_limit_reached = true;
                 }
-                if curr.weight > best_weight {
-                    break;
+            }
+            if fwd_done && bwd_done {
+                break;
+            }
+            let forward = if fwd_done {
+                false
+            } else if bwd_done {
+                true
+            } else {
+                let fwd_weight = self.heap_fwd.peek().unwrap().weight;
+                let bwd_weight = self.heap_bwd.peek().unwrap().weight;
+                match bias_factor {
+                    None => fwd_weight <= bwd_weight,
+                    Some(factor) => fwd_weight as f64 <= bwd_weight as f64 * factor,
                 }
-                // stall on demand optimization
-                if self.is_stallable_fwd(graph, curr) {
-                    continue;
+            };
+            if forward {
+                self.settle_next_fwd(graph, &mut best_weight, &mut meeting_node);
+            } else {
+                self.settle_next_bwd(graph, &mut best_weight, &mut meeting_node);
+            }
+        }
+
+        if best_weight > max_weight {
+            Ok((INVALID_NODE, WEIGHT_MAX))
+        } else {
+            self.stats.meeting_node = (meeting_node != INVALID_NODE).then_some(meeting_node);
+            Ok((meeting_node, best_weight))
+        }
+    }
+
+    /// Calculates the shortest path visiting all the given `waypoints` in order, as a single
+    /// `ShortestPath`. Use `calc_route_with_legs` to get the path between each pair of
+    /// consecutive waypoints separately.
+    pub fn calc_path_via(
+        &mut self,
+        graph: &FastGraph,
+        waypoints: &[NodeId],
+    ) -> Option<ShortestPath> {
+        self.calc_route_with_legs(graph, waypoints)
+            .map(|route| route.flatten())
+    }
+
+    /// Calculates up to `k` loopless paths from `start` to `end` in order of increasing weight
+    /// (Yen's algorithm). The first path is the shortest path. Fewer than `k` paths are returned
+    /// if there are no more loopless paths, and none if `end` cannot be reached at all.
+    ///
+    /// Note that edges of the input graph for which the preparation found a shorter path between
+    /// the same two nodes can be dropped from the prepared graph, so paths using such an edge
+    /// might be missing from the result.
+    pub fn calc_k_paths(
+        &mut self,
+        graph: &FastGraph,
+        start: NodeId,
+        end: NodeId,
+        k: usize,
+    ) -> Vec<ShortestPath> {
+        match self.calc_path(graph, start, end) {
+            Some(shortest_path) => k_shortest_paths::calc_k_paths(graph, shortest_path, k)
+                .into_iter()
+                .map(|path| {
+                    path.with_graph_fingerprint(Some(graph.get_fingerprint()))
+                        .with_weight_unit(graph.get_weight_unit())
+                })
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// Calculates the shortest path between two points that lie somewhere on the edges of the
+    /// graph, see `EdgePoint`. The source and target points are represented by the virtual node
+    /// ids `num_nodes` and `num_nodes + 1` in the returned path.
+    pub fn calc_path_between_edge_points(
+        &mut self,
+        graph: &FastGraph,
+        source: &EdgePoint,
+        target: &EdgePoint,
+    ) -> Option<ShortestPath> {
+        edge_point::calc_path(self, graph, source, target)
+    }
+
+    /// Calculates the route from `start` to `end` that visits all the given `stops` in the order
+    /// that minimizes the total weight. Use `end == start` for a round trip. The legs of the
+    /// returned route follow the optimal order of the stops. Returns `None` if there is no route
+    /// visiting all stops.
+    ///
+    /// This calculates the weights between all stops and then finds the best order exactly, so it
+    /// can only be used for a small number of stops (at most `MAX_STOPS`).
+    pub fn calc_route_with_best_order(
+        &mut self,
+        graph: &FastGraph,
+        start: NodeId,
+        stops: &[NodeId],
+        end: NodeId,
+    ) -> Option<RouteWithLegs> {
+        assert!(
+            stops.len() <= stop_order::MAX_STOPS,
+            "there can be at most {} stops",
+            stop_order::MAX_STOPS
+        );
+        let mut locations = Vec::with_capacity(stops.len() + 2);
+        locations.push(start);
+        locations.extend_from_slice(stops);
+        locations.push(end);
+        // we never go back to the start or away from the end, so these weights are not needed
+        let mut weights = vec![vec![WEIGHT_MAX; locations.len()]; locations.len()];
+        for (i, row) in weights.iter_mut().enumerate().take(locations.len() - 1) {
+            let row_weights = self.calc_weights_one_to_many(graph, locations[i], &locations[1..]);
+            row[1..].copy_from_slice(&row_weights);
+        }
+        let (order, _) = stop_order::find_best_order(&weights)?;
+        let mut waypoints = Vec::with_capacity(locations.len());
+        waypoints.push(start);
+        waypoints.extend(order.iter().map(|i| locations[*i]));
+        waypoints.push(end);
+        self.calc_route_with_legs(graph, &waypoints)
+    }
+
+    /// Calculates a route visiting all the given `waypoints` in order, see `RouteWithLegs`.
+    /// Returns `None` if there is no path between any two consecutive waypoints.
+    pub fn calc_route_with_legs(
+        &mut self,
+        graph: &FastGraph,
+        waypoints: &[NodeId],
+    ) -> Option<RouteWithLegs> {
+        assert!(
+            waypoints.len() > 1,
+            "there have to be at least two waypoints"
+        );
+        let mut legs = Vec::with_capacity(waypoints.len() - 1);
+        for pair in waypoints.windows(2) {
+            legs.push(self.calc_path(graph, pair[0], pair[1])?);
+        }
+        Some(RouteWithLegs::new(legs))
+    }
+
+    /// Calculates the weights for all combinations of the given `sources` and `targets`, including
+    /// their initial weights, see `calc_path_multiple_sources_and_targets`. The entry `[i][j]` of
+    /// the result is `sources[i].1 + weight of the shortest path + targets[j].1`, or `WEIGHT_MAX`
+    /// if there is no path or any of the initial weights is `WEIGHT_MAX`.
+    pub fn calc_weights_multiple_sources_and_targets(
+        &mut self,
+        graph: &FastGraph,
+        sources: &[(NodeId, Weight)],
+        targets: &[(NodeId, Weight)],
+    ) -> Vec<Vec<Weight>> {
+        let target_nodes: Vec<NodeId> = targets.iter().map(|(node, _)| *node).collect();
+        sources
+            .iter()
+            .map(|(source, source_weight)| {
+                self.calc_weights_one_to_many(graph, *source, &target_nodes)
+                    .into_iter()
+                    .zip(targets)
+                    .map(|(weight, (_, target_weight))| {
+                        if weight == WEIGHT_MAX
+                            || *source_weight == WEIGHT_MAX
+                            || *target_weight == WEIGHT_MAX
+                        {
+                            WEIGHT_MAX
+                        } else {
+                            source_weight + weight + target_weight
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Calculates the shortest path weights from `source` to all the given `targets`. The result
+    /// contains one weight per target (in the same order) and `WEIGHT_MAX` for targets that cannot
+    /// be reached.
+    ///
+    /// Rather than running one query per target this runs a single upward search from `source`
+    /// followed by a downward sweep over the part of the hierarchy that can reach any of the
+    /// targets (RPHAST), so it is much faster than calling `calc_path` for each target.
+    pub fn calc_weights_one_to_many(
+        &mut self,
+        graph: &FastGraph,
+        source: NodeId,
+        targets: &[NodeId],
+    ) -> Vec<Weight> {
+        assert_eq!(
+            graph.get_num_nodes(),
+            self.num_nodes,
+            "given graph has invalid node count"
+        );
+        assert!(source < self.num_nodes, "invalid start node");
+        for target in targets {
+            assert!(*target < self.num_nodes, "invalid end node");
+        }
+        // the downward sweep below modifies the forward search data
+        self.warm_start = None;
+        self.valid_flags_bwd.invalidate_all();
+        self.run_upward_search_fwd(graph, source, WEIGHT_MAX);
+
+        // select all nodes from which any of the targets can be reached going downwards. the
+        // backward valid flags are only used to mark the selected nodes here.
+        self.sweep_nodes.clear();
+        for target in targets {
+            if !self.valid_flags_bwd.is_valid(*target) {
+                self.valid_flags_bwd.set_valid(*target);
+                self.sweep_nodes.push(*target);
+            }
+        }
+        let mut i = 0;
+        while i < self.sweep_nodes.len() {
+            let node = self.sweep_nodes[i];
+            for edge_id in graph.begin_in_edges(node)..graph.end_in_edges(node) {
+                let adj = graph.edges_bwd[edge_id].adj_node;
+                if !self.valid_flags_bwd.is_valid(adj) {
+                    self.valid_flags_bwd.set_valid(adj);
+                    self.sweep_nodes.push(adj);
                 }
-                let begin = graph.begin_out_edges(curr.node_id);
-                let end = graph.end_out_edges(curr.node_id);
-                for edge_id in begin..end {
-                    let adj = graph.edges_fwd[edge_id].adj_node;
-                    let edge_weight = graph.edges_fwd[edge_id].weight;
-                    let weight = curr.weight + edge_weight;
-                    if weight < self.get_weight_fwd(adj) {
-                        self.update_node_fwd(adj, weight, curr.node_id, edge_id);
-                        self.heap_fwd.push(HeapItem::new(weight, adj));
-                    }
+            }
+            i += 1;
+        }
+
+        self.sweep_nodes
+            .sort_unstable_by_key(|node| Reverse(graph.ranks[*node]));
+        self.sweep_down(graph, WEIGHT_MAX);
+        targets
+            .iter()
+            .map(|target| self.get_weight_fwd(*target))
+            .collect()
+    }
+
+    /// Like `calc_weights_one_to_many`, but returns an error instead of panicking if the calculator
+    /// does not fit the graph or any of the nodes does not exist.
+    pub fn try_calc_weights_one_to_many(
+        &mut self,
+        graph: &FastGraph,
+        source: NodeId,
+        targets: &[NodeId],
+    ) -> Result<Vec<Weight>, Error> {
+        self.check_query(
+            graph,
+            std::iter::once(source).chain(targets.iter().copied()),
+        )?;
+        Ok(self.calc_weights_one_to_many(graph, source, targets))
+    }
+
+    /// Finds the (at most) `k` targets among `candidates` that are closest to `source` and returns
+    /// them together with their shortest path weights, ordered by weight. Candidates that cannot
+    /// be reached are omitted, and among candidates with the same weight the ones that come first
+    /// in `candidates` are preferred.
+    ///
+    /// The forward search from `source` is run only once, see `set_source`. The backward search of
+    /// each candidate is stopped as soon as its weight cannot be smaller than the weight of the
+    /// k-th best candidate found so far, so this is much faster than calculating all weights if
+    /// `k` is small.
+    pub fn calc_nearest_targets(
+        &mut self,
+        graph: &FastGraph,
+        source: NodeId,
+        candidates: &[NodeId],
+        k: usize,
+    ) -> Vec<(NodeId, Weight)> {
+        self.reset_stats();
+        self.run_source_search(graph, source);
+        let mut nearest: Vec<(NodeId, Weight)> = Vec::with_capacity(k + 1);
+        if k == 0 {
+            return nearest;
+        }
+        for &candidate in candidates {
+            let max_weight = if nearest.len() == k {
+                nearest[k - 1].1
+            } else {
+                WEIGHT_MAX
+            };
+            if let Some(weight) = self.run_target_weight_search(graph, candidate, max_weight) {
+                let pos = nearest.partition_point(|(_, w)| *w <= weight);
+                nearest.insert(pos, (candidate, weight));
+                nearest.truncate(k);
+            }
+        }
+        nearest
+    }
+
+    /// Calculates all nodes that can be reached from `source` with a path weight of at most
+    /// `max_weight` (an isochrone), together with their shortest path weights. The nodes are
+    /// ordered by node id.
+    ///
+    /// This runs an upward search limited to `max_weight` followed by a downward sweep over all
+    /// nodes (PHAST), so its running time is proportional to the size of the graph rather than to
+    /// the number of reachable nodes.
+    pub fn calc_reachable_nodes(
+        &mut self,
+        graph: &FastGraph,
+        source: NodeId,
+        max_weight: Weight,
+    ) -> Vec<(NodeId, Weight)> {
+        assert_eq!(
+            graph.get_num_nodes(),
+            self.num_nodes,
+            "given graph has invalid node count"
+        );
+        assert!(source < self.num_nodes, "invalid start node");
+        self.warm_start = None;
+        self.run_upward_search_fwd(graph, source, max_weight);
+        self.sweep_nodes.clear();
+        self.sweep_nodes
+            .extend(graph.get_node_ordering().into_iter().rev());
+        self.sweep_down(graph, max_weight);
+        (0..self.num_nodes)
+            .filter(|node| self.get_weight_fwd(*node) <= max_weight)
+            .map(|node| (node, self.get_weight_fwd(node)))
+            .collect()
+    }
+
+    /// Improves the weights of the forward search by going through `sweep_nodes`, which must be
+    /// sorted by descending rank, and relaxing their incoming downward edges. Since the weights of
+    /// all higher ranked nodes are final once we look at a node, the weights of all swept nodes are
+    /// correct afterwards, as long as they do not exceed `max_weight`.
+    fn sweep_down(&mut self, graph: &FastGraph, max_weight: Weight) {
+        for i in 0..self.sweep_nodes.len() {
+            let node = self.sweep_nodes[i];
+            for edge_id in graph.begin_in_edges(node)..graph.end_in_edges(node) {
+                let adj = graph.edges_bwd[edge_id].adj_node;
+                let adj_weight = self.get_weight_fwd(adj);
+                if adj_weight == WEIGHT_MAX {
+                    continue;
                 }
-                self.data_fwd[curr.node_id].settled = true;
-                if self.valid_flags_bwd.is_valid(curr.node_id)
-                    && curr.weight + self.get_weight_bwd(curr.node_id) < best_weight
-                {
-                    best_weight = curr.weight + self.get_weight_bwd(curr.node_id);
-                    meeting_node = curr.node_id;
+                let weight = adj_weight + graph.edges_bwd[edge_id].weight;
+                if weight <= max_weight && weight < self.get_weight_fwd(node) {
+                    self.update_node_fwd(node, weight, adj, edge_id);
                 }
-                break;
             }
+        }
+    }
 
-            loop {
-                if self.heap_bwd.is_empty() {
-                    break;
+    /// Runs the forward search from `source` without any other stopping criterion than
+    /// `max_weight`, such that afterwards the weights of all nodes in the upward search space of
+    /// `source` are known (up to `max_weight`).
+    fn run_upward_search_fwd(&mut self, graph: &FastGraph, source: NodeId, max_weight: Weight) {
+        self.reset_stats();
+        self.heap_fwd.clear();
+        self.valid_flags_fwd.invalidate_all();
+        self.update_node_fwd(source, 0, source, INVALID_EDGE);
+        self.heap_fwd.push(HeapItem::new(0, source));
+        self.stats.num_heap_pushes += 1;
+        while let Some(curr) = self.heap_fwd.pop() {
+            if self.is_settled_fwd(curr.node_id) {
+                continue;
+            }
+            let begin = graph.begin_out_edges(curr.node_id);
+            let end = graph.end_out_edges(curr.node_id);
+            for edge_id in begin..end {
+                let adj = graph.edges_fwd[edge_id].adj_node;
+                let weight = curr.weight + graph.edges_fwd[edge_id].weight;
+                if weight <= max_weight && weight < self.get_weight_fwd(adj) {
+                    self.update_node_fwd(adj, weight, curr.node_id, edge_id);
+                    self.heap_fwd.push(HeapItem::new(weight, adj));
+                    self.stats.num_heap_pushes += 1;
                 }
-                let curr = self.heap_bwd.pop().unwrap();
-                if self.is_settled_bwd(curr.node_id) {
+            }
+            self.data_fwd[curr.node_id].settled = true;
+            self.on_settled(curr, SearchDirection::Forward);
+        }
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = QueryStats::default();
+        self.search_space.clear();
+    }
+
+    fn on_settled(&mut self, item: HeapItem, direction: SearchDirection) {
+        match direction {
+            SearchDirection::Forward => self.stats.num_settled_fwd += 1,
+            SearchDirection::Backward => self.stats.num_settled_bwd += 1,
+        }
+        if self.record_search_space {
+            self.search_space.push(SettledNode {
+                node: item.node_id,
+                weight: item.weight,
+                direction,
+            });
+        }
+    }
+
+    /// Settles the next node of the forward search and updates the best weight and meeting node
+    /// in case the two searches meet at this node.
+    fn settle_next_fwd(
+        &mut self,
+        graph: &FastGraph,
+        best_weight: &mut Weight,
+        meeting_node: &mut NodeId,
+    ) {
+        while let Some(curr) = self.heap_fwd.pop() {
+            if self.is_settled_fwd(curr.node_id) {
+                continue;
+            }
+            if curr.weight > *best_weight {
+                return;
+            }
+            // stall on demand optimization
+            if self.is_stallable_fwd(graph, curr) {
+                self.stats.num_stalled += 1;
+                continue;
+            }
+            let begin = graph.begin_out_edges(curr.node_id);
+            let end = if self.is_prunable(curr, *best_weight, SearchDirection::Forward) {
+                begin
+            } else {
+                graph.end_out_edges(curr.node_id)
+            };
+            for edge_id in begin..end {
+                if !self.is_flagged(edge_id, SearchDirection::Forward) {
                     continue;
                 }
-                if curr.weight > best_weight {
-                    break;
+                let adj = graph.edges_fwd[edge_id].adj_node;
+                let edge_weight = graph.edges_fwd[edge_id].weight;
+                let weight = curr.weight + edge_weight;
+                if weight < self.get_weight_fwd(adj) {
+                    self.update_node_fwd(adj, weight, curr.node_id, edge_id);
+                    self.heap_fwd.push(HeapItem::new(weight, adj));
+                    self.stats.num_heap_pushes += 1;
                 }
-                // stall on demand optimization
-                if self.is_stallable_bwd(graph, curr) {
+            }
+            self.data_fwd[curr.node_id].settled = true;
+            self.on_settled(curr, SearchDirection::Forward);
+            if self.valid_flags_bwd.is_valid(curr.node_id)
+                && curr.weight + self.get_weight_bwd(curr.node_id) < *best_weight
+            {
+                *best_weight = curr.weight + self.get_weight_bwd(curr.node_id);
+                *meeting_node = curr.node_id;
+            }
+            return;
+        }
+    }
+
+    /// Like `settle_next_fwd`, but for the backward search
+    fn settle_next_bwd(
+        &mut self,
+        graph: &FastGraph,
+        best_weight: &mut Weight,
+        meeting_node: &mut NodeId,
+    ) {
+        while let Some(curr) = self.heap_bwd.pop() {
+            if self.is_settled_bwd(curr.node_id) {
+                continue;
+            }
+            if curr.weight > *best_weight {
+                return;
+            }
+            // stall on demand optimization
+            if self.is_stallable_bwd(graph, curr) {
+                self.stats.num_stalled += 1;
+                continue;
+            }
+            let begin = graph.begin_in_edges(curr.node_id);
+            let end = if self.is_prunable(curr, *best_weight, SearchDirection::Backward) {
+                begin
+            } else {
+                graph.end_in_edges(curr.node_id)
+            };
+            for edge_id in begin..end {
+                if !self.is_flagged(edge_id, SearchDirection::Backward) {
                     continue;
                 }
-                let begin = graph.begin_in_edges(curr.node_id);
-                let end = graph.end_in_edges(curr.node_id);
-                for edge_id in begin..end {
-                    let adj = graph.edges_bwd[edge_id].adj_node;
-                    let edge_weight = graph.edges_bwd[edge_id].weight;
-                    let weight = curr.weight + edge_weight;
-                    if weight < self.get_weight_bwd(adj) {
-                        self.update_node_bwd(adj, weight, curr.node_id, edge_id);
-                        self.heap_bwd.push(HeapItem::new(weight, adj));
-                    }
+                let adj = graph.edges_bwd[edge_id].adj_node;
+                let edge_weight = graph.edges_bwd[edge_id].weight;
+                let weight = curr.weight + edge_weight;
+                if weight < self.get_weight_bwd(adj) {
+                    self.update_node_bwd(adj, weight, curr.node_id, edge_id);
+                    self.heap_bwd.push(HeapItem::new(weight, adj));
+                    self.stats.num_heap_pushes += 1;
                 }
-                self.data_bwd[curr.node_id].settled = true;
-                if self.valid_flags_fwd.is_valid(curr.node_id)
-                    && curr.weight + self.get_weight_fwd(curr.node_id) < best_weight
-                {
-                    best_weight = curr.weight + self.get_weight_fwd(curr.node_id);
-                    meeting_node = curr.node_id;
-                }
-                break;
             }
+            self.data_bwd[curr.node_id].settled = true;
+            self.on_settled(curr, SearchDirection::Backward);
+            if self.valid_flags_fwd.is_valid(curr.node_id)
+                && curr.weight + self.get_weight_fwd(curr.node_id) < *best_weight
+            {
+                *best_weight = curr.weight + self.get_weight_fwd(curr.node_id);
+                *meeting_node = curr.node_id;
+            }
+            return;
+        }
+    }
+
+    /// Returns false if the arc flags show that the given edge cannot lead to the target (or
+    /// come from the source).
+    fn is_flagged(&self, edge_id: EdgeId, direction: SearchDirection) -> bool {
+        match (&self.arc_flags, self.arc_flag_regions) {
+            (Some(arc_flags), Some((source_region, target_region))) => match direction {
+                SearchDirection::Forward => arc_flags.is_flagged_fwd(edge_id, target_region),
+                SearchDirection::Backward => arc_flags.is_flagged_bwd(edge_id, source_region),
+            },
+            _ => true,
         }
+    }
 
-        if meeting_node == INVALID_NODE {
-            None
-        } else {
-            assert!(best_weight < WEIGHT_MAX);
-            let nodes = self.extract_nodes(graph, meeting_node);
-            assert!(!nodes.is_empty());
-            Some(ShortestPath::new(
-                nodes[0],
-                nodes[nodes.len() - 1],
-                best_weight,
-                nodes,
-            ))
+    /// Returns true if the landmarks show that no path via the given node can be shorter than
+    /// `best_weight`, so its edges do not need to be relaxed.
+    fn is_prunable(
+        &mut self,
+        curr: HeapItem,
+        best_weight: Weight,
+        direction: SearchDirection,
+    ) -> bool {
+        let (landmarks, (start, end)) = match (&self.landmarks, self.landmark_query) {
+            (Some(landmarks), Some(query)) => (landmarks, query),
+            _ => return false,
+        };
+        let bound = match direction {
+            SearchDirection::Forward => landmarks.calc_lower_bound(curr.node_id, end),
+            SearchDirection::Backward => landmarks.calc_lower_bound(start, curr.node_id),
+        };
+        let prunable = curr.weight.saturating_add(bound) >= best_weight;
+        if prunable {
+            self.stats.num_pruned += 1;
         }
+        prunable
     }
 
     fn is_stallable_fwd(&self, graph: &FastGraph, curr: HeapItem) -> bool {
@@ -244,94 +1198,146 @@ impl PathCalculator {
         false
     }
 
-    fn extract_nodes(&self, graph: &FastGraph, meeting_node: NodeId) -> Vec<NodeId> {
+    fn extract_nodes(&mut self, graph: &FastGraph, meeting_node: NodeId) -> Vec<NodeId> {
         assert_ne!(meeting_node, INVALID_NODE);
         assert!(self.valid_flags_fwd.is_valid(meeting_node));
         assert!(self.valid_flags_bwd.is_valid(meeting_node));
         let mut result = Vec::new();
         let mut node = meeting_node;
+        let mut max_depth = 0;
+        let mut zero_weights = false;
         while self.data_fwd[node].inc_edge != INVALID_EDGE {
-            PathCalculator::unpack_fwd(graph, &mut result, self.data_fwd[node].inc_edge, true);
+            let depth = PathCalculator::unpack_fwd(
+                graph,
+                &mut result,
+                self.data_fwd[node].inc_edge,
+                true,
+                &mut zero_weights,
+            );
+            max_depth = max(max_depth, depth);
             node = self.data_fwd[node].parent;
         }
         result.reverse();
         node = meeting_node;
         while self.data_bwd[node].inc_edge != INVALID_EDGE {
-            PathCalculator::unpack_bwd(graph, &mut result, self.data_bwd[node].inc_edge, false);
+            let depth = PathCalculator::unpack_bwd(
+                graph,
+                &mut result,
+                self.data_bwd[node].inc_edge,
+                false,
+                &mut zero_weights,
+            );
+            max_depth = max(max_depth, depth);
             node = self.data_bwd[node].parent;
         }
+        self.stats.max_unpack_depth = max_depth;
         // we stored the target node as 'parent' of the root of the shortest tree, so we can use it
         // here
         result.push(node);
+        if zero_weights {
+            remove_cycles(&mut result);
+        }
         result
     }
 
-    fn unpack_fwd(graph: &FastGraph, nodes: &mut Vec<NodeId>, edge_id: EdgeId, reverse: bool) {
+    /// Adds the nodes of the given edge to `nodes` and returns the nesting depth of its shortcuts.
+    /// `zero_weights` is set to true if one of its original edges has zero weight.
+    fn unpack_fwd(
+        graph: &FastGraph,
+        nodes: &mut Vec<NodeId>,
+        edge_id: EdgeId,
+        reverse: bool,
+        zero_weights: &mut bool,
+    ) -> usize {
         if !graph.edges_fwd[edge_id].is_shortcut() {
             nodes.push(graph.edges_fwd[edge_id].base_node);
-            return;
+            *zero_weights |= graph.edges_fwd[edge_id].weight == 0;
+            return 0;
         }
-        if reverse {
-            PathCalculator::unpack_fwd(
+        let depth = if reverse {
+            let out_depth = PathCalculator::unpack_fwd(
                 graph,
                 nodes,
                 graph.edges_fwd[edge_id].replaced_out_edge,
                 reverse,
+                zero_weights,
             );
-            PathCalculator::unpack_bwd(
+            let in_depth = PathCalculator::unpack_bwd(
                 graph,
                 nodes,
                 graph.edges_fwd[edge_id].replaced_in_edge,
                 reverse,
+                zero_weights,
             );
+            max(out_depth, in_depth)
         } else {
-            PathCalculator::unpack_bwd(
+            let in_depth = PathCalculator::unpack_bwd(
                 graph,
                 nodes,
                 graph.edges_fwd[edge_id].replaced_in_edge,
                 reverse,
+                zero_weights,
             );
-            PathCalculator::unpack_fwd(
+            let out_depth = PathCalculator::unpack_fwd(
                 graph,
                 nodes,
                 graph.edges_fwd[edge_id].replaced_out_edge,
                 reverse,
+                zero_weights,
             );
-        }
+            max(in_depth, out_depth)
+        };
+        depth + 1
     }
 
-    fn unpack_bwd(graph: &FastGraph, nodes: &mut Vec<NodeId>, edge_id: EdgeId, reverse: bool) {
+    /// Adds the nodes of the given edge to `nodes` and returns the nesting depth of its shortcuts.
+    /// `zero_weights` is set to true if one of its original edges has zero weight.
+    fn unpack_bwd(
+        graph: &FastGraph,
+        nodes: &mut Vec<NodeId>,
+        edge_id: EdgeId,
+        reverse: bool,
+        zero_weights: &mut bool,
+    ) -> usize {
         if !graph.edges_bwd[edge_id].is_shortcut() {
             nodes.push(graph.edges_bwd[edge_id].adj_node);
-            return;
+            *zero_weights |= graph.edges_bwd[edge_id].weight == 0;
+            return 0;
         }
-        if reverse {
-            PathCalculator::unpack_fwd(
+        let depth = if reverse {
+            let out_depth = PathCalculator::unpack_fwd(
                 graph,
                 nodes,
                 graph.edges_bwd[edge_id].replaced_out_edge,
                 reverse,
+                zero_weights,
             );
-            PathCalculator::unpack_bwd(
+            let in_depth = PathCalculator::unpack_bwd(
                 graph,
                 nodes,
                 graph.edges_bwd[edge_id].replaced_in_edge,
                 reverse,
+                zero_weights,
             );
+            max(out_depth, in_depth)
         } else {
-            PathCalculator::unpack_bwd(
+            let in_depth = PathCalculator::unpack_bwd(
                 graph,
                 nodes,
                 graph.edges_bwd[edge_id].replaced_in_edge,
                 reverse,
+                zero_weights,
             );
-            PathCalculator::unpack_fwd(
+            let out_depth = PathCalculator::unpack_fwd(
                 graph,
                 nodes,
                 graph.edges_bwd[edge_id].replaced_out_edge,
                 reverse,
+                zero_weights,
             );
-        }
+            max(in_depth, out_depth)
+        };
+        depth + 1
     }
 
     fn update_node_fwd(&mut self, node: NodeId, weight: Weight, parent: NodeId, inc_edge: EdgeId) {
@@ -375,6 +1381,25 @@ impl PathCalculator {
     }
 }
 
+/// Identifies the forward search that is stored by `set_source`. We keep the address
+/// and size of the graph to detect (most) cases where the calculator is used with another graph.
+#[derive(PartialEq)]
+struct WarmStart {
+    source: NodeId,
+    /// see `FastGraph::get_fingerprint`, which changes whenever the graph is modified, e.g. by
+    /// `FastGraph::update_weights`
+    graph_fingerprint: u64,
+}
+
+impl WarmStart {
+    fn new(graph: &FastGraph, source: NodeId) -> Self {
+        WarmStart {
+            source,
+            graph_fingerprint: graph.get_fingerprint(),
+        }
+    }
+}
+
 struct Data {
     settled: bool,
     weight: Weight,
@@ -393,12 +1418,44 @@ impl Data {
     }
 }
 
+/// Removes the cycles from the given nodes of a shortest path. With zero weight edges the
+/// contraction hierarchy can contain shortcuts that lead around a cycle of zero weight edges, and
+/// these would show up in the unpacked path otherwise.
+fn remove_cycles(nodes: &mut Vec<NodeId>) {
+    let mut positions: HashMap<NodeId, usize> = HashMap::with_capacity(nodes.len());
+    let mut result = Vec::with_capacity(nodes.len());
+    for node in nodes.drain(..) {
+        if let Some(&position) = positions.get(&node) {
+            for removed in result.drain(position + 1..) {
+                positions.remove(&removed);
+            }
+        } else {
+            positions.insert(node, result.len());
+            result.push(node);
+        }
+    }
+    *nodes = result;
+}
+
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use crate::fast_graph::FastGraphEdge;
+    use crate::input_graph::InputGraph;
 
     use super::*;
 
+    #[test]
+    fn remove_cycles() {
+        let mut nodes = vec![0, 1, 2, 3, 1, 4, 5, 6, 4, 7, 4, 8];
+        super::remove_cycles(&mut nodes);
+        assert_eq!(vec![0, 1, 4, 8], nodes);
+        let mut nodes = vec![3, 2, 1];
+        super::remove_cycles(&mut nodes);
+        assert_eq!(vec![3, 2, 1], nodes);
+    }
+
     #[test]
     fn unpack_fwd_single() {
         // 0 -> 1
@@ -406,7 +1463,10 @@ mod tests {
         g.edges_fwd
             .push(FastGraphEdge::new(0, 1, 3, INVALID_EDGE, INVALID_EDGE));
         let mut nodes = vec![];
-        PathCalculator::unpack_fwd(&g, &mut nodes, 0, false);
+        assert_eq!(
+            0,
+            PathCalculator::unpack_fwd(&g, &mut nodes, 0, false, &mut false)
+        );
         assert_eq!(nodes, vec![0]);
     }
 
@@ -421,7 +1481,299 @@ mod tests {
             .push(FastGraphEdge::new(2, 1, 3, INVALID_EDGE, INVALID_EDGE));
         g.first_edge_ids_fwd = vec![0, 2, 0, 0];
         let mut nodes = vec![];
-        PathCalculator::unpack_fwd(&g, &mut nodes, 1, false);
+        assert_eq!(
+            1,
+            PathCalculator::unpack_fwd(&g, &mut nodes, 1, false, &mut false)
+        );
         assert_eq!(nodes, vec![1, 0]);
     }
+
+    #[test]
+    fn query_stats() {
+        // 0 -> 1 -> 2 -> 3
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 1);
+        g.add_edge(2, 3, 1);
+        g.freeze();
+        // contracting 1 and then 2 yields the shortcut 0 -> 3, which contains the shortcut 0 -> 2
+        let fast_graph = crate::prepare_with_order(&g, &[1, 2, 0, 3]).unwrap();
+        let mut calc = PathCalculator::new(fast_graph.get_num_nodes());
+        assert_eq!(&QueryStats::default(), calc.get_stats());
+        let path = calc.calc_path(&fast_graph, 0, 3).unwrap();
+        assert_eq!(&vec![0, 1, 2, 3], path.get_nodes());
+        let stats = calc.get_stats().clone();
+        assert_eq!(2, stats.get_max_unpack_depth());
+        assert!(stats.get_num_settled_fwd() > 0);
+        assert!(stats.get_num_settled_bwd() > 0);
+        assert!(
+            stats.get_num_heap_pushes()
+                >= stats.get_num_settled_fwd() + stats.get_num_settled_bwd()
+        );
+        // the stats are reset for every query
+        assert!(calc.calc_path(&fast_graph, 2, 3).is_some());
+        assert_eq!(0, calc.get_stats().get_max_unpack_depth());
+        assert!(calc.calc_path(&fast_graph, 3, 0).is_none());
+        assert_eq!(0, calc.get_stats().get_max_unpack_depth());
+    }
+
+    #[test]
+    fn reuse_for_graphs_of_different_sizes() {
+        // 0 -> 1 -> 2 -> 3
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 1);
+        g.add_edge(2, 3, 1);
+        g.freeze();
+        let large = crate::prepare(&g);
+        // 0 -> 1
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 5);
+        g.freeze();
+        let small = crate::prepare(&g);
+        let mut calc = PathCalculator::new(0);
+        for _ in 0..2 {
+            let path = calc.for_graph(&large).calc_path(&large, 0, 3).unwrap();
+            assert_eq!(&vec![0, 1, 2, 3], path.get_nodes());
+            assert!(calc.calc_path_warm_start(&large, 0, 2).is_some());
+            let path = calc.for_graph(&small).calc_path(&small, 0, 1).unwrap();
+            assert_eq!(5, path.get_weight());
+            assert!(calc.calc_path(&small, 1, 0).is_none());
+            // the warm start of the larger graph must not be reused
+            assert_eq!(
+                5,
+                calc.calc_path_warm_start(&small, 0, 1)
+                    .unwrap()
+                    .get_weight()
+            );
+        }
+    }
+
+    #[test]
+    fn warm_start_after_update_weights() {
+        // 0 -> 1 -> 2 -> 3
+        // |              ^
+        // 4 -------------/
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 10);
+        g.add_edge(1, 2, 10);
+        g.add_edge(2, 3, 10);
+        g.add_edge(0, 4, 20);
+        g.add_edge(4, 3, 20);
+        g.freeze();
+        let mut fast_graph = crate::prepare(&g);
+        let mut calc = PathCalculator::new(fast_graph.get_num_nodes());
+        calc.set_source(&fast_graph, 0);
+        assert_eq!(30, calc.calc_path_to(&fast_graph, 3).unwrap().get_weight());
+        fast_graph.update_weights(&[(0, 4, 5), (4, 3, 5)]).unwrap();
+        // the stored forward search is outdated and must not be used
+        let path = calc.calc_path_warm_start(&fast_graph, 0, 3).unwrap();
+        assert_eq!(10, path.get_weight());
+        assert_eq!(&vec![0, 4, 3], path.get_nodes());
+        fast_graph.update_weights(&[(1, 2, 1)]).unwrap();
+        calc.set_source(&fast_graph, 0);
+        assert_eq!(11, calc.calc_path_to(&fast_graph, 2).unwrap().get_weight());
+        assert_eq!(
+            calc.calc_weight(&fast_graph, 0, 3),
+            calc.calc_path_warm_start(&fast_graph, 0, 3)
+                .map(|p| p.get_weight())
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "the source was set for another graph")]
+    fn panic_if_calc_path_to_after_update_weights() {
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 10);
+        g.freeze();
+        let mut fast_graph = crate::prepare(&g);
+        let mut calc = PathCalculator::new(fast_graph.get_num_nodes());
+        calc.set_source(&fast_graph, 0);
+        fast_graph.update_weights(&[(0, 1, 5)]).unwrap();
+        calc.calc_path_to(&fast_graph, 1);
+    }
+
+    #[test]
+    fn set_source() {
+        // 0 -> 1 -> 2
+        // |         ^
+        // 3 --------/
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 1);
+        g.add_edge(0, 3, 1);
+        g.add_edge(3, 2, 3);
+        g.freeze();
+        let fast_graph = crate::prepare(&g);
+        let mut calc = PathCalculator::new(fast_graph.get_num_nodes());
+        assert_eq!(None, calc.get_source());
+        calc.set_source(&fast_graph, 0);
+        assert_eq!(Some(0), calc.get_source());
+        let num_settled_fwd = calc.get_stats().get_num_settled_fwd();
+        assert!(num_settled_fwd > 0);
+        let path = calc.calc_path_to(&fast_graph, 2).unwrap();
+        assert_eq!(&vec![0, 1, 2], path.get_nodes());
+        // only the backward search is run for each target
+        assert_eq!(0, calc.get_stats().get_num_settled_fwd());
+        assert_eq!(1, calc.calc_path_to(&fast_graph, 3).unwrap().get_weight());
+        assert!(calc.calc_path_to(&fast_graph, 0).is_some());
+        // setting the same source again does not repeat the forward search
+        calc.set_source(&fast_graph, 0);
+        assert_eq!(0, calc.get_stats().get_num_settled_fwd());
+        // other queries discard the stored forward search
+        calc.calc_path(&fast_graph, 1, 2);
+        assert_eq!(None, calc.get_source());
+    }
+
+    #[test]
+    #[should_panic(expected = "no source is set")]
+    fn calc_path_to_without_source() {
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.freeze();
+        let fast_graph = crate::prepare(&g);
+        let mut calc = PathCalculator::new(fast_graph.get_num_nodes());
+        calc.calc_path_to(&fast_graph, 1);
+    }
+
+    #[test]
+    fn errors_instead_of_panics() {
+        // 0 -> 1 -> 2
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 1);
+        g.freeze();
+        let fast_graph = crate::prepare(&g);
+        let mut calc = PathCalculator::new(fast_graph.get_num_nodes());
+        let invalid = Error::InvalidNode {
+            node: 3,
+            num_nodes: 3,
+        };
+        assert_eq!(
+            Ok(Some(2)),
+            calc.try_calc_path(&fast_graph, 0, 2)
+                .map(|p| p.map(|p| p.get_weight()))
+        );
+        assert_eq!(Err(invalid), calc.try_calc_path(&fast_graph, 3, 2));
+        assert_eq!(Ok(None), calc.try_calc_weight(&fast_graph, 2, 0));
+        assert_eq!(Err(invalid), calc.try_calc_weight(&fast_graph, 0, 3));
+        assert_eq!(
+            Ok(vec![1, 2]),
+            calc.try_calc_weights_one_to_many(&fast_graph, 0, &[1, 2])
+        );
+        assert_eq!(
+            Err(invalid),
+            calc.try_calc_weights_one_to_many(&fast_graph, 0, &[1, 3])
+        );
+        assert_eq!(
+            Ok(None),
+            calc.try_calc_path_multiple_sources_and_targets(&fast_graph, vec![], vec![(1, 0)])
+        );
+        assert_eq!(
+            Err(invalid),
+            calc.try_calc_path_multiple_sources_and_targets(
+                &fast_graph,
+                vec![(0, 0)],
+                vec![(3, 0)]
+            )
+        );
+        let mut calc = PathCalculator::new(5);
+        assert_eq!(
+            Err(Error::NodeCountMismatch {
+                graph_nodes: 3,
+                calculator_nodes: 5
+            }),
+            calc.try_calc_path(&fast_graph, 0, 2)
+        );
+    }
+
+    #[test]
+    fn max_settled_nodes() {
+        // 0 -> 1 -> 2 -> 3 -> 4
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 1);
+        g.add_edge(2, 3, 1);
+        g.add_edge(3, 4, 1);
+        g.freeze();
+        // the forward search from 0 has to settle all nodes, the backward search only node 4
+        let fast_graph = crate::prepare_with_order(&g, &[0, 1, 2, 3, 4]).unwrap();
+        let mut calc = PathCalculator::new(fast_graph.get_num_nodes());
+        calc.set_max_settled_nodes(Some(3));
+        assert_eq!(Some(3), calc.get_max_settled_nodes());
+        assert!(calc.calc_path(&fast_graph, 0, 4).is_none());
+        assert!(calc.get_stats().is_settled_node_limit_reached());
+        assert_eq!(3, calc.get_stats().get_num_settled_fwd());
+        let path = calc.calc_path(&fast_graph, 0, 2).unwrap();
+        assert!(path.is_exact());
+        assert!(!calc.get_stats().is_settled_node_limit_reached());
+        calc.set_max_settled_nodes(None);
+        let path = calc.calc_path(&fast_graph, 0, 4).unwrap();
+        assert!(path.is_exact());
+        assert_eq!(4, path.get_weight());
+    }
+
+    #[test]
+    fn record_search_space() {
+        // 0 -> 1 -> 2
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 2);
+        g.freeze();
+        let fast_graph = crate::prepare_with_order(&g, &[0, 2, 1]).unwrap();
+        let mut calc = PathCalculator::new(fast_graph.get_num_nodes());
+        assert!(calc.calc_path(&fast_graph, 0, 2).is_some());
+        assert!(calc.get_search_space().is_empty());
+        calc.set_record_search_space(true);
+        assert!(calc.calc_path(&fast_graph, 0, 2).is_some());
+        let search_space = calc.get_search_space();
+        assert_eq!(
+            calc.get_stats().get_num_settled_fwd() + calc.get_stats().get_num_settled_bwd(),
+            search_space.len()
+        );
+        assert!(search_space.contains(&SettledNode {
+            node: 1,
+            weight: 1,
+            direction: SearchDirection::Forward
+        }));
+        assert!(search_space.contains(&SettledNode {
+            node: 1,
+            weight: 2,
+            direction: SearchDirection::Backward
+        }));
+        assert_eq!(Some(1), calc.get_stats().get_meeting_node());
+        assert!(calc.calc_path(&fast_graph, 2, 0).is_none());
+        assert_eq!(None, calc.get_stats().get_meeting_node());
+        // the buffer is reset for every query
+        assert!(calc.calc_path(&fast_graph, 1, 1).is_some());
+        assert_eq!(2, calc.get_search_space().len());
+        assert_eq!(Some(1), calc.get_stats().get_meeting_node());
+        calc.set_record_search_space(false);
+        assert!(calc.get_search_space().is_empty());
+    }
+
+    #[test]
+    fn cancel_query() {
+        // 0 -> 1 -> 2 -> 3
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 1);
+        g.add_edge(2, 3, 1);
+        g.freeze();
+        let fast_graph = crate::prepare(&g);
+        let mut calc = PathCalculator::new(fast_graph.get_num_nodes());
+        let token = CancellationToken::new();
+        let path = calc.calc_path_cancellable(&fast_graph, 0, 3, &token);
+        assert_eq!(Ok(3), path.map(|p| p.unwrap().get_weight()));
+        token.clone().cancel();
+        assert!(token.is_cancelled());
+        let path = calc.calc_path_cancellable(&fast_graph, 0, 3, &token);
+        assert_eq!(Err(Cancelled), path);
+        let token = CancellationToken::with_timeout(Duration::from_secs(0));
+        let path = calc.calc_path_cancellable(&fast_graph, 0, 3, &token);
+        assert_eq!(Err(Cancelled), path);
+        let token = CancellationToken::with_timeout(Duration::from_secs(1000));
+        let path = calc.calc_path_cancellable(&fast_graph, 3, 0, &token);
+        assert_eq!(Ok(None), path);
+    }
 }
@@ -17,14 +17,19 @@
  * under the License.
  */
 
+use std::collections::HashSet;
+
 use crate::constants::Weight;
-use crate::constants::{NodeId, INVALID_NODE};
+use crate::constants::{EdgeId, NodeId, INVALID_EDGE, INVALID_NODE};
 use crate::input_graph::InputGraph;
 
 pub struct PreparationGraph {
     pub out_edges: Vec<Vec<Arc>>,
     pub in_edges: Vec<Vec<Arc>>,
     num_nodes: usize,
+    /// the `(from, to)` arcs that must not be bypassed by witnesses that are longer than the
+    /// shortcut they replace, see `Params::approximation_epsilon`
+    protected_arcs: HashSet<(NodeId, NodeId)>,
 }
 
 impl PreparationGraph {
@@ -35,21 +40,50 @@ impl PreparationGraph {
             out_edges,
             in_edges,
             num_nodes,
+            protected_arcs: HashSet::new(),
         }
     }
 
     pub fn from_input_graph(input_graph: &InputGraph) -> Self {
         let mut graph = PreparationGraph::new(input_graph.get_num_nodes());
-        for e in input_graph.get_edges() {
-            graph.add_edge(e.from, e.to, e.weight);
+        for (id, e) in input_graph.get_edges().iter().enumerate() {
+            graph.add_input_edge(e.from, e.to, e.weight, id);
         }
         graph
     }
 
+    /// Replaces the edges of this graph with those of the given input graph. Unlike
+    /// `from_input_graph` this keeps the memory that was allocated for the edges of every node.
+    pub fn reset_from_input_graph(&mut self, input_graph: &InputGraph) {
+        let num_nodes = input_graph.get_num_nodes();
+        self.out_edges
+            .resize_with(num_nodes, || Vec::with_capacity(3));
+        self.in_edges
+            .resize_with(num_nodes, || Vec::with_capacity(3));
+        for edges in self.out_edges.iter_mut().chain(self.in_edges.iter_mut()) {
+            edges.clear();
+        }
+        self.num_nodes = num_nodes;
+        self.protected_arcs.clear();
+        for (id, e) in input_graph.get_edges().iter().enumerate() {
+            self.add_input_edge(e.from, e.to, e.weight, id);
+        }
+    }
+
     pub fn add_edge(&mut self, from: NodeId, to: NodeId, weight: Weight) {
         self.add_edge_or_shortcut(from, to, weight, INVALID_NODE);
     }
 
+    /// Adds the edge with the given id of the input graph, see `FastGraphEdge::input_edge_id`
+    pub fn add_input_edge(&mut self, from: NodeId, to: NodeId, weight: Weight, id: EdgeId) {
+        self.assert_valid_node_id(to);
+        let mut arc = Arc::new(to, weight, INVALID_NODE);
+        arc.input_edge_id = id;
+        self.out_edges[from].push(arc.clone());
+        arc.adj_node = from;
+        self.in_edges[to].push(arc);
+    }
+
     pub fn add_edge_or_shortcut(
         &mut self,
         from: NodeId,
@@ -93,6 +127,8 @@ impl PreparationGraph {
                         in_edge.weight = weight;
                         out_edge.center_node = center_node;
                         in_edge.center_node = center_node;
+                        out_edge.input_edge_id = INVALID_EDGE;
+                        in_edge.input_edge_id = INVALID_EDGE;
                     }
                 }
                 return true;
@@ -105,7 +141,23 @@ impl PreparationGraph {
         self.num_nodes
     }
 
+    pub fn protect_arc(&mut self, from: NodeId, to: NodeId) {
+        self.protected_arcs.insert((from, to));
+    }
+
+    pub fn is_protected(&self, from: NodeId, to: NodeId) -> bool {
+        !self.protected_arcs.is_empty() && self.protected_arcs.contains(&(from, to))
+    }
+
     pub fn disconnect(&mut self, node: NodeId) {
+        if !self.protected_arcs.is_empty() {
+            for edge in &self.out_edges[node] {
+                self.protected_arcs.remove(&(node, edge.adj_node));
+            }
+            for edge in &self.in_edges[node] {
+                self.protected_arcs.remove(&(edge.adj_node, node));
+            }
+        }
         for i in 0..self.out_edges[node].len() {
             let adj = self.out_edges[node][i].adj_node;
             self.remove_in_edge(adj, node);
@@ -159,6 +211,8 @@ pub struct Arc {
     pub adj_node: NodeId,
     pub weight: Weight,
     pub center_node: NodeId,
+    /// the id of the input edge this arc was created from, see `FastGraphEdge::input_edge_id`
+    pub input_edge_id: EdgeId,
 }
 
 impl Arc {
@@ -167,6 +221,7 @@ impl Arc {
             adj_node,
             weight,
             center_node,
+            input_edge_id: INVALID_EDGE,
         }
     }
 }
@@ -225,7 +280,7 @@ mod tests {
         assert_eq!(0, adj_nodes(g.get_in_edges(2)).len());
     }
 
-    fn adj_nodes(edges: &Vec<Arc>) -> Vec<NodeId> {
+    fn adj_nodes(edges: &[Arc]) -> Vec<NodeId> {
         edges.iter().map(|e| e.adj_node).collect::<Vec<NodeId>>()
     }
 }
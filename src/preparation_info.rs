@@ -0,0 +1,88 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::fast_graph_builder::{Params, ParamsWithOrder};
+use crate::weight_unit::WeightUnit;
+
+/// The parameters that were used to prepare a `FastGraph`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum PreparationParams {
+    /// The graph was prepared using `prepare()` or `prepare_with_params()`
+    Heuristic(Params),
+    /// The graph was prepared using `prepare_with_order()` or `prepare_with_order_with_params()`
+    WithOrder(ParamsWithOrder),
+    /// The graph was not created by this crate's preparation, e.g. using `FastGraph::new()`
+    Unknown,
+    /// The graph was created using `CustomizableGraph::customize()`
+    Customized,
+}
+
+/// Describes how a `FastGraph` was produced. This is stored (and serialized) along with the graph
+/// so it is possible to find out how a given graph was built and to reproduce it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PreparationInfo {
+    params: PreparationParams,
+    crate_version: String,
+    created_at: Option<u64>,
+    #[serde(default)]
+    weight_unit: Option<WeightUnit>,
+}
+
+impl PreparationInfo {
+    pub fn new(params: PreparationParams) -> Self {
+        PreparationInfo {
+            params,
+            crate_version: String::from(env!("CARGO_PKG_VERSION")),
+            created_at: None,
+            weight_unit: None,
+        }
+    }
+
+    pub fn get_params(&self) -> &PreparationParams {
+        &self.params
+    }
+
+    /// The version of this crate that was used to prepare the graph.
+    pub fn get_crate_version(&self) -> &str {
+        &self.crate_version
+    }
+
+    /// The creation time in seconds since the unix epoch, if it was set using
+    /// `FastGraph::set_created_at()`.
+    pub fn get_created_at(&self) -> Option<u64> {
+        self.created_at
+    }
+
+    pub(crate) fn set_created_at(&mut self, seconds_since_epoch: u64) {
+        self.created_at = Some(seconds_since_epoch);
+    }
+
+    /// The unit of the weights, if it was set for the input graph or using
+    /// `FastGraph::set_weight_unit()`.
+    pub fn get_weight_unit(&self) -> Option<WeightUnit> {
+        self.weight_unit
+    }
+
+    pub(crate) fn set_weight_unit(&mut self, weight_unit: Option<WeightUnit>) {
+        self.weight_unit = weight_unit;
+    }
+}
@@ -0,0 +1,98 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use crate::constants::{NodeId, Weight};
+
+/// The direction of the search that settled a node, see `SettledNode`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchDirection {
+    Forward,
+    Backward,
+}
+
+/// A node settled during a query along with its weight from the start (forward search) or to the
+/// end (backward search), see `PathCalculator::set_record_search_space`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SettledNode {
+    pub node: NodeId,
+    pub weight: Weight,
+    pub direction: SearchDirection,
+}
+
+/// Counters describing the work done by the most recent query of a `PathCalculator`, e.g. to
+/// compare the query performance of graphs that were prepared using different `Params`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryStats {
+    pub(crate) num_settled_fwd: usize,
+    pub(crate) num_settled_bwd: usize,
+    pub(crate) num_heap_pushes: usize,
+    pub(crate) num_stalled: usize,
+    pub(crate) num_pruned: usize,
+    pub(crate) max_unpack_depth: usize,
+    pub(crate) settled_node_limit_reached: bool,
+    pub(crate) meeting_node: Option<NodeId>,
+}
+
+impl QueryStats {
+    /// The number of nodes settled by the forward search
+    pub fn get_num_settled_fwd(&self) -> usize {
+        self.num_settled_fwd
+    }
+
+    /// The number of nodes settled by the backward search
+    pub fn get_num_settled_bwd(&self) -> usize {
+        self.num_settled_bwd
+    }
+
+    /// The number of items pushed to the heaps of both searches
+    pub fn get_num_heap_pushes(&self) -> usize {
+        self.num_heap_pushes
+    }
+
+    /// The number of nodes that were not settled, because the stall-on-demand optimization found
+    /// a shorter path to them
+    pub fn get_num_stalled(&self) -> usize {
+        self.num_stalled
+    }
+
+    /// The number of settled nodes whose edges were not relaxed, because the landmarks showed
+    /// that they cannot be part of a shorter path, see `PathCalculator::set_landmarks`
+    pub fn get_num_pruned(&self) -> usize {
+        self.num_pruned
+    }
+
+    /// The maximum nesting depth of the shortcuts that were unpacked to build the path. This is
+    /// zero if the path contains no shortcuts.
+    pub fn get_max_unpack_depth(&self) -> usize {
+        self.max_unpack_depth
+    }
+
+    /// Whether the search was stopped early because it reached the limit set by
+    /// `PathCalculator::set_max_settled_nodes`, in which case the result might not be optimal
+    pub fn is_settled_node_limit_reached(&self) -> bool {
+        self.settled_node_limit_reached
+    }
+
+    /// The node where the forward and the backward search met on the shortest path, or `None` if
+    /// no path was found. Together with `PathCalculator::get_search_space` this can be used to
+    /// visualize a query.
+    pub fn get_meeting_node(&self) -> Option<NodeId> {
+        self.meeting_node
+    }
+}
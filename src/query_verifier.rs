@@ -0,0 +1,183 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use crate::constants::{NodeId, Weight};
+use crate::dijkstra::Dijkstra;
+use crate::fast_graph::FastGraph;
+use crate::input_graph::InputGraph;
+use crate::path_calculator::PathCalculator;
+use crate::preparation_graph::PreparationGraph;
+use crate::shortest_path::ShortestPath;
+
+/// Re-checks every n-th query against a plain Dijkstra search on the original graph and records
+/// the queries where the results differ. This is slow and only meant as a safety net for long
+/// running services, e.g. to detect a corrupted graph file early. The verifier keeps its own copy
+/// of the input graph.
+pub struct QueryVerifier {
+    sample_interval: usize,
+    num_queries: usize,
+    num_verified: usize,
+    graph: PreparationGraph,
+    dijkstra: Dijkstra,
+    discrepancies: Vec<Discrepancy>,
+}
+
+/// A query for which the contraction hierarchy and the verification returned different weights.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Discrepancy {
+    pub source: NodeId,
+    pub target: NodeId,
+    /// The weight found by the verification, `None` if there is no path
+    pub expected: Option<Weight>,
+    /// The weight of the path returned by the query, `None` if no path was found
+    pub actual: Option<Weight>,
+}
+
+impl QueryVerifier {
+    /// Creates a verifier that checks one in `sample_interval` queries. `input_graph` must be the
+    /// graph the `FastGraph` was prepared from.
+    pub fn new(input_graph: &InputGraph, sample_interval: usize) -> Self {
+        assert!(sample_interval > 0, "the sample interval must be positive");
+        QueryVerifier {
+            sample_interval,
+            num_queries: 0,
+            num_verified: 0,
+            graph: PreparationGraph::from_input_graph(input_graph),
+            dijkstra: Dijkstra::new(input_graph.get_num_nodes()),
+            discrepancies: vec![],
+        }
+    }
+
+    /// Calculates the shortest path using the given calculator and verifies the result if this
+    /// query was sampled.
+    pub fn calc_path(
+        &mut self,
+        calc: &mut PathCalculator,
+        graph: &FastGraph,
+        source: NodeId,
+        target: NodeId,
+    ) -> Option<ShortestPath> {
+        let path = calc.calc_path(graph, source, target);
+        self.record(source, target, path.as_ref().map(|p| p.get_weight()));
+        path
+    }
+
+    /// Counts a query that was run elsewhere and verifies its weight if it was sampled. Returns
+    /// `false` if the query was verified and the weight turned out to be wrong.
+    pub fn record(&mut self, source: NodeId, target: NodeId, actual: Option<Weight>) -> bool {
+        self.num_queries += 1;
+        if self.num_queries % self.sample_interval != 0 {
+            return true;
+        }
+        self.verify(source, target, actual)
+    }
+
+    /// Verifies the weight of the given query regardless of the sampling. Returns `false` and
+    /// records a discrepancy if the weight is wrong.
+    pub fn verify(&mut self, source: NodeId, target: NodeId, actual: Option<Weight>) -> bool {
+        self.num_verified += 1;
+        let expected = self
+            .dijkstra
+            .calc_path(&self.graph, source, target)
+            .map(|p| p.get_weight());
+        if expected == actual {
+            return true;
+        }
+        self.discrepancies.push(Discrepancy {
+            source,
+            target,
+            expected,
+            actual,
+        });
+        false
+    }
+
+    pub fn get_num_queries(&self) -> usize {
+        self.num_queries
+    }
+
+    pub fn get_num_verified(&self) -> usize {
+        self.num_verified
+    }
+
+    pub fn get_discrepancies(&self) -> &[Discrepancy] {
+        &self.discrepancies
+    }
+
+    /// Removes and returns the discrepancies recorded so far, e.g. to report them periodically.
+    pub fn take_discrepancies(&mut self) -> Vec<Discrepancy> {
+        std::mem::take(&mut self.discrepancies)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_and_detect_discrepancies() {
+        // 0 -> 1 -> 2
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 3);
+        g.add_edge(1, 2, 4);
+        g.freeze();
+        let fast_graph = crate::prepare(&g);
+        let mut calc = crate::create_calculator(&fast_graph);
+        let mut verifier = QueryVerifier::new(&g, 2);
+        for _ in 0..5 {
+            assert!(verifier.calc_path(&mut calc, &fast_graph, 0, 2).is_some());
+            assert!(verifier.calc_path(&mut calc, &fast_graph, 2, 0).is_none());
+        }
+        assert_eq!(10, verifier.get_num_queries());
+        assert_eq!(5, verifier.get_num_verified());
+        assert!(verifier.get_discrepancies().is_empty());
+
+        // a corrupted graph where the edge 1 -> 2 got a different weight
+        let mut corrupted = fast_graph.clone();
+        for edges in [&mut corrupted.edges_fwd, &mut corrupted.edges_bwd] {
+            for edge in edges.iter_mut() {
+                if edge.base_node + edge.adj_node == 3 {
+                    edge.weight = 9;
+                }
+            }
+        }
+        let mut calc = crate::create_calculator(&corrupted);
+        let mut verifier = QueryVerifier::new(&g, 1);
+        assert!(verifier.calc_path(&mut calc, &corrupted, 1, 2).is_some());
+        assert!(!verifier.record(0, 2, Some(8)));
+        assert_eq!(
+            vec![
+                Discrepancy {
+                    source: 1,
+                    target: 2,
+                    expected: Some(4),
+                    actual: Some(9),
+                },
+                Discrepancy {
+                    source: 0,
+                    target: 2,
+                    expected: Some(7),
+                    actual: Some(8),
+                }
+            ],
+            verifier.take_discrepancies()
+        );
+        assert!(verifier.get_discrepancies().is_empty());
+    }
+}
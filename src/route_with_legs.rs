@@ -0,0 +1,103 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use crate::constants::NodeId;
+use crate::constants::Weight;
+use crate::shortest_path::ShortestPath;
+
+/// A route visiting a sequence of waypoints, consisting of one `ShortestPath` (leg) between each
+/// pair of consecutive waypoints.
+#[derive(Debug, Clone)]
+pub struct RouteWithLegs {
+    legs: Vec<ShortestPath>,
+    total_weight: Weight,
+}
+
+impl RouteWithLegs {
+    pub fn new(legs: Vec<ShortestPath>) -> Self {
+        assert!(!legs.is_empty(), "a route needs at least one leg");
+        let total_weight = legs.iter().map(|leg| leg.get_weight()).sum();
+        RouteWithLegs { legs, total_weight }
+    }
+
+    pub fn get_source(&self) -> NodeId {
+        self.legs[0].get_source()
+    }
+
+    pub fn get_target(&self) -> NodeId {
+        self.legs[self.legs.len() - 1].get_target()
+    }
+
+    pub fn get_legs(&self) -> &Vec<ShortestPath> {
+        &self.legs
+    }
+
+    pub fn get_total_weight(&self) -> Weight {
+        self.total_weight
+    }
+
+    /// Combines all legs into a single `ShortestPath`. The node shared by two consecutive legs is
+    /// only included once.
+    pub fn flatten(&self) -> ShortestPath {
+        let mut nodes = self.legs[0].get_nodes().clone();
+        for leg in &self.legs[1..] {
+            nodes.extend_from_slice(&leg.get_nodes()[1..]);
+        }
+        let fingerprint = self.legs[0].get_graph_fingerprint();
+        let same_graph = self
+            .legs
+            .iter()
+            .all(|leg| leg.get_graph_fingerprint() == fingerprint);
+        let weight_unit = self.legs[0].get_weight_unit();
+        let same_unit = self
+            .legs
+            .iter()
+            .all(|leg| leg.get_weight_unit() == weight_unit);
+        ShortestPath::new(
+            self.get_source(),
+            self.get_target(),
+            self.total_weight,
+            nodes,
+        )
+        .with_graph_fingerprint(if same_graph { fingerprint } else { None })
+        .with_weight_unit(if same_unit { weight_unit } else { None })
+        .with_exact(self.legs.iter().all(|leg| leg.is_exact()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flatten() {
+        let route = RouteWithLegs::new(vec![
+            ShortestPath::new(0, 2, 5, vec![0, 1, 2]),
+            ShortestPath::singular(2),
+            ShortestPath::new(2, 4, 3, vec![2, 3, 4]),
+        ]);
+        assert_eq!(0, route.get_source());
+        assert_eq!(4, route.get_target());
+        assert_eq!(8, route.get_total_weight());
+        assert_eq!(3, route.get_legs().len());
+        let path = route.flatten();
+        assert_eq!(ShortestPath::new(0, 4, 8, vec![]), path);
+        assert_eq!(&vec![0, 1, 2, 3, 4], path.get_nodes());
+    }
+}
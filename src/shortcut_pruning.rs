@@ -0,0 +1,187 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use crate::constants::{EdgeId, INVALID_EDGE};
+use crate::fast_graph::{FastGraph, FastGraphEdge};
+use crate::fast_graph_update::UpdatedEdges;
+use crate::path_calculator::PathCalculator;
+
+/// Removes the shortcuts of `graph` for which there is a strictly shorter path between the same
+/// two nodes, see `FastGraph::prune_shortcuts`. Returns the remaining edges and the number of
+/// removed shortcuts.
+///
+/// Such a shortcut can never be part of a shortest path, so the queries do not need it. All
+/// shortcuts are checked against the original graph and removed at once, which is fine, because
+/// the edges a remaining shortcut consists of are shortest paths themselves, i.e. they are never
+/// removed.
+pub fn prune_shortcuts(graph: &FastGraph) -> (UpdatedEdges, usize) {
+    let mut calculator = PathCalculator::new(graph.get_num_nodes());
+    let mut is_useless = |edge: &FastGraphEdge, from, to| {
+        edge.is_shortcut()
+            && edge.weight > 0
+            && calculator
+                .calc_path_with_limit(graph, from, to, edge.weight - 1)
+                .is_some()
+    };
+    let keep_fwd: Vec<bool> = graph
+        .edges_fwd
+        .iter()
+        .map(|edge| !is_useless(edge, edge.base_node, edge.adj_node))
+        .collect();
+    let keep_bwd: Vec<bool> = graph
+        .edges_bwd
+        .iter()
+        .map(|edge| !is_useless(edge, edge.adj_node, edge.base_node))
+        .collect();
+    let (new_ids_fwd, first_edge_ids_fwd) = calc_new_ids(&graph.first_edge_ids_fwd, &keep_fwd);
+    let (new_ids_bwd, first_edge_ids_bwd) = calc_new_ids(&graph.first_edge_ids_bwd, &keep_bwd);
+    let convert = |edge: &FastGraphEdge| {
+        if !edge.is_shortcut() {
+            return *edge;
+        }
+        let replaced_in_edge = new_ids_bwd[edge.replaced_in_edge];
+        let replaced_out_edge = new_ids_fwd[edge.replaced_out_edge];
+        assert!(
+            replaced_in_edge != INVALID_EDGE && replaced_out_edge != INVALID_EDGE,
+            "the edges of a remaining shortcut must not be removed"
+        );
+        FastGraphEdge::new(
+            edge.base_node,
+            edge.adj_node,
+            edge.weight,
+            replaced_in_edge,
+            replaced_out_edge,
+        )
+    };
+    let edges_fwd: Vec<FastGraphEdge> = graph
+        .edges_fwd
+        .iter()
+        .zip(&keep_fwd)
+        .filter(|(_, keep)| **keep)
+        .map(|(edge, _)| convert(edge))
+        .collect();
+    let edges_bwd: Vec<FastGraphEdge> = graph
+        .edges_bwd
+        .iter()
+        .zip(&keep_bwd)
+        .filter(|(_, keep)| **keep)
+        .map(|(edge, _)| convert(edge))
+        .collect();
+    let num_removed =
+        graph.edges_fwd.len() + graph.edges_bwd.len() - edges_fwd.len() - edges_bwd.len();
+    (
+        (edges_fwd, first_edge_ids_fwd, edges_bwd, first_edge_ids_bwd),
+        num_removed,
+    )
+}
+
+/// Returns the new id of every edge (or `INVALID_EDGE` if it is removed) and the new first edge
+/// ids, given which of the edges are kept.
+fn calc_new_ids(first_edge_ids: &[EdgeId], keep: &[bool]) -> (Vec<EdgeId>, Vec<EdgeId>) {
+    let mut new_ids = vec![INVALID_EDGE; keep.len()];
+    let mut new_first_edge_ids = vec![0; first_edge_ids.len()];
+    let mut num_kept = 0;
+    for rank in 0..first_edge_ids.len() - 1 {
+        for id in first_edge_ids[rank]..first_edge_ids[rank + 1] {
+            if keep[id] {
+                new_ids[id] = num_kept;
+                num_kept += 1;
+            }
+        }
+        new_first_edge_ids[rank + 1] = num_kept;
+    }
+    (new_ids, new_first_edge_ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fast_graph_builder::ParamsWithOrder;
+    use crate::floyd_warshall::FloydWarshall;
+    use crate::input_graph::InputGraph;
+    use crate::{calc_path, prepare_with_order_with_params, WEIGHT_MAX};
+
+    #[test]
+    fn prune_superseded_shortcut() {
+        // 0 -> 1 -> 2
+        //  \-> 3 ->/
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 5);
+        g.add_edge(1, 2, 5);
+        g.add_edge(0, 3, 1);
+        g.add_edge(3, 2, 1);
+        g.freeze();
+        // without witness searches contracting node 1 adds the shortcut 0 -> 2, even though the
+        // path via node 3 is shorter
+        let params = ParamsWithOrder::new(0);
+        let mut fast_graph = prepare_with_order_with_params(&g, &[1, 0, 2, 3], &params).unwrap();
+        assert_eq!(3, fast_graph.get_num_out_edges());
+        assert_eq!(2, fast_graph.get_num_in_edges());
+        assert_eq!(1, fast_graph.prune_shortcuts());
+        assert_eq!(Ok(()), fast_graph.validate());
+        assert_eq!(2, fast_graph.get_num_out_edges());
+        assert_eq!(2, fast_graph.get_num_in_edges());
+        assert!(fast_graph.get_edges_fwd().iter().all(|e| !e.is_shortcut()));
+        let path = calc_path(&fast_graph, 0, 2).unwrap();
+        assert_eq!(&vec![0, 3, 2], path.get_nodes());
+        assert_eq!(
+            &vec![1, 2],
+            calc_path(&fast_graph, 1, 2).unwrap().get_nodes()
+        );
+    }
+
+    #[test]
+    fn prune_random_graphs() {
+        use rand::Rng;
+
+        let mut rng = crate::tests::create_rng();
+        let mut num_removed = 0;
+        for _ in 0..50 {
+            let g = InputGraph::random(|| rng.gen(), 30, 3.0);
+            let num_nodes = g.get_num_nodes();
+            // without witness searches there are many unnecessary shortcuts
+            let order: Vec<usize> = (0..num_nodes).collect();
+            let params = ParamsWithOrder::new(0);
+            let mut fast_graph = prepare_with_order_with_params(&g, &order, &params).unwrap();
+            let num_edges = fast_graph.get_num_out_edges() + fast_graph.get_num_in_edges();
+            let removed = fast_graph.prune_shortcuts();
+            assert_eq!(
+                num_edges - removed,
+                fast_graph.get_num_out_edges() + fast_graph.get_num_in_edges()
+            );
+            num_removed += removed;
+            fast_graph.validate().unwrap();
+            // pruning a second time does not find anything
+            assert_eq!(0, fast_graph.prune_shortcuts());
+            let mut fw = FloydWarshall::new(num_nodes);
+            fw.prepare(&g);
+            for source in 0..num_nodes {
+                for target in 0..num_nodes {
+                    let path = calc_path(&fast_graph, source, target);
+                    assert_eq!(
+                        fw.calc_weight(source, target),
+                        path.map_or(WEIGHT_MAX, |p| p.get_weight()),
+                        "{:?}",
+                        g
+                    );
+                }
+            }
+        }
+        assert!(num_removed > 0);
+    }
+}
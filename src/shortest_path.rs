@@ -17,10 +17,15 @@
  * under the License.
  */
 
+use crate::constants::EdgeKey;
 use crate::constants::NodeId;
 use crate::constants::Weight;
 use crate::constants::WEIGHT_MAX;
 use crate::constants::WEIGHT_ZERO;
+use crate::fast_graph::FastGraph;
+use crate::geojson;
+use crate::input_graph::InputGraph;
+use crate::weight_unit::WeightUnit;
 
 #[derive(Debug, Clone)]
 pub struct ShortestPath {
@@ -28,12 +33,17 @@ pub struct ShortestPath {
     target: NodeId,
     weight: Weight,
     nodes: Vec<NodeId>,
+    graph_fingerprint: Option<u64>,
+    weight_unit: Option<WeightUnit>,
+    exact: bool,
 }
 
 impl PartialEq for ShortestPath {
     fn eq(&self, other: &ShortestPath) -> bool {
         self.source == other.source && self.target == other.target && self.weight == other.weight
-        // do not insist on equal nodes arrays, because there can be unambiguous shortest paths
+        // do not insist on equal nodes arrays, because there can be unambiguous shortest paths.
+        // the graph fingerprint and weight unit are ignored as well, so paths can be compared
+        // across graphs
     }
 }
 
@@ -44,6 +54,9 @@ impl ShortestPath {
             target,
             weight,
             nodes,
+            graph_fingerprint: None,
+            weight_unit: None,
+            exact: true,
         }
     }
 
@@ -53,6 +66,9 @@ impl ShortestPath {
             target: node,
             weight: WEIGHT_ZERO,
             nodes: vec![node],
+            graph_fingerprint: None,
+            weight_unit: None,
+            exact: true,
         }
     }
 
@@ -62,6 +78,9 @@ impl ShortestPath {
             target,
             weight: WEIGHT_MAX,
             nodes: vec![],
+            graph_fingerprint: None,
+            weight_unit: None,
+            exact: true,
         }
     }
 
@@ -84,4 +103,195 @@ impl ShortestPath {
     pub fn is_found(&self) -> bool {
         self.weight != WEIGHT_MAX
     }
+
+    /// The fingerprint of the graph this path was calculated on (see `FastGraph::get_fingerprint`),
+    /// if it was calculated by a `PathCalculator`. This can be used to detect that a cached path
+    /// belongs to an outdated graph.
+    pub fn get_graph_fingerprint(&self) -> Option<u64> {
+        self.graph_fingerprint
+    }
+
+    pub(crate) fn with_graph_fingerprint(mut self, graph_fingerprint: Option<u64>) -> Self {
+        self.graph_fingerprint = graph_fingerprint;
+        self
+    }
+
+    /// The unit of the weight of the graph this path was calculated on, if it is known, see
+    /// `FastGraph::get_weight_unit`.
+    pub fn get_weight_unit(&self) -> Option<WeightUnit> {
+        self.weight_unit
+    }
+
+    pub(crate) fn with_weight_unit(mut self, weight_unit: Option<WeightUnit>) -> Self {
+        self.weight_unit = weight_unit;
+        self
+    }
+
+    /// Whether this is guaranteed to be the shortest path. This is only false if the search was
+    /// stopped early, see `PathCalculator::set_max_settled_nodes`.
+    pub fn is_exact(&self) -> bool {
+        self.exact
+    }
+
+    pub(crate) fn with_exact(mut self, exact: bool) -> Self {
+        self.exact = exact;
+        self
+    }
+
+    /// Returns the ids of the edges of this path (see `InputGraph::get_edge_id`), or `None` if
+    /// two consecutive nodes are not connected by an edge of the given graph, e.g. because the
+    /// path was calculated on another graph.
+    pub fn as_edge_ids(&self, input_graph: &InputGraph) -> Option<Vec<usize>> {
+        self.nodes
+            .windows(2)
+            .map(|pair| input_graph.get_edge_id(pair[0], pair[1]))
+            .collect()
+    }
+
+    /// Returns the keys of the edges of this path that were given in
+    /// `InputGraph::add_edge_with_key`, or `None` if one of the edges does not exist in the given
+    /// graph or was added without a key.
+    pub fn as_edge_keys(&self, input_graph: &InputGraph) -> Option<Vec<EdgeKey>> {
+        self.nodes
+            .windows(2)
+            .map(|pair| {
+                let edge_id = input_graph.get_edge_id(pair[0], pair[1])?;
+                input_graph.get_edge_key(edge_id)
+            })
+            .collect()
+    }
+
+    /// Like `as_edge_ids`, but uses the ids that were stored in the given graph during the
+    /// preparation (see `FastGraphEdge::input_edge_id`), so the input graph is not needed. Returns
+    /// `None` if the id of one of the edges is not known, e.g. because the path was calculated on
+    /// another graph.
+    pub fn as_input_edge_ids(&self, fast_graph: &FastGraph) -> Option<Vec<usize>> {
+        self.nodes
+            .windows(2)
+            .map(|pair| fast_graph.get_input_edge_id(pair[0], pair[1]))
+            .collect()
+    }
+
+    /// Returns this path as GeoJSON, i.e. a FeatureCollection with a LineString along its nodes,
+    /// e.g. to look at it in a map viewer. `coordinates` contains the (latitude, longitude) pair of
+    /// every node. The properties of the feature are the source, the target and the weight.
+    pub fn to_geojson(&self, coordinates: &[(f64, f64)]) -> String {
+        geojson::path_to_geojson(self, coordinates)
+    }
+
+    /// Encodes the given edge ids compactly for storage, see `as_edge_ids`. Every id is stored as
+    /// the difference to the previous one using a variable number of bytes, so the edges of a
+    /// path that are close to each other in the graph typically only take one or two bytes.
+    pub fn encode_edge_ids(edge_ids: &[usize]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(edge_ids.len() * 2);
+        let mut prev = 0;
+        for edge_id in edge_ids {
+            let delta = *edge_id as i64 - prev as i64;
+            // zigzag encoding, so small negative differences stay small as well
+            let mut value = ((delta << 1) ^ (delta >> 63)) as u64;
+            while value >= 0x80 {
+                bytes.push((value as u8) | 0x80);
+                value >>= 7;
+            }
+            bytes.push(value as u8);
+            prev = *edge_id;
+        }
+        bytes
+    }
+
+    /// Decodes edge ids that were encoded using `encode_edge_ids`.
+    pub fn decode_edge_ids(bytes: &[u8]) -> Result<Vec<usize>, String> {
+        let mut edge_ids = vec![];
+        let mut prev: i64 = 0;
+        let mut value: u64 = 0;
+        let mut shift = 0;
+        for byte in bytes {
+            if shift > 63 {
+                return Err("invalid edge id encoding, value is too long".to_string());
+            }
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 != 0 {
+                shift += 7;
+                continue;
+            }
+            let delta = (value >> 1) as i64 ^ -((value & 1) as i64);
+            let edge_id = prev
+                .checked_add(delta)
+                .filter(|id| *id >= 0)
+                .ok_or_else(|| "invalid edge id encoding, negative edge id".to_string())?;
+            edge_ids.push(edge_id as usize);
+            prev = edge_id;
+            value = 0;
+            shift = 0;
+        }
+        if shift != 0 {
+            return Err("invalid edge id encoding, unexpected end of input".to_string());
+        }
+        Ok(edge_ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn as_edge_ids() {
+        // 0 -> 1 -> 2
+        //  \-------/
+        let mut g = InputGraph::new();
+        g.add_edge(0, 2, 5);
+        g.add_edge(1, 2, 1);
+        g.add_edge(0, 1, 1);
+        g.add_edge(0, 1, 3);
+        g.freeze();
+        assert_eq!(Some(0), g.get_edge_id(0, 1));
+        assert_eq!(Some(1), g.get_edge_id(0, 2));
+        assert_eq!(Some(2), g.get_edge_id(1, 2));
+        assert_eq!(None, g.get_edge_id(2, 0));
+        let path = ShortestPath::new(0, 2, 2, vec![0, 1, 2]);
+        assert_eq!(Some(vec![0, 2]), path.as_edge_ids(&g));
+        assert_eq!(Some(vec![]), ShortestPath::singular(1).as_edge_ids(&g));
+        let path = ShortestPath::new(2, 1, 5, vec![2, 0, 1]);
+        assert_eq!(None, path.as_edge_ids(&g));
+    }
+
+    #[test]
+    fn as_edge_keys() {
+        // 0 -> 1 -> 2 -> 3
+        //  \-------/
+        let mut g = InputGraph::new();
+        g.add_edge_with_key(0, 2, 5, 100);
+        g.add_edge_bidir_with_key(1, 2, 1, 200);
+        // only the key of the parallel edge with the lowest weight is kept
+        g.add_edge_with_key(0, 1, 3, 301);
+        g.add_edge_with_key(0, 1, 1, 300);
+        g.add_edge(2, 3, 1);
+        g.freeze();
+        assert_eq!(Some(300), g.get_edge_key(g.get_edge_id(0, 1).unwrap()));
+        assert_eq!(Some(200), g.get_edge_key(g.get_edge_id(2, 1).unwrap()));
+        assert_eq!(None, g.get_edge_key(g.get_edge_id(2, 3).unwrap()));
+        let path = ShortestPath::new(0, 2, 2, vec![0, 1, 2]);
+        assert_eq!(Some(vec![300, 200]), path.as_edge_keys(&g));
+        let path = ShortestPath::new(2, 0, 2, vec![2, 1, 0]);
+        assert_eq!(None, path.as_edge_keys(&g));
+        let path = ShortestPath::new(0, 3, 6, vec![0, 2, 3]);
+        assert_eq!(None, path.as_edge_keys(&g));
+        assert_eq!(Some(vec![]), ShortestPath::singular(1).as_edge_keys(&g));
+    }
+
+    #[test]
+    fn encode_and_decode_edge_ids() {
+        let edge_ids = vec![5, 6, 4, 0, 1000, 300_000, usize::MAX >> 1, 3];
+        let bytes = ShortestPath::encode_edge_ids(&edge_ids);
+        assert_eq!(&[10, 2, 3, 7], &bytes[..4]);
+        assert_eq!(Ok(edge_ids), ShortestPath::decode_edge_ids(&bytes));
+        assert_eq!(Ok(vec![]), ShortestPath::decode_edge_ids(&[]));
+        assert!(ShortestPath::encode_edge_ids(&[]).is_empty());
+        // the last byte indicates that more bytes follow
+        assert!(ShortestPath::decode_edge_ids(&[0x80]).is_err());
+        // -1
+        assert!(ShortestPath::decode_edge_ids(&[1]).is_err());
+        assert!(ShortestPath::decode_edge_ids(&[0xff; 11]).is_err());
+    }
 }
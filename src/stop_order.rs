@@ -0,0 +1,216 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use crate::constants::{Weight, WEIGHT_MAX};
+
+/// The maximum number of stops for which the optimal order can be calculated. The running time
+/// and memory grow exponentially with the number of stops.
+pub const MAX_STOPS: usize = 16;
+
+/// Finds the order of stops that minimizes the total weight of a route that begins at the start,
+/// visits all stops and then goes to the end (Held-Karp). `weights[i][j]` is the weight from
+/// location `i` to location `j`, where location `0` is the start, locations `1..=n` are the `n`
+/// stops and location `n + 1` is the end. `WEIGHT_MAX` means there is no path.
+///
+/// Returns the stops in the optimal order (as indices into `1..=n`) and the total weight, or
+/// `None` if there is no route visiting all stops.
+pub fn find_best_order(weights: &[Vec<Weight>]) -> Option<(Vec<usize>, Weight)> {
+    assert!(weights.len() >= 2, "there has to be a start and an end");
+    let num_stops = weights.len() - 2;
+    assert!(
+        num_stops <= MAX_STOPS,
+        "there can be at most {} stops",
+        MAX_STOPS
+    );
+    let end = num_stops + 1;
+    let add = |a: Weight, b: Weight| {
+        if a == WEIGHT_MAX || b == WEIGHT_MAX {
+            WEIGHT_MAX
+        } else {
+            a.saturating_add(b)
+        }
+    };
+    if num_stops == 0 {
+        let weight = weights[0][end];
+        return if weight == WEIGHT_MAX {
+            None
+        } else {
+            Some((vec![], weight))
+        };
+    }
+
+    // best[set][i] is the minimum weight of a route from the start visiting exactly the stops in
+    // `set` and ending at stop `i` (which must be contained in `set`)
+    let num_sets = 1 << num_stops;
+    let mut best = vec![vec![WEIGHT_MAX; num_stops]; num_sets];
+    let mut prev = vec![vec![usize::MAX; num_stops]; num_sets];
+    for i in 0..num_stops {
+        best[1 << i][i] = weights[0][i + 1];
+    }
+    for set in 1..num_sets {
+        for last in 0..num_stops {
+            if set & (1 << last) == 0 || best[set][last] == WEIGHT_MAX {
+                continue;
+            }
+            for next in 0..num_stops {
+                if set & (1 << next) != 0 {
+                    continue;
+                }
+                let weight = add(best[set][last], weights[last + 1][next + 1]);
+                let next_set = set | (1 << next);
+                if weight < best[next_set][next] {
+                    best[next_set][next] = weight;
+                    prev[next_set][next] = last;
+                }
+            }
+        }
+    }
+
+    let full_set = num_sets - 1;
+    let (mut last, total_weight) = (0..num_stops)
+        .map(|i| (i, add(best[full_set][i], weights[i + 1][end])))
+        .min_by_key(|(_, weight)| *weight)
+        .unwrap();
+    if total_weight == WEIGHT_MAX {
+        return None;
+    }
+    let mut order = Vec::with_capacity(num_stops);
+    let mut set = full_set;
+    loop {
+        order.push(last + 1);
+        let before = prev[set][last];
+        set &= !(1 << last);
+        if set == 0 {
+            break;
+        }
+        last = before;
+    }
+    order.reverse();
+    Some((order, total_weight))
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::Rng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn no_stops() {
+        assert_eq!(
+            Some((vec![], 5)),
+            find_best_order(&[vec![0, 5], vec![3, 0]])
+        );
+        assert_eq!(None, find_best_order(&[vec![0, WEIGHT_MAX], vec![3, 0]]));
+    }
+
+    #[test]
+    fn simple() {
+        // start -> 2 -> 1 -> end is the only route
+        let m = WEIGHT_MAX;
+        let weights = vec![
+            vec![0, m, 1, m],
+            vec![m, 0, m, 4],
+            vec![m, 2, 0, m],
+            vec![m, m, m, 0],
+        ];
+        assert_eq!(Some((vec![2, 1], 7)), find_best_order(&weights));
+        let weights = vec![
+            vec![0, m, 1, m],
+            vec![m, 0, m, m],
+            vec![m, 2, 0, m],
+            vec![m, m, m, 0],
+        ];
+        assert_eq!(None, find_best_order(&weights));
+    }
+
+    #[test]
+    fn compare_with_brute_force() {
+        let seed = [7; 32];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        for _ in 0..100 {
+            let num_stops = rng.gen_range(1, 6);
+            let n = num_stops + 2;
+            let weights: Vec<Vec<Weight>> = (0..n)
+                .map(|_| {
+                    (0..n)
+                        .map(|_| {
+                            if rng.gen_range(0, 5) == 0 {
+                                WEIGHT_MAX
+                            } else {
+                                rng.gen_range(0, 20)
+                            }
+                        })
+                        .collect()
+                })
+                .collect();
+            let expected = brute_force(&weights);
+            let result = find_best_order(&weights);
+            assert_eq!(
+                expected.map(|(_, weight)| weight),
+                result.clone().map(|(_, weight)| weight)
+            );
+            if let Some((order, weight)) = result {
+                assert_eq!(weight, route_weight(&weights, &order));
+            }
+        }
+    }
+
+    fn brute_force(weights: &[Vec<Weight>]) -> Option<(Vec<usize>, Weight)> {
+        let num_stops = weights.len() - 2;
+        let mut order: Vec<usize> = (1..=num_stops).collect();
+        let mut best: Option<(Vec<usize>, Weight)> = None;
+        permutations(&mut order, 0, &mut |order| {
+            let weight = route_weight(weights, order);
+            if weight < WEIGHT_MAX && best.as_ref().map_or(true, |(_, w)| weight < *w) {
+                best = Some((order.to_vec(), weight));
+            }
+        });
+        best
+    }
+
+    fn route_weight(weights: &[Vec<Weight>], order: &[usize]) -> Weight {
+        let mut locations = vec![0];
+        locations.extend_from_slice(order);
+        locations.push(weights.len() - 1);
+        let mut total = 0;
+        for pair in locations.windows(2) {
+            let weight = weights[pair[0]][pair[1]];
+            if weight == WEIGHT_MAX {
+                return WEIGHT_MAX;
+            }
+            total += weight;
+        }
+        total
+    }
+
+    fn permutations(order: &mut Vec<usize>, k: usize, f: &mut dyn FnMut(&[usize])) {
+        if k == order.len() {
+            f(order);
+            return;
+        }
+        for i in k..order.len() {
+            order.swap(k, i);
+            permutations(order, k + 1, f);
+            order.swap(k, i);
+        }
+    }
+}
@@ -0,0 +1,149 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use std::sync::Arc;
+
+use crate::constants::NodeId;
+use crate::fast_graph::FastGraph;
+use crate::input_graph::Edge;
+use crate::original_edge_search;
+use crate::shortest_path::ShortestPath;
+
+/// A view of a shared `FastGraph` that only contains some of its edges, e.g. to exclude toll
+/// roads for some of the tenants of a server. Each view only stores one bit per original edge, so
+/// many views of the same graph are much cheaper than preparing a graph for each of them.
+///
+/// The allowed edges change the shortest paths in a way the contraction hierarchy was not prepared
+/// for, so the queries use the original edges only, see `original_edge_search::calc_path`.
+#[derive(Debug, Clone)]
+pub struct SubgraphView {
+    graph: Arc<FastGraph>,
+    /// the position of the bit of the first original out edge of each node
+    first_bits: Vec<usize>,
+    mask: Vec<u64>,
+}
+
+impl SubgraphView {
+    /// Creates a view of `graph` that contains the original edges for which `is_allowed` returns
+    /// true, see `FastGraph::get_original_out_edges`.
+    pub fn new<F: FnMut(&Edge) -> bool>(graph: Arc<FastGraph>, mut is_allowed: F) -> Self {
+        let num_nodes = graph.get_num_nodes();
+        let mut first_bits = Vec::with_capacity(num_nodes + 1);
+        let mut mask = vec![];
+        let mut bit = 0;
+        for node in 0..num_nodes {
+            first_bits.push(bit);
+            for edge in graph.get_original_out_edges(node) {
+                if bit % 64 == 0 {
+                    mask.push(0);
+                }
+                if is_allowed(&edge) {
+                    mask[bit / 64] |= 1 << (bit % 64);
+                }
+                bit += 1;
+            }
+        }
+        first_bits.push(bit);
+        SubgraphView {
+            graph,
+            first_bits,
+            mask,
+        }
+    }
+
+    pub fn get_graph(&self) -> &Arc<FastGraph> {
+        &self.graph
+    }
+
+    /// Returns the number of original edges that are contained in this view.
+    pub fn get_num_allowed_edges(&self) -> usize {
+        self.mask
+            .iter()
+            .map(|bits| bits.count_ones() as usize)
+            .sum()
+    }
+
+    /// Returns true if this view contains an original edge from `from` to `to`.
+    pub fn is_allowed(&self, from: NodeId, to: NodeId) -> bool {
+        self.graph
+            .get_original_out_edges(from)
+            .iter()
+            .enumerate()
+            .any(|(position, edge)| edge.to == to && self.is_allowed_at(from, position))
+    }
+
+    /// Calculates the shortest path from `start` to `end` that only uses the edges of this view.
+    /// This does not use the shortcuts of the prepared graph and is therefore much slower than
+    /// `PathCalculator::calc_path`.
+    pub fn calc_path(&self, start: NodeId, end: NodeId) -> Option<ShortestPath> {
+        original_edge_search::calc_path_with_positions(&self.graph, start, end, |edge, position| {
+            self.is_allowed_at(edge.from, position)
+                .then_some(edge.weight)
+        })
+    }
+
+    fn is_allowed_at(&self, node: NodeId, position: usize) -> bool {
+        let bit = self.first_bits[node] + position;
+        debug_assert!(bit < self.first_bits[node + 1]);
+        self.mask[bit / 64] & (1 << (bit % 64)) != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::input_graph::InputGraph;
+
+    use super::*;
+
+    #[test]
+    fn views_of_the_same_graph() {
+        // 0 -> 1 -> 2 -> 3
+        //  \-> 4 -> 5 ->/
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 1);
+        g.add_edge(2, 3, 1);
+        g.add_edge(0, 4, 2);
+        g.add_edge(4, 5, 2);
+        g.add_edge(5, 3, 2);
+        g.freeze();
+        let fast_graph = Arc::new(crate::prepare(&g));
+        let all = SubgraphView::new(fast_graph.clone(), |_| true);
+        // the edge 1 -> 2 is a toll road
+        let no_toll = SubgraphView::new(fast_graph.clone(), |edge| (edge.from, edge.to) != (1, 2));
+        let none = SubgraphView::new(fast_graph.clone(), |_| false);
+        assert_eq!(6, all.get_num_allowed_edges());
+        assert_eq!(5, no_toll.get_num_allowed_edges());
+        assert_eq!(0, none.get_num_allowed_edges());
+        assert!(all.is_allowed(1, 2));
+        assert!(!no_toll.is_allowed(1, 2));
+        assert!(no_toll.is_allowed(4, 5));
+        assert!(!all.is_allowed(2, 1));
+        assert_eq!(4, Arc::strong_count(&fast_graph));
+
+        let path = all.calc_path(0, 3).unwrap();
+        assert_eq!(&vec![0, 1, 2, 3], path.get_nodes());
+        let path = no_toll.calc_path(0, 3).unwrap();
+        assert_eq!(&vec![0, 4, 5, 3], path.get_nodes());
+        assert_eq!(6, path.get_weight());
+        assert!(no_toll.calc_path(1, 2).is_none());
+        assert!(none.calc_path(0, 3).is_none());
+        assert!(none.calc_path(0, 0).is_some());
+    }
+}
@@ -17,8 +17,6 @@
  * under the License.
  */
 
-use std::u32::MAX;
-
 use crate::constants::NodeId;
 
 /// Maintains a collection of N boolean flags that can efficiently be reset by incrementing a
@@ -45,7 +43,7 @@ impl ValidFlags {
     }
 
     pub fn invalidate_all(&mut self) {
-        if self.valid_flag == MAX {
+        if self.valid_flag == u32::MAX {
             self.valid_flags = vec![0; self.valid_flags.len()];
             self.valid_flag = 1;
         } else {
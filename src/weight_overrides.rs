@@ -0,0 +1,89 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use std::collections::HashMap;
+
+use crate::constants::{NodeId, Weight, WEIGHT_MAX};
+use crate::fast_graph::FastGraph;
+use crate::original_edge_search;
+use crate::shortest_path::ShortestPath;
+
+/// Calculates the shortest path from `start` to `end` where the weights of some edges are replaced
+/// by the weights given in `overrides`, e.g. to take live traffic into account. The edges are
+/// identified by their `(from, to)` nodes. An edge with weight `WEIGHT_MAX` is not used at all.
+///
+/// Recalculating the weights of the affected shortcuts is not enough, because the preparation did
+/// not create shortcuts for paths that were not shortest paths before, and these paths might be
+/// the shortest ones with the new weights. So this uses the original edges only, see
+/// `original_edge_search::calc_path`.
+pub fn calc_path(
+    graph: &FastGraph,
+    start: NodeId,
+    end: NodeId,
+    overrides: &HashMap<(NodeId, NodeId), Weight>,
+) -> Option<ShortestPath> {
+    original_edge_search::calc_path(graph, start, end, |edge| {
+        match overrides.get(&(edge.from, edge.to)) {
+            Some(&WEIGHT_MAX) => None,
+            Some(weight) => Some(*weight),
+            None => Some(edge.weight),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::input_graph::InputGraph;
+
+    use super::*;
+
+    #[test]
+    fn override_weights() {
+        // 0 -> 1 -> 2 -> 3
+        //  \-> 4 -> 5 ->/
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 1);
+        g.add_edge(2, 3, 1);
+        g.add_edge(0, 4, 2);
+        g.add_edge(4, 5, 2);
+        g.add_edge(5, 3, 2);
+        g.freeze();
+        let fast_graph = crate::prepare(&g);
+        let mut overrides = HashMap::new();
+        let path = calc_path(&fast_graph, 0, 3, &overrides).unwrap();
+        assert_eq!(&vec![0, 1, 2, 3], path.get_nodes());
+        assert_eq!(3, path.get_weight());
+        // a traffic jam between 1 and 2
+        overrides.insert((1, 2), 10);
+        let path = calc_path(&fast_graph, 0, 3, &overrides).unwrap();
+        assert_eq!(&vec![0, 4, 5, 3], path.get_nodes());
+        assert_eq!(6, path.get_weight());
+        // the other route gets faster than before
+        overrides.insert((4, 5), 0);
+        overrides.insert((5, 3), 0);
+        let path = calc_path(&fast_graph, 0, 4, &overrides).unwrap();
+        assert_eq!(2, path.get_weight());
+        let path = calc_path(&fast_graph, 0, 3, &overrides).unwrap();
+        assert_eq!(2, path.get_weight());
+        overrides.insert((0, 4), WEIGHT_MAX);
+        overrides.insert((0, 1), WEIGHT_MAX);
+        assert!(calc_path(&fast_graph, 0, 3, &overrides).is_none());
+    }
+}
@@ -0,0 +1,161 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{Weight, WEIGHT_MAX};
+use crate::input_graph::InputGraph;
+use crate::shortest_path::ShortestPath;
+
+/// The largest integer up to which every integer can be represented exactly as `f64`
+const MAX_EXACT_WEIGHT: u64 = 1 << 53;
+
+/// Converts floating point costs to the integer weights used by this crate and back. A cost is
+/// divided by the resolution and rounded to the nearest integer, e.g. with a resolution of `0.01`
+/// the cost `2.345` becomes the weight `235`, which is converted back to `2.35`.
+///
+/// Rounding the edge costs means the weight of a path with `n` edges can differ from the sum of
+/// its costs by up to `n * resolution / 2`, and paths whose costs differ by less than that might
+/// be considered equally long. Costs below half the resolution become zero, so such edges are
+/// skipped unless `InputGraph::set_allow_zero_weights` is used. On the other hand the total
+/// weight of a path must not exceed `get_max_weight`, so the resolution should be as coarse as
+/// possible while still distinguishing the costs that matter to you.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct WeightScale {
+    resolution: f64,
+}
+
+impl WeightScale {
+    pub fn new(resolution: f64) -> Self {
+        assert!(
+            resolution.is_finite() && resolution > 0.0,
+            "the resolution must be positive, but was: {}",
+            resolution
+        );
+        WeightScale { resolution }
+    }
+
+    pub fn get_resolution(&self) -> f64 {
+        self.resolution
+    }
+
+    /// Returns the largest weight that can be converted back to a cost without losing precision.
+    pub fn get_max_weight(&self) -> Weight {
+        MAX_EXACT_WEIGHT.min(WEIGHT_MAX as u64 - 1) as Weight
+    }
+
+    /// Returns the largest cost that can be represented, which is the limit for single edges as
+    /// well as for the total cost of a path, see `fits`.
+    pub fn get_max_cost(&self) -> f64 {
+        self.to_cost(self.get_max_weight())
+    }
+
+    /// Converts the given cost to a weight. Returns an error if the cost is negative, not a number
+    /// or larger than `get_max_cost`.
+    pub fn to_weight(&self, cost: f64) -> Result<Weight, String> {
+        if cost.is_nan() || cost < 0.0 {
+            return Err(format!("the cost must not be negative, but was: {}", cost));
+        }
+        let weight = (cost / self.resolution).round();
+        if weight > self.get_max_weight() as f64 {
+            return Err(format!(
+                "the cost {} exceeds the maximum cost {} for the resolution {}",
+                cost,
+                self.get_max_cost(),
+                self.resolution
+            ));
+        }
+        Ok(weight as Weight)
+    }
+
+    /// Converts the given weight, e.g. the weight of a path, back to a cost.
+    pub fn to_cost(&self, weight: Weight) -> f64 {
+        weight as f64 * self.resolution
+    }
+
+    /// Returns the cost of the given path, or `None` if no path was found.
+    pub fn get_path_cost(&self, path: &ShortestPath) -> Option<f64> {
+        if path.is_found() {
+            Some(self.to_cost(path.get_weight()))
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if no shortest path in the given (frozen) graph can exceed `get_max_weight`,
+    /// see `InputGraph::get_max_path_weight`. Otherwise the costs of long paths might be imprecise.
+    pub fn fits(&self, input_graph: &InputGraph) -> bool {
+        input_graph.get_max_path_weight() <= self.get_max_weight()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_costs() {
+        let scale = WeightScale::new(0.01);
+        assert_eq!(Ok(235), scale.to_weight(2.345));
+        assert_eq!(Ok(0), scale.to_weight(0.004));
+        assert_eq!(Ok(1), scale.to_weight(0.006));
+        assert!((scale.to_cost(235) - 2.35).abs() < 1e-9);
+        assert_eq!(1 << 53, scale.get_max_weight());
+        assert_eq!(Ok(1 << 53), scale.to_weight(scale.get_max_cost()));
+        assert!(scale.to_weight(scale.get_max_cost() * 1.01).is_err());
+        assert!(scale.to_weight(f64::INFINITY).is_err());
+        assert!(scale.to_weight(f64::NAN).is_err());
+        assert!(scale.to_weight(-1.0).is_err());
+    }
+
+    #[test]
+    fn path_costs() {
+        let scale = WeightScale::new(0.5);
+        let mut g = InputGraph::new();
+        g.add_edge_f64(0, 1, 1.2, &scale);
+        g.add_edge_bidir_f64(1, 2, 0.8, &scale);
+        // rounds to zero and is skipped
+        g.add_edge_f64(2, 3, 0.2, &scale);
+        g.freeze();
+        assert_eq!(3, g.get_num_edges());
+        assert!(scale.fits(&g));
+        let fast_graph = crate::prepare(&g);
+        let path = crate::calc_path(&fast_graph, 0, 2).unwrap();
+        assert_eq!(Some(2.0), scale.get_path_cost(&path));
+        assert_eq!(None, scale.get_path_cost(&ShortestPath::none(2, 0)));
+
+        let mut g = InputGraph::new();
+        g.add_edge_f64(0, 1, scale.get_max_cost(), &scale);
+        g.add_edge_f64(1, 2, 1.0, &scale);
+        g.freeze();
+        assert!(!scale.fits(&g));
+    }
+
+    #[test]
+    #[should_panic(expected = "the resolution must be positive")]
+    fn panic_if_zero_resolution() {
+        WeightScale::new(0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid cost: the cost must not be negative")]
+    fn panic_if_negative_cost() {
+        let mut g = InputGraph::new();
+        g.add_edge_f64(0, 1, -3.0, &WeightScale::new(1.0));
+    }
+}
@@ -0,0 +1,94 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use serde::{Deserialize, Serialize};
+
+use crate::constants::Weight;
+
+/// The physical quantity the weights of a graph are measured in
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaseUnit {
+    Seconds,
+    Meters,
+    /// Any other quantity, e.g. a generalized cost
+    Other,
+}
+
+/// Describes how the weights of a graph are to be interpreted, i.e. a weight `w` means `w * factor`
+/// base units. For example weights in milliseconds have the base unit `Seconds` and the factor
+/// `0.001`. The unit is only stored along with the graph and the calculated paths, it does not
+/// change any calculations.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct WeightUnit {
+    base_unit: BaseUnit,
+    factor: f64,
+}
+
+impl WeightUnit {
+    pub fn new(base_unit: BaseUnit, factor: f64) -> Self {
+        assert!(
+            factor.is_finite() && factor > 0.0,
+            "the factor must be positive, but was: {}",
+            factor
+        );
+        WeightUnit { base_unit, factor }
+    }
+
+    pub fn seconds() -> Self {
+        WeightUnit::new(BaseUnit::Seconds, 1.0)
+    }
+
+    pub fn milliseconds() -> Self {
+        WeightUnit::new(BaseUnit::Seconds, 0.001)
+    }
+
+    pub fn meters() -> Self {
+        WeightUnit::new(BaseUnit::Meters, 1.0)
+    }
+
+    pub fn centimeters() -> Self {
+        WeightUnit::new(BaseUnit::Meters, 0.01)
+    }
+
+    pub fn get_base_unit(&self) -> BaseUnit {
+        self.base_unit
+    }
+
+    pub fn get_factor(&self) -> f64 {
+        self.factor
+    }
+
+    /// Converts the given weight to the base unit, e.g. milliseconds to seconds.
+    pub fn to_base_unit(&self, weight: Weight) -> f64 {
+        weight as f64 * self.factor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert() {
+        assert_eq!(1.5, WeightUnit::milliseconds().to_base_unit(1500));
+        assert_eq!(BaseUnit::Meters, WeightUnit::centimeters().get_base_unit());
+        assert_eq!(2.0, WeightUnit::centimeters().to_base_unit(200));
+        assert_eq!(7.5, WeightUnit::new(BaseUnit::Other, 2.5).to_base_unit(3));
+    }
+}
@@ -33,10 +33,18 @@ pub struct WitnessSearch {
     start_node: NodeId,
     avoid_node: NodeId,
     settled_nodes: usize,
+    /// The weight limits of the targets of `find_max_weights` that were not found yet
+    target_limits: Vec<Option<Weight>>,
+    max_hops: usize,
 }
 
 impl WitnessSearch {
     pub fn new(num_nodes: usize) -> Self {
+        WitnessSearch::with_max_hops(num_nodes, None)
+    }
+
+    /// Like `new`, but the searches do not follow paths with more than `max_hops` edges, if set.
+    pub fn with_max_hops(num_nodes: usize, max_hops: Option<usize>) -> Self {
         let heap = BinaryHeap::new();
         WitnessSearch {
             num_nodes,
@@ -46,9 +54,17 @@ impl WitnessSearch {
             start_node: INVALID_NODE,
             avoid_node: INVALID_NODE,
             settled_nodes: 0,
+            target_limits: vec![None; num_nodes],
+            max_hops: max_hops.unwrap_or(usize::MAX),
         }
     }
 
+    /// Changes the hop limit, see `with_max_hops`. This must not be called between `init` and the
+    /// searches that continue from it.
+    pub fn set_max_hops(&mut self, max_hops: Option<usize>) {
+        self.max_hops = max_hops.unwrap_or(usize::MAX);
+    }
+
     /// Initializes the witness search for a given start and avoid node. Calling this method
     /// resets/clears previously calculated data.
     pub fn init(&mut self, start: NodeId, avoid_node: NodeId) {
@@ -62,33 +78,43 @@ impl WitnessSearch {
 
         self.heap.clear();
         self.valid_flags.invalidate_all();
-        self.update_node(start, 0);
+        self.update_node(start, 0, 0, INVALID_NODE);
         self.heap.push(HeapItem::new(0, start));
         self.settled_nodes = 0;
     }
 
-    /// Returns an upper bound for the shortest path weight between the start node and a given target
-    /// node.
-    /// Calling this method runs Dijkstra's algorithm for the given start_node. The avoid_node will
-    /// never be visited. There are multiple criteria that make the search stop:
-    ///   1) the target is settled. the returned weight will be the actual shortest path weight.
-    ///   2) the next node to be settled exceeds the given weight_limit. the returned weight will
-    ///      be the best known upper bound for the real shortest path weight at this point. it will
-    ///      always be larger than weight_limit in this case.
-    ///   3) the tentative weight of the target is found to be equal or smaller than weight_limit.
-    ///      this way the search can be stopped without finding the actual shortest path as soon as
-    ///      any path with weight <= weight_limit has been found.
-    ///   4) settled_nodes_limit nodes have been settled. the returned weight will be the best known
-    ///      upper bound for the real shortest path weight at this point.
-    /// The shortest path tree established during the search will be re-used until the init
-    /// function is called again.
-    pub fn find_max_weight(
+    /// Finds upper bounds for the shortest path weights between the start node and the given
+    /// targets, which are given as distinct `(target, weight_limit)` pairs. The upper bounds are
+    /// written to `weights`, in the same order as `targets`.
+    /// Calling this method runs Dijkstra's algorithm for the given start_node. The avoid_node and
+    /// the nodes marked in `blocked` will never be visited. The blocked nodes must not change until
+    /// the init function is called again. There are multiple criteria that make the search stop:
+    ///   1) every target is settled or, see 3), found. the returned weights of the settled targets
+    ///      will be the actual shortest path weights.
+    ///   2) the next node to be settled exceeds the largest weight_limit of the targets that were
+    ///      not found yet. the returned weights will be the best known upper bounds for the real
+    ///      shortest path weights at this point. they will always be larger than the weight_limit
+    ///      of these targets in this case.
+    ///   3) a target counts as found once its tentative weight is equal or smaller than its
+    ///      weight_limit. this way the search can be stopped without finding the actual shortest
+    ///      paths as soon as any paths with weight <= weight_limit have been found.
+    ///   4) settled_nodes_limit nodes have been settled. the returned weights will be the best known
+    ///      upper bounds for the real shortest path weights at this point.
+    ///
+    /// If there is a hop limit, see `with_max_hops`, the paths that have as many edges as the limit
+    /// are not extended any further.
+    ///
+    /// Searching all targets at once rather than one after the other saves time for nodes
+    /// with many neighbors. The shortest path tree established during the search will be re-used
+    /// until the init function is called again.
+    pub fn find_max_weights(
         &mut self,
         graph: &PreparationGraph,
-        target: NodeId,
-        weight_limit: Weight,
+        targets: &[(NodeId, Weight)],
         settled_nodes_limit: usize,
-    ) -> Weight {
+        blocked: &[bool],
+        weights: &mut Vec<Weight>,
+    ) {
         assert_eq!(
             graph.get_num_nodes(),
             self.num_nodes,
@@ -96,63 +122,105 @@ impl WitnessSearch {
         );
         assert_ne!(
             self.start_node, INVALID_NODE,
-            "the start node must be valid, call init() before find_max_weight()"
+            "the start node must be valid, call init() before find_max_weights()"
         );
-        assert!(
-            self.start_node != self.avoid_node && target != self.avoid_node,
-            "path calculation must not start or end with avoided node"
-        );
-        if target == self.start_node {
-            return WEIGHT_ZERO;
-        }
-        if self.valid_flags.is_valid(target)
-            && (self.data[target].settled || self.data[target].weight <= weight_limit)
-        {
-            return self.data[target].weight;
+        for (target, weight_limit) in targets {
+            assert_ne!(
+                *target, self.avoid_node,
+                "path calculation must not end with avoided node"
+            );
+            if !self.is_resolved(*target, *weight_limit) {
+                self.target_limits[*target] = Some(*weight_limit);
+            }
         }
-        while !self.heap.is_empty() {
+        let mut max_weight_limit = self.get_max_open_weight_limit(targets);
+        while let Some(limit) = max_weight_limit {
             if self.settled_nodes >= settled_nodes_limit {
                 break;
             }
-            let curr = *self.heap.peek().unwrap();
-            if curr.weight > weight_limit {
-                break;
-            }
+            let curr = match self.heap.peek() {
+                Some(curr) if curr.weight <= limit => *curr,
+                _ => break,
+            };
             self.heap.pop();
             if self.is_settled(curr.node_id) {
-                // todo: since we are not using a special decrease key operation yet we need to
-                // filter out duplicate heap items here
                 continue;
             }
-            let mut found_target = false;
-            for i in 0..graph.out_edges[curr.node_id].len() {
-                let adj = graph.out_edges[curr.node_id][i].adj_node;
-                if adj == self.avoid_node {
+            let hops = self.data[curr.node_id].hops + 1;
+            let out_edges = if hops <= self.max_hops {
+                graph.out_edges[curr.node_id].as_slice()
+            } else {
+                &[]
+            };
+            for edge in out_edges {
+                let adj = edge.adj_node;
+                if adj == self.avoid_node || blocked.get(adj).is_some_and(|b| *b) {
                     continue;
                 }
-                let edge_weight = graph.out_edges[curr.node_id][i].weight;
-                let weight = curr.weight + edge_weight;
+                let weight = curr.weight + edge.weight;
                 if weight < self.get_current_weight(adj) {
-                    self.update_node(adj, weight);
+                    self.update_node(adj, weight, hops, curr.node_id);
                     self.heap.push(HeapItem::new(weight, adj));
-                    if adj == target && weight <= weight_limit {
-                        found_target = true;
+                    if let Some(target_limit) = self.target_limits[adj] {
+                        if weight <= target_limit {
+                            self.target_limits[adj] = None;
+                            if Some(target_limit) == max_weight_limit {
+                                max_weight_limit = self.get_max_open_weight_limit(targets);
+                            }
+                        }
                     }
                 }
             }
             self.data[curr.node_id].settled = true;
             self.settled_nodes += 1;
-            if found_target || curr.node_id == target {
-                break;
+            // once a target is settled its weight is final, even if it exceeds the limit
+            if self.target_limits[curr.node_id].take() == max_weight_limit {
+                max_weight_limit = self.get_max_open_weight_limit(targets);
             }
         }
-        self.get_current_weight(target)
+        weights.clear();
+        for (target, _) in targets {
+            self.target_limits[*target] = None;
+            weights.push(if *target == self.start_node {
+                WEIGHT_ZERO
+            } else {
+                self.get_current_weight(*target)
+            });
+        }
+    }
+
+    /// Adds the `(from, to)` arcs of the path with the weight `find_max_weights` returned for
+    /// `target` to `arcs`. This must be called before the next call of `init`.
+    pub fn add_witness_arcs(&self, target: NodeId, arcs: &mut Vec<(NodeId, NodeId)>) {
+        if !self.valid_flags.is_valid(target) {
+            return;
+        }
+        let mut node = target;
+        while self.data[node].parent != INVALID_NODE {
+            arcs.push((self.data[node].parent, node));
+            node = self.data[node].parent;
+        }
     }
 
-    fn update_node(&mut self, node: NodeId, weight: Weight) {
+    fn get_max_open_weight_limit(&self, targets: &[(NodeId, Weight)]) -> Option<Weight> {
+        targets
+            .iter()
+            .filter_map(|(target, _)| self.target_limits[*target])
+            .max()
+    }
+
+    fn is_resolved(&self, target: NodeId, weight_limit: Weight) -> bool {
+        target == self.start_node
+            || (self.valid_flags.is_valid(target)
+                && (self.data[target].settled || self.data[target].weight <= weight_limit))
+    }
+
+    fn update_node(&mut self, node: NodeId, weight: Weight, hops: usize, parent: NodeId) {
         self.valid_flags.set_valid(node);
         self.data[node].settled = false;
         self.data[node].weight = weight;
+        self.data[node].hops = hops;
+        self.data[node].parent = parent;
     }
 
     fn is_settled(&self, node: NodeId) -> bool {
@@ -171,6 +239,10 @@ impl WitnessSearch {
 struct Data {
     settled: bool,
     weight: Weight,
+    /// the number of edges of the path with the current weight
+    hops: usize,
+    /// the previous node on the path with the current weight
+    parent: NodeId,
 }
 
 impl Data {
@@ -179,6 +251,8 @@ impl Data {
         Data {
             settled: false,
             weight: WEIGHT_MAX,
+            hops: 0,
+            parent: INVALID_NODE,
         }
     }
 }
@@ -187,6 +261,26 @@ impl Data {
 mod tests {
     use super::*;
 
+    impl WitnessSearch {
+        fn find_max_weight(
+            &mut self,
+            graph: &PreparationGraph,
+            target: NodeId,
+            weight_limit: Weight,
+            settled_nodes_limit: usize,
+        ) -> Weight {
+            let mut weights = vec![];
+            self.find_max_weights(
+                graph,
+                &[(target, weight_limit)],
+                settled_nodes_limit,
+                &[],
+                &mut weights,
+            );
+            weights[0]
+        }
+    }
+
     #[test]
     fn avoid_node() {
         // 0 -> 1 -> 2
@@ -287,6 +381,67 @@ mod tests {
         assert_eq!(2, ws.settled_nodes);
     }
 
+    #[test]
+    fn many_targets() {
+        // 0 -> 1 -> 2 -> 3 -> 4
+        //  \-> 5 ------>/
+        let mut g = PreparationGraph::new(6);
+        for i in 0..4 {
+            g.add_edge(i, i + 1, 1);
+        }
+        g.add_edge(0, 5, 1);
+        g.add_edge(5, 3, 5);
+        let mut ws = WitnessSearch::new(g.get_num_nodes());
+        let mut weights = vec![];
+        ws.init(0, 1);
+        // 3 can only be reached via 5, which exceeds its limit, and 4 is too far away for its
+        // limit, so the search stops once the next node to be settled exceeds the largest limit
+        ws.find_max_weights(
+            &g,
+            &[(5, 1), (3, 4), (4, 5), (0, 0)],
+            100,
+            &[],
+            &mut weights,
+        );
+        assert_eq!(vec![1, 6, WEIGHT_MAX, 0], weights);
+        assert_eq!(2, ws.settled_nodes);
+        // the search tree is re-used
+        ws.find_max_weights(&g, &[(4, 10), (5, 0)], 100, &[], &mut weights);
+        assert_eq!(vec![7, 1], weights);
+        assert_eq!(3, ws.settled_nodes);
+
+        // the search stops as soon as all targets are found
+        ws.init(0, INVALID_NODE);
+        ws.find_max_weights(&g, &[(2, 3), (5, 3)], 100, &[], &mut weights);
+        assert_eq!(vec![2, 1], weights);
+        assert_eq!(2, ws.settled_nodes);
+        // blocked nodes are never visited
+        ws.init(0, INVALID_NODE);
+        let mut blocked = vec![false; 6];
+        blocked[2] = true;
+        ws.find_max_weights(&g, &[(3, 3)], 100, &blocked, &mut weights);
+        assert_eq!(vec![6], weights);
+    }
+
+    #[test]
+    fn limit_hops() {
+        // 0 -> 1 -> 2 -> 3
+        //  \----------->/
+        let mut g = PreparationGraph::new(4);
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 1);
+        g.add_edge(2, 3, 1);
+        g.add_edge(0, 3, 10);
+        let mut ws = WitnessSearch::with_max_hops(g.get_num_nodes(), Some(2));
+        ws.init(0, INVALID_NODE);
+        assert_eq!(2, ws.find_max_weight(&g, 2, 5, 100));
+        // the path 0 -> 1 -> 2 -> 3 has too many edges
+        assert_eq!(10, ws.find_max_weight(&g, 3, 5, 100));
+        let mut ws = WitnessSearch::with_max_hops(g.get_num_nodes(), Some(3));
+        ws.init(0, INVALID_NODE);
+        assert_eq!(3, ws.find_max_weight(&g, 3, 5, 100));
+    }
+
     #[test]
     fn large_edge_weight() {
         // 100 <- 99 <- ... <- 3 -> 2 -> 1
@@ -335,4 +490,27 @@ mod tests {
         // large weight edges
         assert_eq!(2, ws.settled_nodes);
     }
+
+    #[test]
+    fn witness_arcs() {
+        // 0 -> 1 -> 2 -> 3
+        //  \-------->/
+        let mut g = PreparationGraph::new(4);
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 1);
+        g.add_edge(2, 3, 1);
+        g.add_edge(0, 2, 5);
+        let mut ws = WitnessSearch::new(g.get_num_nodes());
+        ws.init(0, 3);
+        assert_eq!(2, ws.find_max_weight(&g, 2, 2, usize::MAX));
+        let mut arcs = vec![];
+        ws.add_witness_arcs(2, &mut arcs);
+        assert_eq!(vec![(1, 2), (0, 1)], arcs);
+        // the search avoids node 1 and finds the longer path
+        ws.init(0, 1);
+        assert_eq!(5, ws.find_max_weight(&g, 2, 5, usize::MAX));
+        arcs.clear();
+        ws.add_witness_arcs(2, &mut arcs);
+        assert_eq!(vec![(0, 2)], arcs);
+    }
 }
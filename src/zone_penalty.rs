@@ -0,0 +1,88 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+use crate::constants::{NodeId, Weight};
+use crate::fast_graph::FastGraph;
+use crate::original_edge_search;
+use crate::shortest_path::ShortestPath;
+
+/// Calculates the shortest path from `start` to `end` where `penalty` is added to the weight of
+/// every edge that connects two nodes of different zones, e.g. to avoid entering or leaving a
+/// congestion charge zone. `zones[node]` is the zone of each node, nodes that do not belong to any
+/// particular zone can simply share the same zone. The weight of the returned path includes the
+/// penalties.
+///
+/// The penalties change the weights of the shortcuts in a way the contraction hierarchy was not
+/// prepared for, so this uses the original edges only, see `original_edge_search::calc_path`.
+pub fn calc_path(
+    graph: &FastGraph,
+    start: NodeId,
+    end: NodeId,
+    zones: &[usize],
+    penalty: Weight,
+) -> Option<ShortestPath> {
+    assert_eq!(
+        graph.get_num_nodes(),
+        zones.len(),
+        "there has to be one zone per node"
+    );
+    original_edge_search::calc_path(graph, start, end, |edge| {
+        if zones[edge.from] != zones[edge.to] {
+            Some(edge.weight.saturating_add(penalty))
+        } else {
+            Some(edge.weight)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::input_graph::InputGraph;
+
+    use super::*;
+
+    #[test]
+    fn avoid_zone() {
+        // 0 -> 1 -> 2 -> 3
+        //  \-> 4 -> 5 ->/
+        let mut g = InputGraph::new();
+        g.add_edge(0, 1, 1);
+        g.add_edge(1, 2, 1);
+        g.add_edge(2, 3, 1);
+        g.add_edge(0, 4, 2);
+        g.add_edge(4, 5, 2);
+        g.add_edge(5, 3, 2);
+        g.freeze();
+        let fast_graph = crate::prepare(&g);
+        // without penalty we go through the zone
+        let zones = vec![0, 1, 1, 0, 0, 0];
+        let path = calc_path(&fast_graph, 0, 3, &zones, 0).unwrap();
+        assert_eq!(&vec![0, 1, 2, 3], path.get_nodes());
+        assert_eq!(3, path.get_weight());
+        // entering and leaving the zone costs 2 * 5, so we take the detour
+        let path = calc_path(&fast_graph, 0, 3, &zones, 5).unwrap();
+        assert_eq!(&vec![0, 4, 5, 3], path.get_nodes());
+        assert_eq!(6, path.get_weight());
+        // if we start in the zone we have to pay once
+        let path = calc_path(&fast_graph, 1, 3, &zones, 5).unwrap();
+        assert_eq!(&vec![1, 2, 3], path.get_nodes());
+        assert_eq!(7, path.get_weight());
+        assert!(calc_path(&fast_graph, 3, 0, &zones, 5).is_none());
+    }
+}